@@ -0,0 +1,56 @@
+use {
+  super::slab::Heap,
+  crate::support::sync::{Mutex, MutexGuard},
+  core::sync::atomic::{AtomicUsize, Ordering}
+};
+
+// Small objects come from one of several independent heaps. Threads are
+// spread over them round robin and move to another one when their own is
+// contended, so that concurrent allocations rarely wait on the same lock.
+pub const ARENA_COUNT: usize = 8;
+
+static ARENAS: [Mutex<Heap>; ARENA_COUNT] = [
+  Mutex::new(Heap::new(0)),
+  Mutex::new(Heap::new(1)),
+  Mutex::new(Heap::new(2)),
+  Mutex::new(Heap::new(3)),
+  Mutex::new(Heap::new(4)),
+  Mutex::new(Heap::new(5)),
+  Mutex::new(Heap::new(6)),
+  Mutex::new(Heap::new(7))
+];
+static NEXT_ARENA: AtomicUsize = AtomicUsize::new(0);
+
+#[thread_local]
+static mut THREAD_ARENA: usize = usize::MAX;
+
+// Index of the arena the calling thread allocates from.
+pub fn current() -> usize {
+  let index = unsafe { THREAD_ARENA };
+  if index < ARENA_COUNT {
+    return index;
+  }
+  let index = NEXT_ARENA.fetch_add(1, Ordering::Relaxed) % ARENA_COUNT;
+  unsafe { THREAD_ARENA = index };
+  index
+}
+
+pub fn lock(index: usize) -> MutexGuard<'static, Heap> {
+  ARENAS[index].lock()
+}
+
+// Lock the arena of the calling thread. If another thread holds it, the
+// first free arena is taken instead and becomes the thread's arena.
+pub fn lock_current() -> MutexGuard<'static, Heap> {
+  let index = current();
+  if let Some(heap) = ARENAS[index].try_lock() {
+    return heap;
+  }
+  for other in (1..ARENA_COUNT).map(|i| (index + i) % ARENA_COUNT) {
+    if let Some(heap) = ARENAS[other].try_lock() {
+      unsafe { THREAD_ARENA = other };
+      return heap;
+    }
+  }
+  ARENAS[index].lock()
+}
@@ -0,0 +1,218 @@
+use {
+  super::{large, slab::Slab, sys},
+  crate::support::sync::Mutex,
+  core::{
+    ptr,
+    sync::atomic::{AtomicU64, Ordering}
+  },
+  syscalls::{Sysno, syscall}
+};
+
+// Every small object ends with a trailer recording where the user block
+// starts and ends inside the object. The bytes between the end of the user
+// block and the trailer are filled with canary bytes derived from a per
+// process secret.
+pub const TRAILER_SIZE: usize = 8;
+pub const REDZONE: usize = TRAILER_SIZE + 8;
+
+const POISON: u8 = 0xdf;
+const QUARANTINE_SLOTS: usize = 1024;
+const LARGE_QUARANTINE_SLOTS: usize = 16;
+
+static SECRET: AtomicU64 = AtomicU64::new(0);
+
+static LARGE_QUARANTINE: Mutex<LargeQuarantine> =
+  Mutex::new(LargeQuarantine::new());
+
+#[repr(C)]
+struct Trailer {
+  start: u32,
+  end: u32
+}
+
+fn secret() -> u64 {
+  let mut value = SECRET.load(Ordering::Relaxed);
+  if value == 0 {
+    let _ =
+      unsafe { syscall!(Sysno::getrandom, ptr::addr_of_mut!(value), 8, 0) };
+    // Fall back to something address dependent if getrandom is missing.
+    value |= (ptr::addr_of!(SECRET) as u64).rotate_left(29) | 1;
+    SECRET.store(value, Ordering::Relaxed);
+  }
+  value
+}
+
+#[inline]
+fn canary_byte(
+  secret: u64,
+  addr: usize
+) -> u8 {
+  (secret >> ((addr & 7) * 8)) as u8
+}
+
+fn fill_canary(
+  start: usize,
+  end: usize
+) {
+  let secret = secret();
+  for addr in start..end {
+    unsafe { *(addr as *mut u8) = canary_byte(secret, addr) };
+  }
+}
+
+fn canary_intact(
+  start: usize,
+  end: usize
+) -> bool {
+  let secret = secret();
+  (start..end)
+    .all(|addr| unsafe { *(addr as *const u8) } == canary_byte(secret, addr))
+}
+
+#[inline]
+fn trailer(
+  obj: usize,
+  obj_size: usize
+) -> *mut Trailer {
+  (obj + obj_size - TRAILER_SIZE) as *mut Trailer
+}
+
+pub fn arm_small(
+  obj: usize,
+  obj_size: usize,
+  p: usize,
+  size: usize
+) {
+  let t = trailer(obj, obj_size);
+  unsafe {
+    (*t).start = (p - obj) as u32;
+    (*t).end = (p - obj + size) as u32;
+  }
+  fill_canary(p + size, obj + obj_size - TRAILER_SIZE);
+}
+
+pub fn requested_size(
+  obj: usize,
+  obj_size: usize
+) -> usize {
+  let t = trailer(obj, obj_size);
+  unsafe { ((*t).end - (*t).start) as usize }
+}
+
+pub fn check_small(
+  obj: usize,
+  obj_size: usize,
+  p: usize,
+  size: Option<usize>
+) {
+  let t = trailer(obj, obj_size);
+  let (start, end) = unsafe { ((*t).start as usize, (*t).end as usize) };
+  assert!(p - obj == start, "free(): invalid pointer {p:#x}");
+  assert!(
+    size.is_none_or(|size| size == end - start),
+    "free_sized(): size does not match allocation at {p:#x}"
+  );
+  assert!(
+    canary_intact(obj + end, obj + obj_size - TRAILER_SIZE),
+    "free(): heap buffer overflow detected past {p:#x}"
+  );
+}
+
+pub fn poison(
+  obj: usize,
+  len: usize
+) {
+  unsafe { ptr::write_bytes(obj as *mut u8, POISON, len) };
+}
+
+pub fn poison_intact(
+  obj: usize,
+  len: usize
+) -> bool {
+  (obj..obj + len).all(|addr| unsafe { *(addr as *const u8) } == POISON)
+}
+
+// Freed objects wait here before they can be handed out again, so that a
+// dangling pointer keeps pointing at poisoned memory for a while.
+pub struct Quarantine {
+  slots: [(*mut Slab, usize); QUARANTINE_SLOTS],
+  head: usize,
+  len: usize
+}
+
+impl Quarantine {
+  pub const fn new() -> Self {
+    Self { slots: [(ptr::null_mut(), 0); QUARANTINE_SLOTS], head: 0, len: 0 }
+  }
+
+  // Returns the oldest entry once the quarantine is full.
+  pub fn push(
+    &mut self,
+    slab: *mut Slab,
+    obj: usize
+  ) -> Option<(*mut Slab, usize)> {
+    let tail = (self.head + self.len) % QUARANTINE_SLOTS;
+    if self.len < QUARANTINE_SLOTS {
+      self.slots[tail] = (slab, obj);
+      self.len += 1;
+      return None;
+    }
+    let evicted = self.slots[self.head];
+    self.slots[self.head] = (slab, obj);
+    self.head = (self.head + 1) % QUARANTINE_SLOTS;
+    Some(evicted)
+  }
+}
+
+struct LargeQuarantine {
+  slots: [*mut large::LargeSpan; LARGE_QUARANTINE_SLOTS],
+  next: usize
+}
+
+unsafe impl Send for LargeQuarantine {}
+
+impl LargeQuarantine {
+  const fn new() -> Self {
+    Self { slots: [ptr::null_mut(); LARGE_QUARANTINE_SLOTS], next: 0 }
+  }
+}
+
+pub fn arm_large(
+  span: &large::LargeSpan,
+  p: usize
+) {
+  fill_canary(p + span.size, span.accessible_end());
+}
+
+pub fn free_large(
+  span: *mut large::LargeSpan,
+  p: usize,
+  size: Option<usize>
+) {
+  let s = unsafe { &mut *span };
+  assert!(p - span as usize == s.offset, "free(): invalid pointer {p:#x}");
+  assert!(
+    size.is_none_or(|size| size == s.size),
+    "free_sized(): size does not match allocation at {p:#x}"
+  );
+  assert!(
+    canary_intact(p + s.size, s.accessible_end()),
+    "free(): heap buffer overflow detected past {p:#x}"
+  );
+
+  // Keep the pages mapped but inaccessible so that a use after free faults
+  // right away.
+  s.magic = large::LARGE_FREED_MAGIC;
+  sys::protect(p as *mut u8, span as usize + s.len - p, false);
+
+  let mut q = LARGE_QUARANTINE.lock();
+  let slot = q.next;
+  let evicted = q.slots[slot];
+  q.slots[slot] = span;
+  q.next = (slot + 1) % LARGE_QUARANTINE_SLOTS;
+  drop(q);
+
+  if !evicted.is_null() {
+    large::deallocate(evicted);
+  }
+}
@@ -0,0 +1,188 @@
+use {
+  super::{slab::SLAB_SIZE, sys},
+  core::{
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering}
+  }
+};
+
+// Large allocations get their own mapping. The span header sits at a
+// SLAB_SIZE boundary below the returned pointer so that the lookup used for
+// slabs finds it as well.
+pub const LARGE_HEADER_SIZE: usize = 64;
+pub const LARGE_MAGIC: usize = 0x6f75_6d61_6c61_7267;
+pub const LARGE_FREED_MAGIC: usize = 0x6f75_6d61_6672_6565;
+
+// With guard pages an inaccessible page follows every large block, and one
+// sits between the header page and the block when the alignment allows it.
+#[cfg(feature = "malloc-guard-pages")]
+const GUARD_PAGES: usize = 1;
+#[cfg(not(feature = "malloc-guard-pages"))]
+const GUARD_PAGES: usize = 0;
+
+// Mappings currently held for large blocks and the most seen at once.
+static MAPPED_COUNT: AtomicUsize = AtomicUsize::new(0);
+static MAPPED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static MAX_MAPPED_COUNT: AtomicUsize = AtomicUsize::new(0);
+static MAX_MAPPED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+pub struct LargeStats {
+  pub count: usize,
+  pub bytes: usize,
+  pub max_count: usize,
+  pub max_bytes: usize
+}
+
+pub fn stats() -> LargeStats {
+  LargeStats {
+    count: MAPPED_COUNT.load(Ordering::Relaxed),
+    bytes: MAPPED_BYTES.load(Ordering::Relaxed),
+    max_count: MAX_MAPPED_COUNT.load(Ordering::Relaxed),
+    max_bytes: MAX_MAPPED_BYTES.load(Ordering::Relaxed)
+  }
+}
+
+fn account(
+  count: isize,
+  bytes: isize
+) {
+  let count = MAPPED_COUNT
+    .fetch_add(count as usize, Ordering::Relaxed)
+    .wrapping_add(count as usize);
+  let bytes = MAPPED_BYTES
+    .fetch_add(bytes as usize, Ordering::Relaxed)
+    .wrapping_add(bytes as usize);
+  MAX_MAPPED_COUNT.fetch_max(count, Ordering::Relaxed);
+  MAX_MAPPED_BYTES.fetch_max(bytes, Ordering::Relaxed);
+}
+
+#[repr(C)]
+pub struct LargeSpan {
+  pub magic: usize,
+  pub len: usize,
+  pub offset: usize,
+  pub size: usize
+}
+
+impl LargeSpan {
+  #[inline]
+  pub fn accessible_end(&self) -> usize {
+    ptr::from_ref(self) as usize + self.len - GUARD_PAGES * sys::page_size()
+  }
+
+  #[inline]
+  pub fn usable_size(
+    &self,
+    p: *mut u8
+  ) -> usize {
+    self.accessible_end() - p as usize
+  }
+}
+
+// Hardened builds keep the header on a page of its own, so the block can be
+// protected once it is freed.
+fn header_offset(
+  align: usize,
+  page: usize
+) -> usize {
+  let min = if cfg!(feature = "hardened-malloc") {
+    page * (1 + GUARD_PAGES)
+  } else {
+    LARGE_HEADER_SIZE
+  };
+  align.max(min).min(SLAB_SIZE)
+}
+
+pub fn allocate(
+  size: usize,
+  align: usize
+) -> *mut u8 {
+  let page = sys::page_size();
+  let guard = GUARD_PAGES * page;
+  let offset = header_offset(align, page);
+  let Some(len) = align
+    .max(SLAB_SIZE)
+    .checked_add(offset)
+    .and_then(|n| n.checked_add(size))
+    .and_then(|n| n.checked_add(page + guard))
+    .filter(|n| isize::try_from(*n).is_ok())
+  else {
+    return ptr::null_mut();
+  };
+  let len = sys::align_down(len, page);
+
+  let base = sys::map(len) as usize;
+  if base == 0 {
+    return ptr::null_mut();
+  }
+
+  // The span has to be SLAB_SIZE aligned and the user pointer right after
+  // it has to satisfy the requested alignment.
+  let span = if align > SLAB_SIZE {
+    sys::align_up(base + offset, align) - offset
+  } else {
+    sys::align_up(base, SLAB_SIZE)
+  };
+  let user = span + offset;
+  let end = sys::align_up(user + size, page) + guard;
+
+  sys::unmap(base as *mut u8, span - base);
+  sys::unmap(end as *mut u8, base + len - end);
+  if guard != 0 {
+    sys::protect((end - guard) as *mut u8, guard, false);
+    if offset >= 2 * page {
+      sys::protect((span + page) as *mut u8, offset - page, false);
+    }
+  }
+
+  let header = span as *mut LargeSpan;
+  unsafe {
+    (*header).magic = LARGE_MAGIC;
+    (*header).len = end - span;
+    (*header).offset = offset;
+    (*header).size = size;
+    #[cfg(feature = "hardened-malloc")]
+    super::hardened::arm_large(&*header, user);
+  }
+  account(1, (end - span) as isize);
+  user as *mut u8
+}
+
+pub fn deallocate(span: *mut LargeSpan) {
+  unsafe {
+    (*span).magic = 0;
+    account(-1, -((*span).len as isize));
+    sys::unmap(span.cast::<u8>(), (*span).len);
+  }
+}
+
+// Grow or shrink the mapping behind `p` without moving it.
+pub fn resize_in_place(
+  span: *mut LargeSpan,
+  p: *mut u8,
+  size: usize
+) -> bool {
+  let page = sys::page_size();
+  let Some(end) =
+    (p as usize).checked_add(size).and_then(|n| n.checked_add(page - 1))
+  else {
+    return false;
+  };
+  let end = sys::align_down(end, page);
+  unsafe {
+    let old_end = span as usize + (*span).len;
+    if end <= old_end {
+      sys::unmap(end as *mut u8, old_end - end);
+    } else if !sys::remap_in_place(
+      span.cast::<u8>(),
+      (*span).len,
+      end - span as usize
+    ) {
+      return false;
+    }
+    account(0, (end - span as usize) as isize - (*span).len as isize);
+    (*span).len = end - span as usize;
+    (*span).size = size;
+  }
+  true
+}
@@ -0,0 +1,259 @@
+mod arena;
+#[cfg(feature = "hardened-malloc")]
+mod hardened;
+mod large;
+mod slab;
+mod sys;
+#[cfg(not(feature = "hardened-malloc"))]
+mod tcache;
+
+use {
+  allocator::alloc::{GlobalAlloc, Layout},
+  core::{cmp, ptr}
+};
+pub use {
+  arena::ARENA_COUNT,
+  large::LargeStats,
+  slab::{HeapStats, MIN_ALIGN, SLAB_SIZE, own_brk},
+  sys::page_size
+};
+
+// Room reserved at the end of every small block for the hardened checks.
+#[cfg(feature = "hardened-malloc")]
+const REDZONE: usize = hardened::REDZONE;
+#[cfg(not(feature = "hardened-malloc"))]
+const REDZONE: usize = 0;
+
+enum Block {
+  Small(*mut slab::Slab),
+  Large(*mut large::LargeSpan)
+}
+
+// Every block has a header on the SLAB_SIZE boundary below it. Objects never
+// start at the boundary itself, so a pointer that sits on one belongs to the
+// span right before it.
+fn classify(p: *mut u8) -> Block {
+  let span = sys::align_down(p as usize - 1, slab::SLAB_SIZE);
+  match unsafe { *(span as *const usize) } {
+    | slab::SLAB_MAGIC => Block::Small(span as *mut slab::Slab),
+    | large::LARGE_MAGIC => Block::Large(span as *mut large::LargeSpan),
+    | large::LARGE_FREED_MAGIC => {
+      panic!("free(): double free detected for {p:?}")
+    },
+    | _ => panic!("free(): invalid pointer {p:?}")
+  }
+}
+
+pub fn allocate(
+  size: usize,
+  align: usize
+) -> *mut u8 {
+  let size = size.max(1);
+  let align = align.max(MIN_ALIGN);
+
+  // Over-aligned small blocks come from a larger size class, the pointer
+  // handed out is aligned inside the object and frees map it back to the
+  // object start.
+  if size <= slab::MAX_SMALL_SIZE && align <= sys::DEFAULT_PAGE_SIZE {
+    let padded = size + REDZONE + align - MIN_ALIGN;
+    if padded <= slab::MAX_SMALL_SIZE {
+      let class = slab::size_class(padded);
+      // Hardened builds skip the thread caches so that every free goes
+      // through the quarantine of the owning arena.
+      #[cfg(not(feature = "hardened-malloc"))]
+      let obj = tcache::alloc(class);
+      #[cfg(feature = "hardened-malloc")]
+      let obj = arena::lock_current().alloc(class);
+      if obj.is_null() {
+        return obj;
+      }
+      let p = sys::align_up(obj as usize, align);
+      #[cfg(feature = "hardened-malloc")]
+      hardened::arm_small(obj as usize, slab::CLASS_SIZES[class], p, size);
+      return p as *mut u8;
+    }
+  }
+
+  large::allocate(size, align)
+}
+
+pub fn allocate_zeroed(
+  size: usize,
+  align: usize
+) -> *mut u8 {
+  let p = allocate(size, align);
+  if !p.is_null() {
+    // Fresh mappings are already zeroed by the kernel.
+    if let Block::Small(_) = classify(p) {
+      unsafe { ptr::write_bytes(p, 0, size) };
+    }
+  }
+  p
+}
+
+pub fn deallocate(p: *mut u8) {
+  deallocate_checked(p, None);
+}
+
+pub fn deallocate_sized(
+  p: *mut u8,
+  size: usize
+) {
+  deallocate_checked(p, Some(size.max(1)));
+}
+
+#[cfg(not(feature = "hardened-malloc"))]
+fn deallocate_checked(
+  p: *mut u8,
+  _size: Option<usize>
+) {
+  if p.is_null() {
+    return;
+  }
+
+  match classify(p) {
+    | Block::Small(slab) => tcache::free(slab, p),
+    | Block::Large(span) => large::deallocate(span)
+  }
+}
+
+#[cfg(feature = "hardened-malloc")]
+fn deallocate_checked(
+  p: *mut u8,
+  size: Option<usize>
+) {
+  if p.is_null() {
+    return;
+  }
+
+  match classify(p) {
+    | Block::Small(slab) => {
+      let owner = unsafe { (*slab).arena as usize };
+      arena::lock(owner).quarantine(slab, p, size);
+    },
+    | Block::Large(span) => hardened::free_large(span, p as usize, size)
+  }
+}
+
+pub fn usable_size(p: *mut u8) -> usize {
+  if p.is_null() {
+    return 0;
+  }
+
+  match classify(p) {
+    | Block::Small(slab) => unsafe {
+      #[cfg(feature = "hardened-malloc")]
+      {
+        let obj = (*slab).object_start(p);
+        hardened::requested_size(obj, (*slab).object_size())
+      }
+      #[cfg(not(feature = "hardened-malloc"))]
+      (*slab).usable_size(p)
+    },
+    | Block::Large(span) => unsafe {
+      if cfg!(feature = "hardened-malloc") {
+        (*span).size
+      } else {
+        (*span).usable_size(p)
+      }
+    }
+  }
+}
+
+pub fn reallocate(
+  p: *mut u8,
+  size: usize,
+  align: usize
+) -> *mut u8 {
+  if p.is_null() {
+    return allocate(size, align);
+  }
+
+  // Hardened builds always move the block so that stale pointers to the old
+  // one end up in the quarantine.
+  let usable = usable_size(p);
+  match classify(p) {
+    | _ if cfg!(feature = "hardened-malloc") => {},
+    | Block::Small(_) => {
+      // Stay in place unless the block would be mostly wasted.
+      if size <= usable && size >= usable / 2 {
+        return p;
+      }
+    },
+    | Block::Large(span) => {
+      if size > slab::MAX_SMALL_SIZE && large::resize_in_place(span, p, size) {
+        return p;
+      }
+    }
+  }
+
+  let new = allocate(size, align);
+  if new.is_null() {
+    return new;
+  }
+  unsafe { ptr::copy_nonoverlapping(p, new, cmp::min(size, usable)) };
+  deallocate(p);
+  new
+}
+
+pub fn arena_stats(index: usize) -> HeapStats {
+  arena::lock(index).stats()
+}
+
+pub fn large_stats() -> LargeStats {
+  large::stats()
+}
+
+// Return cached and empty slab memory to the kernel. Only the cache of the
+// calling thread can be flushed.
+pub fn trim() -> bool {
+  #[cfg(not(feature = "hardened-malloc"))]
+  tcache::flush_all();
+  let mut released = false;
+  for index in 0..ARENA_COUNT {
+    released |= arena::lock(index).trim();
+  }
+  released
+}
+
+pub struct Malloc;
+
+#[global_allocator]
+static ALLOCATOR: Malloc = Malloc;
+
+unsafe impl GlobalAlloc for Malloc {
+  #[inline]
+  unsafe fn alloc(
+    &self,
+    layout: Layout
+  ) -> *mut u8 {
+    allocate(layout.size(), layout.align())
+  }
+
+  #[inline]
+  unsafe fn alloc_zeroed(
+    &self,
+    layout: Layout
+  ) -> *mut u8 {
+    allocate_zeroed(layout.size(), layout.align())
+  }
+
+  #[inline]
+  unsafe fn dealloc(
+    &self,
+    ptr: *mut u8,
+    _layout: Layout
+  ) {
+    deallocate(ptr);
+  }
+
+  #[inline]
+  unsafe fn realloc(
+    &self,
+    ptr: *mut u8,
+    layout: Layout,
+    new_size: usize
+  ) -> *mut u8 {
+    reallocate(ptr, new_size, layout.align())
+  }
+}
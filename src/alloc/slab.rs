@@ -0,0 +1,459 @@
+use {
+  super::sys,
+  crate::support::sync::Mutex,
+  core::{
+    mem,
+    ptr,
+    sync::atomic::{AtomicBool, Ordering}
+  }
+};
+
+#[cfg(feature = "hardened-malloc")]
+use super::hardened;
+
+// Small objects are carved out of slabs: naturally aligned 64 KiB blocks
+// holding objects of a single size class, with the slab header at the start.
+pub const SLAB_SIZE: usize = 64 * 1024;
+#[cfg(not(feature = "hardened-malloc"))]
+pub const SLAB_HEADER_SIZE: usize = 64;
+#[cfg(feature = "hardened-malloc")]
+pub const SLAB_HEADER_SIZE: usize = 576;
+pub const SLAB_MAGIC: usize = 0x6f75_6d61_736c_6162;
+
+pub const MIN_ALIGN: usize = 16;
+pub const MAX_SMALL_SIZE: usize = 16384;
+pub const NUM_CLASSES: usize = 36;
+
+pub const CLASS_SIZES: [usize; NUM_CLASSES] = [
+  16, 32, 48, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 448, 512,
+  640, 768, 896, 1024, 1280, 1536, 1792, 2048, 2560, 3072, 3584, 4096, 5120,
+  6144, 7168, 8192, 10240, 12288, 14336, 16384
+];
+
+// Number of empty slabs kept around before their memory is returned to the
+// kernel.
+const EMPTY_SLAB_CACHE: usize = 4;
+
+const _: () = assert!(mem::size_of::<Slab>() <= SLAB_HEADER_SIZE);
+
+// The program break is shared by all arenas, and only grown in processes the
+// library started: in any other the malloc of the C library that did moves it
+// as well.
+static OWN_BRK: AtomicBool = AtomicBool::new(false);
+static BRK_END: Mutex<usize> = Mutex::new(0);
+
+// Let slabs come from the program break, before anything is allocated.
+pub fn own_brk() {
+  OWN_BRK.store(true, Ordering::Relaxed);
+}
+
+// Classes are spaced by 16 bytes up to 128 and then by four steps per power
+// of two.
+#[inline]
+pub fn size_class(size: usize) -> usize {
+  if size <= 128 {
+    return size.max(1).div_ceil(MIN_ALIGN) - 1;
+  }
+  let lg = (usize::BITS - 1 - (size - 1).leading_zeros()) as usize;
+  let step = ((size - (1 << lg) - 1) >> (lg - 2)) + 1;
+  8 + (lg - 7) * 4 + step - 1
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+#[repr(u8)]
+pub enum Source {
+  Brk,
+  Mmap
+}
+
+pub struct FreeObject {
+  pub next: *mut FreeObject
+}
+
+#[repr(C)]
+pub struct Slab {
+  pub magic: usize,
+  pub class: u32,
+  pub used: u32,
+  pub capacity: u32,
+  bump: u32,
+  free: *mut FreeObject,
+  next: *mut Slab,
+  prev: *mut Slab,
+  source: Source,
+  committed: bool,
+  // Index of the arena the slab belongs to.
+  pub arena: u8,
+  // One bit per object, set while the object is handed out.
+  #[cfg(feature = "hardened-malloc")]
+  allocated: [u64; (SLAB_SIZE / MIN_ALIGN).div_ceil(64)]
+}
+
+impl Slab {
+  #[inline]
+  pub fn object_size(&self) -> usize {
+    CLASS_SIZES[self.class as usize]
+  }
+
+  // Map any pointer inside an object back to the start of that object.
+  #[inline]
+  pub fn object_start(
+    &self,
+    p: *mut u8
+  ) -> usize {
+    let base = ptr::from_ref(self) as usize + SLAB_HEADER_SIZE;
+    let size = self.object_size();
+    let index = (p as usize - base) / size;
+    base + index * size
+  }
+
+  #[cfg(feature = "hardened-malloc")]
+  #[inline]
+  fn object_index(
+    &self,
+    obj: usize
+  ) -> usize {
+    (obj - ptr::from_ref(self) as usize - SLAB_HEADER_SIZE) / self.object_size()
+  }
+
+  #[cfg(not(feature = "hardened-malloc"))]
+  #[inline]
+  pub fn usable_size(
+    &self,
+    p: *mut u8
+  ) -> usize {
+    self.object_start(p) + self.object_size() - p as usize
+  }
+}
+
+// Counters reported by mallinfo2 and malloc_stats. Objects sitting in the
+// thread caches count as in use.
+#[derive(Clone, Copy)]
+pub struct HeapStats {
+  pub slabs: usize,
+  pub empty: usize,
+  pub empty_committed: usize,
+  pub in_use: usize
+}
+
+impl HeapStats {
+  // Bytes of slab memory currently backed by the kernel.
+  pub fn committed(&self) -> usize {
+    let decommitted = self.empty - self.empty_committed;
+    (self.slabs - decommitted) * SLAB_SIZE + decommitted * sys::page_size()
+  }
+}
+
+pub struct Heap {
+  index: u8,
+  partial: [*mut Slab; NUM_CLASSES],
+  empty: *mut Slab,
+  stats: HeapStats,
+  #[cfg(feature = "hardened-malloc")]
+  quarantine: hardened::Quarantine
+}
+
+unsafe impl Send for Heap {}
+
+impl Heap {
+  pub const fn new(index: u8) -> Self {
+    Self {
+      index,
+      partial: [ptr::null_mut(); NUM_CLASSES],
+      empty: ptr::null_mut(),
+      stats: HeapStats { slabs: 0, empty: 0, empty_committed: 0, in_use: 0 },
+      #[cfg(feature = "hardened-malloc")]
+      quarantine: hardened::Quarantine::new()
+    }
+  }
+
+  pub fn alloc(
+    &mut self,
+    class: usize
+  ) -> *mut u8 {
+    let mut slab = self.partial[class];
+    if slab.is_null() {
+      slab = self.take_slab();
+      if slab.is_null() {
+        return ptr::null_mut();
+      }
+      unsafe { self.format_slab(slab, class) };
+      self.push_partial(class, slab);
+    }
+
+    let s = unsafe { &mut *slab };
+    let obj = if !s.free.is_null() {
+      let obj = s.free;
+      s.free = unsafe { (*obj).next };
+      obj.cast::<u8>()
+    } else {
+      let obj = (slab as usize + SLAB_HEADER_SIZE + s.bump as usize) as *mut u8;
+      s.bump += s.object_size() as u32;
+      obj
+    };
+
+    #[cfg(feature = "hardened-malloc")]
+    {
+      let index = s.object_index(obj as usize);
+      s.allocated[index / 64] |= 1 << (index % 64);
+    }
+
+    s.used += 1;
+    self.stats.in_use += s.object_size();
+    if s.used == s.capacity {
+      self.remove_partial(class, slab);
+    }
+    obj
+  }
+
+  pub fn free(
+    &mut self,
+    slab: *mut Slab,
+    p: *mut u8
+  ) {
+    let s = unsafe { &mut *slab };
+    let class = s.class as usize;
+    let obj = s.object_start(p) as *mut FreeObject;
+    unsafe { (*obj).next = s.free };
+    s.free = obj;
+
+    if s.used == s.capacity {
+      self.push_partial(class, slab);
+    }
+    s.used -= 1;
+    self.stats.in_use -= s.object_size();
+    if s.used == 0 {
+      self.remove_partial(class, slab);
+      self.push_empty(slab);
+    }
+  }
+
+  // Check a block that is being freed and park it in the quarantine. Only
+  // the block that drops out of the quarantine goes back to its slab.
+  #[cfg(feature = "hardened-malloc")]
+  pub fn quarantine(
+    &mut self,
+    slab: *mut Slab,
+    p: *mut u8,
+    size: Option<usize>
+  ) {
+    let s = unsafe { &mut *slab };
+    let obj = s.object_start(p);
+    let index = s.object_index(obj);
+    assert!(
+      s.allocated[index / 64] & (1 << (index % 64)) != 0,
+      "free(): double free detected for {p:?}"
+    );
+    hardened::check_small(obj, s.object_size(), p as usize, size);
+    s.allocated[index / 64] &= !(1 << (index % 64));
+    hardened::poison(obj, s.object_size());
+
+    if let Some((slab, obj)) = self.quarantine.push(slab, obj) {
+      let len = unsafe { (*slab).object_size() };
+      assert!(
+        hardened::poison_intact(obj, len),
+        "malloc: use after free detected at {obj:#x}"
+      );
+      self.free(slab, obj as *mut u8);
+    }
+  }
+
+  pub fn stats(&self) -> HeapStats {
+    self.stats
+  }
+
+  // Give the memory of every empty slab back to the kernel. Returns whether
+  // anything was released.
+  pub fn trim(&mut self) -> bool {
+    let mut slab = self.empty;
+    let mut released = false;
+    self.empty = ptr::null_mut();
+    self.stats.empty = 0;
+    self.stats.empty_committed = 0;
+    while !slab.is_null() {
+      let next = unsafe { (*slab).next };
+      if unsafe { !(*slab).committed } {
+        self.link_empty(slab);
+      } else if self.release(slab) {
+        released |= unsafe { !(*slab).committed };
+        self.link_empty(slab);
+      } else {
+        released = true;
+      }
+      slab = next;
+    }
+    released
+  }
+
+  unsafe fn format_slab(
+    &self,
+    slab: *mut Slab,
+    class: usize
+  ) {
+    let s = &mut *slab;
+    s.magic = SLAB_MAGIC;
+    s.arena = self.index;
+    s.class = class as u32;
+    s.used = 0;
+    s.capacity = ((SLAB_SIZE - SLAB_HEADER_SIZE) / CLASS_SIZES[class]) as u32;
+    s.bump = 0;
+    s.free = ptr::null_mut();
+    s.next = ptr::null_mut();
+    s.prev = ptr::null_mut();
+    #[cfg(feature = "hardened-malloc")]
+    {
+      s.allocated = [0; (SLAB_SIZE / MIN_ALIGN).div_ceil(64)];
+    }
+  }
+
+  fn push_partial(
+    &mut self,
+    class: usize,
+    slab: *mut Slab
+  ) {
+    let head = self.partial[class];
+    unsafe {
+      (*slab).prev = ptr::null_mut();
+      (*slab).next = head;
+      if !head.is_null() {
+        (*head).prev = slab;
+      }
+    }
+    self.partial[class] = slab;
+  }
+
+  fn remove_partial(
+    &mut self,
+    class: usize,
+    slab: *mut Slab
+  ) {
+    unsafe {
+      let (prev, next) = ((*slab).prev, (*slab).next);
+      if prev.is_null() {
+        self.partial[class] = next;
+      } else {
+        (*prev).next = next;
+      }
+      if !next.is_null() {
+        (*next).prev = prev;
+      }
+      (*slab).next = ptr::null_mut();
+      (*slab).prev = ptr::null_mut();
+    }
+  }
+
+  fn push_empty(
+    &mut self,
+    slab: *mut Slab
+  ) {
+    unsafe {
+      (*slab).magic = 0;
+      (*slab).used = 0;
+    }
+    if self.stats.empty_committed >= EMPTY_SLAB_CACHE && !self.release(slab) {
+      return;
+    }
+    self.link_empty(slab);
+  }
+
+  fn link_empty(
+    &mut self,
+    slab: *mut Slab
+  ) {
+    unsafe {
+      (*slab).next = self.empty;
+      if (*slab).committed {
+        self.stats.empty_committed += 1;
+      }
+    }
+    self.stats.empty += 1;
+    self.empty = slab;
+  }
+
+  // Return the memory of an empty slab to the kernel. Slabs from the program
+  // break keep their first page so they can stay on the empty list; returns
+  // false when the slab was unmapped entirely.
+  fn release(
+    &mut self,
+    slab: *mut Slab
+  ) -> bool {
+    unsafe {
+      if (*slab).source == Source::Mmap {
+        sys::unmap(slab.cast::<u8>(), SLAB_SIZE);
+        self.stats.slabs -= 1;
+        return false;
+      }
+      let page = sys::page_size();
+      if page < SLAB_SIZE {
+        sys::decommit(slab.cast::<u8>().wrapping_add(page), SLAB_SIZE - page);
+        (*slab).committed = false;
+      }
+    }
+    true
+  }
+
+  fn take_slab(&mut self) -> *mut Slab {
+    if !self.empty.is_null() {
+      let slab = self.empty;
+      unsafe {
+        self.empty = (*slab).next;
+        if (*slab).committed {
+          self.stats.empty_committed -= 1;
+        }
+        self.stats.empty -= 1;
+        (*slab).committed = true;
+      }
+      return slab;
+    }
+
+    let (base, source) = match Self::grow_brk() {
+      | Some(base) => (base, Source::Brk),
+      | None => match Self::map_aligned() {
+        | Some(base) => (base, Source::Mmap),
+        | None => return ptr::null_mut()
+      }
+    };
+    let slab = base as *mut Slab;
+    unsafe {
+      (*slab).source = source;
+      (*slab).committed = true;
+    }
+    self.stats.slabs += 1;
+    slab
+  }
+
+  fn grow_brk() -> Option<usize> {
+    if !OWN_BRK.load(Ordering::Relaxed) {
+      return None;
+    }
+    let mut brk_end = BRK_END.lock();
+    // Once something else has moved the break, slabs come from mmap instead:
+    // growing it from a stale end would cut off memory handed out since.
+    let current = sys::brk(0);
+    if current == 0 || (*brk_end != 0 && current != *brk_end) {
+      return None;
+    }
+    *brk_end = current;
+    let start = sys::align_up(*brk_end, SLAB_SIZE);
+    let end = start.checked_add(SLAB_SIZE)?;
+    if sys::brk(end) != end {
+      return None;
+    }
+    *brk_end = end;
+    Some(start)
+  }
+
+  fn map_aligned() -> Option<usize> {
+    let base = sys::map(2 * SLAB_SIZE) as usize;
+    if base == 0 {
+      return None;
+    }
+    let start = sys::align_up(base, SLAB_SIZE);
+    sys::unmap(base as *mut u8, start - base);
+    sys::unmap(
+      (start + SLAB_SIZE) as *mut u8,
+      base + 2 * SLAB_SIZE - start - SLAB_SIZE
+    );
+    Some(start)
+  }
+}
@@ -0,0 +1,94 @@
+use {
+  crate::start,
+  core::ptr,
+  syscalls::{Sysno, syscall}
+};
+
+const PROT_READ: usize = 0x1;
+const PROT_WRITE: usize = 0x2;
+const MAP_PRIVATE: usize = 0x02;
+const MAP_ANONYMOUS: usize = 0x20;
+const MADV_DONTNEED: usize = 4;
+
+pub const DEFAULT_PAGE_SIZE: usize = 4096;
+
+#[inline]
+pub fn page_size() -> usize {
+  match start::get_auxv(start::AT_PAGESZ) {
+    | 0 => DEFAULT_PAGE_SIZE,
+    | size => size
+  }
+}
+
+#[inline]
+pub const fn align_up(
+  value: usize,
+  align: usize
+) -> usize {
+  (value + align - 1) & !(align - 1)
+}
+
+#[inline]
+pub const fn align_down(
+  value: usize,
+  align: usize
+) -> usize {
+  value & !(align - 1)
+}
+
+pub fn map(len: usize) -> *mut u8 {
+  match unsafe {
+    syscall!(
+      Sysno::mmap,
+      0,
+      len,
+      PROT_READ | PROT_WRITE,
+      MAP_PRIVATE | MAP_ANONYMOUS,
+      -1isize,
+      0
+    )
+  } {
+    | Ok(addr) => addr as *mut u8,
+    | Err(_) => ptr::null_mut()
+  }
+}
+
+pub fn unmap(
+  addr: *mut u8,
+  len: usize
+) {
+  if len != 0 {
+    let _ = unsafe { syscall!(Sysno::munmap, addr, len) };
+  }
+}
+
+pub fn protect(
+  addr: *mut u8,
+  len: usize,
+  accessible: bool
+) {
+  let prot = if accessible { PROT_READ | PROT_WRITE } else { 0 };
+  let _ = unsafe { syscall!(Sysno::mprotect, addr, len, prot) };
+}
+
+// Give the pages back to the kernel while keeping the address range mapped.
+pub fn decommit(
+  addr: *mut u8,
+  len: usize
+) {
+  let _ = unsafe { syscall!(Sysno::madvise, addr, len, MADV_DONTNEED) };
+}
+
+// Resize a mapping without moving it.
+pub fn remap_in_place(
+  addr: *mut u8,
+  old_len: usize,
+  new_len: usize
+) -> bool {
+  unsafe { syscall!(Sysno::mremap, addr, old_len, new_len, 0) }.is_ok()
+}
+
+// Returns the new program break, which is the old one if the call failed.
+pub fn brk(addr: usize) -> usize {
+  unsafe { syscall!(Sysno::brk, addr) }.unwrap_or(0)
+}
@@ -0,0 +1,128 @@
+use {
+  super::{
+    arena,
+    slab::{self, FreeObject, Slab},
+    sys
+  },
+  core::ptr
+};
+
+// Each thread keeps a few free objects of the smaller size classes on a list
+// of its own, so most allocations and frees do not touch an arena lock at
+// all. Objects move between the cache and the arenas in batches.
+const CACHED_CLASSES: usize = 28;
+const CACHE_LIMIT: u32 = 64;
+const REFILL_BATCH: u32 = 16;
+const FLUSH_BATCH: u32 = CACHE_LIMIT / 2;
+
+const _: () = assert!(slab::CLASS_SIZES[CACHED_CLASSES - 1] == 4096);
+
+#[derive(Clone, Copy)]
+struct Bin {
+  head: *mut FreeObject,
+  count: u32
+}
+
+impl Bin {
+  #[inline]
+  fn push(
+    &mut self,
+    obj: usize
+  ) {
+    let obj = obj as *mut FreeObject;
+    unsafe { (*obj).next = self.head };
+    self.head = obj;
+    self.count += 1;
+  }
+
+  #[inline]
+  fn pop(&mut self) -> *mut u8 {
+    let obj = self.head;
+    self.head = unsafe { (*obj).next };
+    self.count -= 1;
+    obj.cast::<u8>()
+  }
+}
+
+#[thread_local]
+static mut CACHE: [Bin; CACHED_CLASSES] =
+  [Bin { head: ptr::null_mut(), count: 0 }; CACHED_CLASSES];
+
+#[inline]
+fn bin(class: usize) -> &'static mut Bin {
+  unsafe { &mut (*ptr::addr_of_mut!(CACHE))[class] }
+}
+
+pub fn alloc(class: usize) -> *mut u8 {
+  if class >= CACHED_CLASSES {
+    return arena::lock_current().alloc(class);
+  }
+
+  let bin = bin(class);
+  if bin.count == 0 {
+    let mut heap = arena::lock_current();
+    for _ in 0..REFILL_BATCH {
+      let obj = heap.alloc(class);
+      if obj.is_null() {
+        break;
+      }
+      bin.push(obj as usize);
+    }
+    if bin.count == 0 {
+      return ptr::null_mut();
+    }
+  }
+  bin.pop()
+}
+
+// Objects owned by another arena go straight back to it, everything else
+// stays in the cache of the calling thread.
+pub fn free(
+  slab: *mut Slab,
+  p: *mut u8
+) {
+  let s = unsafe { &*slab };
+  let class = s.class as usize;
+  let owner = s.arena as usize;
+  if class >= CACHED_CLASSES || owner != arena::current() {
+    arena::lock(owner).free(slab, p);
+    return;
+  }
+
+  let bin = bin(class);
+  if bin.count == CACHE_LIMIT {
+    flush(bin, FLUSH_BATCH);
+  }
+  bin.push(s.object_start(p));
+}
+
+pub fn flush_all() {
+  for class in 0..CACHED_CLASSES {
+    let bin = bin(class);
+    flush(bin, bin.count);
+  }
+}
+
+// Hand `count` objects back to the arenas owning them. The thread may have
+// moved to another arena since the objects were cached, so the owner is
+// looked up for every object.
+fn flush(
+  bin: &mut Bin,
+  count: u32
+) {
+  let mut held = None;
+  for _ in 0..count.min(bin.count) {
+    let obj = bin.pop();
+    let slab = sys::align_down(obj as usize, slab::SLAB_SIZE) as *mut Slab;
+    let owner = unsafe { (*slab).arena as usize };
+    let heap = match held {
+      | Some((index, ref mut heap)) if index == owner => heap,
+      | _ => {
+        // Never hold two arena locks at once.
+        held = None;
+        &mut held.insert((owner, arena::lock(owner))).1
+      }
+    };
+    heap.free(slab, obj);
+  }
+}
@@ -1,10 +1,10 @@
 use {
   crate::c_int,
   core::{fmt, fmt::Write, panic::PanicInfo},
-  syscalls::{syscall, Sysno}
+  syscalls::{Sysno, syscall}
 };
 
-struct IoWrite(pub c_int);
+pub struct IoWrite(pub c_int);
 
 impl IoWrite {
   fn write(
@@ -0,0 +1,47 @@
+use core::{
+  arch::{asm, global_asm},
+  ffi::VaList
+};
+
+pub const FE_TONEAREST: i32 = 0;
+pub const FE_UPWARD: i32 = 0x400000;
+pub const FE_DOWNWARD: i32 = 0x800000;
+pub const FE_TOWARDZERO: i32 = 0xc00000;
+
+// long double is IEEE binary128.
+pub const LDBL_MANT_DIG: u32 = 113;
+
+pub fn get_rounding() -> i32 {
+  let fpcr: u64;
+  unsafe { asm!("mrs {}, fpcr", out(reg) fpcr, options(nomem, nostack)) };
+  (fpcr & 0xc00000) as i32
+}
+
+#[repr(C)]
+struct VaListTag {
+  stack: usize,
+  gr_top: usize,
+  vr_top: usize,
+  gr_offs: i32,
+  vr_offs: i32
+}
+
+// long double arguments use the SIMD registers while some are left and the
+// stack afterwards, 16-byte aligned in both cases.
+pub fn va_arg_long_double(ap: &mut VaList) -> u128 {
+  let tag = core::ptr::from_mut(ap).cast::<VaListTag>();
+  unsafe {
+    let offs = (*tag).vr_offs;
+    let addr = if offs <= -16 {
+      (*tag).vr_offs = offs + 16;
+      (*tag).vr_top.wrapping_add_signed(offs as isize)
+    } else {
+      let addr = ((*tag).stack + 15) & !15;
+      (*tag).stack = addr + 16;
+      addr
+    };
+    (addr as *const u128).read_unaligned()
+  }
+}
+
+global_asm!(include_str!("long_double.s"));
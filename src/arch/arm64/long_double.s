@@ -0,0 +1,21 @@
+// long double is returned in q0. Each entry point calls its Rust half with
+// the same arguments and moves the 128 bits it returns in x1:x0.
+.macro long_double name, impl
+.text
+.align 2
+.globl \name
+.type \name,%function
+\name:
+	stp	x29, x30, [sp, #-16]!
+	mov	x29, sp
+	bl	\impl
+	fmov	d0, x0
+	mov	v0.d[1], x1
+	ldp	x29, x30, [sp], #16
+	ret
+.size \name, .-\name
+.endm
+
+long_double ouma_strtold, __oumalibc_strtold
+long_double ouma_strtold_l, __oumalibc_strtold_l
+long_double ouma_wcstold, __oumalibc_wcstold
@@ -1 +1,3 @@
+pub mod float;
+pub mod thread;
 pub mod types;
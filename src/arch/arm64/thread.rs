@@ -0,0 +1,18 @@
+use core::{arch::asm, ffi::c_void};
+
+// AArch64 uses TLS variant I: the thread pointer addresses a 16 byte thread
+// control block and the static TLS block follows it.
+pub const TLS_ABOVE_TP: bool = true;
+
+#[inline]
+pub fn get_thread_pointer() -> *mut c_void {
+  let tp: *mut c_void;
+  unsafe { asm!("mrs {}, tpidr_el0", out(reg) tp, options(nomem, nostack)) };
+  tp
+}
+
+#[inline]
+pub fn set_thread_pointer(tp: *mut c_void) -> bool {
+  unsafe { asm!("msr tpidr_el0, {}", in(reg) tp, options(nostack)) };
+  true
+}
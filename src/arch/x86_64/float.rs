@@ -0,0 +1,44 @@
+use core::{
+  arch::{asm, global_asm},
+  ffi::VaList
+};
+
+pub const FE_TONEAREST: i32 = 0;
+pub const FE_DOWNWARD: i32 = 0x400;
+pub const FE_UPWARD: i32 = 0x800;
+pub const FE_TOWARDZERO: i32 = 0xc00;
+
+// long double is the x87 80-bit extended format with an explicit integer
+// bit.
+pub const LDBL_MANT_DIG: u32 = 64;
+
+// Rounding mode of the SSE unit, which fesetround keeps in sync with the
+// x87 one.
+pub fn get_rounding() -> i32 {
+  let mut csr: u32 = 0;
+  unsafe {
+    asm!("stmxcsr [{}]", in(reg) core::ptr::addr_of_mut!(csr), options(nostack));
+  }
+  ((csr >> 3) & 0xc00) as i32
+}
+
+#[repr(C)]
+struct VaListTag {
+  gp_offset: u32,
+  fp_offset: u32,
+  overflow_arg_area: usize,
+  reg_save_area: usize
+}
+
+// long double arguments are always passed in memory, 16-byte aligned in the
+// overflow area.
+pub fn va_arg_long_double(ap: &mut VaList) -> u128 {
+  let tag = core::ptr::from_mut(ap).cast::<VaListTag>();
+  unsafe {
+    let addr = ((*tag).overflow_arg_area + 15) & !15;
+    (*tag).overflow_arg_area = addr + 16;
+    (addr as *const u128).read() & ((1 << 80) - 1)
+  }
+}
+
+global_asm!(include_str!("long_double.s"));
@@ -0,0 +1,21 @@
+// long double is returned in st(0). Each entry point calls its Rust half
+// with the same arguments and loads the 80 bits it returns in rdx:rax.
+.macro long_double name, impl
+.text
+.align 16
+.globl \name
+.type \name,@function
+\name:
+	sub	rsp, 24
+	call	\impl
+	mov	[rsp], rax
+	mov	[rsp + 8], rdx
+	fld	tbyte ptr [rsp]
+	add	rsp, 24
+	ret
+.size \name, .-\name
+.endm
+
+long_double ouma_strtold, __oumalibc_strtold
+long_double ouma_strtold_l, __oumalibc_strtold_l
+long_double ouma_wcstold, __oumalibc_wcstold
@@ -0,0 +1,25 @@
+use {
+  core::ffi::c_void,
+  syscalls::{Sysno, syscall}
+};
+
+const ARCH_SET_FS: usize = 0x1002;
+const ARCH_GET_FS: usize = 0x1003;
+
+// x86_64 uses TLS variant II: the static TLS block lives right below the
+// thread pointer and the thread control block starts at it.
+pub const TLS_ABOVE_TP: bool = false;
+
+#[inline]
+pub fn get_thread_pointer() -> *mut c_void {
+  let mut tp: usize = 0;
+  let _ = unsafe {
+    syscall!(Sysno::arch_prctl, ARCH_GET_FS, core::ptr::addr_of_mut!(tp))
+  };
+  tp as *mut c_void
+}
+
+#[inline]
+pub fn set_thread_pointer(tp: *mut c_void) -> bool {
+  unsafe { syscall!(Sysno::arch_prctl, ARCH_SET_FS, tp) }.is_ok()
+}
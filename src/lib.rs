@@ -37,9 +37,11 @@
   clippy::missing_trait_methods,
 
   // Documentation related
-  clippy::missing_panics_doc
+  clippy::missing_errors_doc,
+  clippy::missing_panics_doc,
+  clippy::missing_safety_doc
 )]
-#![feature(thread_local)]
+#![feature(c_variadic, thread_local)]
 
 extern crate alloc as allocator;
 extern crate cbitset;
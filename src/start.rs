@@ -1,6 +1,230 @@
-use core::ffi::c_void;
+use {
+  crate::{
+    alloc,
+    arch::thread,
+    c_char,
+    c_int,
+    std::{errno, stdlib}
+  },
+  core::{ffi::c_void, mem, ptr},
+  syscalls::{Sysno, syscall}
+};
+
+pub const AT_NULL: usize = 0;
+pub const AT_PHDR: usize = 3;
+pub const AT_PHENT: usize = 4;
+pub const AT_PHNUM: usize = 5;
+pub const AT_PAGESZ: usize = 6;
+pub const AT_UID: usize = 11;
+pub const AT_EUID: usize = 12;
+pub const AT_GID: usize = 13;
+pub const AT_EGID: usize = 14;
+pub const AT_SECURE: usize = 23;
+pub const AT_RANDOM: usize = 25;
+
+const AUXV_MAX: usize = 64;
+
+const PT_PHDR: u32 = 6;
+const PT_TLS: u32 = 7;
+
+#[repr(C)]
+struct ProgramHeader {
+  kind: u32,
+  flags: u32,
+  offset: usize,
+  vaddr: usize,
+  paddr: usize,
+  filesz: usize,
+  memsz: usize,
+  align: usize
+}
+
+#[repr(C)]
+pub struct ThreadControlBlock {
+  pub this: *mut ThreadControlBlock,
+  pub dtv: *mut usize
+}
+
+static mut AUXV: [usize; AUXV_MAX] = [0; AUXV_MAX];
+
+extern "C" {
+  static __preinit_array_start: [Option<extern "C" fn()>; 0];
+  static __preinit_array_end: [Option<extern "C" fn()>; 0];
+  static __init_array_start: [Option<extern "C" fn()>; 0];
+  static __init_array_end: [Option<extern "C" fn()>; 0];
+  static __fini_array_start: [Option<extern "C" fn()>; 0];
+  static __fini_array_end: [Option<extern "C" fn()>; 0];
+
+  fn _init();
+  fn _fini();
+  fn main(
+    argc: c_int,
+    argv: *mut *mut c_char,
+    envp: *mut *mut c_char
+  ) -> c_int;
+}
+
+#[inline]
+pub fn get_auxv(kind: usize) -> usize {
+  if kind < AUXV_MAX { unsafe { AUXV[kind] } } else { 0 }
+}
+
+#[inline]
+const fn align_up(
+  value: usize,
+  align: usize
+) -> usize {
+  (value + align - 1) & !(align - 1)
+}
+
+fn find_tls_segment() -> Option<&'static ProgramHeader> {
+  let phdr = get_auxv(AT_PHDR) as *const ProgramHeader;
+  let phnum = get_auxv(AT_PHNUM);
+  if phdr.is_null() || get_auxv(AT_PHENT) != mem::size_of::<ProgramHeader>() {
+    return None;
+  }
+
+  let headers = unsafe { core::slice::from_raw_parts(phdr, phnum) };
+  headers.iter().find(|ph| ph.kind == PT_TLS)
+}
+
+fn load_base() -> usize {
+  let phdr = get_auxv(AT_PHDR) as *const ProgramHeader;
+  let phnum = get_auxv(AT_PHNUM);
+  if phdr.is_null() {
+    return 0;
+  }
+
+  let headers = unsafe { core::slice::from_raw_parts(phdr, phnum) };
+  headers
+    .iter()
+    .find(|ph| ph.kind == PT_PHDR)
+    .map_or(0, |ph| (phdr as usize).wrapping_sub(ph.vaddr))
+}
+
+// Set up static TLS for the main thread: map a block large enough for the
+// PT_TLS image and the thread control block, copy the image in and point
+// the thread register at the control block.
+fn init_tls() {
+  if !thread::get_thread_pointer().is_null() {
+    return;
+  }
+
+  let (image, filesz, memsz, align) = match find_tls_segment() {
+    | Some(ph) => (
+      load_base().wrapping_add(ph.vaddr) as *const u8,
+      ph.filesz,
+      ph.memsz,
+      ph.align.max(mem::align_of::<ThreadControlBlock>())
+    ),
+    | None => (ptr::null(), 0, 0, mem::align_of::<ThreadControlBlock>())
+  };
+
+  let tcb_size = mem::size_of::<ThreadControlBlock>();
+  let offset = if thread::TLS_ABOVE_TP {
+    align_up(tcb_size, align)
+  } else {
+    align_up(memsz, align)
+  };
+  let size = align + offset + memsz + tcb_size;
+
+  let Ok(base) =
+    (unsafe { syscall!(Sysno::mmap, 0, size, 0x3, 0x22, -1isize, 0) })
+  else {
+    panic!("Cannot map memory for thread local storage");
+  };
+
+  let (tp, block) = if thread::TLS_ABOVE_TP {
+    let tp = align_up(base, align);
+    (tp, tp + offset)
+  } else {
+    let tp = align_up(base + offset, align);
+    (tp, tp - offset)
+  };
+
+  unsafe {
+    if filesz != 0 {
+      ptr::copy_nonoverlapping(image, block as *mut u8, filesz);
+    }
+    let tcb = tp as *mut ThreadControlBlock;
+    (*tcb).this = tcb;
+    (*tcb).dtv = ptr::null_mut();
+  }
+
+  assert!(
+    thread::set_thread_pointer(tp as *mut c_void),
+    "Cannot set thread pointer"
+  );
+}
+
+unsafe fn run_array(
+  start: *const Option<extern "C" fn()>,
+  end: *const Option<extern "C" fn()>
+) {
+  let mut f = start;
+  while f < end {
+    if let Some(func) = *f {
+      func();
+    }
+    f = f.wrapping_add(1);
+  }
+}
+
+unsafe fn run_array_reverse(
+  start: *const Option<extern "C" fn()>,
+  end: *const Option<extern "C" fn()>
+) {
+  let mut f = end;
+  while f > start {
+    f = f.wrapping_sub(1);
+    if let Some(func) = *f {
+      func();
+    }
+  }
+}
+
+pub fn run_fini() {
+  unsafe {
+    run_array_reverse(__fini_array_start.as_ptr(), __fini_array_end.as_ptr());
+    _fini();
+  }
+}
 
 #[no_mangle]
-extern "C" fn __oumalibc_start(raw_args: *mut c_void) {
-  panic!("We are in libc! Arguments: {raw_args:?}");
+extern "C" fn __oumalibc_start(raw_args: *mut c_void) -> ! {
+  let sp = raw_args.cast::<usize>();
+  let argc = unsafe { *sp };
+  let argv = sp.wrapping_add(1).cast::<*mut c_char>();
+  let envp = argv.wrapping_add(argc + 1);
+
+  let mut env = envp;
+  unsafe {
+    while !(*env).is_null() {
+      env = env.wrapping_add(1);
+    }
+  }
+
+  let mut auxv = env.wrapping_add(1).cast::<usize>();
+  unsafe {
+    while *auxv != AT_NULL {
+      let kind = *auxv;
+      if kind < AUXV_MAX {
+        AUXV[kind] = *auxv.wrapping_add(1);
+      }
+      auxv = auxv.wrapping_add(2);
+    }
+  }
+
+  alloc::own_brk();
+  init_tls();
+  errno::set_errno(0);
+  unsafe { stdlib::ouma_environ = envp };
+
+  let status = unsafe {
+    run_array(__preinit_array_start.as_ptr(), __preinit_array_end.as_ptr());
+    _init();
+    run_array(__init_array_start.as_ptr(), __init_array_end.as_ptr());
+    main(argc as c_int, argv, envp)
+  };
+  stdlib::ouma_exit(status)
 }
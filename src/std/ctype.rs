@@ -5,7 +5,7 @@ use crate::{
   char32_t,
   locale_t,
   mbstate_t,
-  std::wctype,
+  std::{stdlib, wctype},
   support::locale,
   wctrans_t,
   wctype_t,
@@ -49,7 +49,13 @@ fn totrans_l(
   if (loc.ctype.mbtoc32)(&mut c32, &mut buf, 1, &mut ps) != 1 {
     return c as c_int;
   }
-  wctype::ouma_towctrans_l(c32 as wint_t, wctrans, locale) as c_int
+  // The result goes back to a byte, unless it takes more than one.
+  let c32 = wctype::ouma_towctrans_l(c32 as wint_t, wctrans, locale);
+  let mut out = [0 as c_char; stdlib::MB_LEN_MAX as usize];
+  if (loc.ctype.c32tomb)(out.as_mut_ptr(), c32 as char32_t, &raw mut ps) != 1 {
+    return c as c_int;
+  }
+  c_int::from(out[0] as c_uchar)
 }
 
 #[no_mangle]
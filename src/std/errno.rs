@@ -4,6 +4,11 @@ use crate::c_int;
 #[thread_local]
 pub static mut __oumalibc_errno: c_int = 0;
 
+#[inline]
+pub fn get_errno() -> c_int {
+  unsafe { __oumalibc_errno }
+}
+
 #[inline]
 pub fn set_errno(errno: c_int) {
   unsafe { __oumalibc_errno = errno };
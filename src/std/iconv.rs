@@ -0,0 +1,289 @@
+use {
+  crate::{
+    c_char,
+    c_int,
+    char32_t,
+    iconv_t,
+    mbstate_t,
+    size_t,
+    std::{errno, stdlib},
+    support::{
+      locale::{
+        self,
+        ctype::{LocaleCtype, registry, translit}
+      },
+      string
+    }
+  },
+  core::{mem, slice}
+};
+
+// What iconv_open gives on failure, (iconv_t)-1.
+const ICONV_ERROR: iconv_t = usize::MAX as iconv_t;
+
+// Room for a character written as its stand-in.
+const STANDIN_MAX: usize = translit::LIMIT * stdlib::MB_LEN_MAX as usize;
+
+pub struct IconvStruct {
+  from: LocaleCtype,
+  to: LocaleCtype,
+  from_state: mbstate_t,
+  to_state: mbstate_t,
+  // Write an ASCII stand-in, or failing that a '?', for a character the
+  // target codeset lacks.
+  translit: bool,
+  // Skip invalid input and characters the target codeset lacks.
+  ignore: bool
+}
+
+fn bytes<'a>(s: *const c_char) -> &'a [u8] {
+  unsafe { slice::from_raw_parts(s.cast(), string::string_length(s)) }
+}
+
+// Split a name such as "ASCII//TRANSLIT//IGNORE" into the codeset and
+// whether to transliterate and to ignore. Suffixes may also be joined by
+// commas, as in "//TRANSLIT,IGNORE"; unknown ones are passed over.
+fn suffixes(code: &[u8]) -> (&[u8], bool, bool) {
+  let mut parts = code.split(|&b| b == b'/');
+  let name = parts.next().unwrap_or_default();
+  let (mut translit, mut ignore) = (false, false);
+  for suffix in parts.flat_map(|part| part.split(|&b| b == b',')) {
+    if suffix.eq_ignore_ascii_case(b"TRANSLIT") {
+      translit = true;
+    } else if suffix.eq_ignore_ascii_case(b"IGNORE") {
+      ignore = true;
+    }
+  }
+  (name, translit, ignore)
+}
+
+// The codeset called `name`, where the empty name is the one of the
+// current locale.
+fn codeset(name: &[u8]) -> Option<LocaleCtype> {
+  if name.is_empty() {
+    Some(unsafe { (*locale::get_thread_locale()).ctype })
+  } else {
+    registry::find_any(name)
+  }
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_iconv_open(
+  tocode: *const c_char,
+  fromcode: *const c_char
+) -> iconv_t {
+  let (to, translit, ignore) = suffixes(bytes(tocode));
+  let (from, _, _) = suffixes(bytes(fromcode));
+  let (Some(to), Some(from)) = (codeset(to), codeset(from)) else {
+    errno::set_errno(errno::EINVAL);
+    return ICONV_ERROR;
+  };
+  let cd =
+    stdlib::ouma_malloc(mem::size_of::<IconvStruct>()).cast::<IconvStruct>();
+  if cd.is_null() {
+    return ICONV_ERROR;
+  }
+  unsafe {
+    cd.write(IconvStruct {
+      from,
+      to,
+      from_state: mbstate_t::new(),
+      to_state: mbstate_t::new(),
+      translit,
+      ignore
+    });
+  }
+  cd
+}
+
+// Encode `c32` into `buf`, giving the length, or None if the codeset lacks
+// it. The state changes only when it has it.
+fn encode(
+  to: &LocaleCtype,
+  c32: char32_t,
+  buf: &mut [u8],
+  state: &mut mbstate_t
+) -> Option<usize> {
+  let mut new = *state;
+  let len = (to.c32tomb)(buf.as_mut_ptr().cast(), c32, &raw mut new);
+  let len = usize::try_from(len).ok()?;
+  *state = new;
+  Some(len)
+}
+
+// Encode the stand-in for `c32` into `buf`, or a '?' if there is none or
+// the codeset lacks part of it.
+fn transliterate(
+  to: &LocaleCtype,
+  c32: char32_t,
+  buf: &mut [u8; STANDIN_MAX],
+  state: &mut mbstate_t
+) -> Option<usize> {
+  let standins = translit::find(c32).into_iter().chain(["?"]);
+  'standins: for standin in standins {
+    let mut new = *state;
+    let mut len = 0;
+    for b in standin.bytes() {
+      match encode(to, char32_t::from(b), &mut buf[len..], &mut new) {
+        | Some(n) => len += n,
+        | None => continue 'standins
+      }
+    }
+    *state = new;
+    return Some(len);
+  }
+  None
+}
+
+// Convert characters from `input` to `output` from offset `out` on until
+// either runs out, leaving `input` and `out` past the ones converted. Gives
+// the number of characters converted irreversibly: written as stand-ins or
+// skipped.
+fn convert(
+  cd: &mut IconvStruct,
+  input: &mut &[u8],
+  output: &mut [u8],
+  out: &mut usize
+) -> Result<size_t, c_int> {
+  let mut irreversible = 0;
+  while !input.is_empty() {
+    let mut from_state = cd.from_state;
+    let mut c32 = 0;
+    let used = (cd.from.mbtoc32)(
+      &raw mut c32,
+      input.as_ptr().cast(),
+      input.len(),
+      &raw mut from_state
+    );
+    let used = match used {
+      | -2 => {
+        // Input that ends in shift sequences leaves nothing pending, and
+        // is taken; otherwise it ends within a character.
+        let (mut pending, mut partial, mut high) = (0, 0, 0);
+        locale::mbstate_get_multibyte(
+          &raw mut from_state,
+          &raw mut pending,
+          &raw mut partial,
+          &raw mut high
+        );
+        if pending != 0 || high != 0 {
+          return Err(errno::EINVAL);
+        }
+        *input = &[];
+        cd.from_state = from_state;
+        break;
+      },
+      | -1 if cd.ignore => {
+        *input = &input[1..];
+        irreversible += 1;
+        continue;
+      },
+      | -1 => return Err(errno::EILSEQ),
+      | used => used as usize
+    };
+    let mut buf = [0; STANDIN_MAX];
+    let mut to_state = cd.to_state;
+    let len = match encode(&cd.to, c32, &mut buf, &mut to_state) {
+      | Some(len) => len,
+      | None if cd.translit || cd.ignore => {
+        irreversible += 1;
+        let standin = if cd.translit {
+          transliterate(&cd.to, c32, &mut buf, &mut to_state)
+        } else {
+          None
+        };
+        match standin {
+          | Some(len) => len,
+          | None if cd.ignore => 0,
+          | None => return Err(errno::EILSEQ)
+        }
+      },
+      | None => return Err(errno::EILSEQ)
+    };
+    let Some(written) = output.get_mut(*out..*out + len) else {
+      return Err(errno::E2BIG);
+    };
+    written.copy_from_slice(&buf[..len]);
+    *out += len;
+    *input = &input[used..];
+    cd.from_state = from_state;
+    cd.to_state = to_state;
+  }
+  Ok(irreversible)
+}
+
+// Write the sequence returning the output to the initial state at offset
+// `out` of `output`, if there is room for it, and reset both states.
+fn reset(
+  cd: &mut IconvStruct,
+  output: Option<&mut [u8]>,
+  out: &mut usize
+) -> Result<(), c_int> {
+  if let Some(output) = output {
+    let mut buf = [0; stdlib::MB_LEN_MAX as usize];
+    let mut state = cd.to_state;
+    let len = (cd.to.unshift)(buf.as_mut_ptr().cast(), &raw mut state) as usize;
+    let Some(written) = output.get_mut(*out..*out + len) else {
+      return Err(errno::E2BIG);
+    };
+    written.copy_from_slice(&buf[..len]);
+    *out += len;
+  }
+  cd.from_state = mbstate_t::new();
+  cd.to_state = mbstate_t::new();
+  Ok(())
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_iconv(
+  cd: iconv_t,
+  inbuf: *mut *mut c_char,
+  inbytesleft: *mut size_t,
+  outbuf: *mut *mut c_char,
+  outbytesleft: *mut size_t
+) -> size_t {
+  if cd.is_null() || cd == ICONV_ERROR {
+    errno::set_errno(errno::EBADF);
+    return size_t::MAX;
+  }
+  let cd = unsafe { &mut *cd };
+  let mut output: &mut [u8] = &mut [];
+  let has_output = !outbuf.is_null() && unsafe { !(*outbuf).is_null() };
+  if has_output {
+    output =
+      unsafe { slice::from_raw_parts_mut((*outbuf).cast(), *outbytesleft) };
+  }
+  let mut out = 0;
+  let result = if inbuf.is_null() || unsafe { (*inbuf).is_null() } {
+    reset(cd, has_output.then_some(output), &mut out).map(|()| 0)
+  } else {
+    let mut input =
+      unsafe { slice::from_raw_parts((*inbuf).cast(), *inbytesleft) };
+    let result = convert(cd, &mut input, output, &mut out);
+    unsafe {
+      *inbuf = (*inbuf).add(*inbytesleft - input.len());
+      *inbytesleft = input.len();
+    }
+    result
+  };
+  if has_output {
+    unsafe {
+      *outbuf = (*outbuf).add(out);
+      *outbytesleft -= out;
+    }
+  }
+  result.unwrap_or_else(|err| {
+    errno::set_errno(err);
+    size_t::MAX
+  })
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_iconv_close(cd: iconv_t) -> c_int {
+  if cd.is_null() || cd == ICONV_ERROR {
+    errno::set_errno(errno::EBADF);
+    return -1;
+  }
+  stdlib::ouma_free(cd.cast());
+  0
+}
@@ -0,0 +1,141 @@
+use crate::{
+  c_char,
+  c_int,
+  locale_t,
+  nl_item,
+  std::locale::{LC_CTYPE, LC_MESSAGES, LC_MONETARY, LC_NUMERIC, LC_TIME},
+  support::locale
+};
+
+// Items are numbered within their category, which takes the high bits.
+const fn item(
+  category: c_int,
+  index: c_int
+) -> nl_item {
+  (category << 16) | index
+}
+
+pub const CODESET: nl_item = item(LC_CTYPE, 14);
+pub const ABDAY_1: nl_item = item(LC_TIME, 0x00);
+pub const ABDAY_2: nl_item = item(LC_TIME, 0x01);
+pub const ABDAY_3: nl_item = item(LC_TIME, 0x02);
+pub const ABDAY_4: nl_item = item(LC_TIME, 0x03);
+pub const ABDAY_5: nl_item = item(LC_TIME, 0x04);
+pub const ABDAY_6: nl_item = item(LC_TIME, 0x05);
+pub const ABDAY_7: nl_item = item(LC_TIME, 0x06);
+pub const DAY_1: nl_item = item(LC_TIME, 0x07);
+pub const DAY_2: nl_item = item(LC_TIME, 0x08);
+pub const DAY_3: nl_item = item(LC_TIME, 0x09);
+pub const DAY_4: nl_item = item(LC_TIME, 0x0a);
+pub const DAY_5: nl_item = item(LC_TIME, 0x0b);
+pub const DAY_6: nl_item = item(LC_TIME, 0x0c);
+pub const DAY_7: nl_item = item(LC_TIME, 0x0d);
+pub const ABMON_1: nl_item = item(LC_TIME, 0x0e);
+pub const ABMON_2: nl_item = item(LC_TIME, 0x0f);
+pub const ABMON_3: nl_item = item(LC_TIME, 0x10);
+pub const ABMON_4: nl_item = item(LC_TIME, 0x11);
+pub const ABMON_5: nl_item = item(LC_TIME, 0x12);
+pub const ABMON_6: nl_item = item(LC_TIME, 0x13);
+pub const ABMON_7: nl_item = item(LC_TIME, 0x14);
+pub const ABMON_8: nl_item = item(LC_TIME, 0x15);
+pub const ABMON_9: nl_item = item(LC_TIME, 0x16);
+pub const ABMON_10: nl_item = item(LC_TIME, 0x17);
+pub const ABMON_11: nl_item = item(LC_TIME, 0x18);
+pub const ABMON_12: nl_item = item(LC_TIME, 0x19);
+pub const MON_1: nl_item = item(LC_TIME, 0x1a);
+pub const MON_2: nl_item = item(LC_TIME, 0x1b);
+pub const MON_3: nl_item = item(LC_TIME, 0x1c);
+pub const MON_4: nl_item = item(LC_TIME, 0x1d);
+pub const MON_5: nl_item = item(LC_TIME, 0x1e);
+pub const MON_6: nl_item = item(LC_TIME, 0x1f);
+pub const MON_7: nl_item = item(LC_TIME, 0x20);
+pub const MON_8: nl_item = item(LC_TIME, 0x21);
+pub const MON_9: nl_item = item(LC_TIME, 0x22);
+pub const MON_10: nl_item = item(LC_TIME, 0x23);
+pub const MON_11: nl_item = item(LC_TIME, 0x24);
+pub const MON_12: nl_item = item(LC_TIME, 0x25);
+pub const AM_STR: nl_item = item(LC_TIME, 0x26);
+pub const PM_STR: nl_item = item(LC_TIME, 0x27);
+pub const D_T_FMT: nl_item = item(LC_TIME, 0x28);
+pub const D_FMT: nl_item = item(LC_TIME, 0x29);
+pub const T_FMT: nl_item = item(LC_TIME, 0x2a);
+pub const T_FMT_AMPM: nl_item = item(LC_TIME, 0x2b);
+pub const ERA: nl_item = item(LC_TIME, 0x2c);
+pub const ERA_D_FMT: nl_item = item(LC_TIME, 0x2e);
+pub const ALT_DIGITS: nl_item = item(LC_TIME, 0x2f);
+pub const ERA_D_T_FMT: nl_item = item(LC_TIME, 0x30);
+pub const ERA_T_FMT: nl_item = item(LC_TIME, 0x31);
+pub const ALTMON_1: nl_item = item(LC_TIME, 0x32);
+pub const ALTMON_2: nl_item = item(LC_TIME, 0x33);
+pub const ALTMON_3: nl_item = item(LC_TIME, 0x34);
+pub const ALTMON_4: nl_item = item(LC_TIME, 0x35);
+pub const ALTMON_5: nl_item = item(LC_TIME, 0x36);
+pub const ALTMON_6: nl_item = item(LC_TIME, 0x37);
+pub const ALTMON_7: nl_item = item(LC_TIME, 0x38);
+pub const ALTMON_8: nl_item = item(LC_TIME, 0x39);
+pub const ALTMON_9: nl_item = item(LC_TIME, 0x3a);
+pub const ALTMON_10: nl_item = item(LC_TIME, 0x3b);
+pub const ALTMON_11: nl_item = item(LC_TIME, 0x3c);
+pub const ALTMON_12: nl_item = item(LC_TIME, 0x3d);
+pub const ABALTMON_1: nl_item = item(LC_TIME, 0x3e);
+pub const ABALTMON_2: nl_item = item(LC_TIME, 0x3f);
+pub const ABALTMON_3: nl_item = item(LC_TIME, 0x40);
+pub const ABALTMON_4: nl_item = item(LC_TIME, 0x41);
+pub const ABALTMON_5: nl_item = item(LC_TIME, 0x42);
+pub const ABALTMON_6: nl_item = item(LC_TIME, 0x43);
+pub const ABALTMON_7: nl_item = item(LC_TIME, 0x44);
+pub const ABALTMON_8: nl_item = item(LC_TIME, 0x45);
+pub const ABALTMON_9: nl_item = item(LC_TIME, 0x46);
+pub const ABALTMON_10: nl_item = item(LC_TIME, 0x47);
+pub const ABALTMON_11: nl_item = item(LC_TIME, 0x48);
+pub const ABALTMON_12: nl_item = item(LC_TIME, 0x49);
+pub const RADIXCHAR: nl_item = item(LC_NUMERIC, 0);
+pub const THOUSEP: nl_item = item(LC_NUMERIC, 1);
+pub const CRNCYSTR: nl_item = item(LC_MONETARY, 15);
+pub const YESEXPR: nl_item = item(LC_MESSAGES, 0);
+pub const NOEXPR: nl_item = item(LC_MESSAGES, 1);
+pub const YESSTR: nl_item = item(LC_MESSAGES, 2);
+pub const NOSTR: nl_item = item(LC_MESSAGES, 3);
+
+#[no_mangle]
+pub extern "C" fn ouma_nl_langinfo(item: nl_item) -> *mut c_char {
+  ouma_nl_langinfo_l(item, locale::get_thread_locale())
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_nl_langinfo_l(
+  item: nl_item,
+  locale: locale_t
+) -> *mut c_char {
+  let locale = unsafe { &*locale };
+  let time = &locale.time;
+  let value = match item {
+    | CODESET => locale.ctype.codeset,
+    | ABDAY_1..=ABDAY_7 => time.abday[(item - ABDAY_1) as usize],
+    | DAY_1..=DAY_7 => time.day[(item - DAY_1) as usize],
+    | ABMON_1..=ABMON_12 => time.abmon[(item - ABMON_1) as usize],
+    | MON_1..=MON_12 => time.mon[(item - MON_1) as usize],
+    | ALTMON_1..=ALTMON_12 => time.alt_mon[(item - ALTMON_1) as usize],
+    | ABALTMON_1..=ABALTMON_12 => time.ab_alt_mon[(item - ABALTMON_1) as usize],
+    | AM_STR => time.am_pm[0],
+    | PM_STR => time.am_pm[1],
+    | D_T_FMT => time.d_t_fmt,
+    | D_FMT => time.d_fmt,
+    | T_FMT => time.t_fmt,
+    | T_FMT_AMPM => time.t_fmt_ampm,
+    | ERA => time.era,
+    | ERA_D_FMT => time.era_d_fmt,
+    | ALT_DIGITS => time.alt_digits,
+    | ERA_D_T_FMT => time.era_d_t_fmt,
+    | ERA_T_FMT => time.era_t_fmt,
+    | RADIXCHAR => locale.numeric.decimal_point,
+    | THOUSEP => locale.numeric.thousands_sep,
+    | CRNCYSTR => locale.monetary.crncystr,
+    | YESEXPR => locale.messages.yesexpr,
+    | NOEXPR => locale.messages.noexpr,
+    | YESSTR => locale.messages.yesstr,
+    | NOSTR => locale.messages.nostr,
+    | _ => c"".as_ptr()
+  };
+  value.cast_mut()
+}
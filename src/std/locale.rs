@@ -0,0 +1,255 @@
+use {
+  crate::{
+    LocaleStruct,
+    c_char,
+    c_int,
+    locale_t,
+    std::{errno, stdlib},
+    support::{
+      locale::{
+        self,
+        CATEGORIES,
+        CATEGORY_NAMES,
+        NAME_MAX,
+        ctype::foreign::{self, EncodingStruct}
+      },
+      string,
+      sync::Mutex
+    }
+  },
+  core::{mem, ptr, slice}
+};
+
+pub const LC_CTYPE: c_int = 0;
+pub const LC_NUMERIC: c_int = 1;
+pub const LC_TIME: c_int = 2;
+pub const LC_COLLATE: c_int = 3;
+pub const LC_MONETARY: c_int = 4;
+pub const LC_MESSAGES: c_int = 5;
+pub const LC_ALL: c_int = 6;
+
+pub const LC_CTYPE_MASK: c_int = 1 << LC_CTYPE;
+pub const LC_NUMERIC_MASK: c_int = 1 << LC_NUMERIC;
+pub const LC_TIME_MASK: c_int = 1 << LC_TIME;
+pub const LC_COLLATE_MASK: c_int = 1 << LC_COLLATE;
+pub const LC_MONETARY_MASK: c_int = 1 << LC_MONETARY;
+pub const LC_MESSAGES_MASK: c_int = 1 << LC_MESSAGES;
+pub const LC_ALL_MASK: c_int = LC_CTYPE_MASK |
+  LC_NUMERIC_MASK |
+  LC_TIME_MASK |
+  LC_COLLATE_MASK |
+  LC_MONETARY_MASK |
+  LC_MESSAGES_MASK;
+
+pub const LC_GLOBAL_LOCALE: locale_t = ptr::without_provenance_mut(usize::MAX);
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct lconv {
+  pub decimal_point: *mut c_char,
+  pub thousands_sep: *mut c_char,
+  pub grouping: *mut c_char,
+  pub int_curr_symbol: *mut c_char,
+  pub currency_symbol: *mut c_char,
+  pub mon_decimal_point: *mut c_char,
+  pub mon_thousands_sep: *mut c_char,
+  pub mon_grouping: *mut c_char,
+  pub positive_sign: *mut c_char,
+  pub negative_sign: *mut c_char,
+  pub int_frac_digits: c_char,
+  pub frac_digits: c_char,
+  pub p_cs_precedes: c_char,
+  pub p_sep_by_space: c_char,
+  pub n_cs_precedes: c_char,
+  pub n_sep_by_space: c_char,
+  pub p_sign_posn: c_char,
+  pub n_sign_posn: c_char,
+  pub int_p_cs_precedes: c_char,
+  pub int_p_sep_by_space: c_char,
+  pub int_n_cs_precedes: c_char,
+  pub int_n_sep_by_space: c_char,
+  pub int_p_sign_posn: c_char,
+  pub int_n_sign_posn: c_char
+}
+
+// What localeconv returned last on this thread.
+#[thread_local]
+static mut LCONV: mem::MaybeUninit<lconv> = mem::MaybeUninit::uninit();
+
+// Serializes changes to the global locale.
+static SETLOCALE: Mutex<()> = Mutex::new(());
+
+// Room for "LC_CTYPE=...;" for every category, which setlocale(LC_ALL, NULL)
+// returns when the categories differ.
+static mut COMPOSITE: [c_char; CATEGORIES * (NAME_MAX + 16)] =
+  [0; CATEGORIES * (NAME_MAX + 16)];
+
+fn bytes<'a>(s: *const c_char) -> &'a [u8] {
+  unsafe { slice::from_raw_parts(s.cast(), string::string_length(s)) }
+}
+
+// The name of the whole global locale, composite if its categories differ.
+fn global_name() -> *mut c_char {
+  let names = unsafe { &mut (*locale::get_global_locale()).names };
+  if names.iter().all(|name| *name == names[0]) {
+    return names[0].as_mut_ptr();
+  }
+  let buf = (&raw mut COMPOSITE).cast::<c_char>();
+  let mut len = 0;
+  for (category, name) in names.iter().enumerate() {
+    let key = CATEGORY_NAMES[category].as_bytes();
+    let value = bytes(name.as_ptr());
+    let sep: &[u8] = if category == 0 { b"" } else { b";" };
+    for &c in sep.iter().chain(key).chain(b"=").chain(value) {
+      unsafe { buf.add(len).write(c as c_char) };
+      len += 1;
+    }
+  }
+  unsafe { buf.add(len).write(0) };
+  buf
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_setlocale(
+  category: c_int,
+  name: *const c_char
+) -> *mut c_char {
+  if !(LC_CTYPE..=LC_ALL).contains(&category) {
+    errno::set_errno(errno::EINVAL);
+    return ptr::null_mut();
+  }
+  let mask = if category == LC_ALL { LC_ALL_MASK } else { 1 << category };
+  let _guard = SETLOCALE.lock();
+  if !name.is_null() {
+    let global = unsafe { &mut *locale::get_global_locale() };
+    if let Err(err) = locale::set(global, mask, bytes(name)) {
+      errno::set_errno(err);
+      return ptr::null_mut();
+    }
+  }
+  if category == LC_ALL {
+    global_name()
+  } else {
+    unsafe {
+      (*locale::get_global_locale()).names[category as usize].as_mut_ptr()
+    }
+  }
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_newlocale(
+  category_mask: c_int,
+  name: *const c_char,
+  base: locale_t
+) -> locale_t {
+  if name.is_null() ||
+    category_mask & !LC_ALL_MASK != 0 ||
+    base == LC_GLOBAL_LOCALE
+  {
+    errno::set_errno(errno::EINVAL);
+    return ptr::null_mut();
+  }
+  let mut new =
+    if base.is_null() { locale::LOCALE_C } else { unsafe { *base } };
+  if let Err(err) = locale::set(&mut new, category_mask, bytes(name)) {
+    errno::set_errno(err);
+    return ptr::null_mut();
+  }
+  // The base is reused, as POSIX allows, so it needs no freeing.
+  let locale = if base.is_null() {
+    stdlib::ouma_malloc(mem::size_of::<LocaleStruct>()).cast::<LocaleStruct>()
+  } else {
+    base
+  };
+  if !locale.is_null() {
+    unsafe { locale.write(new) };
+  }
+  locale
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_duplocale(locale: locale_t) -> locale_t {
+  let old = if locale == LC_GLOBAL_LOCALE {
+    locale::get_global_locale()
+  } else {
+    locale
+  };
+  let new =
+    stdlib::ouma_malloc(mem::size_of::<LocaleStruct>()).cast::<LocaleStruct>();
+  if !new.is_null() {
+    unsafe { new.write(*old) };
+  }
+  new
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_freelocale(locale: locale_t) {
+  stdlib::ouma_free(locale.cast());
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_uselocale(locale: locale_t) -> locale_t {
+  if locale.is_null() {
+    let old = locale::set_thread_locale(ptr::null_mut());
+    locale::set_thread_locale(old);
+    old
+  } else {
+    locale::set_thread_locale(locale)
+  }
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_localeconv() -> *mut lconv {
+  let locale = unsafe { &*locale::get_thread_locale() };
+  let (n, m) = (&locale.numeric, &locale.monetary);
+  let conv = lconv {
+    decimal_point: n.decimal_point.cast_mut(),
+    thousands_sep: n.thousands_sep.cast_mut(),
+    grouping: n.grouping.cast_mut(),
+    int_curr_symbol: m.int_curr_symbol.cast_mut(),
+    currency_symbol: m.currency_symbol.cast_mut(),
+    mon_decimal_point: m.mon_decimal_point.cast_mut(),
+    mon_thousands_sep: m.mon_thousands_sep.cast_mut(),
+    mon_grouping: m.mon_grouping.cast_mut(),
+    positive_sign: m.positive_sign.cast_mut(),
+    negative_sign: m.negative_sign.cast_mut(),
+    int_frac_digits: m.int_frac_digits,
+    frac_digits: m.frac_digits,
+    p_cs_precedes: m.p_cs_precedes,
+    p_sep_by_space: m.p_sep_by_space,
+    n_cs_precedes: m.n_cs_precedes,
+    n_sep_by_space: m.n_sep_by_space,
+    p_sign_posn: m.p_sign_posn,
+    n_sign_posn: m.n_sign_posn,
+    int_p_cs_precedes: m.int_p_cs_precedes,
+    int_p_sep_by_space: m.int_p_sep_by_space,
+    int_n_cs_precedes: m.int_n_cs_precedes,
+    int_n_sep_by_space: m.int_n_sep_by_space,
+    int_p_sign_posn: m.int_p_sign_posn,
+    int_n_sign_posn: m.int_n_sign_posn
+  };
+  let p = (&raw mut LCONV).cast::<lconv>();
+  unsafe { p.write(conv) };
+  p
+}
+
+// Make the encoding `encoding` describes known to locales, iconv and
+// nl_langinfo(CODESET) by its names, as the Encoding trait does in Rust.
+// It is meant for start-up, before anything names the encoding, and the
+// description has to outlive every use of it.
+#[no_mangle]
+pub extern "C" fn ouma_register_encoding(
+  encoding: *const EncodingStruct
+) -> c_int {
+  let result = match unsafe { encoding.as_ref() } {
+    | Some(encoding) => foreign::register(encoding),
+    | None => Err(errno::EINVAL)
+  };
+  match result {
+    | Ok(()) => 0,
+    | Err(err) => {
+      errno::set_errno(err);
+      -1
+    }
+  }
+}
@@ -0,0 +1,125 @@
+use {
+  crate::{alloc, api::IoWrite, c_int, size_t, std::errno},
+  core::{ffi::c_void, fmt::Write, ptr}
+};
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct mallinfo2 {
+  pub arena: size_t,
+  pub ordblks: size_t,
+  pub smblks: size_t,
+  pub hblks: size_t,
+  pub hblkhd: size_t,
+  pub usmblks: size_t,
+  pub fsmblks: size_t,
+  pub uordblks: size_t,
+  pub fordblks: size_t,
+  pub keepcost: size_t
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_malloc_usable_size(ptr: *mut c_void) -> size_t {
+  alloc::usable_size(ptr.cast())
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_memalign(
+  alignment: size_t,
+  size: size_t
+) -> *mut c_void {
+  if !alignment.is_power_of_two() {
+    errno::set_errno(errno::EINVAL);
+    return ptr::null_mut();
+  }
+
+  let ptr = alloc::allocate(size, alignment);
+  if ptr.is_null() {
+    errno::set_errno(errno::ENOMEM);
+  }
+  ptr.cast()
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_valloc(size: size_t) -> *mut c_void {
+  ouma_memalign(alloc::page_size(), size)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_pvalloc(size: size_t) -> *mut c_void {
+  let page = alloc::page_size();
+  let Some(size) = size.max(1).checked_next_multiple_of(page) else {
+    errno::set_errno(errno::ENOMEM);
+    return ptr::null_mut();
+  };
+  ouma_memalign(page, size)
+}
+
+// Slab memory is reported as the main arena and large blocks as mmapped
+// regions. Free bytes are the parts of the slabs not handed out, keepcost is
+// what malloc_trim would give back.
+#[no_mangle]
+pub extern "C" fn ouma_mallinfo2() -> mallinfo2 {
+  let mut info = mallinfo2 {
+    arena: 0,
+    ordblks: 0,
+    smblks: 0,
+    hblks: 0,
+    hblkhd: 0,
+    usmblks: 0,
+    fsmblks: 0,
+    uordblks: 0,
+    fordblks: 0,
+    keepcost: 0
+  };
+  for index in 0..alloc::ARENA_COUNT {
+    let stats = alloc::arena_stats(index);
+    info.arena += stats.committed();
+    info.ordblks += stats.empty;
+    info.uordblks += stats.in_use;
+    info.keepcost += stats.empty_committed * alloc::SLAB_SIZE;
+  }
+  info.fordblks = info.arena - info.uordblks;
+
+  let large = alloc::large_stats();
+  info.hblks = large.count;
+  info.hblkhd = large.bytes;
+  info
+}
+
+// Empty slabs are always released whole, so there is no use for `pad`.
+#[no_mangle]
+pub extern "C" fn ouma_malloc_trim(_pad: size_t) -> c_int {
+  c_int::from(alloc::trim())
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_malloc_stats() {
+  let mut w = IoWrite(2);
+  let (mut system, mut in_use) = (0, 0);
+  for index in 0..alloc::ARENA_COUNT {
+    let stats = alloc::arena_stats(index);
+    if index > 0 && stats.slabs == 0 {
+      continue;
+    }
+    system += stats.committed();
+    in_use += stats.in_use;
+    let _ = write!(
+      w,
+      "Arena {index}:\nsystem bytes     = {:10}\nin use bytes     = {:10}\n",
+      stats.committed(),
+      stats.in_use
+    );
+  }
+
+  let large = alloc::large_stats();
+  let _ = write!(
+    w,
+    "Total (incl. mmap):\nsystem bytes     = {:10}\nin use bytes     = \
+     {:10}\nmax mmap regions = {:10}\nmax mmap bytes   = {:10}\n",
+    system + large.bytes,
+    in_use + large.bytes,
+    large.max_count,
+    large.max_bytes
+  );
+}
@@ -1,10 +1,15 @@
 pub mod ctype;
 pub mod errno;
+pub mod iconv;
+pub mod langinfo;
+pub mod locale;
+pub mod malloc;
 pub mod setjmp;
 pub mod signal;
 pub mod stdio;
 pub mod stdlib;
 pub mod string;
+pub mod time;
 pub mod uchar;
 pub mod wchar;
 pub mod wctype;
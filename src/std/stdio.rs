@@ -1,4 +1,33 @@
-use crate::{c_int, c_uint};
+use {
+  crate::{
+    FILE,
+    c_char,
+    c_int,
+    c_long,
+    c_uint,
+    off_t,
+    size_t,
+    std::errno,
+    support::{
+      printf::{
+        self,
+        Sink,
+        sink::{FdSink, RawSink, VecSink}
+      },
+      scanf::{self, source::StrSource},
+      stream,
+      string,
+      stringstream::StringStream
+    }
+  },
+  allocator::vec::Vec,
+  core::{
+    ffi::{VaList, c_void},
+    mem,
+    ptr,
+    slice
+  }
+};
 
 pub const _IOFBF: c_int = 0;
 pub const _IOLBF: c_int = 1;
@@ -19,3 +48,432 @@ pub const L_tmpnam: c_uint = 20;
 pub const L_ctermid: c_uint = 9;
 pub const L_cuserid: c_uint = 9;
 pub const P_tmpdir: &[u8; 5] = b"/tmp\0";
+
+#[no_mangle]
+pub static mut ouma_stdin: *mut FILE = (&raw const stream::STDIN).cast_mut();
+#[no_mangle]
+pub static mut ouma_stdout: *mut FILE = (&raw const stream::STDOUT).cast_mut();
+#[no_mangle]
+pub static mut ouma_stderr: *mut FILE = (&raw const stream::STDERR).cast_mut();
+
+fn print<S: Sink>(
+  sink: &mut S,
+  format: *const c_char,
+  ap: VaList
+) -> Result<c_int, c_int> {
+  printf::format(sink, format, ap).map(|n| n as c_int)
+}
+
+fn file(res: Result<*mut FILE, c_int>) -> *mut FILE {
+  res.unwrap_or_else(|e| {
+    errno::set_errno(e);
+    ptr::null_mut()
+  })
+}
+
+fn result(res: Result<c_int, c_int>) -> c_int {
+  res.unwrap_or_else(|e| {
+    errno::set_errno(e);
+    -1
+  })
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_vsnprintf(
+  s: *mut c_char,
+  n: size_t,
+  format: *const c_char,
+  ap: VaList
+) -> c_int {
+  if n == 0 {
+    return result(print(&mut StringStream::new(&mut []), format, ap));
+  }
+  let mut ss =
+    StringStream::new(unsafe { slice::from_raw_parts_mut(s, n - 1) });
+  let res = print(&mut ss, format, ap);
+  unsafe { *s.add(ss.written()) = 0 };
+  result(res)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ouma_snprintf(
+  s: *mut c_char,
+  n: size_t,
+  format: *const c_char,
+  args: ...
+) -> c_int {
+  ouma_vsnprintf(s, n, format, args)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_vsprintf(
+  s: *mut c_char,
+  format: *const c_char,
+  ap: VaList
+) -> c_int {
+  let mut sink = RawSink::new(s);
+  let res = print(&mut sink, format, ap);
+  sink.terminate();
+  result(res)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ouma_sprintf(
+  s: *mut c_char,
+  format: *const c_char,
+  args: ...
+) -> c_int {
+  ouma_vsprintf(s, format, args)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_vasprintf(
+  strp: *mut *mut c_char,
+  format: *const c_char,
+  ap: VaList
+) -> c_int {
+  let mut sink = VecSink(Vec::new());
+  let res = print(&mut sink, format, ap).and_then(|n| {
+    sink.write(b"\0")?;
+    Ok(n)
+  });
+  // The buffer was allocated by malloc and is released with free.
+  let s = match res {
+    | Ok(_) => mem::ManuallyDrop::new(sink.0).as_mut_ptr().cast(),
+    | Err(_) => ptr::null_mut()
+  };
+  unsafe { *strp = s };
+  result(res)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ouma_asprintf(
+  strp: *mut *mut c_char,
+  format: *const c_char,
+  args: ...
+) -> c_int {
+  ouma_vasprintf(strp, format, args)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_vdprintf(
+  fd: c_int,
+  format: *const c_char,
+  ap: VaList
+) -> c_int {
+  let mut sink = FdSink::new(fd);
+  result(print(&mut sink, format, ap).and_then(|n| {
+    sink.flush()?;
+    Ok(n)
+  }))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ouma_dprintf(
+  fd: c_int,
+  format: *const c_char,
+  args: ...
+) -> c_int {
+  ouma_vdprintf(fd, format, args)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_fopen(
+  path: *const c_char,
+  mode: *const c_char
+) -> *mut FILE {
+  file(stream::open(path, mode))
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_fdopen(
+  fd: c_int,
+  mode: *const c_char
+) -> *mut FILE {
+  file(stream::fdopen(fd, mode))
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_freopen(
+  path: *const c_char,
+  mode: *const c_char,
+  f: *mut FILE
+) -> *mut FILE {
+  file(stream::reopen(path, mode, f))
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_fclose(f: *mut FILE) -> c_int {
+  match stream::close(f) {
+    | Ok(()) => 0,
+    | Err(e) => {
+      errno::set_errno(e);
+      EOF
+    }
+  }
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_fflush(f: *mut FILE) -> c_int {
+  let res = if f.is_null() {
+    stream::flush_all()
+  } else {
+    unsafe { (*f).lock() }.flush()
+  };
+  match res {
+    | Ok(()) => 0,
+    | Err(e) => {
+      errno::set_errno(e);
+      EOF
+    }
+  }
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_setvbuf(
+  f: *mut FILE,
+  buf: *mut c_char,
+  mode: c_int,
+  size: size_t
+) -> c_int {
+  match unsafe { (*f).lock() }.setvbuf(buf, mode, size) {
+    | Ok(()) => 0,
+    | Err(e) => {
+      errno::set_errno(e);
+      -1
+    }
+  }
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_fread(
+  ptr: *mut c_void,
+  size: size_t,
+  n: size_t,
+  f: *mut FILE
+) -> size_t {
+  let Some(len) = size.checked_mul(n).filter(|&len| len != 0) else {
+    return 0;
+  };
+  let dst = unsafe { slice::from_raw_parts_mut(ptr.cast(), len) };
+  unsafe { (*f).lock() }.read(dst) / size
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_fwrite(
+  ptr: *const c_void,
+  size: size_t,
+  n: size_t,
+  f: *mut FILE
+) -> size_t {
+  let Some(len) = size.checked_mul(n).filter(|&len| len != 0) else {
+    return 0;
+  };
+  let src = unsafe { slice::from_raw_parts(ptr.cast(), len) };
+  unsafe { (*f).lock() }.write(src) / size
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_fgetc(f: *mut FILE) -> c_int {
+  unsafe { (*f).lock() }.getc().map_or(EOF, c_int::from)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_ungetc(
+  c: c_int,
+  f: *mut FILE
+) -> c_int {
+  if c == EOF || !unsafe { (*f).lock() }.ungetc(c as u8) {
+    return EOF;
+  }
+  c_int::from(c as u8)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_fgets(
+  s: *mut c_char,
+  n: c_int,
+  f: *mut FILE
+) -> *mut c_char {
+  let Ok(n) = usize::try_from(n) else {
+    errno::set_errno(errno::EINVAL);
+    return ptr::null_mut();
+  };
+  if n == 0 {
+    errno::set_errno(errno::EINVAL);
+    return ptr::null_mut();
+  }
+  let dst = unsafe { slice::from_raw_parts_mut(s.cast(), n - 1) };
+  let len = {
+    let mut stream = unsafe { (*f).lock() };
+    let len = stream.gets(dst);
+    // Nothing read at end of file leaves the buffer alone, a read error
+    // makes its contents indeterminate.
+    if n > 1 && (len == 0 || stream.error()) {
+      return ptr::null_mut();
+    }
+    len
+  };
+  unsafe { *s.add(len) = 0 };
+  s
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_fputs(
+  s: *const c_char,
+  f: *mut FILE
+) -> c_int {
+  let src =
+    unsafe { slice::from_raw_parts(s.cast(), string::string_length(s)) };
+  if unsafe { (*f).lock() }.write(src) < src.len() {
+    return EOF;
+  }
+  1
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_fseeko(
+  f: *mut FILE,
+  offset: off_t,
+  whence: c_int
+) -> c_int {
+  match unsafe { (*f).lock() }.seek(offset, whence) {
+    | Ok(()) => 0,
+    | Err(e) => {
+      errno::set_errno(e);
+      -1
+    }
+  }
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_fseek(
+  f: *mut FILE,
+  offset: c_long,
+  whence: c_int
+) -> c_int {
+  ouma_fseeko(f, offset, whence)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_ftello(f: *mut FILE) -> off_t {
+  unsafe { (*f).lock() }.tell().unwrap_or_else(|e| {
+    errno::set_errno(e);
+    -1
+  })
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_ftell(f: *mut FILE) -> c_long {
+  ouma_ftello(f)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_feof(f: *mut FILE) -> c_int {
+  c_int::from(unsafe { (*f).lock() }.eof())
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_ferror(f: *mut FILE) -> c_int {
+  c_int::from(unsafe { (*f).lock() }.error())
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_clearerr(f: *mut FILE) {
+  unsafe { (*f).lock() }.clear();
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_fileno(f: *mut FILE) -> c_int {
+  let fd = unsafe { (*f).lock() }.fd();
+  if fd < 0 {
+    errno::set_errno(errno::EBADF);
+  }
+  fd
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_vfprintf(
+  f: *mut FILE,
+  format: *const c_char,
+  ap: VaList
+) -> c_int {
+  result(print(&mut *unsafe { (*f).lock() }, format, ap))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ouma_fprintf(
+  f: *mut FILE,
+  format: *const c_char,
+  args: ...
+) -> c_int {
+  ouma_vfprintf(f, format, args)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_vprintf(
+  format: *const c_char,
+  ap: VaList
+) -> c_int {
+  ouma_vfprintf(unsafe { ouma_stdout }, format, ap)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ouma_printf(
+  format: *const c_char,
+  args: ...
+) -> c_int {
+  ouma_vprintf(format, args)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_vsscanf(
+  s: *const c_char,
+  format: *const c_char,
+  ap: VaList
+) -> c_int {
+  result(scanf::scan(&mut StrSource::new(s), format, ap))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ouma_sscanf(
+  s: *const c_char,
+  format: *const c_char,
+  args: ...
+) -> c_int {
+  ouma_vsscanf(s, format, args)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_vfscanf(
+  f: *mut FILE,
+  format: *const c_char,
+  ap: VaList
+) -> c_int {
+  result(scanf::scan(&mut *unsafe { (*f).lock() }, format, ap))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ouma_fscanf(
+  f: *mut FILE,
+  format: *const c_char,
+  args: ...
+) -> c_int {
+  ouma_vfscanf(f, format, args)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_vscanf(
+  format: *const c_char,
+  ap: VaList
+) -> c_int {
+  ouma_vfscanf(unsafe { ouma_stdin }, format, ap)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ouma_scanf(
+  format: *const c_char,
+  args: ...
+) -> c_int {
+  ouma_vscanf(format, args)
+}
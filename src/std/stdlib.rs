@@ -1,9 +1,38 @@
 use {
-  crate::{c_int, max_align_t, size_t, std::errno, support::locale},
-  allocator::alloc,
-  core::{ffi::c_void, mem, ptr}
+  crate::{
+    alloc,
+    c_char,
+    c_int,
+    c_long,
+    c_longlong,
+    c_ulong,
+    c_ulonglong,
+    char32_t,
+    intmax_t,
+    locale_t,
+    max_align_t,
+    mbstate_t,
+    size_t,
+    start,
+    std::{errno, wchar},
+    support::{
+      environ,
+      locale::{self, numeric},
+      sort,
+      stream,
+      strtofloat,
+      strtointeger::{Char, strtointeger}
+    },
+    uintmax_t,
+    wchar_t
+  },
+  core::{ffi::c_void, mem, ptr},
+  syscalls::{Sysno, syscall}
 };
 
+pub const EXIT_SUCCESS: c_int = 0;
+pub const EXIT_FAILURE: c_int = 1;
+
 pub const MB_LEN_MAX: c_int = 16;
 
 #[no_mangle]
@@ -12,25 +41,118 @@ pub extern "C" fn __oumalibc_get_mb_cur_max() -> size_t {
   loc.ctype.mb_cur_max as size_t
 }
 
+// Shift states of mblen, mbtowc and wctomb, which only the initial call
+// with a null string resets.
+static mut MBLEN_STATE: mbstate_t = mbstate_t::new();
+static mut MBTOWC_STATE: mbstate_t = mbstate_t::new();
+static mut WCTOMB_STATE: mbstate_t = mbstate_t::new();
+
+// A null string resets `state` and reports whether the encoding has shift
+// states. Otherwise the length of the character is returned, 0 for the null
+// character, or -1 with the state reset if it is invalid or incomplete.
+fn mbtowc_with(
+  pwc: *mut wchar_t,
+  s: *const c_char,
+  n: size_t,
+  state: *mut mbstate_t
+) -> c_int {
+  let ctype = unsafe { (*locale::get_thread_locale()).ctype };
+  if s.is_null() {
+    locale::mbstate_set_init(state);
+    return c_int::from(ctype.stateful);
+  }
+  let mut c32: char32_t = 0;
+  let l = (ctype.mbtoc32)(&raw mut c32, s, n, state);
+  if l < 0 {
+    locale::mbstate_set_init(state);
+    errno::set_errno(errno::EILSEQ);
+    return -1;
+  }
+  if !pwc.is_null() {
+    unsafe { *pwc = c32 as wchar_t };
+  }
+  if c32 == 0 { 0 } else { l as c_int }
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_mblen(
+  s: *const c_char,
+  n: size_t
+) -> c_int {
+  mbtowc_with(ptr::null_mut(), s, n, &raw mut MBLEN_STATE)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_mbtowc(
+  pwc: *mut wchar_t,
+  s: *const c_char,
+  n: size_t
+) -> c_int {
+  mbtowc_with(pwc, s, n, &raw mut MBTOWC_STATE)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_wctomb(
+  s: *mut c_char,
+  wc: wchar_t
+) -> c_int {
+  let ctype = unsafe { (*locale::get_thread_locale()).ctype };
+  let state = &raw mut WCTOMB_STATE;
+  if s.is_null() {
+    locale::mbstate_set_init(state);
+    return c_int::from(ctype.stateful);
+  }
+  let l = (ctype.c32tomb)(s, wc as char32_t, state);
+  if l < 0 {
+    locale::mbstate_set_init(state);
+    return -1;
+  }
+  l as c_int
+}
+
+// Unlike their restartable counterparts, mbstowcs and wcstombs start every
+// call in the initial state.
+#[no_mangle]
+pub extern "C" fn ouma_mbstowcs(
+  dst: *mut wchar_t,
+  src: *const c_char,
+  n: size_t
+) -> size_t {
+  let mut state = mbstate_t::new();
+  let mut src = src;
+  wchar::ouma_mbsrtowcs(dst, &raw mut src, n, &raw mut state)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_wcstombs(
+  dst: *mut c_char,
+  src: *const wchar_t,
+  n: size_t
+) -> size_t {
+  let mut state = mbstate_t::new();
+  let mut src = src;
+  wchar::ouma_wcsrtombs(dst, &raw mut src, n, &raw mut state)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_exit(status: c_int) -> ! {
+  start::run_fini();
+  let _ = stream::flush_all();
+  ouma__Exit(status)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma__Exit(status: c_int) -> ! {
+  let _ = unsafe { syscall!(Sysno::exit_group, status) };
+  unreachable!("exit_group returned")
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_malloc(size: size_t) -> *mut c_void {
-  let size = if size == 0 { size + 1 } else { size };
-  let layout = match alloc::Layout::from_size_align(
-    size,
-    mem::align_of::<max_align_t>()
-  ) {
-    | Err(_) => {
-      errno::set_errno(errno::ENOMEM);
-      return ptr::null_mut();
-    },
-    | Ok(result) => result
-  };
-  let ptr = unsafe { alloc::alloc(layout) };
+  let ptr = alloc::allocate(size, mem::align_of::<max_align_t>());
   if ptr.is_null() {
     errno::set_errno(errno::ENOMEM);
-    return ptr::null_mut();
   }
-
   ptr.cast()
 }
 
@@ -39,20 +161,15 @@ pub extern "C" fn ouma_aligned_alloc(
   alignment: size_t,
   size: size_t
 ) -> *mut c_void {
-  let size = if size == 0 { size + 1 } else { size };
-  let layout = match alloc::Layout::from_size_align(size, alignment) {
-    | Err(_) => {
-      errno::set_errno(errno::ENOMEM);
-      return ptr::null_mut();
-    },
-    | Ok(result) => result
-  };
-  let ptr = unsafe { alloc::alloc(layout) };
-  if ptr.is_null() {
-    errno::set_errno(errno::ENOMEM);
+  if !alignment.is_power_of_two() {
+    errno::set_errno(errno::EINVAL);
     return ptr::null_mut();
   }
 
+  let ptr = alloc::allocate(size, alignment);
+  if ptr.is_null() {
+    errno::set_errno(errno::ENOMEM);
+  }
   ptr.cast()
 }
 
@@ -61,28 +178,15 @@ pub extern "C" fn ouma_calloc(
   nmemb: size_t,
   size: size_t
 ) -> *mut c_void {
-  let Some(mut res) = nmemb.checked_mul(size) else {
+  let Some(res) = nmemb.checked_mul(size) else {
     errno::set_errno(errno::ENOMEM);
     return ptr::null_mut();
   };
-  if res == 0 {
-    res += 1;
-  }
-
-  let layout =
-    match alloc::Layout::from_size_align(res, mem::align_of::<max_align_t>()) {
-      | Err(_) => {
-        errno::set_errno(errno::ENOMEM);
-        return ptr::null_mut();
-      },
-      | Ok(result) => result
-    };
-  let ptr = unsafe { alloc::alloc_zeroed(layout) };
+
+  let ptr = alloc::allocate_zeroed(res, mem::align_of::<max_align_t>());
   if ptr.is_null() {
     errno::set_errno(errno::ENOMEM);
-    return ptr::null_mut();
   }
-
   ptr.cast()
 }
 
@@ -91,27 +195,27 @@ pub extern "C" fn ouma_realloc(
   ptr: *mut c_void,
   size: size_t
 ) -> *mut c_void {
-  if ptr.is_null() {
-    return ouma_malloc(size);
+  let new = alloc::reallocate(ptr.cast(), size, mem::align_of::<max_align_t>());
+  if new.is_null() {
+    errno::set_errno(errno::ENOMEM);
   }
+  new.cast()
+}
 
-  let layout = match alloc::Layout::from_size_align(
-    size,
-    mem::align_of::<max_align_t>()
-  ) {
-    | Err(_) => {
-      errno::set_errno(errno::ENOMEM);
-      return ptr::null_mut();
-    },
-    | Ok(result) => result
+#[no_mangle]
+pub extern "C" fn ouma_reallocarray(
+  ptr: *mut c_void,
+  nmemb: size_t,
+  size: size_t
+) -> *mut c_void {
+  let Some(res) = nmemb.checked_mul(size) else {
+    errno::set_errno(errno::ENOMEM);
+    return ptr::null_mut();
   };
 
-  unsafe { alloc::realloc(ptr.cast(), layout, size).cast() }
+  ouma_realloc(ptr, res)
 }
 
-// Inspired by mustang crate:
-// https://github.com/sunfishcode/mustang/blob/main/c-scape/src/malloc/mod.rs
-//
 #[no_mangle]
 pub extern "C" fn ouma_posix_memalign(
   memptr: *mut *mut c_void,
@@ -124,21 +228,19 @@ pub extern "C" fn ouma_posix_memalign(
     return errno::EINVAL;
   }
 
-  let ptr = ouma_aligned_alloc(alignment, size);
+  let ptr = alloc::allocate(size, alignment);
+  if ptr.is_null() {
+    return errno::ENOMEM;
+  }
   unsafe {
-    *memptr = ptr;
+    *memptr = ptr.cast();
   }
   0
 }
 
 #[no_mangle]
 pub extern "C" fn ouma_free(ptr: *mut c_void) {
-  if ptr.is_null() {
-    return;
-  }
-
-  let layout = alloc::Layout::new::<*mut c_void>();
-  unsafe { alloc::dealloc(ptr.cast(), layout) };
+  alloc::deallocate(ptr.cast());
 }
 
 #[no_mangle]
@@ -146,33 +248,323 @@ pub extern "C" fn ouma_free_sized(
   ptr: *mut c_void,
   size: size_t
 ) {
-  if ptr.is_null() {
-    return;
-  }
-
-  let layout = match alloc::Layout::from_size_align(
-    size,
-    mem::align_of::<max_align_t>()
-  ) {
-    | Err(_) => panic!("Cannot create memory layout"),
-    | Ok(result) => result
-  };
-  unsafe { alloc::dealloc(ptr.cast(), layout) };
+  alloc::deallocate_sized(ptr.cast(), size);
 }
 
 #[no_mangle]
-pub extern "C" fn free_aligned_sized(
+pub extern "C" fn ouma_free_aligned_sized(
   ptr: *mut c_void,
-  alignment: size_t,
+  _alignment: size_t,
   size: size_t
 ) {
-  if ptr.is_null() {
-    return;
+  alloc::deallocate_sized(ptr.cast(), size);
+}
+
+// The strtol and wcstol families share one parser working on u64, the signed
+// functions taking the bit pattern of its result.
+pub fn strtoint<C: Char, const MIN: u64, const MAX: u64>(
+  src: *const C,
+  endptr: *mut *mut C,
+  base: c_int
+) -> u64 {
+  let (v, len, err) = strtointeger::<u64, MIN, MAX>(src, base);
+  if !endptr.is_null() {
+    unsafe { *endptr = src.offset(len).cast_mut() };
   }
+  if err != 0 {
+    errno::set_errno(err);
+  }
+  v
+}
 
-  let layout = match alloc::Layout::from_size_align(size, alignment) {
-    | Err(_) => panic!("Cannot create memory layout"),
-    | Ok(result) => result
-  };
-  unsafe { alloc::dealloc(ptr.cast(), layout) };
+pub const LONG_MIN: u64 = c_long::MIN as u64;
+pub const LONG_MAX: u64 = c_long::MAX as u64;
+
+#[no_mangle]
+pub extern "C" fn ouma_strtol(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  base: c_int
+) -> c_long {
+  strtoint::<_, LONG_MIN, LONG_MAX>(src, endptr, base) as c_long
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strtoul(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  base: c_int
+) -> c_ulong {
+  strtoint::<_, 0, { c_ulong::MAX }>(src, endptr, base)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strtoll(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  base: c_int
+) -> c_longlong {
+  strtoint::<_, LONG_MIN, LONG_MAX>(src, endptr, base) as c_longlong
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strtoull(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  base: c_int
+) -> c_ulonglong {
+  strtoint::<_, 0, { c_ulonglong::MAX }>(src, endptr, base)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strtoimax(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  base: c_int
+) -> intmax_t {
+  strtoint::<_, LONG_MIN, LONG_MAX>(src, endptr, base) as intmax_t
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strtoumax(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  base: c_int
+) -> uintmax_t {
+  strtoint::<_, 0, { uintmax_t::MAX }>(src, endptr, base)
+}
+
+// Only ASCII white space, signs and digits matter to these functions, and
+// every locale classifies them alike.
+#[no_mangle]
+pub extern "C" fn ouma_strtol_l(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  base: c_int,
+  _locale: locale_t
+) -> c_long {
+  ouma_strtol(src, endptr, base)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strtoul_l(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  base: c_int,
+  _locale: locale_t
+) -> c_ulong {
+  ouma_strtoul(src, endptr, base)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strtoll_l(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  base: c_int,
+  _locale: locale_t
+) -> c_longlong {
+  ouma_strtoll(src, endptr, base)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strtoull_l(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  base: c_int,
+  _locale: locale_t
+) -> c_ulonglong {
+  ouma_strtoull(src, endptr, base)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_atoi(src: *const c_char) -> c_int {
+  ouma_strtol(src, ptr::null_mut(), 10) as c_int
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_atol(src: *const c_char) -> c_long {
+  ouma_strtol(src, ptr::null_mut(), 10)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_atoll(src: *const c_char) -> c_longlong {
+  ouma_strtoll(src, ptr::null_mut(), 10)
+}
+
+// The strtod family shares one parser giving the bits of each format.
+fn strtoflt(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  format: &strtofloat::Format,
+  radix: &[u8]
+) -> u128 {
+  let (v, len, err) = strtofloat::strtofloat(src, format, radix);
+  if !endptr.is_null() {
+    unsafe { *endptr = src.offset(len).cast_mut() };
+  }
+  if err != 0 {
+    errno::set_errno(err);
+  }
+  v
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strtof(
+  src: *const c_char,
+  endptr: *mut *mut c_char
+) -> f32 {
+  let v = strtoflt(src, endptr, &strtofloat::F32, numeric::radix());
+  f32::from_bits(v as u32)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strtod(
+  src: *const c_char,
+  endptr: *mut *mut c_char
+) -> f64 {
+  let v = strtoflt(src, endptr, &strtofloat::F64, numeric::radix());
+  f64::from_bits(v as u64)
+}
+
+// Rust has no long double, so ouma_strtold and ouma_strtold_l are assembly
+// entry points returning these bits as one.
+#[no_mangle]
+pub extern "C" fn __oumalibc_strtold(
+  src: *const c_char,
+  endptr: *mut *mut c_char
+) -> u128 {
+  strtoflt(src, endptr, &strtofloat::LONG_DOUBLE, numeric::radix())
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_atof(src: *const c_char) -> f64 {
+  ouma_strtod(src, ptr::null_mut())
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strtof_l(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  locale: locale_t
+) -> f32 {
+  let v = strtoflt(src, endptr, &strtofloat::F32, numeric::radix_l(locale));
+  f32::from_bits(v as u32)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strtod_l(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  locale: locale_t
+) -> f64 {
+  let v = strtoflt(src, endptr, &strtofloat::F64, numeric::radix_l(locale));
+  f64::from_bits(v as u64)
+}
+
+#[no_mangle]
+pub extern "C" fn __oumalibc_strtold_l(
+  src: *const c_char,
+  endptr: *mut *mut c_char,
+  locale: locale_t
+) -> u128 {
+  let radix = numeric::radix_l(locale);
+  strtoflt(src, endptr, &strtofloat::LONG_DOUBLE, radix)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_qsort(
+  base: *mut c_void,
+  nel: size_t,
+  width: size_t,
+  compar: extern "C" fn(*const c_void, *const c_void) -> c_int
+) {
+  sort::sort(base.cast(), nel, width, |a, b| {
+    compar(a.cast(), b.cast()).cmp(&0)
+  });
+}
+
+// The argument order of POSIX 2024 and glibc, with `arg` passed last to
+// the comparison.
+#[no_mangle]
+pub extern "C" fn ouma_qsort_r(
+  base: *mut c_void,
+  nel: size_t,
+  width: size_t,
+  compar: extern "C" fn(*const c_void, *const c_void, *mut c_void) -> c_int,
+  arg: *mut c_void
+) {
+  sort::sort(base.cast(), nel, width, |a, b| {
+    compar(a.cast(), b.cast(), arg).cmp(&0)
+  });
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_bsearch(
+  key: *const c_void,
+  base: *const c_void,
+  nel: size_t,
+  width: size_t,
+  compar: extern "C" fn(*const c_void, *const c_void) -> c_int
+) -> *mut c_void {
+  sort::search(base.cast(), nel, width, |p| compar(key, p.cast()).cmp(&0))
+    .map_or(ptr::null_mut(), |p| p.cast_mut().cast())
+}
+
+// Set from the envp given to main at startup.
+#[no_mangle]
+pub static mut ouma_environ: *mut *mut c_char = ptr::null_mut();
+
+#[no_mangle]
+pub extern "C" fn ouma_getenv(name: *const c_char) -> *mut c_char {
+  environ::name(name).map_or(ptr::null_mut(), environ::get)
+}
+
+// No secrets from the environment for set-user-ID and similar programs,
+// which the kernel marks with AT_SECURE.
+#[no_mangle]
+pub extern "C" fn ouma_secure_getenv(name: *const c_char) -> *mut c_char {
+  if start::get_auxv(start::AT_SECURE) != 0 {
+    return ptr::null_mut();
+  }
+  ouma_getenv(name)
+}
+
+fn env_result(result: Result<(), c_int>) -> c_int {
+  match result {
+    | Ok(()) => 0,
+    | Err(err) => {
+      errno::set_errno(err);
+      -1
+    }
+  }
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_setenv(
+  name: *const c_char,
+  value: *const c_char,
+  overwrite: c_int
+) -> c_int {
+  env_result(environ::name(name).and_then(|name| {
+    if value.is_null() {
+      return Err(errno::EINVAL);
+    }
+    environ::setenv(name, value, overwrite != 0)
+  }))
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_unsetenv(name: *const c_char) -> c_int {
+  env_result(environ::name(name).and_then(environ::unsetenv))
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_putenv(string: *mut c_char) -> c_int {
+  env_result(environ::putenv(string))
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_clearenv() -> c_int {
+  environ::clearenv();
+  0
 }
@@ -6,7 +6,14 @@ use {
     locale_t,
     size_t,
     std::{errno, signal, stdlib},
-    support::{string, string::string_length, stringstream::StringStream}
+    support::{
+      locale::{
+        self,
+        collate::{self, Chars}
+      },
+      string::{self, string_length},
+      stringstream::StringStream
+    }
   },
   cbitset::BitSet256,
   core::{arch::asm, ffi::c_void, fmt, ptr, slice}
@@ -529,16 +536,22 @@ pub extern "C" fn ouma_strcoll(
   s1: *const c_char,
   s2: *const c_char
 ) -> c_int {
-  ouma_strcmp(s1, s2)
+  ouma_strcoll_l(s1, s2, locale::get_thread_locale())
 }
 
 #[no_mangle]
 pub extern "C" fn ouma_strcoll_l(
   s1: *const c_char,
   s2: *const c_char,
-  _: locale_t
+  locale: locale_t
 ) -> c_int {
-  ouma_strcmp(s1, s2)
+  let locale = unsafe { &*locale };
+  if locale.collate.levels == 0 {
+    return ouma_strcmp(s1, s2);
+  }
+  let s1 = Chars::new(s1, locale.ctype);
+  let s2 = Chars::new(s2, locale.ctype);
+  collate::compare(&locale.collate, &s1, &s2) as c_int
 }
 
 #[no_mangle]
@@ -547,11 +560,7 @@ pub extern "C" fn ouma_strxfrm(
   s2: *const c_char,
   n: size_t
 ) -> size_t {
-  let len = string_length(s2);
-  if len < n {
-    ouma_strncpy(s1, s2, n);
-  }
-  len
+  ouma_strxfrm_l(s1, s2, n, locale::get_thread_locale())
 }
 
 #[no_mangle]
@@ -559,11 +568,26 @@ pub extern "C" fn ouma_strxfrm_l(
   s1: *mut c_char,
   s2: *const c_char,
   n: size_t,
-  _: locale_t
+  locale: locale_t
 ) -> size_t {
-  let len = string_length(s2);
+  let locale = unsafe { &*locale };
+  if locale.collate.levels == 0 {
+    let len = string_length(s2);
+    if len < n {
+      ouma_strncpy(s1, s2, n);
+    }
+    return len;
+  }
+  let mut len = 0;
+  let s2 = Chars::new(s2, locale.ctype);
+  collate::transform_bytes(&locale.collate, &s2, |b| {
+    if len < n {
+      unsafe { *s1.add(len) = b as c_char };
+    }
+    len += 1;
+  });
   if len < n {
-    ouma_strncpy(s1, s2, n);
+    unsafe { *s1.add(len) = 0 };
   }
   len
 }
@@ -0,0 +1,70 @@
+use {
+  crate::{
+    c_char,
+    c_int,
+    c_long,
+    locale_t,
+    size_t,
+    support::{locale, strftime, strptime}
+  },
+  core::ptr
+};
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct tm {
+  pub tm_sec: c_int,
+  pub tm_min: c_int,
+  pub tm_hour: c_int,
+  pub tm_mday: c_int,
+  pub tm_mon: c_int,
+  pub tm_year: c_int,
+  pub tm_wday: c_int,
+  pub tm_yday: c_int,
+  pub tm_isdst: c_int,
+  pub tm_gmtoff: c_long,
+  pub tm_zone: *const c_char
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strftime(
+  s: *mut c_char,
+  maxsize: size_t,
+  format: *const c_char,
+  timeptr: *const tm
+) -> size_t {
+  ouma_strftime_l(s, maxsize, format, timeptr, locale::get_thread_locale())
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strftime_l(
+  s: *mut c_char,
+  maxsize: size_t,
+  format: *const c_char,
+  timeptr: *const tm,
+  locale: locale_t
+) -> size_t {
+  let (tm, time) = unsafe { (&*timeptr, &(*locale).time) };
+  let mut sink = strftime::ArraySink::new(s.cast(), maxsize);
+  let result = strftime::format(&mut sink, format.cast(), tm, time);
+  // A result that does not fit leaves the array indeterminate, as C
+  // allows.
+  match result {
+    | Ok(()) => sink.terminate(),
+    | Err(_) => 0
+  }
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_strptime(
+  s: *const c_char,
+  format: *const c_char,
+  timeptr: *mut tm
+) -> *mut c_char {
+  let (tm, time) =
+    unsafe { (&mut *timeptr, &(*locale::get_thread_locale()).time) };
+  match strptime::parse(s.cast(), format.cast(), tm, time) {
+    | Some(end) => end.cast_mut().cast(),
+    | None => ptr::null_mut()
+  }
+}
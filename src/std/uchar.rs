@@ -36,7 +36,7 @@ pub extern "C" fn ouma_c16rtomb(
 
   let l = (loc.ctype.c32tomb)(s, c32, ps);
   if l >= 0 {
-    locale::mbstate_set_init(ps);
+    locale::mbstate_clear_surrogate(ps);
   }
   l as size_t
 }
@@ -50,12 +50,10 @@ pub extern "C" fn ouma_c32rtomb(
   let loc = unsafe { *locale::get_thread_locale() };
   let mut buf: [c_char; stdlib::MB_LEN_MAX as usize] =
     [0; stdlib::MB_LEN_MAX as usize];
+  // The shift state stays with the codeset, which returns to the initial one
+  // for the null character.
   let (s, c32) = if s.is_null() { (buf.as_mut_ptr(), 0) } else { (s, c32) };
-  let l = (loc.ctype.c32tomb)(s, c32, ps);
-  if l >= 0 {
-    locale::mbstate_set_init(ps);
-  }
-  l as size_t
+  (loc.ctype.c32tomb)(s, c32, ps) as size_t
 }
 
 #[no_mangle]
@@ -75,11 +73,10 @@ pub extern "C" fn ouma_mbrtoc16(
     (pc16, s, n)
   };
   if locale::mbstate_get_surrogate(ps, pc16) {
-    locale::mbstate_set_init(ps);
+    locale::mbstate_clear_surrogate(ps);
     return -3isize as usize;
   }
   if n == 0 {
-    locale::mbstate_set_init(ps);
     return -2isize as usize;
   }
   let mut c32: char32_t = 0;
@@ -111,7 +108,7 @@ pub extern "C" fn ouma_mbrtoc32(
   let loc = unsafe { *locale::get_thread_locale() };
   let mut c32: char32_t = 0;
   let (pc32, s, n) = if s.is_null() {
-    (core::ptr::addr_of_mut!(c32), core::ptr::null::<c_char>(), 1 as size_t)
+    (core::ptr::addr_of_mut!(c32), c"".as_ptr(), 1 as size_t)
   } else if pc32.is_null() {
     (core::ptr::addr_of_mut!(c32), s, n)
   } else {
@@ -1,18 +1,40 @@
 use {
   crate::{
+    allocator::vec::Vec,
     c_char,
     c_int,
+    c_long,
+    c_longlong,
+    c_ulong,
+    c_ulonglong,
     char32_t,
+    intmax_t,
     locale_t,
     mbstate_t,
     size_t,
-    std::{stdio, stdlib, string, uchar},
-    support::{locale, string::wstring_length},
+    ssize_t,
+    std::{
+      errno,
+      stdio,
+      stdlib::{self, LONG_MAX, LONG_MIN},
+      string,
+      time::tm,
+      uchar
+    },
+    support::{
+      locale::{self, collate, numeric},
+      printf::Sink,
+      strftime,
+      string::wstring_length,
+      strtofloat,
+      strtointeger::Char
+    },
+    uintmax_t,
     wchar_t,
     wint_t
   },
   cbitset::BitSet256,
-  core::{ffi::c_void, mem, ptr}
+  core::{ffi::c_void, mem, ptr, slice}
 };
 
 pub const WEOF: wint_t = 0xFFFF_FFFFu32;
@@ -23,11 +45,10 @@ pub extern "C" fn ouma_btowc(c: c_int) -> wint_t {
     return WEOF;
   }
   let buf = c as c_char;
-  // TODO: mutex lock
-  static mut PRIV: mbstate_t = mbstate_t::new();
+  // A single byte in the initial shift state, whatever came before.
+  let mut ps = mbstate_t::new();
   let mut wc: wchar_t = 0;
-  let status =
-    unsafe { ouma_mbrtowc(&mut wc, core::ptr::addr_of!(buf), 1, &mut PRIV) };
+  let status = ouma_mbrtowc(&mut wc, core::ptr::addr_of!(buf), 1, &raw mut ps);
   if status == usize::MAX || status == usize::MAX - 1 {
     return WEOF;
   }
@@ -511,21 +532,31 @@ pub extern "C" fn ouma_wcsdup(s: *const wchar_t) -> *mut wchar_t {
   c
 }
 
+// The characters of a wide string, for collation.
+fn wide_chars(s: *const wchar_t) -> impl Iterator<Item = char32_t> + Clone {
+  let s = unsafe { slice::from_raw_parts(s, wstring_length(s)) };
+  s.iter().map(|&c| c as char32_t)
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_wcscoll(
   s1: *const wchar_t,
   s2: *const wchar_t
 ) -> c_int {
-  ouma_wcscmp(s1, s2)
+  ouma_wcscoll_l(s1, s2, locale::get_thread_locale())
 }
 
 #[no_mangle]
 pub extern "C" fn ouma_wcscoll_l(
   s1: *const wchar_t,
   s2: *const wchar_t,
-  _loc: locale_t
+  loc: locale_t
 ) -> c_int {
-  ouma_wcscmp(s1, s2)
+  let collate = unsafe { &(*loc).collate };
+  if collate.levels == 0 {
+    return ouma_wcscmp(s1, s2);
+  }
+  collate::compare(collate, &wide_chars(s1), &wide_chars(s2)) as c_int
 }
 
 #[no_mangle]
@@ -534,11 +565,7 @@ pub extern "C" fn ouma_wcsxfrm(
   s2: *const wchar_t,
   n: size_t
 ) -> size_t {
-  let len = wstring_length(s2);
-  if len < n {
-    ouma_wcsncpy(s1, s2, n);
-  }
-  len
+  ouma_wcsxfrm_l(s1, s2, n, locale::get_thread_locale())
 }
 
 #[no_mangle]
@@ -546,18 +573,42 @@ pub extern "C" fn ouma_wcsxfrm_l(
   s1: *mut wchar_t,
   s2: *const wchar_t,
   n: size_t,
-  _loc: locale_t
+  loc: locale_t
 ) -> size_t {
-  let len = wstring_length(s2);
+  let collate = unsafe { &(*loc).collate };
+  if collate.levels == 0 {
+    let len = wstring_length(s2);
+    if len < n {
+      ouma_wcsncpy(s1, s2, n);
+    }
+    return len;
+  }
+  let mut len = 0;
+  collate::transform(collate, &wide_chars(s2), |w| {
+    if len < n {
+      unsafe { *s1.add(len) = w as wchar_t };
+    }
+    len += 1;
+  });
   if len < n {
-    ouma_wcsncpy(s1, s2, n);
+    unsafe { *s1.add(len) = 0 };
   }
   len
 }
 
 #[no_mangle]
 pub extern "C" fn ouma_wctob(c: wint_t) -> c_int {
-  if c <= 0x7F { c as c_int } else { stdio::EOF }
+  if c == WEOF {
+    return stdio::EOF;
+  }
+  let loc = unsafe { *locale::get_thread_locale() };
+  let mut buf: [c_char; stdlib::MB_LEN_MAX as usize] =
+    [0; stdlib::MB_LEN_MAX as usize];
+  let mut ps = mbstate_t::new();
+  if (loc.ctype.c32tomb)(buf.as_mut_ptr(), c as char32_t, &raw mut ps) != 1 {
+    return stdio::EOF;
+  }
+  c_int::from(buf[0] as u8)
 }
 
 #[no_mangle]
@@ -654,7 +705,7 @@ extern "C" fn ouma_mbsnrtowcs(
 }
 
 #[no_mangle]
-extern "C" fn ouma_mbsrtowcs(
+pub extern "C" fn ouma_mbsrtowcs(
   dst: *mut wchar_t,
   src: *mut *const c_char,
   len: size_t,
@@ -680,64 +731,73 @@ pub extern "C" fn ouma_wcsnrtombs(
   len: size_t,
   ps: *mut mbstate_t
 ) -> size_t {
+  static mut PRIV: mbstate_t = mbstate_t::new();
+  // TODO: mutex lock
+  let ps = if ps.is_null() { &raw mut PRIV } else { ps };
+  // Only codesets without shift states write ASCII as it is.
+  let stateful = unsafe { (*locale::get_thread_locale()).ctype.stateful };
   let mut r: usize;
   let mut dsto: usize = 0;
   let mut srco: usize = 0;
   let s1 = unsafe { *src };
-  let buf: [c_char; stdlib::MB_LEN_MAX as usize] =
+  let mut buf: [c_char; stdlib::MB_LEN_MAX as usize] =
     [0; stdlib::MB_LEN_MAX as usize];
 
   if dst.is_null() {
-    while srco < nwc {
+    // Counting leaves the state as it is.
+    let mut state = unsafe { *ps };
+    while dsto < nwc {
       let wc: wchar_t = unsafe { *s1.wrapping_add(dsto) };
 
-      if wc < 0x80 {
+      if wc < 0x80 && !stateful {
         if wc == 0 {
           return srco;
         }
 
         r = 1;
       } else {
-        r = ouma_wcrtomb(buf.as_ptr().cast_mut(), wc, ps);
+        r = ouma_wcrtomb(buf.as_mut_ptr(), wc, &raw mut state);
         if r == -1isize as usize {
           return r;
         }
+        if wc == 0 {
+          return srco + r - 1;
+        }
       }
 
       dsto += 1;
       srco += r;
     }
+    return srco;
   }
 
-  while dsto < len && srco < nwc {
+  while srco < len && dsto < nwc {
     let wc: wchar_t = unsafe { *s1.wrapping_add(dsto) };
 
-    if wc < 0x80 {
+    if wc < 0x80 && !stateful {
       unsafe { *dst.wrapping_add(srco) = wc as c_char };
 
-      if wc == 0 {
-        unsafe { *src = ptr::null_mut() };
-        return srco;
-      }
-
       r = 1;
     } else if (len - srco) >= buf.len() {
       r = ouma_wcrtomb(dst.wrapping_add(srco), wc, ps);
       if r == -1isize as usize {
         unsafe {
-          *src = s1.wrapping_add(srco);
+          *src = s1.wrapping_add(dsto);
         }
         return r;
       }
     } else {
-      r = ouma_wcrtomb(buf.as_ptr().cast_mut(), wc, ps);
+      // The character may not fit, and then it must not have shifted.
+      let state = unsafe { *ps };
+      r = ouma_wcrtomb(buf.as_mut_ptr(), wc, ps);
       if r == -1isize as usize {
         unsafe {
-          *src = s1.wrapping_add(srco);
+          *src = s1.wrapping_add(dsto);
         }
         return r;
       }
       if r > len - srco {
+        unsafe { *ps = state };
         break;
       }
 
@@ -748,14 +808,19 @@ pub extern "C" fn ouma_wcsnrtombs(
       );
     }
 
+    if wc == 0 {
+      unsafe { *src = ptr::null_mut() };
+      return srco + r - 1;
+    }
+
     dsto += 1;
     srco += r;
   }
 
   unsafe {
-    *src = s1.wrapping_add(srco);
+    *src = s1.wrapping_add(dsto);
   }
-  dsto
+  srco
 }
 
 #[no_mangle]
@@ -1052,3 +1117,217 @@ pub extern "C" fn ouma_wcswidth(
   }
   len
 }
+
+#[no_mangle]
+pub extern "C" fn ouma_wcstol(
+  src: *const wchar_t,
+  endptr: *mut *mut wchar_t,
+  base: c_int
+) -> c_long {
+  stdlib::strtoint::<_, LONG_MIN, LONG_MAX>(src, endptr, base) as c_long
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_wcstoul(
+  src: *const wchar_t,
+  endptr: *mut *mut wchar_t,
+  base: c_int
+) -> c_ulong {
+  stdlib::strtoint::<_, 0, { c_ulong::MAX }>(src, endptr, base)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_wcstoll(
+  src: *const wchar_t,
+  endptr: *mut *mut wchar_t,
+  base: c_int
+) -> c_longlong {
+  stdlib::strtoint::<_, LONG_MIN, LONG_MAX>(src, endptr, base) as c_longlong
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_wcstoull(
+  src: *const wchar_t,
+  endptr: *mut *mut wchar_t,
+  base: c_int
+) -> c_ulonglong {
+  stdlib::strtoint::<_, 0, { c_ulonglong::MAX }>(src, endptr, base)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_wcstoimax(
+  src: *const wchar_t,
+  endptr: *mut *mut wchar_t,
+  base: c_int
+) -> intmax_t {
+  stdlib::strtoint::<_, LONG_MIN, LONG_MAX>(src, endptr, base) as intmax_t
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_wcstoumax(
+  src: *const wchar_t,
+  endptr: *mut *mut wchar_t,
+  base: c_int
+) -> uintmax_t {
+  stdlib::strtoint::<_, 0, { uintmax_t::MAX }>(src, endptr, base)
+}
+
+// The wcstod family narrows the ASCII characters that can make up a number
+// and parses them like strtod, each byte standing for one wide character.
+// A radix character outside ASCII therefore ends the number.
+fn wcstoflt(
+  src: *const wchar_t,
+  endptr: *mut *mut wchar_t,
+  format: &strtofloat::Format
+) -> u128 {
+  let radix = numeric::radix();
+  let mut s = src;
+  unsafe {
+    while (*s).is_space() {
+      s = s.add(1);
+    }
+  }
+
+  let mut buf = Vec::new();
+  loop {
+    let c = unsafe { (*s.add(buf.len())).ascii() };
+    let number = c.is_ascii_alphanumeric() || b"+-()_".contains(&c);
+    if c == 0 || !(number || radix.contains(&c)) {
+      break;
+    }
+    if buf.try_reserve(2).is_err() {
+      errno::set_errno(errno::ENOMEM);
+      buf.clear();
+      break;
+    }
+    buf.push(c);
+  }
+  buf.push(0);
+
+  let (v, len, err) =
+    strtofloat::strtofloat(buf.as_ptr().cast(), format, radix);
+  if !endptr.is_null() {
+    let end = if len == 0 { src } else { s.wrapping_offset(len) };
+    unsafe { *endptr = end.cast_mut() };
+  }
+  if err != 0 {
+    errno::set_errno(err);
+  }
+  v
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_wcstof(
+  src: *const wchar_t,
+  endptr: *mut *mut wchar_t
+) -> f32 {
+  f32::from_bits(wcstoflt(src, endptr, &strtofloat::F32) as u32)
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_wcstod(
+  src: *const wchar_t,
+  endptr: *mut *mut wchar_t
+) -> f64 {
+  f64::from_bits(wcstoflt(src, endptr, &strtofloat::F64) as u64)
+}
+
+// The bits of the long double that the ouma_wcstold entry point returns.
+#[no_mangle]
+pub extern "C" fn __oumalibc_wcstold(
+  src: *const wchar_t,
+  endptr: *mut *mut wchar_t
+) -> u128 {
+  wcstoflt(src, endptr, &strtofloat::LONG_DOUBLE)
+}
+
+// Output of strftime decoded into wide characters as it comes, for
+// wcsftime, leaving room for the terminating null wide character.
+struct WideSink {
+  buf: *mut wchar_t,
+  max: usize,
+  len: usize,
+  mbtoc32: fn(*mut char32_t, *const c_char, size_t, *mut mbstate_t) -> ssize_t,
+  state: mbstate_t
+}
+
+impl Sink for WideSink {
+  fn write(
+    &mut self,
+    bytes: &[u8]
+  ) -> Result<(), c_int> {
+    for b in bytes {
+      let mut c: char32_t = 0;
+      match (self.mbtoc32)(
+        &raw mut c,
+        ptr::from_ref(b).cast(),
+        1,
+        &raw mut self.state
+      ) {
+        | -2 => continue,
+        | -1 => return Err(errno::EILSEQ),
+        | _ => {}
+      }
+      if self.max - self.len <= 1 {
+        return Err(errno::ERANGE);
+      }
+      unsafe { *self.buf.add(self.len) = c as wchar_t };
+      self.len += 1;
+    }
+    Ok(())
+  }
+}
+
+#[no_mangle]
+pub extern "C" fn ouma_wcsftime(
+  s: *mut wchar_t,
+  maxsize: size_t,
+  format: *const wchar_t,
+  timeptr: *const tm
+) -> size_t {
+  let loc = unsafe { &*locale::get_thread_locale() };
+
+  // The format is narrowed to the locale's encoding, terminator included.
+  let mut fmt = Vec::new();
+  let mut state = mbstate_t::new();
+  let mut buf = [0; stdlib::MB_LEN_MAX as usize];
+  let mut p = format;
+  loop {
+    let c = unsafe { *p };
+    let n =
+      (loc.ctype.c32tomb)(buf.as_mut_ptr(), c as char32_t, &raw mut state);
+    if n < 0 {
+      errno::set_errno(errno::EILSEQ);
+      return 0;
+    }
+    if fmt.try_reserve(n as usize).is_err() {
+      errno::set_errno(errno::ENOMEM);
+      return 0;
+    }
+    fmt.extend(buf[..n as usize].iter().map(|&b| b as u8));
+    if c == 0 {
+      break;
+    }
+    p = p.wrapping_add(1);
+  }
+
+  let mut sink = WideSink {
+    buf: s,
+    max: maxsize,
+    len: 0,
+    mbtoc32: loc.ctype.mbtoc32,
+    state: mbstate_t::new()
+  };
+  match strftime::format(
+    &mut sink,
+    fmt.as_ptr(),
+    unsafe { &*timeptr },
+    &loc.time
+  ) {
+    | Ok(()) => {
+      unsafe { *s.add(sink.len) = 0 };
+      sink.len
+    },
+    | Err(_) => 0
+  }
+}
@@ -6,6 +6,7 @@ use {
     locale_t,
     mbstate_t,
     std::{errno, stdlib},
+    support::locale,
     wctrans_t,
     wctype_t,
     wint_t
@@ -85,8 +86,63 @@ fn valid_in_locale(
   }
 }
 
-#[no_mangle]
-pub extern "C" fn ouma_iswalnum(wc: wint_t) -> c_int {
+// Whether `wc` is in the class `cc` of `locale`: as the classes of its
+// compiled LC_CTYPE say, or else as Unicode does. `only_valid` leaves out
+// the characters its codeset lacks.
+fn iswctype_in(
+  wc: wint_t,
+  cc: wctype_t,
+  locale: locale_t,
+  only_valid: bool
+) -> c_int {
+  let classes = unsafe { (*locale).classes };
+  let is = match classes.is(cc as usize, wc) {
+    | Some(is) => is,
+    | None => unicode_iswctype(wc, cc) > 0
+  };
+  c_int::from(is && (!only_valid || valid_in_locale(wc, locale)))
+}
+
+fn unicode_iswctype(
+  wc: wint_t,
+  cc: wctype_t
+) -> c_int {
+  match cc {
+    | WCTYPE_ALNUM => unicode_iswalnum(wc),
+    | WCTYPE_ALPHA => unicode_iswalpha(wc),
+    | WCTYPE_BLANK => unicode_iswblank(wc),
+    | WCTYPE_CNTRL => unicode_iswcntrl(wc),
+    | WCTYPE_DIGIT => unicode_iswdigit(wc),
+    | WCTYPE_GRAPH => unicode_iswgraph(wc),
+    | WCTYPE_LOWER => unicode_iswlower(wc),
+    | WCTYPE_PRINT => unicode_iswprint(wc),
+    | WCTYPE_PUNCT => unicode_iswpunct(wc),
+    | WCTYPE_SPACE => unicode_iswspace(wc),
+    | WCTYPE_UPPER => unicode_iswupper(wc),
+    | WCTYPE_XDIGIT => unicode_iswxdigit(wc),
+    | _ => 0
+  }
+}
+
+// What toupper, or else tolower, makes of `wc` in `locale`, by the case
+// maps of its compiled LC_CTYPE or those of Unicode. `only_valid` keeps `wc`
+// when its codeset lacks the result.
+fn towctrans_in(
+  wc: wint_t,
+  upper: bool,
+  locale: locale_t,
+  only_valid: bool
+) -> wint_t {
+  let classes = unsafe { (*locale).classes };
+  let nwc = match classes.map(upper, wc) {
+    | Some(nwc) => nwc,
+    | None if upper => unicode_towupper(wc),
+    | None => unicode_towlower(wc)
+  };
+  if !only_valid || valid_in_locale(nwc, locale) { nwc } else { wc }
+}
+
+fn unicode_iswalnum(wc: wint_t) -> c_int {
   const EXTENTS: [u32; 764] = [
     0x00018009, 0x00020819, 0x00030819, 0x00055000, 0x00059001, 0x0005a800,
     0x0005c801, 0x0005e002, 0x00060016, 0x0006c01e, 0x0007c1c9, 0x0016300b,
@@ -220,17 +276,26 @@ pub extern "C" fn ouma_iswalnum(wc: wint_t) -> c_int {
   c_int::from(wcbsearch(wc, &EXTENTS) >= 0)
 }
 
+#[no_mangle]
+pub extern "C" fn ouma_iswalnum(wc: wint_t) -> c_int {
+  iswctype_in(wc, WCTYPE_ALNUM, locale::get_thread_locale(), false)
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_iswalnum_l(
   wc: wint_t,
   locale: locale_t
 ) -> c_int {
-  c_int::from(ouma_iswalnum(wc) > 0 && valid_in_locale(wc, locale))
+  iswctype_in(wc, WCTYPE_ALNUM, locale, true)
+}
+
+fn unicode_iswalpha(wc: wint_t) -> c_int {
+  c_int::from(!(0x30..=0x39).contains(&wc) && unicode_iswalnum(wc) > 0)
 }
 
 #[no_mangle]
 pub extern "C" fn ouma_iswalpha(wc: wint_t) -> c_int {
-  c_int::from(!(0x30..=0x39).contains(&wc) && ouma_iswalnum(wc) > 0)
+  iswctype_in(wc, WCTYPE_ALPHA, locale::get_thread_locale(), false)
 }
 
 #[no_mangle]
@@ -238,11 +303,10 @@ pub extern "C" fn ouma_iswalpha_l(
   wc: wint_t,
   locale: locale_t
 ) -> c_int {
-  c_int::from(ouma_iswalpha(wc) > 0 && valid_in_locale(wc, locale))
+  iswctype_in(wc, WCTYPE_ALPHA, locale, true)
 }
 
-#[no_mangle]
-pub extern "C" fn ouma_iswblank(wc: wint_t) -> c_int {
+fn unicode_iswblank(wc: wint_t) -> c_int {
   c_int::from(
     wc == 0x9 ||
       wc == 0x20 ||
@@ -254,16 +318,20 @@ pub extern "C" fn ouma_iswblank(wc: wint_t) -> c_int {
   )
 }
 
+#[no_mangle]
+pub extern "C" fn ouma_iswblank(wc: wint_t) -> c_int {
+  iswctype_in(wc, WCTYPE_BLANK, locale::get_thread_locale(), false)
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_iswblank_l(
   wc: wint_t,
   locale: locale_t
 ) -> c_int {
-  c_int::from(ouma_iswblank(wc) > 0 && valid_in_locale(wc, locale))
+  iswctype_in(wc, WCTYPE_BLANK, locale, true)
 }
 
-#[no_mangle]
-pub extern "C" fn ouma_iswcntrl(wc: wint_t) -> c_int {
+fn unicode_iswcntrl(wc: wint_t) -> c_int {
   c_int::from(
     (wc <= 0x1f) ||
       (0x7f..=0x9f).contains(&wc) ||
@@ -271,12 +339,17 @@ pub extern "C" fn ouma_iswcntrl(wc: wint_t) -> c_int {
   )
 }
 
+#[no_mangle]
+pub extern "C" fn ouma_iswcntrl(wc: wint_t) -> c_int {
+  iswctype_in(wc, WCTYPE_CNTRL, locale::get_thread_locale(), false)
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_iswcntrl_l(
   wc: wint_t,
   locale: locale_t
 ) -> c_int {
-  c_int::from(ouma_iswcntrl(wc) > 0 && valid_in_locale(wc, locale))
+  iswctype_in(wc, WCTYPE_CNTRL, locale, true)
 }
 
 #[no_mangle]
@@ -324,9 +397,13 @@ pub extern "C" fn ouma_iswctype_l(
   }
 }
 
+fn unicode_iswdigit(wc: wint_t) -> c_int {
+  c_int::from((0x30..=0x39).contains(&wc))
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_iswdigit(wc: wint_t) -> c_int {
-  c_int::from((0x30..=0x39).contains(&wc))
+  iswctype_in(wc, WCTYPE_DIGIT, locale::get_thread_locale(), false)
 }
 
 #[no_mangle]
@@ -334,11 +411,10 @@ pub extern "C" fn ouma_iswdigit_l(
   wc: wint_t,
   locale: locale_t
 ) -> c_int {
-  c_int::from(ouma_iswdigit(wc) > 0 && valid_in_locale(wc, locale))
+  iswctype_in(wc, WCTYPE_DIGIT, locale, true)
 }
 
-#[no_mangle]
-pub extern "C" fn ouma_iswgraph(wc: wint_t) -> c_int {
+fn unicode_iswgraph(wc: wint_t) -> c_int {
   const EXTENTS: [u32; 730] = [
     0x0001085d, 0x000502d7, 0x001bd005, 0x001c2006, 0x001c6000, 0x001c7013,
     0x001d198c, 0x00298825, 0x002ac831, 0x002c6802, 0x002c8836, 0x002e801a,
@@ -466,16 +542,20 @@ pub extern "C" fn ouma_iswgraph(wc: wint_t) -> c_int {
   c_int::from(wcbsearch(wc, &EXTENTS) >= 0)
 }
 
+#[no_mangle]
+pub extern "C" fn ouma_iswgraph(wc: wint_t) -> c_int {
+  iswctype_in(wc, WCTYPE_GRAPH, locale::get_thread_locale(), false)
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_iswgraph_l(
   wc: wint_t,
   locale: locale_t
 ) -> c_int {
-  c_int::from(ouma_iswgraph(wc) > 0 && valid_in_locale(wc, locale))
+  iswctype_in(wc, WCTYPE_GRAPH, locale, true)
 }
 
-#[no_mangle]
-pub extern "C" fn ouma_iswlower(wc: wint_t) -> c_int {
+fn unicode_iswlower(wc: wint_t) -> c_int {
   const EXTENTS: [u32; 627] = [
     0x00030819, 0x0005a800, 0x00070016, 0x0007c007, 0x00080800, 0x00081800,
     0x00082800, 0x00083800, 0x00084800, 0x00085800, 0x00086800, 0x00087800,
@@ -586,16 +666,20 @@ pub extern "C" fn ouma_iswlower(wc: wint_t) -> c_int {
   c_int::from(wcbsearch(wc, &EXTENTS) >= 0)
 }
 
+#[no_mangle]
+pub extern "C" fn ouma_iswlower(wc: wint_t) -> c_int {
+  iswctype_in(wc, WCTYPE_LOWER, locale::get_thread_locale(), false)
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_iswlower_l(
   wc: wint_t,
   locale: locale_t
 ) -> c_int {
-  c_int::from(ouma_iswlower(wc) > 0 && valid_in_locale(wc, locale))
+  iswctype_in(wc, WCTYPE_LOWER, locale, true)
 }
 
-#[no_mangle]
-pub extern "C" fn ouma_iswprint(wc: wint_t) -> c_int {
+fn unicode_iswprint(wc: wint_t) -> c_int {
   const EXTENTS: [u32; 727] = [
     0x0001005e, 0x000502d7, 0x001bd005, 0x001c2006, 0x001c6000, 0x001c7013,
     0x001d198c, 0x00298825, 0x002ac831, 0x002c6802, 0x002c8836, 0x002e801a,
@@ -723,16 +807,20 @@ pub extern "C" fn ouma_iswprint(wc: wint_t) -> c_int {
   c_int::from(wcbsearch(wc, &EXTENTS) >= 0)
 }
 
+#[no_mangle]
+pub extern "C" fn ouma_iswprint(wc: wint_t) -> c_int {
+  iswctype_in(wc, WCTYPE_PRINT, locale::get_thread_locale(), false)
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_iswprint_l(
   wc: wint_t,
   locale: locale_t
 ) -> c_int {
-  c_int::from(ouma_iswprint(wc) > 0 && valid_in_locale(wc, locale))
+  iswctype_in(wc, WCTYPE_PRINT, locale, true)
 }
 
-#[no_mangle]
-pub extern "C" fn ouma_iswpunct(wc: wint_t) -> c_int {
+fn unicode_iswpunct(wc: wint_t) -> c_int {
   const EXTENTS: [u32; 558] = [
     0x0001080e, 0x0001d006, 0x0002d805, 0x0003d803, 0x00050009, 0x00055806,
     0x0005a000, 0x0005b002, 0x0005d800, 0x0005f800, 0x0006b800, 0x0007b800,
@@ -831,16 +919,20 @@ pub extern "C" fn ouma_iswpunct(wc: wint_t) -> c_int {
   c_int::from(wcbsearch(wc, &EXTENTS) >= 0)
 }
 
+#[no_mangle]
+pub extern "C" fn ouma_iswpunct(wc: wint_t) -> c_int {
+  iswctype_in(wc, WCTYPE_PUNCT, locale::get_thread_locale(), false)
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_iswpunct_l(
   wc: wint_t,
   locale: locale_t
 ) -> c_int {
-  c_int::from(ouma_iswpunct(wc) > 0 && valid_in_locale(wc, locale))
+  iswctype_in(wc, WCTYPE_PUNCT, locale, true)
 }
 
-#[no_mangle]
-pub extern "C" fn ouma_iswspace(wc: wint_t) -> c_int {
+fn unicode_iswspace(wc: wint_t) -> c_int {
   c_int::from(
     (0x9..=0xd).contains(&wc) ||
       wc == 0x20 ||
@@ -853,16 +945,20 @@ pub extern "C" fn ouma_iswspace(wc: wint_t) -> c_int {
   )
 }
 
+#[no_mangle]
+pub extern "C" fn ouma_iswspace(wc: wint_t) -> c_int {
+  iswctype_in(wc, WCTYPE_SPACE, locale::get_thread_locale(), false)
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_iswspace_l(
   wc: wint_t,
   locale: locale_t
 ) -> c_int {
-  c_int::from(ouma_iswspace(wc) > 0 && valid_in_locale(wc, locale))
+  iswctype_in(wc, WCTYPE_SPACE, locale, true)
 }
 
-#[no_mangle]
-pub extern "C" fn ouma_iswupper(wc: wint_t) -> c_int {
+fn unicode_iswupper(wc: wint_t) -> c_int {
   const EXTENTS: [u32; 609] = [
     0x00020819, 0x00060016, 0x0006c006, 0x00080000, 0x00081000, 0x00082000,
     0x00083000, 0x00084000, 0x00085000, 0x00086000, 0x00087000, 0x00088000,
@@ -970,16 +1066,20 @@ pub extern "C" fn ouma_iswupper(wc: wint_t) -> c_int {
   c_int::from(wcbsearch(wc, &EXTENTS) >= 0)
 }
 
+#[no_mangle]
+pub extern "C" fn ouma_iswupper(wc: wint_t) -> c_int {
+  iswctype_in(wc, WCTYPE_UPPER, locale::get_thread_locale(), false)
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_iswupper_l(
   wc: wint_t,
   locale: locale_t
 ) -> c_int {
-  c_int::from(ouma_iswupper(wc) > 0 && valid_in_locale(wc, locale))
+  iswctype_in(wc, WCTYPE_UPPER, locale, true)
 }
 
-#[no_mangle]
-pub extern "C" fn ouma_iswxdigit(wc: wint_t) -> c_int {
+fn unicode_iswxdigit(wc: wint_t) -> c_int {
   c_int::from(
     (0x30..=0x39).contains(&wc) ||
       (0x41..=0x46).contains(&wc) ||
@@ -987,16 +1087,20 @@ pub extern "C" fn ouma_iswxdigit(wc: wint_t) -> c_int {
   )
 }
 
+#[no_mangle]
+pub extern "C" fn ouma_iswxdigit(wc: wint_t) -> c_int {
+  iswctype_in(wc, WCTYPE_XDIGIT, locale::get_thread_locale(), false)
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_iswxdigit_l(
   wc: wint_t,
   locale: locale_t
 ) -> c_int {
-  c_int::from(ouma_iswxdigit(wc) > 0 && valid_in_locale(wc, locale))
+  iswctype_in(wc, WCTYPE_XDIGIT, locale, true)
 }
 
-#[no_mangle]
-pub extern "C" fn ouma_towlower(wc: wint_t) -> wint_t {
+fn unicode_towlower(wc: wint_t) -> wint_t {
   const EXTENTS: [u32; 668] = [
     0x00020819, 0x00060016, 0x0006c006, 0x00080000, 0x00081000, 0x00082000,
     0x00083000, 0x00084000, 0x00085000, 0x00086000, 0x00087000, 0x00088000,
@@ -1147,17 +1251,20 @@ pub extern "C" fn ouma_towlower(wc: wint_t) -> wint_t {
   wint_t::from(towctrans_search(wc, &EXTENTS, &DELTAS))
 }
 
+#[no_mangle]
+pub extern "C" fn ouma_towlower(wc: wint_t) -> wint_t {
+  towctrans_in(wc, false, locale::get_thread_locale(), false)
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_towlower_l(
   wc: wint_t,
   locale: locale_t
 ) -> wint_t {
-  let nwc = ouma_towlower(wc);
-  if valid_in_locale(nwc, locale) { nwc } else { wc }
+  towctrans_in(wc, false, locale, true)
 }
 
-#[no_mangle]
-pub extern "C" fn ouma_towupper(wc: wint_t) -> wint_t {
+fn unicode_towupper(wc: wint_t) -> wint_t {
   const EXTENTS: [u32; 684] = [
     0x00030819, 0x0005a800, 0x00070016, 0x0007c006, 0x0007f800, 0x00080800,
     0x00081800, 0x00082800, 0x00083800, 0x00084800, 0x00085800, 0x00086800,
@@ -1319,13 +1426,17 @@ pub extern "C" fn ouma_towupper(wc: wint_t) -> wint_t {
   wint_t::from(towctrans_search(wc, &EXTENTS, &DELTAS))
 }
 
+#[no_mangle]
+pub extern "C" fn ouma_towupper(wc: wint_t) -> wint_t {
+  towctrans_in(wc, true, locale::get_thread_locale(), false)
+}
+
 #[no_mangle]
 pub extern "C" fn ouma_towupper_l(
   wc: wint_t,
   locale: locale_t
 ) -> wint_t {
-  let nwc = ouma_towupper(wc);
-  if valid_in_locale(nwc, locale) { nwc } else { wc }
+  towctrans_in(wc, true, locale, true)
 }
 
 #[no_mangle]
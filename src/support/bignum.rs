@@ -0,0 +1,197 @@
+use core::cmp::Ordering;
+
+// Enough for any binary floating-point value scaled by a power of ten, the
+// widest being a binary128 subnormal times 10^4966.
+const LIMBS: usize = 264;
+
+// Unsigned integer of at most N little-endian 64-bit limbs.
+#[derive(Clone)]
+pub struct Big<const N: usize = LIMBS> {
+  limbs: [u64; N],
+  len: usize
+}
+
+impl<const N: usize> Big<N> {
+  pub fn from_u128(v: u128) -> Self {
+    let mut big = Self { limbs: [0; N], len: 2 };
+    big.limbs[0] = v as u64;
+    big.limbs[1] = (v >> 64) as u64;
+    big.trim();
+    big
+  }
+
+  fn trim(&mut self) {
+    while self.len > 0 && self.limbs[self.len - 1] == 0 {
+      self.len -= 1;
+    }
+  }
+
+  pub fn is_zero(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn bit_length(&self) -> usize {
+    if self.len == 0 {
+      return 0;
+    }
+    self.len * 64 - self.limbs[self.len - 1].leading_zeros() as usize
+  }
+
+  pub fn shl(
+    &mut self,
+    bits: usize
+  ) {
+    if self.len == 0 {
+      return;
+    }
+    let (words, bits) = (bits / 64, bits % 64);
+    assert!(self.len + words < N, "bignum overflow");
+    if bits != 0 {
+      self.limbs[self.len] = 0;
+      for i in (0..self.len).rev() {
+        self.limbs[i + 1] |= self.limbs[i] >> (64 - bits);
+        self.limbs[i] <<= bits;
+      }
+      self.len += 1;
+    }
+    if words != 0 {
+      self.limbs.copy_within(0..self.len, words);
+      self.limbs[..words].fill(0);
+      self.len += words;
+    }
+    self.trim();
+  }
+
+  pub fn mul_small(
+    &mut self,
+    m: u64
+  ) {
+    let mut carry: u64 = 0;
+    for limb in &mut self.limbs[..self.len] {
+      let v = u128::from(*limb) * u128::from(m) + u128::from(carry);
+      *limb = v as u64;
+      carry = (v >> 64) as u64;
+    }
+    if carry != 0 {
+      assert!(self.len < N, "bignum overflow");
+      self.limbs[self.len] = carry;
+      self.len += 1;
+    }
+  }
+
+  pub fn add_small(
+    &mut self,
+    a: u64
+  ) {
+    let mut carry = a;
+    for limb in &mut self.limbs[..self.len] {
+      if carry == 0 {
+        return;
+      }
+      let (v, c) = limb.overflowing_add(carry);
+      *limb = v;
+      carry = u64::from(c);
+    }
+    if carry != 0 {
+      assert!(self.len < N, "bignum overflow");
+      self.limbs[self.len] = carry;
+      self.len += 1;
+    }
+  }
+
+  pub fn mul_pow10(
+    &mut self,
+    mut n: usize
+  ) {
+    const POW10_19: u64 = 10_000_000_000_000_000_000;
+    while n >= 19 {
+      self.mul_small(POW10_19);
+      n -= 19;
+    }
+    self.mul_small(10u64.pow(n as u32));
+  }
+
+  pub fn add(
+    &mut self,
+    other: &Self
+  ) {
+    let len = self.len.max(other.len);
+    let mut carry = false;
+    for i in 0..len {
+      let a = if i < self.len { self.limbs[i] } else { 0 };
+      let (v, c1) = a.overflowing_add(other.limbs[i]);
+      let (v, c2) = v.overflowing_add(u64::from(carry));
+      self.limbs[i] = v;
+      carry = c1 || c2;
+    }
+    self.len = len;
+    if carry {
+      assert!(self.len < N, "bignum overflow");
+      self.limbs[self.len] = 1;
+      self.len += 1;
+    }
+  }
+
+  // self -= other, which must not be larger.
+  pub fn sub(
+    &mut self,
+    other: &Self
+  ) {
+    let mut borrow = false;
+    for i in 0..self.len {
+      let (v, b1) = self.limbs[i].overflowing_sub(other.limbs[i]);
+      let (v, b2) = v.overflowing_sub(u64::from(borrow));
+      self.limbs[i] = v;
+      borrow = b1 || b2;
+    }
+    self.trim();
+  }
+
+  // Divide by `other` when the quotient is known to be below ten, leaving
+  // the remainder in self.
+  pub fn div_digit(
+    &mut self,
+    other: &Self
+  ) -> u8 {
+    let mut d = 0;
+    while *self >= *other {
+      self.sub(other);
+      d += 1;
+    }
+    d
+  }
+}
+
+impl<const N: usize> PartialEq for Big<N> {
+  fn eq(
+    &self,
+    other: &Self
+  ) -> bool {
+    self.limbs[..self.len] == other.limbs[..other.len]
+  }
+}
+
+impl<const N: usize> Eq for Big<N> {}
+
+impl<const N: usize> PartialOrd for Big<N> {
+  fn partial_cmp(
+    &self,
+    other: &Self
+  ) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<const N: usize> Ord for Big<N> {
+  fn cmp(
+    &self,
+    other: &Self
+  ) -> Ordering {
+    self.len.cmp(&other.len).then_with(|| {
+      self.limbs[..self.len]
+        .iter()
+        .rev()
+        .cmp(other.limbs[..other.len].iter().rev())
+    })
+  }
+}
@@ -0,0 +1,90 @@
+// Arithmetic on the proleptic Gregorian calendar, with years as in the
+// calendar rather than since 1900 and months from 1.
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+pub fn is_leap(year: i64) -> bool {
+  year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+pub fn days_in_month(
+  year: i64,
+  mon: i32
+) -> i32 {
+  match mon {
+    | 2 if is_leap(year) => 29,
+    | 2 => 28,
+    | 4 | 6 | 9 | 11 => 30,
+    | _ => 31
+  }
+}
+
+// Days since 1970-01-01, after Howard Hinnant's days_from_civil.
+pub fn days_from_civil(
+  year: i64,
+  mon: i32,
+  day: i32
+) -> i64 {
+  let y = if mon <= 2 { year - 1 } else { year };
+  let era = y.div_euclid(400);
+  let yoe = y.rem_euclid(400);
+  let m = i64::from(mon);
+  let doy =
+    (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(day) - 1;
+  let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+  era * 146_097 + doe - 719_468
+}
+
+// The date `days` after 1970-01-01.
+pub fn civil_from_days(days: i64) -> (i64, i32, i32) {
+  let z = days + 719_468;
+  let era = z.div_euclid(146_097);
+  let doe = z.rem_euclid(146_097);
+  let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = (doy - (153 * mp + 2) / 5 + 1) as i32;
+  let mon = if mp < 10 { mp + 3 } else { mp - 9 } as i32;
+  let year = yoe + era * 400 + i64::from(mon <= 2);
+  (year, mon, day)
+}
+
+// Day of the week of a day since 1970-01-01, with Sunday as 0.
+pub fn weekday(days: i64) -> i32 {
+  (days + 4).rem_euclid(7) as i32
+}
+
+// Day of the year, from 0.
+pub fn yday(
+  year: i64,
+  mon: i32,
+  day: i32
+) -> i32 {
+  (days_from_civil(year, mon, day) - days_from_civil(year, 1, 1)) as i32
+}
+
+// Weeks in an ISO 8601 week-based year: 53 when it starts on a Thursday,
+// or on a Wednesday in a leap year.
+fn iso_weeks(year: i64) -> i32 {
+  let jan1 = weekday(days_from_civil(year, 1, 1));
+  if jan1 == 4 || jan1 == 3 && is_leap(year) { 53 } else { 52 }
+}
+
+// The ISO 8601 week-based year and week of a day given by its year, day of
+// the year and day of the week. Week 1 is the one holding the first
+// Thursday of the year.
+pub fn iso_week(
+  year: i64,
+  yday: i32,
+  wday: i32
+) -> (i64, i32) {
+  let monday_based = (wday + 6).rem_euclid(7);
+  let week = (yday - monday_based + 10).div_euclid(7);
+  if week < 1 {
+    (year - 1, iso_weeks(year - 1))
+  } else if week > iso_weeks(year) {
+    (year + 1, 1)
+  } else {
+    (year, week)
+  }
+}
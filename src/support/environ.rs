@@ -0,0 +1,213 @@
+use {
+  crate::{
+    allocator::vec::Vec,
+    c_char,
+    c_int,
+    std::{errno, stdlib},
+    support::{string, sync::Mutex}
+  },
+  core::{mem, ptr, slice}
+};
+
+// The environment array once setenv or putenv changed it, and the strings
+// setenv allocated, which are freed with the entries holding them. Strings
+// given to putenv stay the caller's.
+struct Environ {
+  array: Vec<*mut c_char>,
+  owned: Vec<*mut c_char>
+}
+
+unsafe impl Send for Environ {}
+
+static ENVIRON: Mutex<Environ> =
+  Mutex::new(Environ { array: Vec::new(), owned: Vec::new() });
+
+fn bytes<'a>(s: *const c_char) -> &'a [u8] {
+  unsafe { slice::from_raw_parts(s.cast(), string::string_length(s)) }
+}
+
+fn push<T>(
+  v: &mut Vec<T>,
+  x: T
+) -> Result<(), c_int> {
+  v.try_reserve(1).map_err(|_| errno::ENOMEM)?;
+  v.push(x);
+  Ok(())
+}
+
+// The name of a variable, which must be neither empty nor hold '='.
+pub fn name<'a>(s: *const c_char) -> Result<&'a [u8], c_int> {
+  if s.is_null() {
+    return Err(errno::EINVAL);
+  }
+  let name = bytes(s);
+  if name.is_empty() || name.contains(&b'=') {
+    return Err(errno::EINVAL);
+  }
+  Ok(name)
+}
+
+// Whether `entry` is "name=value" for `name`, without reading past its end.
+fn matches(
+  entry: *const c_char,
+  name: &[u8]
+) -> bool {
+  unsafe {
+    for (i, &c) in name.iter().enumerate() {
+      if *entry.add(i) as u8 != c {
+        return false;
+      }
+    }
+    *entry.add(name.len()) as u8 == b'='
+  }
+}
+
+// Value of the variable `name` in `environ`.
+pub fn get(name: &[u8]) -> *mut c_char {
+  let mut p = unsafe { stdlib::ouma_environ };
+  if p.is_null() {
+    return ptr::null_mut();
+  }
+  unsafe {
+    while !(*p).is_null() {
+      if matches(*p, name) {
+        return (*p).add(name.len() + 1);
+      }
+      p = p.add(1);
+    }
+  }
+  ptr::null_mut()
+}
+
+impl Environ {
+  // Make `environ` our array, copying the one from startup or the one the
+  // program installed itself.
+  fn adopt(&mut self) -> Result<(), c_int> {
+    let env = unsafe { stdlib::ouma_environ };
+    if !self.array.is_empty() && env == self.array.as_mut_ptr() {
+      return Ok(());
+    }
+    let mut array = Vec::new();
+    let mut p = env;
+    unsafe {
+      while !p.is_null() && !(*p).is_null() {
+        push(&mut array, *p)?;
+        p = p.add(1);
+      }
+    }
+    push(&mut array, ptr::null_mut())?;
+    self.array = array;
+    self.publish();
+    Ok(())
+  }
+
+  fn publish(&mut self) {
+    unsafe { stdlib::ouma_environ = self.array.as_mut_ptr() };
+  }
+
+  fn position(
+    &self,
+    name: &[u8]
+  ) -> Option<usize> {
+    let len = self.array.len() - 1;
+    self.array[..len].iter().position(|&entry| matches(entry, name))
+  }
+
+  fn release(
+    &mut self,
+    entry: *mut c_char
+  ) {
+    if let Some(i) = self.owned.iter().position(|&s| s == entry) {
+      self.owned.swap_remove(i);
+      stdlib::ouma_free(entry.cast());
+    }
+  }
+
+  // Put `entry` in place of the variable `name`, or add it.
+  fn set(
+    &mut self,
+    name: &[u8],
+    entry: *mut c_char
+  ) -> Result<(), c_int> {
+    self.adopt()?;
+    if let Some(i) = self.position(name) {
+      let old = mem::replace(&mut self.array[i], entry);
+      if old != entry {
+        self.release(old);
+      }
+    } else {
+      self.array.try_reserve(1).map_err(|_| errno::ENOMEM)?;
+      self.array.insert(self.array.len() - 1, entry);
+      self.publish();
+    }
+    Ok(())
+  }
+
+  fn remove(
+    &mut self,
+    name: &[u8]
+  ) -> Result<(), c_int> {
+    self.adopt()?;
+    while let Some(i) = self.position(name) {
+      let old = self.array.remove(i);
+      self.release(old);
+    }
+    Ok(())
+  }
+}
+
+pub fn setenv(
+  name: &[u8],
+  value: *const c_char,
+  overwrite: bool
+) -> Result<(), c_int> {
+  let mut env = ENVIRON.lock();
+  if !overwrite && !get(name).is_null() {
+    return Ok(());
+  }
+
+  let value = bytes(value);
+  let mut entry = Vec::new();
+  entry
+    .try_reserve_exact(name.len() + value.len() + 2)
+    .map_err(|_| errno::ENOMEM)?;
+  entry.extend_from_slice(name);
+  entry.push(b'=');
+  entry.extend_from_slice(value);
+  entry.push(0);
+  let entry = mem::ManuallyDrop::new(entry).as_mut_ptr().cast::<c_char>();
+
+  if let Err(err) = push(&mut env.owned, entry) {
+    stdlib::ouma_free(entry.cast());
+    return Err(err);
+  }
+  let result = env.set(name, entry);
+  if result.is_err() {
+    env.release(entry);
+  }
+  result
+}
+
+pub fn unsetenv(name: &[u8]) -> Result<(), c_int> {
+  ENVIRON.lock().remove(name)
+}
+
+// The string itself becomes the entry, so later changes to it show in the
+// environment. A string without '=' removes the variable, as in glibc.
+pub fn putenv(string: *mut c_char) -> Result<(), c_int> {
+  let s = bytes(string);
+  match s.iter().position(|&c| c == b'=') {
+    | Some(0) => Err(errno::EINVAL),
+    | Some(end) => ENVIRON.lock().set(&s[..end], string),
+    | None => unsetenv(s)
+  }
+}
+
+pub fn clearenv() {
+  let mut env = ENVIRON.lock();
+  for s in mem::take(&mut env.owned) {
+    stdlib::ouma_free(s.cast());
+  }
+  env.array = Vec::new();
+  unsafe { stdlib::ouma_environ = ptr::null_mut() };
+}
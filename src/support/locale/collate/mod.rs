@@ -0,0 +1,212 @@
+use {
+  crate::{
+    c_char,
+    char32_t,
+    mbstate_t,
+    std::stdlib,
+    support::{
+      locale::{
+        ctype::LocaleCtype,
+        format::{self, LEVELS_MAX, SEQUENCE_MAX, Section, Table}
+      },
+      string
+    }
+  },
+  core::cmp::Ordering
+};
+
+// The collation order of a locale file's LC_COLLATE. Strings compare by
+// the weights of their collation elements at the first level, then at the
+// next for as long as they tie.
+#[derive(Clone, Copy, Eq, PartialEq)]
+#[repr(C)]
+pub struct LocaleCollate {
+  // The number of levels, where 0 orders strings by code point as strcmp
+  // does.
+  pub levels: usize,
+  // The primary weight of the character 0, the others it lacks following in
+  // order.
+  pub undefined: u32,
+  pub chars: Table,
+  pub sequences: Table
+}
+
+pub const LOCALE_COLLATE_C: LocaleCollate = LocaleCollate {
+  levels: 0,
+  undefined: 0,
+  chars: Table::EMPTY,
+  sequences: Table::EMPTY
+};
+
+impl LocaleCollate {
+  pub fn load(section: &Section) -> Option<Self> {
+    let levels = section.word(format::COLLATE_LEVELS)? as usize;
+    let undefined = section.word(format::COLLATE_UNDEFINED)?;
+    if !(1..=LEVELS_MAX).contains(&levels) ||
+      undefined.checked_add(0x110000)? > format::WEIGHT_LIMIT
+    {
+      return None;
+    }
+    let chars = section.table(format::COLLATE_CHARS, format::CHAR_WORDS)?;
+    let sequences =
+      section.table(format::COLLATE_SEQUENCES, format::SEQUENCE_WORDS)?;
+    let weights = (0..chars.len())
+      .flat_map(|i| (1..=LEVELS_MAX).map(move |w| chars.get(i, w)))
+      .chain((0..sequences.len()).flat_map(|i| {
+        (SEQUENCE_MAX..format::SEQUENCE_WORDS).map(move |w| sequences.get(i, w))
+      }));
+    for weight in weights {
+      if weight >= format::WEIGHT_LIMIT {
+        return None;
+      }
+    }
+    Some(LocaleCollate { levels, undefined, chars, sequences })
+  }
+}
+
+// The characters of a NUL-terminated string in the codeset of a locale. A
+// byte that starts no character stands for itself.
+#[derive(Clone)]
+pub struct Chars {
+  s: *const c_char,
+  ctype: LocaleCtype,
+  state: mbstate_t
+}
+
+impl Chars {
+  pub fn new(
+    s: *const c_char,
+    ctype: LocaleCtype
+  ) -> Self {
+    Chars { s, ctype, state: mbstate_t::new() }
+  }
+}
+
+impl Iterator for Chars {
+  type Item = char32_t;
+
+  fn next(&mut self) -> Option<char32_t> {
+    let byte = unsafe { *self.s } as u8;
+    if byte == 0 {
+      return None;
+    }
+    let n = string::string_length(self.s).min(stdlib::MB_LEN_MAX as usize);
+    let mut c32 = 0;
+    let used =
+      (self.ctype.mbtoc32)(&raw mut c32, self.s, n, &raw mut self.state);
+    match usize::try_from(used) {
+      | Ok(used) if c32 != 0 => {
+        self.s = self.s.wrapping_add(used);
+        Some(c32)
+      },
+      | _ => {
+        self.state = mbstate_t::new();
+        self.s = self.s.wrapping_add(1);
+        Some(char32_t::from(byte))
+      }
+    }
+  }
+}
+
+// The weights of the collation elements of a string, at every level.
+struct Elements<'a, I> {
+  collate: &'a LocaleCollate,
+  chars: I
+}
+
+impl<I: Iterator<Item = char32_t> + Clone> Iterator for Elements<'_, I> {
+  type Item = [u32; LEVELS_MAX];
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let c = self.chars.next()?;
+    let sequences = &self.collate.sequences;
+    if sequences.search(c).is_ok() {
+      // The longest sequence starting here collates as one.
+      let mut key = [c, 0, 0, 0];
+      let mut n = 1;
+      for next in self.chars.clone().take(SEQUENCE_MAX - 1) {
+        key[n] = next;
+        n += 1;
+      }
+      for len in (2..=n).rev() {
+        let mut sought = [0; SEQUENCE_MAX];
+        sought[..len].copy_from_slice(&key[..len]);
+        let found = sequences.search_by(|i| {
+          (0..SEQUENCE_MAX).map(|w| sequences.get(i, w)).cmp(sought)
+        });
+        if let Ok(i) = found {
+          self.chars.nth(len - 2);
+          return Some([
+            sequences.get(i, SEQUENCE_MAX),
+            sequences.get(i, SEQUENCE_MAX + 1),
+            sequences.get(i, SEQUENCE_MAX + 2)
+          ]);
+        }
+      }
+    }
+    let chars = &self.collate.chars;
+    Some(match chars.search(c) {
+      | Ok(i) => [chars.get(i, 1), chars.get(i, 2), chars.get(i, 3)],
+      | Err(_) => [self.collate.undefined + c, 0, 0]
+    })
+  }
+}
+
+fn weights<'a, I: Iterator<Item = char32_t> + Clone + 'a>(
+  collate: &'a LocaleCollate,
+  chars: I,
+  level: usize
+) -> impl Iterator<Item = u32> + 'a {
+  Elements { collate, chars }.map(move |e| e[level]).filter(|&w| w != 0)
+}
+
+// Order two strings as the collation does, which must have levels.
+pub fn compare<I: Iterator<Item = char32_t> + Clone>(
+  collate: &LocaleCollate,
+  s1: &I,
+  s2: &I
+) -> Ordering {
+  for level in 0..collate.levels {
+    match weights(collate, s1.clone(), level).cmp(weights(
+      collate,
+      s2.clone(),
+      level
+    )) {
+      | Ordering::Equal => {},
+      | order => return order
+    }
+  }
+  Ordering::Equal
+}
+
+// Pass the sort key of a string to `out`, which orders by value as compare
+// does: each weight plus one, and 1 at the end of every level.
+pub fn transform<I: Iterator<Item = char32_t> + Clone>(
+  collate: &LocaleCollate,
+  s: &I,
+  mut out: impl FnMut(u32)
+) {
+  for level in 0..collate.levels {
+    weights(collate, s.clone(), level).for_each(|w| out(w + 1));
+    out(1);
+  }
+}
+
+// The same as bytes, without any NUL: 1 for the end of a level and each
+// weight in three digits of base 254, counting from 2.
+pub fn transform_bytes<I: Iterator<Item = char32_t> + Clone>(
+  collate: &LocaleCollate,
+  s: &I,
+  mut out: impl FnMut(u8)
+) {
+  transform(collate, s, |v| {
+    if v == 1 {
+      out(1);
+    } else {
+      let w = v - 2;
+      out((w / (254 * 254)) as u8 + 2);
+      out((w / 254 % 254) as u8 + 2);
+      out((w % 254) as u8 + 2);
+    }
+  });
+}
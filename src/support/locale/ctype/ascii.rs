@@ -51,8 +51,10 @@ fn c32tomb(
 
 pub const LOCALE_CTYPE_ASCII: locale::ctype::LocaleCtype =
   locale::ctype::LocaleCtype {
-    codeset: "US-ASCII".as_ptr().cast::<c_char>(),
+    codeset: c"US-ASCII".as_ptr(),
     mbtoc32,
     c32tomb,
-    mb_cur_max: 1
+    unshift: locale::ctype::unshift_stateless,
+    mb_cur_max: 1,
+    stateful: false
   };
@@ -0,0 +1,45 @@
+use {
+  super::{
+    LocaleCtype,
+    cjk,
+    multibyte::{self, Codec, Decoded}
+  },
+  crate::char32_t
+};
+
+// Big5: ASCII and two bytes, a lead from 0xa1 to 0xf9 and 157 trails.
+struct Big5;
+
+const TRAILS: usize = 157;
+
+impl Codec for Big5 {
+  fn decode(bytes: &[u8]) -> Decoded {
+    let col = match *bytes {
+      | [b] if b < 0x80 => return Decoded::Char(char32_t::from(b)),
+      | [0xa1..=0xf9] => return Decoded::More,
+      | [_, trail @ 0x40..=0x7e] => trail - 0x40,
+      | [_, trail @ 0xa1..=0xfe] => trail - 0xa1 + 0x3f,
+      | _ => return Decoded::Invalid
+    };
+    let row = usize::from(bytes[0] - 0xa1);
+    multibyte::lookup(&cjk::BIG5, row * TRAILS + usize::from(col))
+  }
+
+  fn encode(
+    c32: char32_t,
+    buf: &mut [u8; 4]
+  ) -> Option<usize> {
+    if c32 < 0x80 {
+      buf[0] = c32 as u8;
+      return Some(1);
+    }
+    let i = multibyte::find(&cjk::BIG5, &cjk::BIG5_BY_CHAR, c32)?;
+    let col = (i % TRAILS) as u8;
+    buf[0] = (i / TRAILS) as u8 + 0xa1;
+    buf[1] = if col < 0x3f { col + 0x40 } else { col - 0x3f + 0xa1 };
+    Some(2)
+  }
+}
+
+pub const LOCALE_CTYPE_BIG5: LocaleCtype =
+  multibyte::locale_ctype::<Big5>(c"BIG5", 2);
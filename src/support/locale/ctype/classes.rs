@@ -0,0 +1,71 @@
+use crate::{
+  support::locale::format::{self, Section, Table},
+  wint_t
+};
+
+// The character classes and case maps of a locale file's LC_CTYPE, which
+// replace those of Unicode that wctype.rs has built in.
+#[derive(Clone, Copy, Eq, PartialEq)]
+#[repr(C)]
+pub struct LocaleClasses {
+  pub loaded: bool,
+  // The ranges of each class, by wctype_t less one.
+  pub ranges: [Table; format::CLASS_NAMES.len()],
+  pub toupper: Table,
+  pub tolower: Table
+}
+
+pub const LOCALE_CLASSES_UNICODE: LocaleClasses = LocaleClasses {
+  loaded: false,
+  ranges: [Table::EMPTY; format::CLASS_NAMES.len()],
+  toupper: Table::EMPTY,
+  tolower: Table::EMPTY
+};
+
+impl LocaleClasses {
+  pub fn load(section: &Section) -> Option<Self> {
+    let mut ranges = [Table::EMPTY; format::CLASS_NAMES.len()];
+    for (i, table) in ranges.iter_mut().enumerate() {
+      *table = section.table(2 * i, format::RANGE_WORDS)?;
+    }
+    Some(LocaleClasses {
+      loaded: true,
+      ranges,
+      toupper: section.table(format::CTYPE_TOUPPER, format::CASE_WORDS)?,
+      tolower: section.table(format::CTYPE_TOLOWER, format::CASE_WORDS)?
+    })
+  }
+
+  // Whether `wc` is in the class wctype calls `class`, or None if Unicode
+  // decides.
+  pub fn is(
+    &self,
+    class: usize,
+    wc: wint_t
+  ) -> Option<bool> {
+    if !self.loaded {
+      return None;
+    }
+    let Some(ranges) = class.checked_sub(1).and_then(|i| self.ranges.get(i))
+    else {
+      return Some(false);
+    };
+    Some(match ranges.search(wc) {
+      | Ok(_) => true,
+      | Err(i) => i > 0 && wc <= ranges.get(i - 1, 1)
+    })
+  }
+
+  // What toupper or tolower makes of `wc`, or None if Unicode decides.
+  pub fn map(
+    &self,
+    upper: bool,
+    wc: wint_t
+  ) -> Option<wint_t> {
+    if !self.loaded {
+      return None;
+    }
+    let map = if upper { &self.toupper } else { &self.tolower };
+    Some(map.search(wc).map_or(wc, |i| map.get(i, 1)))
+  }
+}
@@ -0,0 +1,66 @@
+use {
+  super::{
+    LocaleCtype,
+    cjk,
+    multibyte::{self, Codec, Decoded}
+  },
+  crate::char32_t
+};
+
+// EUC-JP: ASCII, JIS X 0208 in two bytes from 0xa1, halfwidth katakana
+// after 0x8e and JIS X 0212 after 0x8f.
+struct EucJp;
+
+fn cell(b: u8) -> Option<usize> {
+  (0xa1..=0xfe).contains(&b).then(|| usize::from(b - 0xa1))
+}
+
+impl Codec for EucJp {
+  fn decode(bytes: &[u8]) -> Decoded {
+    match *bytes {
+      | [b] if b < 0x80 => Decoded::Char(char32_t::from(b)),
+      | [0x8e | 0x8f | 0xa1..=0xfe] | [0x8f, 0xa1..=0xfe] => Decoded::More,
+      | [0x8e, b @ 0xa1..=0xdf] => {
+        Decoded::Char(0xff61 + char32_t::from(b - 0xa1))
+      },
+      | [0x8f, row, col] => match (cell(row), cell(col)) {
+        | (Some(row), Some(col)) => {
+          multibyte::lookup(&cjk::JIS0212, row * 94 + col)
+        },
+        | _ => Decoded::Invalid
+      },
+      | [row @ 0xa1..=0xfe, col] => match cell(col) {
+        | Some(col) => {
+          multibyte::lookup(&cjk::JIS0208, usize::from(row - 0xa1) * 94 + col)
+        },
+        | None => Decoded::Invalid
+      },
+      | _ => Decoded::Invalid
+    }
+  }
+
+  fn encode(
+    c32: char32_t,
+    buf: &mut [u8; 4]
+  ) -> Option<usize> {
+    if c32 < 0x80 {
+      buf[0] = c32 as u8;
+      return Some(1);
+    }
+    if (0xff61..=0xff9f).contains(&c32) {
+      *buf = [0x8e, (c32 - 0xff61) as u8 + 0xa1, 0, 0];
+      return Some(2);
+    }
+    if let Some(i) = multibyte::find(&cjk::JIS0208, &cjk::JIS0208_BY_CHAR, c32)
+    {
+      *buf = [(i / 94) as u8 + 0xa1, (i % 94) as u8 + 0xa1, 0, 0];
+      return Some(2);
+    }
+    let i = multibyte::find(&cjk::JIS0212, &cjk::JIS0212_BY_CHAR, c32)?;
+    *buf = [0x8f, (i / 94) as u8 + 0xa1, (i % 94) as u8 + 0xa1, 0];
+    Some(3)
+  }
+}
+
+pub const LOCALE_CTYPE_EUCJP: LocaleCtype =
+  multibyte::locale_ctype::<EucJp>(c"EUC-JP", 3);
@@ -0,0 +1,41 @@
+use {
+  super::{
+    LocaleCtype,
+    cjk,
+    multibyte::{self, Codec, Decoded}
+  },
+  crate::char32_t
+};
+
+// EUC-KR: ASCII and KS X 1001 in two bytes from 0xa1.
+struct EucKr;
+
+impl Codec for EucKr {
+  fn decode(bytes: &[u8]) -> Decoded {
+    match *bytes {
+      | [b] if b < 0x80 => Decoded::Char(char32_t::from(b)),
+      | [0xa1..=0xfe] => Decoded::More,
+      | [row, col @ 0xa1..=0xfe] => multibyte::lookup(
+        &cjk::KSC5601,
+        usize::from(row - 0xa1) * 94 + usize::from(col - 0xa1)
+      ),
+      | _ => Decoded::Invalid
+    }
+  }
+
+  fn encode(
+    c32: char32_t,
+    buf: &mut [u8; 4]
+  ) -> Option<usize> {
+    if c32 < 0x80 {
+      buf[0] = c32 as u8;
+      return Some(1);
+    }
+    let i = multibyte::find(&cjk::KSC5601, &cjk::KSC5601_BY_CHAR, c32)?;
+    *buf = [(i / 94) as u8 + 0xa1, (i % 94) as u8 + 0xa1, 0, 0];
+    Some(2)
+  }
+}
+
+pub const LOCALE_CTYPE_EUCKR: LocaleCtype =
+  multibyte::locale_ctype::<EucKr>(c"EUC-KR", 2);
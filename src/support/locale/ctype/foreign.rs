@@ -0,0 +1,147 @@
+use {
+  super::{LocaleCtype, registry},
+  crate::{
+    allocator::vec::Vec,
+    c_char,
+    c_int,
+    char32_t,
+    mbstate_t,
+    size_t,
+    ssize_t,
+    std::{errno, stdlib},
+    support::{string, sync::Mutex}
+  },
+  core::{
+    ptr,
+    slice,
+    sync::atomic::{AtomicPtr, Ordering}
+  }
+};
+
+// An encoding as C code registers it, with the functions and constants of
+// the Encoding trait. The structure and the strings it points to are used
+// for good, so they have to stay valid and unchanged.
+#[repr(C)]
+pub struct EncodingStruct {
+  pub name: *const c_char,
+  // Other names, up to a null pointer. May be null itself.
+  pub aliases: *const *const c_char,
+  pub mb_cur_max: c_int,
+  pub stateful: bool,
+  pub mbtoc32: Option<
+    unsafe extern "C" fn(
+      *mut char32_t,
+      *const c_char,
+      size_t,
+      *mut mbstate_t
+    ) -> ssize_t
+  >,
+  pub c32tomb: Option<
+    unsafe extern "C" fn(*mut c_char, char32_t, *mut mbstate_t) -> ssize_t
+  >,
+  // May be null for encodings without shift states.
+  pub unshift:
+    Option<unsafe extern "C" fn(*mut c_char, *mut mbstate_t) -> ssize_t>
+}
+
+// The character handling of a locale calls plain functions, so each
+// encoding registered from C gets functions of its own that look it up in
+// its slot and call through.
+const SLOTS: usize = 8;
+
+static ENCODINGS: [AtomicPtr<EncodingStruct>; SLOTS] =
+  [const { AtomicPtr::new(ptr::null_mut()) }; SLOTS];
+static USED: Mutex<usize> = Mutex::new(0);
+
+fn encoding<const N: usize>() -> &'static EncodingStruct {
+  unsafe { &*ENCODINGS[N].load(Ordering::Acquire) }
+}
+
+fn mbtoc32<const N: usize>(
+  pc32: *mut char32_t,
+  s: *const c_char,
+  n: size_t,
+  ps: *mut mbstate_t
+) -> ssize_t {
+  encoding::<N>().mbtoc32.map_or(-1, |f| unsafe { f(pc32, s, n, ps) })
+}
+
+fn c32tomb<const N: usize>(
+  s: *mut c_char,
+  c32: char32_t,
+  ps: *mut mbstate_t
+) -> ssize_t {
+  encoding::<N>().c32tomb.map_or(-1, |f| unsafe { f(s, c32, ps) })
+}
+
+fn unshift<const N: usize>(
+  s: *mut c_char,
+  ps: *mut mbstate_t
+) -> ssize_t {
+  encoding::<N>().unshift.map_or(0, |f| unsafe { f(s, ps) })
+}
+
+type Functions = (
+  fn(*mut char32_t, *const c_char, size_t, *mut mbstate_t) -> ssize_t,
+  fn(*mut c_char, char32_t, *mut mbstate_t) -> ssize_t,
+  fn(*mut c_char, *mut mbstate_t) -> ssize_t
+);
+
+macro_rules! functions {
+  ($($n:literal)*) => {
+    [$((mbtoc32::<$n>, c32tomb::<$n>, unshift::<$n>)),*]
+  };
+}
+
+const FUNCTIONS: [Functions; SLOTS] = functions!(0 1 2 3 4 5 6 7);
+
+// The names of `encoding`, copied to a list of their own.
+fn aliases(
+  encoding: &EncodingStruct
+) -> Result<&'static [&'static [u8]], c_int> {
+  let mut count = 0;
+  if !encoding.aliases.is_null() {
+    while !unsafe { *encoding.aliases.add(count) }.is_null() {
+      count += 1;
+    }
+  }
+  let mut aliases = Vec::new();
+  aliases.try_reserve_exact(count).map_err(|_| errno::ENOMEM)?;
+  for i in 0..count {
+    let alias = unsafe { *encoding.aliases.add(i) };
+    let len = string::string_length(alias);
+    aliases.push(unsafe { slice::from_raw_parts(alias.cast::<u8>(), len) });
+  }
+  Ok(aliases.leak())
+}
+
+// Register `encoding` as registry::register does an Encoding. Only SLOTS
+// encodings can be, after which this fails with ENOMEM.
+pub fn register(encoding: &'static EncodingStruct) -> Result<(), c_int> {
+  if encoding.name.is_null() ||
+    encoding.mbtoc32.is_none() ||
+    encoding.c32tomb.is_none() ||
+    !(1..=stdlib::MB_LEN_MAX).contains(&encoding.mb_cur_max)
+  {
+    return Err(errno::EINVAL);
+  }
+  let mut used = USED.lock();
+  if *used == SLOTS {
+    return Err(errno::ENOMEM);
+  }
+  let aliases = aliases(encoding)?;
+  let slot = *used;
+  ENCODINGS[slot].store(ptr::from_ref(encoding).cast_mut(), Ordering::Release);
+  let (mbtoc32, c32tomb, unshift) = FUNCTIONS[slot];
+  let ctype = LocaleCtype {
+    codeset: encoding.name,
+    mbtoc32,
+    c32tomb,
+    unshift,
+    mb_cur_max: encoding.mb_cur_max,
+    stateful: encoding.stateful
+  };
+  registry::register_ctype(ctype, aliases)?;
+  *used += 1;
+  Ok(())
+}
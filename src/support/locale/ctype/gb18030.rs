@@ -0,0 +1,97 @@
+use {
+  super::{
+    LocaleCtype,
+    cjk,
+    multibyte::{self, Codec, Decoded}
+  },
+  crate::char32_t
+};
+
+// GB18030: ASCII, two bytes from a table, and four bytes covering the rest
+// of Unicode, the Basic Multilingual Plane in runs from a table and the
+// other planes from 0x90308130 on.
+struct Gb18030;
+
+const TRAILS: usize = 190;
+// The linear index of 0x90308130.
+const SUPPLEMENTARY: u32 = 189_000;
+
+fn linear(bytes: [u8; 4]) -> u32 {
+  let [a, b, c, d] = bytes.map(u32::from);
+  (((a - 0x81) * 10 + (b - 0x30)) * 126 + (c - 0x81)) * 10 + (d - 0x30)
+}
+
+fn from_linear(index: u32) -> [u8; 4] {
+  let (index, d) = (index / 10, index % 10);
+  let (index, c) = (index / 126, index % 126);
+  let (a, b) = (index / 10, index % 10);
+  [a + 0x81, b + 0x30, c + 0x81, d + 0x30].map(|x| x as u8)
+}
+
+impl Codec for Gb18030 {
+  fn decode(bytes: &[u8]) -> Decoded {
+    match *bytes {
+      | [b] if b < 0x80 => Decoded::Char(char32_t::from(b)),
+      | [0x81..=0xfe] | [_, 0x30..=0x39] | [_, _, 0x81..=0xfe] => Decoded::More,
+      | [lead, trail @ (0x40..=0x7e | 0x80..=0xfe)] => {
+        let col = trail - if trail < 0x7f { 0x40 } else { 0x41 };
+        let row = usize::from(lead - 0x81);
+        multibyte::lookup(&cjk::GB18030, row * TRAILS + usize::from(col))
+      },
+      | [a, b, c, d @ 0x30..=0x39] => {
+        let index = linear([a, b, c, d]);
+        let ranges = &cjk::GB18030_RANGES;
+        let i = ranges.partition_point(|&(start, _)| u32::from(start) <= index);
+        let c32 = if index >= SUPPLEMENTARY {
+          0x10000 + (index - SUPPLEMENTARY)
+        } else if let Some(&(start, c)) = ranges.get(i.wrapping_sub(1)) {
+          char32_t::from(c) + (index - u32::from(start))
+        } else {
+          return Decoded::Invalid;
+        };
+        if c32 > 0xffff && index < SUPPLEMENTARY ||
+          c32 > 0x10ffff ||
+          (0xd800..=0xdfff).contains(&c32)
+        {
+          return Decoded::Invalid;
+        }
+        Decoded::Char(c32)
+      },
+      | _ => Decoded::Invalid
+    }
+  }
+
+  fn encode(
+    c32: char32_t,
+    buf: &mut [u8; 4]
+  ) -> Option<usize> {
+    if c32 < 0x80 {
+      buf[0] = c32 as u8;
+      return Some(1);
+    }
+    if (0xd800..=0xdfff).contains(&c32) || c32 > 0x10ffff {
+      return None;
+    }
+    if let Some(i) = multibyte::find(&cjk::GB18030, &cjk::GB18030_BY_CHAR, c32)
+    {
+      let col = (i % TRAILS) as u8;
+      buf[0] = (i / TRAILS) as u8 + 0x81;
+      buf[1] = col + if col < 0x3f { 0x40 } else { 0x41 };
+      return Some(2);
+    }
+    let index = if c32 >= 0x10000 {
+      SUPPLEMENTARY + (c32 - 0x10000)
+    } else {
+      // Whatever the two byte codes leave is in a run.
+      let ranges = &cjk::GB18030_RANGES;
+      let i = ranges.partition_point(|&(_, c)| char32_t::from(c) <= c32);
+      let (start, c) = ranges[i.checked_sub(1)?];
+      u32::from(start) + (c32 - char32_t::from(c))
+    };
+    *buf = from_linear(index);
+    Some(4)
+  }
+}
+
+pub const LOCALE_CTYPE_GB18030: LocaleCtype =
+  multibyte::locale_ctype::<Gb18030>(c"GB18030", 4);
@@ -0,0 +1,203 @@
+use {
+  super::{LocaleCtype, cjk, multibyte},
+  crate::{
+    c_char,
+    c_uint,
+    c_ushort,
+    char32_t,
+    mbstate_t,
+    size_t,
+    ssize_t,
+    std::errno,
+    support::locale
+  }
+};
+
+// ISO-2022-JP, as in RFC 1468: seven bits, escape sequences switching
+// between ASCII, JIS X 0201 Roman and the two byte JIS X 0208. The shift
+// state is the character set in use.
+const ASCII: c_ushort = 0;
+const ROMAN: c_ushort = 1;
+const JIS0208: c_ushort = 2;
+
+const ESC: u8 = 0x1b;
+
+// What the bytes seen so far come to in a character set.
+enum Step {
+  Char(char32_t),
+  Shift(c_ushort),
+  More,
+  Invalid
+}
+
+fn step(
+  set: c_ushort,
+  bytes: &[u8]
+) -> Step {
+  match *bytes {
+    | [ESC] | [ESC, b'(' | b'$'] => Step::More,
+    | [ESC, b'(', b'B'] => Step::Shift(ASCII),
+    | [ESC, b'(', b'J'] => Step::Shift(ROMAN),
+    // JIS C 6226-1978 and JIS X 0208-1983 share the table.
+    | [ESC, b'$', b'@' | b'B'] => Step::Shift(JIS0208),
+    | [ESC | 0x0e | 0x0f | 0x80..=0xff, ..] => Step::Invalid,
+    | [0x21..=0x7e] if set == JIS0208 => Step::More,
+    | [row, col] => {
+      if !(0x21..=0x7e).contains(&col) {
+        return Step::Invalid;
+      }
+      let i = usize::from(row - 0x21) * 94 + usize::from(col - 0x21);
+      match multibyte::lookup(&cjk::JIS0208, i) {
+        | multibyte::Decoded::Char(c) => Step::Char(c),
+        | _ => Step::Invalid
+      }
+    },
+    | [0x5c] if set == ROMAN => Step::Char(0xa5),
+    | [0x7e] if set == ROMAN => Step::Char(0x203e),
+    // Control characters mean the same in every set.
+    | [b] => Step::Char(char32_t::from(b)),
+    | _ => Step::Invalid
+  }
+}
+
+// An escape sequence or the first byte of a JIS X 0208 character may wait
+// in the state: `partial` holds them, the first in the most significant
+// place, and `bytesleft` counts them.
+fn mbtoc32(
+  pc32: *mut char32_t,
+  s: *const c_char,
+  n: size_t,
+  ps: *mut mbstate_t
+) -> ssize_t {
+  static mut PRIV: mbstate_t = mbstate_t::new();
+  // TODO: mutex lock
+  let state = if ps.is_null() { &raw mut PRIV } else { ps };
+  if n < 1 {
+    return -2;
+  }
+  let mut buf = [0u8; 3];
+  let (mut len, mut partial, mut lowerbound) = (0, 0, 0);
+  locale::mbstate_get_multibyte(
+    state,
+    &raw mut len,
+    &raw mut partial,
+    &raw mut lowerbound
+  );
+  let mut len = (len as usize).min(buf.len() - 1);
+  for (i, b) in buf[..len].iter_mut().enumerate() {
+    *b = (partial >> (8 * (len - 1 - i))) as u8;
+  }
+  let mut set = locale::mbstate_get_shift(state);
+  let mut used = 0;
+  while used < n {
+    buf[len] = unsafe { *s.add(used) } as u8;
+    len += 1;
+    used += 1;
+    match step(set, &buf[..len]) {
+      | Step::Char(c) => {
+        unsafe { *pc32 = c };
+        // The null character ends a string, back in the initial state.
+        locale::mbstate_set_init(state);
+        if c != 0 {
+          locale::mbstate_set_shift(state, set);
+        }
+        return used as ssize_t;
+      },
+      | Step::Shift(to) => {
+        set = to;
+        len = 0;
+      },
+      | Step::More => {},
+      | Step::Invalid => {
+        errno::set_errno(errno::EILSEQ);
+        return -1;
+      }
+    }
+  }
+  let partial = buf[..len].iter().fold(0, |p, &b| (p << 8) | char32_t::from(b));
+  locale::mbstate_set_multibyte(state, len as c_uint, partial, 0);
+  locale::mbstate_set_shift(state, set);
+  -2
+}
+
+// The state of c32tomb and unshift when given none.
+static mut PRIV: mbstate_t = mbstate_t::new();
+
+// Write the escape sequence to `set` unless it is already in use.
+fn shift(
+  s: *mut u8,
+  set: c_ushort,
+  state: *mut mbstate_t
+) -> usize {
+  if locale::mbstate_get_shift(state) == set {
+    return 0;
+  }
+  let escape = match set {
+    | ASCII => b"\x1b(B",
+    | ROMAN => b"\x1b(J",
+    | _ => b"\x1b$B"
+  };
+  unsafe { s.copy_from_nonoverlapping(escape.as_ptr(), escape.len()) };
+  locale::mbstate_set_shift(state, set);
+  escape.len()
+}
+
+// The set to write a character in, staying in `set` where that will do,
+// and its bytes there.
+fn encode(
+  set: c_ushort,
+  c32: char32_t
+) -> Option<(c_ushort, [u8; 2], usize)> {
+  let b = c32 as u8;
+  match c32 {
+    // Lines end in ASCII, and so do strings.
+    | 0 | 0x0a | 0x0d | 0x5c | 0x7e => Some((ASCII, [b, 0], 1)),
+    | 0x0e | 0x0f | 0x1b => None,
+    | 0x01..=0x1f | 0x7f => Some((set, [b, 0], 1)),
+    | 0x20..=0x7d if set == ROMAN => Some((ROMAN, [b, 0], 1)),
+    | 0x20..=0x7d => Some((ASCII, [b, 0], 1)),
+    | 0xa5 => Some((ROMAN, [0x5c, 0], 1)),
+    | 0x203e => Some((ROMAN, [0x7e, 0], 1)),
+    | _ => {
+      let i = multibyte::find(&cjk::JIS0208, &cjk::JIS0208_BY_CHAR, c32)?;
+      Some((JIS0208, [(i / 94) as u8 + 0x21, (i % 94) as u8 + 0x21], 2))
+    }
+  }
+}
+
+fn c32tomb(
+  s: *mut c_char,
+  c32: char32_t,
+  ps: *mut mbstate_t
+) -> ssize_t {
+  // TODO: mutex lock
+  let state = if ps.is_null() { &raw mut PRIV } else { ps };
+  let Some((set, bytes, len)) = encode(locale::mbstate_get_shift(state), c32)
+  else {
+    errno::set_errno(errno::EILSEQ);
+    return -1;
+  };
+  let s = s.cast::<u8>();
+  let escape = shift(s, set, state);
+  unsafe { s.add(escape).copy_from_nonoverlapping(bytes.as_ptr(), len) };
+  (escape + len) as ssize_t
+}
+
+fn unshift(
+  s: *mut c_char,
+  ps: *mut mbstate_t
+) -> ssize_t {
+  // TODO: mutex lock
+  let state = if ps.is_null() { &raw mut PRIV } else { ps };
+  shift(s.cast::<u8>(), ASCII, state) as ssize_t
+}
+
+pub const LOCALE_CTYPE_ISO2022JP: LocaleCtype = LocaleCtype {
+  codeset: c"ISO-2022-JP".as_ptr(),
+  mbtoc32,
+  c32tomb,
+  unshift,
+  // An escape sequence and a JIS X 0208 character.
+  mb_cur_max: 5,
+  stateful: true
+};
@@ -1,29 +1,44 @@
 pub mod ascii;
+pub mod big5;
+pub mod charsets;
+pub mod cjk;
+pub mod classes;
+pub mod eucjp;
+pub mod euckr;
+pub mod foreign;
+pub mod gb18030;
+pub mod iso2022jp;
+pub mod multibyte;
+pub mod registry;
+pub mod shiftjis;
+pub mod single;
+pub mod translit;
+pub mod unicode;
+pub mod utf7;
 pub mod utf8;
 
-use {
-  crate::{c_char, c_int, char32_t, mbstate_t, size_t, ssize_t},
-  core::ptr
-};
+use crate::{c_char, c_int, char32_t, mbstate_t, size_t, ssize_t};
 
-#[derive(Clone, Copy, Eq, PartialEq)]
+#[derive(Clone, Copy)]
 #[repr(C)]
 pub struct LocaleCtype {
   pub codeset: *const c_char,
   pub mbtoc32:
     fn(*mut char32_t, *const c_char, size_t, *mut mbstate_t) -> ssize_t,
+  // Stateful encodings write the sequence returning to the initial shift
+  // state before a null character.
   pub c32tomb: fn(*mut c_char, char32_t, *mut mbstate_t) -> ssize_t,
-  pub mb_cur_max: c_int
+  // Write just that sequence, giving its length.
+  pub unshift: fn(*mut c_char, *mut mbstate_t) -> ssize_t,
+  pub mb_cur_max: c_int,
+  // Whether the encoding has shift states, as mbtowc(NULL, ...) reports.
+  pub stateful: bool
 }
 
 impl LocaleCtype {
-  pub fn new() -> Self {
-    Self {
-      codeset: ptr::null::<c_char>(),
-      mbtoc32: |_, _, _, _| unimplemented!(),
-      c32tomb: |_, _, _| unimplemented!(),
-      mb_cur_max: 0
-    }
+  // That of the C locale.
+  pub const fn new() -> Self {
+    ascii::LOCALE_CTYPE_ASCII
   }
 
   pub fn as_ptr(&mut self) -> *mut LocaleCtype {
@@ -36,3 +51,11 @@ impl Default for LocaleCtype {
     Self::new()
   }
 }
+
+// The unshift of every codeset without shift states.
+pub fn unshift_stateless(
+  _: *mut c_char,
+  _: *mut mbstate_t
+) -> ssize_t {
+  0
+}
@@ -0,0 +1,135 @@
+use {
+  super::LocaleCtype,
+  crate::{
+    c_char,
+    c_int,
+    c_uint,
+    char32_t,
+    mbstate_t,
+    size_t,
+    ssize_t,
+    std::errno,
+    support::locale
+  },
+  core::ffi::CStr
+};
+
+// What the first bytes of a character decode to.
+pub enum Decoded {
+  Char(char32_t),
+  // A valid beginning, needing more bytes.
+  More,
+  Invalid
+}
+
+// A stateless codeset of at most four bytes per character.
+pub trait Codec {
+  // Decode the bytes seen so far of one character.
+  fn decode(bytes: &[u8]) -> Decoded;
+  // Encode a character into `buf`, giving the number of bytes.
+  fn encode(
+    c32: char32_t,
+    buf: &mut [u8; 4]
+  ) -> Option<usize>;
+}
+
+// The bytes of an incomplete character wait in the state: `partial` holds
+// them, the first in the most significant place, and `bytesleft` counts
+// them.
+fn mbtoc32<C: Codec>(
+  pc32: *mut char32_t,
+  s: *const c_char,
+  n: size_t,
+  ps: *mut mbstate_t
+) -> ssize_t {
+  static mut PRIV: mbstate_t = mbstate_t::new();
+  // TODO: mutex lock
+  let state = if ps.is_null() { &raw mut PRIV } else { ps };
+  if n < 1 {
+    return -2;
+  }
+  let mut buf = [0u8; 4];
+  let (mut len, mut partial, mut lowerbound) = (0, 0, 0);
+  locale::mbstate_get_multibyte(
+    state,
+    &raw mut len,
+    &raw mut partial,
+    &raw mut lowerbound
+  );
+  let mut len = (len as usize).min(buf.len() - 1);
+  for (i, b) in buf[..len].iter_mut().enumerate() {
+    *b = (partial >> (8 * (len - 1 - i))) as u8;
+  }
+  let mut used = 0;
+  while used < n {
+    buf[len] = unsafe { *s.add(used) } as u8;
+    len += 1;
+    used += 1;
+    match C::decode(&buf[..len]) {
+      | Decoded::Char(c) => {
+        unsafe { *pc32 = c };
+        locale::mbstate_set_init(state);
+        return used as ssize_t;
+      },
+      | Decoded::More if len < buf.len() => {},
+      | _ => {
+        errno::set_errno(errno::EILSEQ);
+        return -1;
+      }
+    }
+  }
+  let partial = buf[..len].iter().fold(0, |p, &b| (p << 8) | char32_t::from(b));
+  locale::mbstate_set_multibyte(state, len as c_uint, partial, 0);
+  -2
+}
+
+fn c32tomb<C: Codec>(
+  s: *mut c_char,
+  c32: char32_t,
+  _: *mut mbstate_t
+) -> ssize_t {
+  let mut buf = [0u8; 4];
+  let Some(len) = C::encode(c32, &mut buf) else {
+    errno::set_errno(errno::EILSEQ);
+    return -1;
+  };
+  unsafe { s.cast::<u8>().copy_from_nonoverlapping(buf.as_ptr(), len) };
+  len as ssize_t
+}
+
+pub const fn locale_ctype<C: Codec>(
+  codeset: &'static CStr,
+  mb_cur_max: c_int
+) -> LocaleCtype {
+  LocaleCtype {
+    codeset: codeset.as_ptr(),
+    mbtoc32: mbtoc32::<C>,
+    c32tomb: c32tomb::<C>,
+    unshift: super::unshift_stateless,
+    mb_cur_max,
+    stateful: false
+  }
+}
+
+// The index of the code of a character in a table of the cjk module.
+pub fn find(
+  table: &[u16],
+  by_char: &[u16],
+  c32: char32_t
+) -> Option<usize> {
+  let i = by_char
+    .binary_search_by_key(&c32, |&i| char32_t::from(table[usize::from(i)]))
+    .ok()?;
+  Some(usize::from(by_char[i]))
+}
+
+// The character at an index of a table of the cjk module.
+pub fn lookup(
+  table: &[u16],
+  index: usize
+) -> Decoded {
+  match table.get(index) {
+    | Some(&c) if c != 0 => Decoded::Char(char32_t::from(c)),
+    | _ => Decoded::Invalid
+  }
+}
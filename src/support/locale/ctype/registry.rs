@@ -0,0 +1,195 @@
+use {
+  super::{
+    LocaleCtype,
+    ascii,
+    big5,
+    charsets,
+    eucjp,
+    euckr,
+    gb18030,
+    iso2022jp,
+    shiftjis,
+    single,
+    unicode,
+    utf7,
+    utf8
+  },
+  crate::{
+    allocator::vec::Vec,
+    c_char,
+    c_int,
+    char32_t,
+    mbstate_t,
+    size_t,
+    ssize_t,
+    std::errno,
+    support::sync::Mutex
+  },
+  core::{ffi::CStr, iter}
+};
+
+// A character encoding, which locales, iconv and nl_langinfo(CODESET) find
+// by name once it is registered. An embedder adds its own by implementing
+// this and calling `register` at init time, before any locale or conversion
+// descriptor names it. C code does the same with ouma_register_encoding.
+//
+// Every function works on one character and the conversion state `ps`,
+// which is null when the caller has no state of its own; the encoding then
+// keeps one, as the C functions without a state argument do.
+pub trait Encoding {
+  // The name nl_langinfo(CODESET) gives.
+  const NAME: &'static CStr;
+  // Other names for it. Names are matched ignoring case and anything but
+  // letters and digits, so "UTF-8" is also "utf8".
+  const ALIASES: &'static [&'static [u8]] = &[];
+  // The most bytes one character takes, shift sequences included, at most
+  // MB_LEN_MAX.
+  const MB_CUR_MAX: c_int;
+  // Whether there are shift states, so that the meaning of a byte depends
+  // on the ones before it.
+  const STATEFUL: bool = false;
+
+  // Decode a character from at most `n` bytes of `s` into `pc32`, giving
+  // the number of bytes it took. A character left incomplete gives -2, with
+  // the `n` bytes kept in the state; an invalid one gives -1 with errno set
+  // to EILSEQ. The null character leaves the state initial.
+  fn mbtoc32(
+    pc32: *mut char32_t,
+    s: *const c_char,
+    n: size_t,
+    ps: *mut mbstate_t
+  ) -> ssize_t;
+
+  // Encode `c32` into `s`, giving the number of bytes written, or -1 with
+  // errno set to EILSEQ if the encoding has no such character. A stateful
+  // encoding returns to the initial state before the null character.
+  fn c32tomb(
+    s: *mut c_char,
+    c32: char32_t,
+    ps: *mut mbstate_t
+  ) -> ssize_t;
+
+  // Write the sequence returning to the initial state, giving its length.
+  fn unshift(
+    _: *mut c_char,
+    _: *mut mbstate_t
+  ) -> ssize_t {
+    0
+  }
+}
+
+// The character handling of an encoding.
+pub const fn locale_ctype<E: Encoding>() -> LocaleCtype {
+  LocaleCtype {
+    codeset: E::NAME.as_ptr(),
+    mbtoc32: E::mbtoc32,
+    c32tomb: E::c32tomb,
+    unshift: E::unshift,
+    mb_cur_max: E::MB_CUR_MAX,
+    stateful: E::STATEFUL
+  }
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+  ctype: LocaleCtype,
+  aliases: &'static [&'static [u8]]
+}
+
+// The codeset name points to static data.
+unsafe impl Send for Entry {}
+
+// The letters and digits of a name, in lower case.
+pub fn key(name: &[u8]) -> impl Iterator<Item = u8> + '_ {
+  name.iter().filter(|c| c.is_ascii_alphanumeric()).map(u8::to_ascii_lowercase)
+}
+
+impl Entry {
+  fn is_called(
+    &self,
+    name: &[u8]
+  ) -> bool {
+    let codeset = unsafe { CStr::from_ptr(self.ctype.codeset) };
+    iter::once(codeset.to_bytes())
+      .chain(self.aliases.iter().copied())
+      .any(|alias| key(alias).eq(key(name)))
+  }
+}
+
+// The encodings of more than a byte per character. Those of one byte come
+// from the charsets tables.
+const MULTIBYTE: [Entry; 9] = [
+  Entry { ctype: utf8::LOCALE_CTYPE_UTF8, aliases: &[] },
+  Entry { ctype: utf7::LOCALE_CTYPE_UTF7, aliases: &[] },
+  Entry {
+    ctype: ascii::LOCALE_CTYPE_ASCII,
+    aliases: &[b"ASCII", b"ANSI_X3.4-1968", b"ISO646-US"]
+  },
+  Entry { ctype: eucjp::LOCALE_CTYPE_EUCJP, aliases: &[b"UJIS"] },
+  Entry { ctype: shiftjis::LOCALE_CTYPE_SHIFTJIS, aliases: &[b"SJIS"] },
+  Entry { ctype: gb18030::LOCALE_CTYPE_GB18030, aliases: &[] },
+  Entry { ctype: big5::LOCALE_CTYPE_BIG5, aliases: &[] },
+  Entry { ctype: euckr::LOCALE_CTYPE_EUCKR, aliases: &[] },
+  Entry { ctype: iso2022jp::LOCALE_CTYPE_ISO2022JP, aliases: &[] }
+];
+
+// The encodings of 16 and 32 bit units, which only iconv may use: a locale
+// codeset leaves null bytes out of any character but the null one.
+const UNICODE: [Entry; 8] = [
+  Entry { ctype: unicode::LOCALE_CTYPE_UTF16, aliases: &[] },
+  Entry { ctype: unicode::LOCALE_CTYPE_UTF16BE, aliases: &[] },
+  Entry { ctype: unicode::LOCALE_CTYPE_UTF16LE, aliases: &[] },
+  Entry { ctype: unicode::LOCALE_CTYPE_UTF32, aliases: &[] },
+  Entry {
+    ctype: unicode::LOCALE_CTYPE_UTF32BE,
+    aliases: &[b"UCS-4", b"UCS-4BE"]
+  },
+  Entry { ctype: unicode::LOCALE_CTYPE_UTF32LE, aliases: &[b"UCS-4LE"] },
+  Entry { ctype: unicode::LOCALE_CTYPE_UCS2, aliases: &[b"UCS-2BE"] },
+  Entry { ctype: unicode::LOCALE_CTYPE_UCS2LE, aliases: &[] }
+];
+
+static REGISTERED: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+// Make `E` known by its names, taking them over from any encoding built in
+// or registered before.
+pub fn register<E: Encoding>() -> Result<(), c_int> {
+  register_ctype(locale_ctype::<E>(), E::ALIASES)
+}
+
+// Make the encoding with the character handling `ctype` known by its
+// codeset and `aliases`, as `register` does.
+pub fn register_ctype(
+  ctype: LocaleCtype,
+  aliases: &'static [&'static [u8]]
+) -> Result<(), c_int> {
+  let mut registered = REGISTERED.lock();
+  registered.try_reserve(1).map_err(|_| errno::ENOMEM)?;
+  registered.push(Entry { ctype, aliases });
+  Ok(())
+}
+
+// The character handling of the encoding called `name`, for a locale.
+pub fn find(name: &[u8]) -> Option<LocaleCtype> {
+  let single = charsets::KEYS
+    .iter()
+    .zip(single::LOCALE_CTYPES)
+    .map(|(&aliases, ctype)| Entry { ctype, aliases });
+  REGISTERED
+    .lock()
+    .iter()
+    .rev()
+    .copied()
+    .chain(MULTIBYTE)
+    .chain(single)
+    .find(|entry| entry.is_called(name))
+    .map(|entry| entry.ctype)
+}
+
+// The character handling of the encoding called `name`, for a conversion,
+// which may also take UTF-16, UTF-32 and UCS-2.
+pub fn find_any(name: &[u8]) -> Option<LocaleCtype> {
+  find(name).or_else(|| {
+    UNICODE.iter().find(|entry| entry.is_called(name)).map(|entry| entry.ctype)
+  })
+}
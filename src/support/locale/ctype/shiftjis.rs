@@ -0,0 +1,64 @@
+use {
+  super::{
+    LocaleCtype,
+    cjk,
+    multibyte::{self, Codec, Decoded}
+  },
+  crate::char32_t
+};
+
+// Shift_JIS: ASCII, halfwidth katakana in one byte from 0xa1 and JIS X 0208
+// with two rows to each lead byte. Bytes 0x5c and 0x7e stay the backslash
+// and the tilde, so the codeset agrees with ASCII as the C locale needs.
+struct ShiftJis;
+
+impl Codec for ShiftJis {
+  fn decode(bytes: &[u8]) -> Decoded {
+    match *bytes {
+      | [b] if b < 0x80 => Decoded::Char(char32_t::from(b)),
+      | [b @ 0xa1..=0xdf] => Decoded::Char(0xff61 + char32_t::from(b - 0xa1)),
+      | [0x81..=0x9f | 0xe0..=0xef] => Decoded::More,
+      | [lead, trail @ (0x40..=0x7e | 0x80..=0xfc)] => {
+        let lead = usize::from(lead - if lead < 0xa0 { 0x81 } else { 0xc1 });
+        let trail = usize::from(trail);
+        let (row, col) = if trail >= 0x9f {
+          (lead * 2 + 1, trail - 0x9f)
+        } else if trail > 0x7f {
+          (lead * 2, trail - 0x41)
+        } else {
+          (lead * 2, trail - 0x40)
+        };
+        multibyte::lookup(&cjk::JIS0208, row * 94 + col)
+      },
+      | _ => Decoded::Invalid
+    }
+  }
+
+  fn encode(
+    c32: char32_t,
+    buf: &mut [u8; 4]
+  ) -> Option<usize> {
+    if c32 < 0x80 {
+      buf[0] = c32 as u8;
+      return Some(1);
+    }
+    if (0xff61..=0xff9f).contains(&c32) {
+      buf[0] = (c32 - 0xff61) as u8 + 0xa1;
+      return Some(1);
+    }
+    let i = multibyte::find(&cjk::JIS0208, &cjk::JIS0208_BY_CHAR, c32)?;
+    let (row, col) = ((i / 94) as u8, (i % 94) as u8);
+    buf[0] = row / 2 + if row < 62 { 0x81 } else { 0xc1 };
+    buf[1] = if row % 2 == 1 {
+      col + 0x9f
+    } else if col >= 63 {
+      col + 0x41
+    } else {
+      col + 0x40
+    };
+    Some(2)
+  }
+}
+
+pub const LOCALE_CTYPE_SHIFTJIS: LocaleCtype =
+  multibyte::locale_ctype::<ShiftJis>(c"SHIFT_JIS", 2);
@@ -0,0 +1,80 @@
+use {
+  super::{LocaleCtype, charsets},
+  crate::{
+    c_char,
+    char32_t,
+    mbstate_t,
+    size_t,
+    ssize_t,
+    std::errno,
+    support::locale
+  }
+};
+
+// Codesets of one byte per character, agreeing with ASCII below 0x80 and
+// taking the rest from a table, one instance of each function per codeset.
+fn mbtoc32<const C: usize>(
+  pc32: *mut char32_t,
+  s: *const c_char,
+  n: size_t,
+  ps: *mut mbstate_t
+) -> ssize_t {
+  if n < 1 {
+    return -2;
+  }
+  let b = unsafe { *s.cast::<u8>() };
+  let c = if b < 0x80 {
+    char32_t::from(b)
+  } else {
+    char32_t::from(charsets::HIGH[C][usize::from(b - 0x80)])
+  };
+  if c == 0 && b != 0 {
+    errno::set_errno(errno::EILSEQ);
+    return -1;
+  }
+  unsafe { *pc32 = c };
+  locale::mbstate_set_init(ps);
+  1
+}
+
+fn c32tomb<const C: usize>(
+  s: *mut c_char,
+  c32: char32_t,
+  _: *mut mbstate_t
+) -> ssize_t {
+  let b = if c32 < 0x80 {
+    Some(c32 as u8)
+  } else {
+    charsets::HIGH[C]
+      .iter()
+      .position(|&c| c != 0 && char32_t::from(c) == c32)
+      .map(|i| i as u8 + 0x80)
+  };
+  let Some(b) = b else {
+    errno::set_errno(errno::EILSEQ);
+    return -1;
+  };
+  unsafe { *s = b as c_char };
+  1
+}
+
+const fn locale_ctype<const C: usize>() -> LocaleCtype {
+  LocaleCtype {
+    codeset: charsets::NAMES[C].as_ptr(),
+    mbtoc32: mbtoc32::<C>,
+    c32tomb: c32tomb::<C>,
+    unshift: super::unshift_stateless,
+    mb_cur_max: 1,
+    stateful: false
+  }
+}
+
+macro_rules! locale_ctypes {
+  ($($c:literal)*) => {
+    [$(locale_ctype::<$c>()),*]
+  };
+}
+
+pub const LOCALE_CTYPES: [LocaleCtype; charsets::COUNT] = locale_ctypes!(
+  0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25
+);
@@ -0,0 +1,190 @@
+use {
+  super::LocaleCtype,
+  crate::{
+    c_char,
+    c_int,
+    c_uint,
+    c_ushort,
+    char32_t,
+    mbstate_t,
+    size_t,
+    ssize_t,
+    std::errno,
+    support::locale
+  },
+  core::ffi::CStr
+};
+
+// The Unicode encoding forms of 16 and 32 bit units. Their characters hold
+// null bytes, so only iconv uses them, never a locale.
+const BIG: u8 = 0;
+const LITTLE: u8 = 1;
+// Decoding follows a byte order mark and takes big endian without one;
+// encoding writes one before the first character, in big endian.
+const MARKED: u8 = 2;
+
+// The shift state of a marked encoding is the byte order in use, plus one,
+// and 0 until it is known.
+const MARKED_BIG: c_ushort = BIG as c_ushort + 1;
+const MARKED_LITTLE: c_ushort = LITTLE as c_ushort + 1;
+
+// The bytes of an incomplete unit wait in `partial`, the first in the most
+// significant place, and `bytesleft` counts them. A high surrogate waits in
+// `lowerbound` for its low one. The byte order found stays after the null
+// character, as the text goes on in it.
+fn mbtoc32<const UNIT: usize, const ORDER: u8, const SURROGATES: bool>(
+  pc32: *mut char32_t,
+  s: *const c_char,
+  n: size_t,
+  ps: *mut mbstate_t
+) -> ssize_t {
+  static mut PRIV: mbstate_t = mbstate_t::new();
+  // TODO: mutex lock
+  let state = if ps.is_null() { &raw mut PRIV } else { ps };
+  if n < 1 {
+    return -2;
+  }
+  let (mut len, mut partial, mut high) = (0, 0, 0);
+  locale::mbstate_get_multibyte(
+    state,
+    &raw mut len,
+    &raw mut partial,
+    &raw mut high
+  );
+  let mut shift = locale::mbstate_get_shift(state);
+  let mut used = 0;
+  let c = loop {
+    if used == n {
+      locale::mbstate_set_multibyte(state, len, partial, high);
+      locale::mbstate_set_shift(state, shift);
+      return -2;
+    }
+    partial = (partial << 8) | char32_t::from(unsafe { *s.add(used) } as u8);
+    used += 1;
+    len += 1;
+    if len < UNIT as c_uint {
+      continue;
+    }
+    let mut unit = partial;
+    (len, partial) = (0, 0);
+    if ORDER == MARKED && shift == 0 {
+      shift = MARKED_BIG;
+      if unit == 0xfeff {
+        continue;
+      }
+      if unit == 0xfeff_u32.swap_bytes() >> (32 - 8 * UNIT) {
+        shift = MARKED_LITTLE;
+        continue;
+      }
+    }
+    if ORDER == LITTLE || shift == MARKED_LITTLE {
+      unit = unit.swap_bytes() >> (32 - 8 * UNIT);
+    }
+    match unit {
+      | 0xd800..=0xdbff if SURROGATES && high == 0 => high = unit,
+      | 0xdc00..=0xdfff if high != 0 => {
+        break 0x10000 + ((high & 0x3ff) << 10 | (unit & 0x3ff));
+      },
+      | 0xd800..=0xdfff | 0x110000.. => {
+        errno::set_errno(errno::EILSEQ);
+        return -1;
+      },
+      | _ if high == 0 => break unit,
+      | _ => {
+        errno::set_errno(errno::EILSEQ);
+        return -1;
+      }
+    }
+  };
+  unsafe { *pc32 = c };
+  locale::mbstate_set_multibyte(state, 0, 0, 0);
+  locale::mbstate_set_shift(state, shift);
+  used as ssize_t
+}
+
+fn c32tomb<const UNIT: usize, const ORDER: u8, const SURROGATES: bool>(
+  s: *mut c_char,
+  c32: char32_t,
+  ps: *mut mbstate_t
+) -> ssize_t {
+  static mut PRIV: mbstate_t = mbstate_t::new();
+  // TODO: mutex lock
+  let state = if ps.is_null() { &raw mut PRIV } else { ps };
+  let mut units = [0; 3];
+  let mut count = 0;
+  if ORDER == MARKED && locale::mbstate_get_shift(state) == 0 {
+    units[0] = 0xfeff;
+    count = 1;
+  }
+  match c32 {
+    | 0x10000..=0x10ffff if UNIT == 2 && SURROGATES => {
+      let c = c32 - 0x10000;
+      units[count] = 0xd800 | c >> 10;
+      units[count + 1] = 0xdc00 | (c & 0x3ff);
+      count += 2;
+    },
+    | 0..=0xd7ff | 0xe000..=0xffff => {
+      units[count] = c32;
+      count += 1;
+    },
+    | 0x10000..=0x10ffff if UNIT == 4 => {
+      units[count] = c32;
+      count += 1;
+    },
+    | _ => {
+      errno::set_errno(errno::EILSEQ);
+      return -1;
+    }
+  }
+  if ORDER == MARKED {
+    locale::mbstate_set_shift(state, MARKED_BIG);
+  }
+  let s = s.cast::<u8>();
+  for (i, &unit) in units[..count].iter().enumerate() {
+    let bytes = if ORDER == LITTLE {
+      unit.to_le_bytes()
+    } else {
+      (unit << (32 - 8 * UNIT)).to_be_bytes()
+    };
+    unsafe {
+      s.add(i * UNIT).copy_from_nonoverlapping(bytes.as_ptr(), UNIT);
+    }
+  }
+  (count * UNIT) as ssize_t
+}
+
+const fn locale_ctype<
+  const UNIT: usize,
+  const ORDER: u8,
+  const SURROGATES: bool
+>(
+  codeset: &'static CStr
+) -> LocaleCtype {
+  LocaleCtype {
+    codeset: codeset.as_ptr(),
+    mbtoc32: mbtoc32::<UNIT, ORDER, SURROGATES>,
+    c32tomb: c32tomb::<UNIT, ORDER, SURROGATES>,
+    unshift: super::unshift_stateless,
+    // A byte order mark and a surrogate pair, or one unit.
+    mb_cur_max: if UNIT == 2 { 6 } else { 8 } as c_int,
+    stateful: false
+  }
+}
+
+pub const LOCALE_CTYPE_UTF16: LocaleCtype =
+  locale_ctype::<2, MARKED, true>(c"UTF-16");
+pub const LOCALE_CTYPE_UTF16BE: LocaleCtype =
+  locale_ctype::<2, BIG, true>(c"UTF-16BE");
+pub const LOCALE_CTYPE_UTF16LE: LocaleCtype =
+  locale_ctype::<2, LITTLE, true>(c"UTF-16LE");
+pub const LOCALE_CTYPE_UTF32: LocaleCtype =
+  locale_ctype::<4, MARKED, false>(c"UTF-32");
+pub const LOCALE_CTYPE_UTF32BE: LocaleCtype =
+  locale_ctype::<4, BIG, false>(c"UTF-32BE");
+pub const LOCALE_CTYPE_UTF32LE: LocaleCtype =
+  locale_ctype::<4, LITTLE, false>(c"UTF-32LE");
+// UCS-2 is big endian unless marked otherwise, as in ISO 10646.
+pub const LOCALE_CTYPE_UCS2: LocaleCtype =
+  locale_ctype::<2, BIG, false>(c"UCS-2");
+pub const LOCALE_CTYPE_UCS2LE: LocaleCtype =
+  locale_ctype::<2, LITTLE, false>(c"UCS-2LE");
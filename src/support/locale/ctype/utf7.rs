@@ -0,0 +1,236 @@
+use {
+  super::LocaleCtype,
+  crate::{
+    c_char,
+    c_ushort,
+    char32_t,
+    mbstate_t,
+    size_t,
+    ssize_t,
+    std::errno,
+    support::locale
+  }
+};
+
+// UTF-7, as in RFC 2152: characters are written as they are or, after a
+// '+', as UTF-16 in modified base64 up to a '-' or any other character. The
+// shift state tells which, `partial` holds the bits not yet taken and
+// `bytesleft` counts them. Decoding keeps a high surrogate in `lowerbound`
+// until its low one comes.
+const DIRECT: c_ushort = 0;
+// Right after the '+', where a '-' stands for the '+' itself.
+const OPENED: c_ushort = 1;
+const BASE64: c_ushort = 2;
+
+const ALPHABET: &[u8; 64] =
+  b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(b: u8) -> Option<char32_t> {
+  ALPHABET.iter().position(|&a| a == b).map(|i| i as char32_t)
+}
+
+// Characters written as they are, the RFC's sets D and O and white space.
+fn decodes_direct(b: u8) -> bool {
+  matches!(b, 0 | b'\t' | b'\n' | b'\r') ||
+    (b' '..=b'}').contains(&b) && b != b'+' && b != b'\\'
+}
+
+// Of those, the ones c32tomb writes as they are, leaving out the set O
+// that mail gateways may mangle.
+fn encodes_direct(c32: char32_t) -> bool {
+  u8::try_from(c32).is_ok_and(|b| {
+    b.is_ascii_alphanumeric() || b"\0\t\n\r '(),-./:?".contains(&b)
+  })
+}
+
+fn mbtoc32(
+  pc32: *mut char32_t,
+  s: *const c_char,
+  n: size_t,
+  ps: *mut mbstate_t
+) -> ssize_t {
+  static mut PRIV: mbstate_t = mbstate_t::new();
+  // TODO: mutex lock
+  let state = if ps.is_null() { &raw mut PRIV } else { ps };
+  if n < 1 {
+    return -2;
+  }
+  let (mut nbits, mut bits, mut high) = (0, 0, 0);
+  locale::mbstate_get_multibyte(
+    state,
+    &raw mut nbits,
+    &raw mut bits,
+    &raw mut high
+  );
+  let mut shift = locale::mbstate_get_shift(state);
+  let mut used = 0;
+  let c = loop {
+    if used == n {
+      locale::mbstate_set_multibyte(state, nbits, bits, high);
+      locale::mbstate_set_shift(state, shift);
+      return -2;
+    }
+    let b = unsafe { *s.add(used) } as u8;
+    used += 1;
+    if shift == DIRECT {
+      if b == b'+' {
+        shift = OPENED;
+        continue;
+      }
+      if decodes_direct(b) {
+        break char32_t::from(b);
+      }
+    } else if let Some(v) = base64(b) {
+      shift = BASE64;
+      bits = (bits << 6) | v;
+      nbits += 6;
+      if nbits < 16 {
+        continue;
+      }
+      nbits -= 16;
+      let c16 = bits >> nbits;
+      bits &= (1 << nbits) - 1;
+      match c16 {
+        | 0xd800..=0xdbff if high == 0 => high = c16,
+        | 0xdc00..=0xdfff if high != 0 => {
+          let c = 0x10000 + ((high & 0x3ff) << 10 | (c16 & 0x3ff));
+          high = 0;
+          break c;
+        },
+        | 0x0000..=0xd7ff | 0xe000..=0xffff if high == 0 => break c16,
+        | _ => {
+          errno::set_errno(errno::EILSEQ);
+          return -1;
+        }
+      }
+      continue;
+    } else if shift == OPENED && b == b'-' {
+      shift = DIRECT;
+      break char32_t::from(b'+');
+    } else if shift == BASE64 && high == 0 && nbits < 6 && bits == 0 {
+      // What is left over pads the last base64 character out.
+      shift = DIRECT;
+      nbits = 0;
+      if b == b'-' {
+        continue;
+      }
+      if decodes_direct(b) {
+        break char32_t::from(b);
+      }
+    }
+    errno::set_errno(errno::EILSEQ);
+    return -1;
+  };
+  unsafe { *pc32 = c };
+  if c == 0 {
+    locale::mbstate_set_init(state);
+  } else {
+    locale::mbstate_set_multibyte(state, nbits, bits, high);
+    locale::mbstate_set_shift(state, shift);
+  }
+  used as ssize_t
+}
+
+// The state of c32tomb and unshift when given none.
+static mut PRIV: mbstate_t = mbstate_t::new();
+
+// Write the bits left over and the '-' closing base64, if it is open.
+fn close(
+  s: *mut u8,
+  state: *mut mbstate_t
+) -> usize {
+  if locale::mbstate_get_shift(state) == DIRECT {
+    return 0;
+  }
+  let (mut nbits, mut bits, mut lowerbound) = (0, 0, 0);
+  locale::mbstate_get_multibyte(
+    state,
+    &raw mut nbits,
+    &raw mut bits,
+    &raw mut lowerbound
+  );
+  let mut len = 0;
+  if nbits > 0 {
+    unsafe { *s = ALPHABET[(bits << (6 - nbits)) as usize & 0x3f] };
+    len += 1;
+  }
+  unsafe { *s.add(len) = b'-' };
+  locale::mbstate_set_multibyte(state, 0, 0, 0);
+  locale::mbstate_set_shift(state, DIRECT);
+  len + 1
+}
+
+fn c32tomb(
+  s: *mut c_char,
+  c32: char32_t,
+  ps: *mut mbstate_t
+) -> ssize_t {
+  // TODO: mutex lock
+  let state = if ps.is_null() { &raw mut PRIV } else { ps };
+  let s = s.cast::<u8>();
+  let shift = locale::mbstate_get_shift(state);
+  if encodes_direct(c32) {
+    let len = close(s, state);
+    unsafe { *s.add(len) = c32 as u8 };
+    return len as ssize_t + 1;
+  }
+  if c32 == char32_t::from(b'+') && shift == DIRECT {
+    unsafe { s.copy_from_nonoverlapping(b"+-".as_ptr(), 2) };
+    return 2;
+  }
+  let (units, count) = match c32 {
+    | 0xd800..=0xdfff | 0x110000.. => {
+      errno::set_errno(errno::EILSEQ);
+      return -1;
+    },
+    | 0x10000.. => {
+      let c = c32 - 0x10000;
+      ([0xd800 | c >> 10, 0xdc00 | (c & 0x3ff)], 2)
+    },
+    | _ => ([c32, 0], 1)
+  };
+  let mut len = 0;
+  if shift == DIRECT {
+    unsafe { *s = b'+' };
+    len = 1;
+  }
+  let (mut nbits, mut bits, mut lowerbound) = (0, 0, 0);
+  locale::mbstate_get_multibyte(
+    state,
+    &raw mut nbits,
+    &raw mut bits,
+    &raw mut lowerbound
+  );
+  for &unit in &units[..count] {
+    bits = (bits << 16) | unit;
+    nbits += 16;
+    while nbits >= 6 {
+      nbits -= 6;
+      unsafe { *s.add(len) = ALPHABET[(bits >> nbits) as usize & 0x3f] };
+      len += 1;
+    }
+    bits &= (1 << nbits) - 1;
+  }
+  locale::mbstate_set_multibyte(state, nbits, bits, 0);
+  locale::mbstate_set_shift(state, BASE64);
+  len as ssize_t
+}
+
+fn unshift(
+  s: *mut c_char,
+  ps: *mut mbstate_t
+) -> ssize_t {
+  // TODO: mutex lock
+  let state = if ps.is_null() { &raw mut PRIV } else { ps };
+  close(s.cast::<u8>(), state) as ssize_t
+}
+
+pub const LOCALE_CTYPE_UTF7: LocaleCtype = LocaleCtype {
+  codeset: c"UTF-7".as_ptr(),
+  mbtoc32,
+  c32tomb,
+  unshift,
+  // A '+' and a surrogate pair, 32 bits, in base64.
+  mb_cur_max: 6,
+  stateful: true
+};
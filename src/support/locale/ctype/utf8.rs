@@ -151,8 +151,10 @@ fn mbtoc32(
 
 pub const LOCALE_CTYPE_UTF8: locale::ctype::LocaleCtype =
   locale::ctype::LocaleCtype {
-    codeset: "UTF-8".as_ptr().cast::<c_char>(),
+    codeset: c"UTF-8".as_ptr(),
     mbtoc32,
     c32tomb,
-    mb_cur_max: 4
+    unshift: locale::ctype::unshift_stateless,
+    mb_cur_max: 4,
+    stateful: false
   };
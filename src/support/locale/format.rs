@@ -0,0 +1,264 @@
+use core::{cmp::Ordering, ffi::CStr, ptr};
+
+// The compiled locale files newlocale maps from LOCPATH, and localedef
+// writes. This module uses nothing but core, so that localedef builds it
+// as it is.
+//
+// Every number is a little-endian 32-bit word. A file starts with a header:
+//
+//   magic    8 bytes, MAGIC
+//   version  VERSION
+//   codeset  offset of the name of the codeset its strings are in
+//   count    number of sections
+//
+// followed by `count` section entries of three words each: the category,
+// numbered as LC_CTYPE to LC_MESSAGES, the offset of the section and its
+// length in words. A category without a section keeps the conventions of C.
+//
+// A section is a list of words, whose meaning is fixed for each category
+// below. A string is the offset of a NUL-terminated string and a table two
+// words, the offset and the number of its records. Offsets are counted from
+// the start of the file.
+pub const MAGIC: [u8; 8] = *b"ouma-loc";
+pub const VERSION: u32 = 1;
+
+pub const HEADER_SIZE: usize = 20;
+pub const ENTRY_SIZE: usize = 12;
+
+pub const LC_CTYPE: u32 = 0;
+pub const LC_NUMERIC: u32 = 1;
+pub const LC_TIME: u32 = 2;
+pub const LC_COLLATE: u32 = 3;
+pub const LC_MONETARY: u32 = 4;
+pub const LC_MESSAGES: u32 = 5;
+
+// LC_CTYPE: a table of ranges for each class wctype knows, in the order of
+// CLASS_NAMES, then the tables of toupper and tolower. Ranges are records
+// of the first and the last character, sorted and apart, and case maps
+// records of a character and what it maps to, sorted by the former.
+pub const CLASS_NAMES: [&str; 12] = [
+  "alnum", "alpha", "blank", "cntrl", "digit", "graph", "lower", "print",
+  "punct", "space", "upper", "xdigit"
+];
+pub const CTYPE_TOUPPER: usize = 2 * CLASS_NAMES.len();
+pub const CTYPE_TOLOWER: usize = CTYPE_TOUPPER + 2;
+pub const CTYPE_WORDS: usize = CTYPE_TOLOWER + 2;
+pub const RANGE_WORDS: usize = 2;
+pub const CASE_WORDS: usize = 2;
+
+// LC_NUMERIC: the strings decimal_point, thousands_sep and grouping.
+pub const NUMERIC_WORDS: usize = 3;
+
+// LC_MONETARY: the strings int_curr_symbol, currency_symbol,
+// mon_decimal_point, mon_thousands_sep, mon_grouping, positive_sign,
+// negative_sign and the CRNCYSTR of nl_langinfo, then the values
+// int_frac_digits, frac_digits, p_cs_precedes, p_sep_by_space,
+// n_cs_precedes, n_sep_by_space, p_sign_posn, n_sign_posn and the same six
+// with int_ in front, CHAR_MAX standing for none.
+pub const MONETARY_STRINGS: usize = 8;
+pub const MONETARY_WORDS: usize = MONETARY_STRINGS + 14;
+
+// LC_TIME: the strings abday, day, abmon, mon, ab_alt_mon, alt_mon and
+// am_pm, each of them in order, then d_t_fmt, d_fmt, t_fmt, t_fmt_ampm,
+// era, era_d_fmt, era_t_fmt, era_d_t_fmt and alt_digits.
+pub const TIME_WORDS: usize = 7 + 7 + 12 + 12 + 12 + 12 + 2 + 9;
+
+// LC_COLLATE: the number of levels compared, the weight characters the
+// table lacks start from, the table of characters and that of sequences
+// collating as one. A character has a record of itself and its weights at
+// the three levels, sorted by character. A sequence has one of up to
+// SEQUENCE_MAX characters, padded with zeros, and its weights, sorted by the
+// characters. A weight of 0 leaves the element out at that level.
+pub const COLLATE_LEVELS: usize = 0;
+pub const COLLATE_UNDEFINED: usize = 1;
+pub const COLLATE_CHARS: usize = 2;
+pub const COLLATE_SEQUENCES: usize = 4;
+pub const COLLATE_WORDS: usize = 6;
+pub const LEVELS_MAX: usize = 3;
+pub const SEQUENCE_MAX: usize = 4;
+pub const CHAR_WORDS: usize = 1 + LEVELS_MAX;
+pub const SEQUENCE_WORDS: usize = SEQUENCE_MAX + LEVELS_MAX;
+// Weights stay below this, characters the table lacks included, so that
+// strxfrm writes each in three bytes of 2 to 255.
+pub const WEIGHT_LIMIT: u32 = 254 * 254 * 254;
+
+// LC_MESSAGES: the strings yesexpr, noexpr, yesstr and nostr.
+pub const MESSAGES_WORDS: usize = 4;
+
+// The number of words in the section of each category.
+pub const SECTION_WORDS: [usize; 6] = [
+  CTYPE_WORDS,
+  NUMERIC_WORDS,
+  TIME_WORDS,
+  COLLATE_WORDS,
+  MONETARY_WORDS,
+  MESSAGES_WORDS
+];
+
+fn word_at(
+  data: &[u8],
+  offset: usize
+) -> Option<u32> {
+  let bytes = data.get(offset..offset.checked_add(4)?)?;
+  Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+// A table of records of a few words each, within a file.
+#[derive(Clone, Copy, Eq, PartialEq)]
+#[repr(C)]
+pub struct Table {
+  data: *const u8,
+  len: usize,
+  words: usize
+}
+
+impl Table {
+  pub const EMPTY: Table = Table { data: ptr::null(), len: 0, words: 0 };
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  // Word `word` of record `record`, both within bounds.
+  pub fn get(
+    &self,
+    record: usize,
+    word: usize
+  ) -> u32 {
+    assert!(record < self.len && word < self.words);
+    let bytes = unsafe {
+      *self.data.add((record * self.words + word) * 4).cast::<[u8; 4]>()
+    };
+    u32::from_le_bytes(bytes)
+  }
+
+  // Binary search by `f`, which orders each record against the one sought.
+  pub fn search_by(
+    &self,
+    mut f: impl FnMut(usize) -> Ordering
+  ) -> Result<usize, usize> {
+    let (mut low, mut high) = (0, self.len);
+    while low < high {
+      let mid = low + (high - low) / 2;
+      match f(mid) {
+        | Ordering::Less => low = mid + 1,
+        | Ordering::Greater => high = mid,
+        | Ordering::Equal => return Ok(mid)
+      }
+    }
+    Err(low)
+  }
+
+  // Binary search by the first word of each record.
+  pub fn search(
+    &self,
+    key: u32
+  ) -> Result<usize, usize> {
+    self.search_by(|i| self.get(i, 0).cmp(&key))
+  }
+}
+
+// A file, checked to be one as far as its header and section entries go.
+#[derive(Clone, Copy)]
+pub struct File<'a> {
+  data: &'a [u8],
+  pub codeset: &'a CStr
+}
+
+// The section of one category.
+#[derive(Clone, Copy)]
+pub struct Section<'a> {
+  data: &'a [u8],
+  offset: usize,
+  words: usize
+}
+
+fn string_at(
+  data: &[u8],
+  offset: u32
+) -> Option<&CStr> {
+  CStr::from_bytes_until_nul(data.get(offset as usize..)?).ok()
+}
+
+impl<'a> File<'a> {
+  pub fn parse(data: &'a [u8]) -> Option<Self> {
+    if data.get(..MAGIC.len())? != MAGIC || word_at(data, 8)? != VERSION {
+      return None;
+    }
+    let codeset = string_at(data, word_at(data, 12)?)?;
+    let file = File { data, codeset };
+    for i in 0..file.count()? {
+      let (category, section) = file.entry(i)?;
+      if category as usize >= SECTION_WORDS.len() ||
+        section.words < SECTION_WORDS[category as usize]
+      {
+        return None;
+      }
+    }
+    Some(file)
+  }
+
+  fn count(&self) -> Option<usize> {
+    let count = word_at(self.data, 16)? as usize;
+    (count <= SECTION_WORDS.len()).then_some(count)
+  }
+
+  fn entry(
+    &self,
+    i: usize
+  ) -> Option<(u32, Section<'a>)> {
+    let at = HEADER_SIZE + i * ENTRY_SIZE;
+    let category = word_at(self.data, at)?;
+    let offset = word_at(self.data, at + 4)? as usize;
+    let words = word_at(self.data, at + 8)? as usize;
+    self.data.get(offset..offset.checked_add(words.checked_mul(4)?)?)?;
+    Some((category, Section { data: self.data, offset, words }))
+  }
+
+  // The section of `category`, if the file has one.
+  pub fn section(
+    &self,
+    category: u32
+  ) -> Option<Section<'a>> {
+    (0..self.count()?)
+      .filter_map(|i| self.entry(i))
+      .find(|&(c, _)| c == category)
+      .map(|(_, section)| section)
+  }
+}
+
+impl<'a> Section<'a> {
+  pub fn word(
+    &self,
+    i: usize
+  ) -> Option<u32> {
+    if i >= self.words {
+      return None;
+    }
+    word_at(self.data, self.offset + i * 4)
+  }
+
+  pub fn string(
+    &self,
+    i: usize
+  ) -> Option<&'a CStr> {
+    string_at(self.data, self.word(i)?)
+  }
+
+  // The table at words `i` and `i + 1`, of records of `words` words.
+  pub fn table(
+    &self,
+    i: usize,
+    words: usize
+  ) -> Option<Table> {
+    let offset = self.word(i)? as usize;
+    let len = self.word(i + 1)? as usize;
+    let size = len.checked_mul(words)?.checked_mul(4)?;
+    let bytes = self.data.get(offset..offset.checked_add(size)?)?;
+    Some(Table { data: bytes.as_ptr(), len, words })
+  }
+}
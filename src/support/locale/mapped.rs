@@ -0,0 +1,184 @@
+use {
+  super::{
+    NAME_MAX,
+    ctype::registry,
+    format::File,
+    name::{self, Name}
+  },
+  crate::{
+    allocator::vec::Vec,
+    c_int,
+    start,
+    std::errno,
+    support::sync::Mutex
+  },
+  core::{ffi::CStr, slice},
+  syscalls::{Sysno, syscall}
+};
+
+const O_RDONLY: c_int = 0;
+const O_CLOEXEC: c_int = 0o2000000;
+const AT_FDCWD: c_int = -100;
+const SEEK_END: c_int = 2;
+const PROT_READ: usize = 0x1;
+const MAP_PRIVATE: usize = 0x02;
+
+// Where compiled locales are looked for when LOCPATH, a list of directories
+// separated by colons, does not say. Programs the kernel marks with
+// AT_SECURE ignore LOCPATH.
+const LOCPATH_DEFAULT: &[u8] = b"/usr/lib/ouma/locale";
+const PATH_MAX: usize = 4096;
+
+// A file mapped for good, which every locale loaded from it points into.
+struct Mapped {
+  path: Vec<u8>,
+  data: &'static [u8]
+}
+
+// The mapping is read-only and never goes away.
+unsafe impl Send for Mapped {}
+
+static MAPPED: Mutex<Vec<Mapped>> = Mutex::new(Vec::new());
+
+// Map the whole file open as `fd`, which must not be empty.
+fn map_fd(fd: usize) -> Option<&'static [u8]> {
+  let len = unsafe { syscall!(Sysno::lseek, fd, 0, SEEK_END) }.ok()?;
+  if len == 0 {
+    return None;
+  }
+  let addr =
+    unsafe { syscall!(Sysno::mmap, 0, len, PROT_READ, MAP_PRIVATE, fd, 0) }
+      .ok()?;
+  Some(unsafe { slice::from_raw_parts(addr as *const u8, len) })
+}
+
+// The locale file at `path`, NUL-terminated, mapping it the first time.
+// Gives None if there is no such file or it is not a locale, and ENOMEM if
+// it cannot be kept.
+fn map(path: &[u8]) -> Result<Option<File<'static>>, c_int> {
+  let mut mapped = MAPPED.lock();
+  if let Some(m) = mapped.iter().find(|m| m.path[..] == *path) {
+    return Ok(File::parse(m.data));
+  }
+  let flags = O_RDONLY | O_CLOEXEC;
+  let Ok(fd) =
+    (unsafe { syscall!(Sysno::openat, AT_FDCWD, path.as_ptr(), flags) })
+  else {
+    return Ok(None);
+  };
+  let data = map_fd(fd);
+  let _ = unsafe { syscall!(Sysno::close, fd) };
+  let unmap = |data: &[u8]| {
+    let _ = unsafe { syscall!(Sysno::munmap, data.as_ptr(), data.len()) };
+  };
+  let (data, file) = match data.map(|data| (data, File::parse(data))) {
+    | Some((data, Some(file))) => (data, file),
+    | Some((data, None)) => {
+      unmap(data);
+      return Ok(None);
+    },
+    | None => return Ok(None)
+  };
+  let mut copy = Vec::new();
+  if mapped.try_reserve(1).is_err() || copy.try_reserve(path.len()).is_err() {
+    unmap(data);
+    return Err(errno::ENOMEM);
+  }
+  copy.extend_from_slice(path);
+  mapped.push(Mapped { path: copy, data });
+  Ok(Some(file))
+}
+
+// Whether a file whose strings are in `own` serves a name asking for
+// `codeset`, which any codeset the library knows does when the name has
+// none.
+fn serves(
+  own: &CStr,
+  codeset: Option<&[u8]>
+) -> bool {
+  let Some(own) = registry::find(own.to_bytes()) else {
+    return false;
+  };
+  let own = unsafe { CStr::from_ptr(own.codeset) }.to_bytes();
+  codeset.is_none_or(|codeset| {
+    registry::find(codeset).is_some_and(|ctype| {
+      let name = unsafe { CStr::from_ptr(ctype.codeset) }.to_bytes();
+      registry::key(name).eq(registry::key(own))
+    })
+  })
+}
+
+// Write the name of the file of the locale `parsed` into `buf`, with
+// `codeset` in place of its own.
+fn file_name<'a>(
+  buf: &'a mut [u8; NAME_MAX],
+  parsed: &Name,
+  codeset: Option<&[u8]>
+) -> &'a [u8] {
+  let parts = [
+    (&b""[..], Some(parsed.language)),
+    (b"_", parsed.territory),
+    (b".", codeset),
+    (b"@", parsed.modifier)
+  ];
+  let mut len = 0;
+  for (separator, part) in parts {
+    if let Some(part) = part {
+      for &c in separator.iter().chain(part) {
+        buf[len] = c;
+        len += 1;
+      }
+    }
+  }
+  &buf[..len]
+}
+
+// The compiled locale for the valid name `name`, looked for in every
+// directory of LOCPATH, if there is one serving its codeset. Files that do
+// not, or are not locales, are passed over for those after them.
+pub fn find(name: &[u8]) -> Result<Option<File<'static>>, c_int> {
+  let parsed = name::parse(name).ok_or(errno::ENOENT)?;
+  if parsed.is_c() {
+    return Ok(None);
+  }
+  let locpath = if start::get_auxv(start::AT_SECURE) != 0 {
+    None
+  } else {
+    name::variable(b"LOCPATH")
+  };
+  // The name itself, then with the codeset as "utf8" for "UTF-8", then
+  // without it.
+  let (mut key, mut normalized, mut bare) =
+    ([0; NAME_MAX], [0; NAME_MAX], [0; NAME_MAX]);
+  let mut candidates = [Some(name), None, None];
+  if let Some(codeset) = parsed.codeset {
+    let mut len = 0;
+    for (d, c) in key.iter_mut().zip(registry::key(codeset)) {
+      *d = c;
+      len += 1;
+    }
+    candidates[1] =
+      Some(file_name(&mut normalized, &parsed, Some(&key[..len])));
+    candidates[2] = Some(file_name(&mut bare, &parsed, None));
+  }
+  let dirs = locpath.unwrap_or(LOCPATH_DEFAULT).split(|&c| c == b':');
+  for dir in dirs.filter(|dir| !dir.is_empty()) {
+    for candidate in candidates.into_iter().flatten() {
+      let len = dir.len() + 1 + candidate.len();
+      if len >= PATH_MAX {
+        continue;
+      }
+      let mut path = [0; PATH_MAX];
+      path[..dir.len()].copy_from_slice(dir);
+      path[dir.len()] = b'/';
+      path[dir.len() + 1..len].copy_from_slice(candidate);
+      match map(&path[..=len])? {
+        | Some(file) if serves(file.codeset, parsed.codeset) => {
+          return Ok(Some(file));
+        },
+        | _ => {}
+      }
+    }
+  }
+  Ok(None)
+}
@@ -0,0 +1,48 @@
+use {
+  crate::{c_char, support::locale::format::Section},
+  core::ptr
+};
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+#[repr(C)]
+pub struct LocaleMessages {
+  // Extended regular expressions matching an affirmative and a negative
+  // answer, and the words for yes and no.
+  pub yesexpr: *const c_char,
+  pub noexpr: *const c_char,
+  pub yesstr: *const c_char,
+  pub nostr: *const c_char
+}
+
+impl LocaleMessages {
+  pub fn new() -> Self {
+    Self {
+      yesexpr: ptr::null::<c_char>(),
+      noexpr: ptr::null::<c_char>(),
+      yesstr: ptr::null::<c_char>(),
+      nostr: ptr::null::<c_char>()
+    }
+  }
+
+  pub fn load(section: &Section<'static>) -> Option<Self> {
+    Some(Self {
+      yesexpr: section.string(0)?.as_ptr(),
+      noexpr: section.string(1)?.as_ptr(),
+      yesstr: section.string(2)?.as_ptr(),
+      nostr: section.string(3)?.as_ptr()
+    })
+  }
+}
+
+impl Default for LocaleMessages {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+pub const LOCALE_MESSAGES_C: LocaleMessages = LocaleMessages {
+  yesexpr: c"^[yY]".as_ptr(),
+  noexpr: c"^[nN]".as_ptr(),
+  yesstr: c"yes".as_ptr(),
+  nostr: c"no".as_ptr()
+};
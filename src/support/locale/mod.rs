@@ -1,29 +1,225 @@
+pub mod collate;
 pub mod ctype;
+pub mod format;
+pub mod mapped;
+pub mod messages;
+pub mod monetary;
+pub mod name;
 pub mod numeric;
 pub mod time;
 
-use crate::{c_uint, char16_t, char32_t, locale_t, mbstate_t, LocaleStruct};
+use {
+  crate::{
+    LocaleStruct,
+    c_char,
+    c_int,
+    c_uint,
+    c_ushort,
+    char16_t,
+    char32_t,
+    locale_t,
+    mbstate_t,
+    std::{
+      errno,
+      locale::{
+        LC_COLLATE,
+        LC_CTYPE,
+        LC_GLOBAL_LOCALE,
+        LC_MESSAGES,
+        LC_MONETARY,
+        LC_NUMERIC,
+        LC_TIME
+      }
+    }
+  },
+  core::ptr,
+  format::Section
+};
 
-#[thread_local]
-pub static mut ThreadLocale: LocaleStruct = unsafe { OLOCALE_C_UTF8 };
+// Categories a locale is made of, numbered as LC_CTYPE to LC_MESSAGES.
+pub const CATEGORIES: usize = 6;
+// Longest name a category may have, with its terminating NUL.
+pub const NAME_MAX: usize = 64;
+
+pub const CATEGORY_NAMES: [&str; CATEGORIES] = [
+  "LC_CTYPE",
+  "LC_NUMERIC",
+  "LC_TIME",
+  "LC_COLLATE",
+  "LC_MONETARY",
+  "LC_MESSAGES"
+];
+
+pub type Names = [[c_char; NAME_MAX]; CATEGORIES];
+
+// Every category called `name`, NUL-terminated.
+pub const fn names(name: &[u8]) -> Names {
+  let mut names = [[0; NAME_MAX]; CATEGORIES];
+  let mut i = 0;
+  while i < CATEGORIES {
+    let mut j = 0;
+    while j < name.len() {
+      names[i][j] = name[j] as c_char;
+      j += 1;
+    }
+    i += 1;
+  }
+  names
+}
+
+pub const LOCALE_C: LocaleStruct = LocaleStruct {
+  ctype: ctype::ascii::LOCALE_CTYPE_ASCII,
+  numeric: numeric::LOCALE_NUMERIC_C,
+  monetary: monetary::LOCALE_MONETARY_C,
+  time: time::LOCALE_TIME_C,
+  messages: messages::LOCALE_MESSAGES_C,
+  collate: collate::LOCALE_COLLATE_C,
+  classes: ctype::classes::LOCALE_CLASSES_UNICODE,
+  names: names(b"C")
+};
+
+pub const LOCALE_C_UTF8: LocaleStruct = LocaleStruct {
+  ctype: ctype::utf8::LOCALE_CTYPE_UTF8,
+  numeric: numeric::LOCALE_NUMERIC_C,
+  monetary: monetary::LOCALE_MONETARY_C,
+  time: time::LOCALE_TIME_C,
+  messages: messages::LOCALE_MESSAGES_C,
+  collate: collate::LOCALE_COLLATE_C,
+  classes: ctype::classes::LOCALE_CLASSES_UNICODE,
+  names: names(b"C.UTF-8")
+};
 
-// TODO: remove when newlocale is done
 #[no_mangle]
-pub static mut OLOCALE_C: LocaleStruct =
-  LocaleStruct { ctype: ctype::ascii::LOCALE_CTYPE_ASCII };
+pub static mut OLOCALE_C: LocaleStruct = LOCALE_C;
 
 #[no_mangle]
-pub static mut OLOCALE_C_UTF8: LocaleStruct =
-  LocaleStruct { ctype: ctype::utf8::LOCALE_CTYPE_UTF8 };
+pub static mut OLOCALE_C_UTF8: LocaleStruct = LOCALE_C_UTF8;
+
+// The locale setlocale changes. Programs start in C.UTF-8, which is the C
+// locale with UTF-8 multibyte characters.
+pub static mut GlobalLocale: LocaleStruct = LOCALE_C_UTF8;
+
+// The locale uselocale installed for this thread, if any.
+#[thread_local]
+static mut ThreadLocale: locale_t = LC_GLOBAL_LOCALE;
+
+#[inline]
+pub fn get_global_locale() -> locale_t {
+  &raw mut GlobalLocale
+}
 
 #[inline]
 pub fn get_thread_locale() -> locale_t {
-  unsafe { &mut ThreadLocale }
+  let locale = unsafe { ThreadLocale };
+  if locale == LC_GLOBAL_LOCALE { get_global_locale() } else { locale }
 }
 
+// Install `locale` for this thread, LC_GLOBAL_LOCALE going back to the
+// global locale, and give the one installed before.
 #[inline]
-pub fn set_thread_locale(locale: locale_t) {
-  unsafe { ThreadLocale = *locale };
+pub fn set_thread_locale(locale: locale_t) -> locale_t {
+  unsafe { ptr::replace(&raw mut ThreadLocale, locale) }
+}
+
+// The conventions in `section` if the file has one, or else those of C.
+fn from_section<T>(
+  section: Option<Section<'static>>,
+  load: fn(&Section<'static>) -> Option<T>,
+  c: T
+) -> Result<T, c_int> {
+  section.map_or(Ok(c), |section| load(&section).ok_or(errno::ENOENT))
+}
+
+// Load `category` of `locale` from the locale called `name`: from its
+// compiled file under LOCPATH, or for C and POSIX with the conventions of C
+// in the codeset the name gives. Other names without a file are ENOENT.
+fn load(
+  locale: &mut LocaleStruct,
+  category: usize,
+  name: &[u8]
+) -> Result<(), c_int> {
+  let parsed = name::parse(name).ok_or(errno::ENOENT)?;
+  let file = mapped::find(name)?;
+  if file.is_none() && !parsed.is_c() {
+    return Err(errno::ENOENT);
+  }
+  let section = file.and_then(|file| file.section(category as u32));
+  match category as c_int {
+    | LC_CTYPE => {
+      let codeset = match file {
+        | Some(file) => Some(file.codeset.to_bytes()),
+        | None => parsed.codeset
+      };
+      locale.ctype = match codeset {
+        | Some(codeset) => {
+          ctype::registry::find(codeset).ok_or(errno::ENOENT)?
+        },
+        | None if parsed.is_c() => ctype::ascii::LOCALE_CTYPE_ASCII,
+        | None => ctype::utf8::LOCALE_CTYPE_UTF8
+      };
+      locale.classes = from_section(
+        section,
+        ctype::classes::LocaleClasses::load,
+        ctype::classes::LOCALE_CLASSES_UNICODE
+      )?;
+    },
+    | LC_NUMERIC => {
+      locale.numeric = from_section(
+        section,
+        numeric::LocaleNumeric::load,
+        numeric::LOCALE_NUMERIC_C
+      )?;
+    },
+    | LC_MONETARY => {
+      locale.monetary = from_section(
+        section,
+        monetary::LocaleMonetary::load,
+        monetary::LOCALE_MONETARY_C
+      )?;
+    },
+    | LC_TIME => {
+      locale.time =
+        from_section(section, time::LocaleTime::load, time::LOCALE_TIME_C)?;
+    },
+    | LC_COLLATE => {
+      locale.collate = from_section(
+        section,
+        collate::LocaleCollate::load,
+        collate::LOCALE_COLLATE_C
+      )?;
+    },
+    | LC_MESSAGES => {
+      locale.messages = from_section(
+        section,
+        messages::LocaleMessages::load,
+        messages::LOCALE_MESSAGES_C
+      )?;
+    },
+    | _ => {}
+  }
+  let slot = &mut locale.names[category];
+  *slot = [0; NAME_MAX];
+  for (d, &c) in slot.iter_mut().zip(name) {
+    *d = c as c_char;
+  }
+  Ok(())
+}
+
+// Set the categories of `locale` whose bits are in `mask` to those of the
+// locale called `name`. Nothing changes unless every one of them loads.
+pub fn set(
+  locale: &mut LocaleStruct,
+  mask: c_int,
+  name: &[u8]
+) -> Result<(), c_int> {
+  let mut new = *locale;
+  for category in 0..CATEGORIES {
+    if mask & (1 << category) != 0 {
+      load(&mut new, category, name::resolve(name, category)?)?;
+    }
+  }
+  *locale = new;
+  Ok(())
 }
 
 #[inline]
@@ -40,7 +236,8 @@ pub fn mbstate_get_init(mbs: *const mbstate_t) -> bool {
   unsafe {
     mbs.is_null() ||
       ((*mbs).surrogate < 0xd800 || (*mbs).surrogate > 0xdfff) &&
-        (*mbs).bytesleft == 0
+        (*mbs).bytesleft == 0 &&
+        (*mbs).shift == 0
   }
 }
 
@@ -81,6 +278,13 @@ pub fn mbstate_set_surrogate(
   unsafe { (*mbs).surrogate = surrogate };
 }
 
+#[inline]
+pub fn mbstate_clear_surrogate(mbs: *mut mbstate_t) {
+  if !mbs.is_null() {
+    unsafe { (*mbs).surrogate = 0 };
+  }
+}
+
 #[inline]
 pub fn mbstate_get_surrogate(
   mbs: *const mbstate_t,
@@ -94,3 +298,16 @@ pub fn mbstate_get_surrogate(
   }
   true
 }
+
+#[inline]
+pub fn mbstate_set_shift(
+  mbs: *mut mbstate_t,
+  shift: c_ushort
+) {
+  unsafe { (*mbs).shift = shift };
+}
+
+#[inline]
+pub fn mbstate_get_shift(mbs: *const mbstate_t) -> c_ushort {
+  unsafe { (*mbs).shift }
+}
@@ -0,0 +1,132 @@
+use {
+  crate::{
+    c_char,
+    support::locale::format::{self, Section}
+  },
+  core::{ffi::CStr, ptr}
+};
+
+// Values lconv gives as char, where CHAR_MAX means not available.
+pub const CHAR_MAX: c_char = c_char::MAX;
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+#[repr(C)]
+pub struct LocaleMonetary {
+  pub int_curr_symbol: *const c_char,
+  pub currency_symbol: *const c_char,
+  pub mon_decimal_point: *const c_char,
+  pub mon_thousands_sep: *const c_char,
+  pub mon_grouping: *const c_char,
+  pub positive_sign: *const c_char,
+  pub negative_sign: *const c_char,
+  // The currency symbol as nl_langinfo(CRNCYSTR) gives it, preceded by '-'
+  // if it goes before the value, '+' if after and '.' if it replaces the
+  // radix character.
+  pub crncystr: *const c_char,
+  pub int_frac_digits: c_char,
+  pub frac_digits: c_char,
+  pub p_cs_precedes: c_char,
+  pub p_sep_by_space: c_char,
+  pub n_cs_precedes: c_char,
+  pub n_sep_by_space: c_char,
+  pub p_sign_posn: c_char,
+  pub n_sign_posn: c_char,
+  pub int_p_cs_precedes: c_char,
+  pub int_p_sep_by_space: c_char,
+  pub int_n_cs_precedes: c_char,
+  pub int_n_sep_by_space: c_char,
+  pub int_p_sign_posn: c_char,
+  pub int_n_sign_posn: c_char
+}
+
+impl LocaleMonetary {
+  pub fn new() -> Self {
+    Self {
+      int_curr_symbol: ptr::null::<c_char>(),
+      currency_symbol: ptr::null::<c_char>(),
+      mon_decimal_point: ptr::null::<c_char>(),
+      mon_thousands_sep: ptr::null::<c_char>(),
+      mon_grouping: ptr::null::<c_char>(),
+      positive_sign: ptr::null::<c_char>(),
+      negative_sign: ptr::null::<c_char>(),
+      crncystr: ptr::null::<c_char>(),
+      int_frac_digits: 0,
+      frac_digits: 0,
+      p_cs_precedes: 0,
+      p_sep_by_space: 0,
+      n_cs_precedes: 0,
+      n_sep_by_space: 0,
+      p_sign_posn: 0,
+      n_sign_posn: 0,
+      int_p_cs_precedes: 0,
+      int_p_sep_by_space: 0,
+      int_n_cs_precedes: 0,
+      int_n_sep_by_space: 0,
+      int_p_sign_posn: 0,
+      int_n_sign_posn: 0
+    }
+  }
+
+  pub fn load(section: &Section<'static>) -> Option<Self> {
+    let string = |i| section.string(i).map(CStr::as_ptr);
+    let value = |i| {
+      let word = section.word(format::MONETARY_STRINGS + i)?;
+      c_char::try_from(word).ok()
+    };
+    Some(Self {
+      int_curr_symbol: string(0)?,
+      currency_symbol: string(1)?,
+      mon_decimal_point: string(2)?,
+      mon_thousands_sep: string(3)?,
+      mon_grouping: string(4)?,
+      positive_sign: string(5)?,
+      negative_sign: string(6)?,
+      crncystr: string(7)?,
+      int_frac_digits: value(0)?,
+      frac_digits: value(1)?,
+      p_cs_precedes: value(2)?,
+      p_sep_by_space: value(3)?,
+      n_cs_precedes: value(4)?,
+      n_sep_by_space: value(5)?,
+      p_sign_posn: value(6)?,
+      n_sign_posn: value(7)?,
+      int_p_cs_precedes: value(8)?,
+      int_p_sep_by_space: value(9)?,
+      int_n_cs_precedes: value(10)?,
+      int_n_sep_by_space: value(11)?,
+      int_p_sign_posn: value(12)?,
+      int_n_sign_posn: value(13)?
+    })
+  }
+}
+
+impl Default for LocaleMonetary {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+pub const LOCALE_MONETARY_C: LocaleMonetary = LocaleMonetary {
+  int_curr_symbol: c"".as_ptr(),
+  currency_symbol: c"".as_ptr(),
+  mon_decimal_point: c"".as_ptr(),
+  mon_thousands_sep: c"".as_ptr(),
+  mon_grouping: c"".as_ptr(),
+  positive_sign: c"".as_ptr(),
+  negative_sign: c"".as_ptr(),
+  crncystr: c"-".as_ptr(),
+  int_frac_digits: CHAR_MAX,
+  frac_digits: CHAR_MAX,
+  p_cs_precedes: CHAR_MAX,
+  p_sep_by_space: CHAR_MAX,
+  n_cs_precedes: CHAR_MAX,
+  n_sep_by_space: CHAR_MAX,
+  p_sign_posn: CHAR_MAX,
+  n_sign_posn: CHAR_MAX,
+  int_p_cs_precedes: CHAR_MAX,
+  int_p_sep_by_space: CHAR_MAX,
+  int_n_cs_precedes: CHAR_MAX,
+  int_n_sep_by_space: CHAR_MAX,
+  int_p_sign_posn: CHAR_MAX,
+  int_n_sign_posn: CHAR_MAX
+};
@@ -0,0 +1,117 @@
+use {
+  super::{CATEGORIES, CATEGORY_NAMES, NAME_MAX},
+  crate::{
+    c_int,
+    std::errno,
+    support::{environ, string}
+  },
+  core::slice
+};
+
+// A locale name of the form language[_territory][.codeset][@modifier].
+pub struct Name<'a> {
+  pub language: &'a [u8],
+  pub territory: Option<&'a [u8]>,
+  pub codeset: Option<&'a [u8]>,
+  pub modifier: Option<&'a [u8]>
+}
+
+impl Name<'_> {
+  // Whether this is the C or POSIX locale, with or without a codeset.
+  pub fn is_c(&self) -> bool {
+    (self.language == b"C" || self.language == b"POSIX") &&
+      self.territory.is_none() &&
+      self.modifier.is_none()
+  }
+}
+
+fn split_at(
+  s: &[u8],
+  c: u8
+) -> (&[u8], Option<&[u8]>) {
+  match s.iter().position(|&x| x == c) {
+    | Some(i) => (&s[..i], Some(&s[i + 1..])),
+    | None => (s, None)
+  }
+}
+
+fn valid(
+  part: Option<&[u8]>,
+  extra: &[u8]
+) -> bool {
+  part.is_none_or(|s| {
+    !s.is_empty() &&
+      s.iter().all(|c| c.is_ascii_alphanumeric() || extra.contains(c))
+  })
+}
+
+// Split a name into its parts. Only letters, digits and a little punctuation
+// are accepted, so a name can never reach outside a directory of locales.
+pub fn parse(name: &[u8]) -> Option<Name<'_>> {
+  if name.len() >= NAME_MAX {
+    return None;
+  }
+  let (rest, modifier) = split_at(name, b'@');
+  let (rest, codeset) = split_at(rest, b'.');
+  let (language, territory) = split_at(rest, b'_');
+  if language.is_empty() || !language.iter().all(u8::is_ascii_alphabetic) {
+    return None;
+  }
+  if !valid(territory, b"") ||
+    !valid(codeset, b"-_.:") ||
+    !valid(modifier, b"-_")
+  {
+    return None;
+  }
+  Some(Name { language, territory, codeset, modifier })
+}
+
+// The value of the environment variable `name`, unless it is empty.
+pub fn variable<'a>(name: &[u8]) -> Option<&'a [u8]> {
+  let value = environ::get(name);
+  if value.is_null() {
+    return None;
+  }
+  let value = unsafe {
+    slice::from_raw_parts(value.cast::<u8>(), string::string_length(value))
+  };
+  (!value.is_empty()).then_some(value)
+}
+
+// The name the environment gives `category`: LC_ALL, then the variable named
+// after the category, then LANG, then C.
+pub fn from_environ<'a>(category: usize) -> &'a [u8] {
+  variable(b"LC_ALL")
+    .or_else(|| variable(CATEGORY_NAMES[category].as_bytes()))
+    .or_else(|| variable(b"LANG"))
+    .unwrap_or(b"C")
+}
+
+// The name of `category` within a name given to setlocale or newlocale. An
+// empty name asks the environment, and a composite one such as
+// "LC_CTYPE=C.UTF-8;LC_NUMERIC=C;..." as setlocale(LC_ALL, NULL) returns
+// must name every category.
+pub fn resolve(
+  name: &[u8],
+  category: usize
+) -> Result<&[u8], c_int> {
+  if name.is_empty() {
+    return Ok(from_environ(category));
+  }
+  if !name.contains(&b';') {
+    return Ok(name);
+  }
+  let mut found = [None; CATEGORIES];
+  for part in name.split(|&c| c == b';') {
+    let (key, value) = split_at(part, b'=');
+    let i = CATEGORY_NAMES
+      .iter()
+      .position(|n| n.as_bytes() == key)
+      .ok_or(errno::ENOENT)?;
+    found[i] = Some(value.ok_or(errno::ENOENT)?);
+  }
+  if found.iter().any(Option::is_none) {
+    return Err(errno::ENOENT);
+  }
+  found[category].ok_or(errno::ENOENT)
+}
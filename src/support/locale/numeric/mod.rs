@@ -1 +1,100 @@
+use {
+  crate::{
+    c_char,
+    locale_t,
+    support::{
+      locale::{self, format::Section},
+      string
+    }
+  },
+  core::{ptr, slice}
+};
 
+#[derive(Clone, Copy, Eq, PartialEq)]
+#[repr(C)]
+pub struct LocaleNumeric {
+  pub decimal_point: *const c_char,
+  pub thousands_sep: *const c_char,
+  pub grouping: *const c_char
+}
+
+impl LocaleNumeric {
+  pub fn new() -> Self {
+    Self {
+      decimal_point: ptr::null::<c_char>(),
+      thousands_sep: ptr::null::<c_char>(),
+      grouping: ptr::null::<c_char>()
+    }
+  }
+
+  pub fn load(section: &Section<'static>) -> Option<Self> {
+    Some(Self {
+      decimal_point: section.string(0)?.as_ptr(),
+      thousands_sep: section.string(1)?.as_ptr(),
+      grouping: section.string(2)?.as_ptr()
+    })
+  }
+}
+
+impl Default for LocaleNumeric {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+pub const LOCALE_NUMERIC_C: LocaleNumeric = LocaleNumeric {
+  decimal_point: c".".as_ptr(),
+  thousands_sep: c"".as_ptr(),
+  grouping: c"".as_ptr()
+};
+
+// The radix character of the thread's locale, which may take several bytes.
+pub fn radix() -> &'static [u8] {
+  radix_l(locale::get_thread_locale())
+}
+
+pub fn radix_l(locale: locale_t) -> &'static [u8] {
+  bytes(unsafe { (*locale).numeric.decimal_point })
+}
+
+// The thousands separator and grouping of the thread's locale, for the '
+// flag of printf.
+pub fn separator() -> (&'static [u8], &'static [u8]) {
+  let numeric = unsafe { (*locale::get_thread_locale()).numeric };
+  (bytes(numeric.thousands_sep), bytes(numeric.grouping))
+}
+
+// Whether a separator goes before the last `right` digits of an integer
+// part. Each byte of `grouping` is the size of the next group leftwards,
+// the last one repeating, and CHAR_MAX stops grouping.
+pub fn is_boundary(
+  grouping: &[u8],
+  right: usize
+) -> bool {
+  let (mut sum, mut size) = (0, 0);
+  let mut groups = grouping.iter();
+  loop {
+    match groups.next() {
+      | Some(&g) if g >= c_char::MAX as u8 => return false,
+      | Some(&g) => size = g as usize,
+      | None if size == 0 => return false,
+      | None => {}
+    }
+    sum += size;
+    if sum >= right {
+      return sum == right;
+    }
+  }
+}
+
+// How many separators an integer part of `n` digits takes.
+pub fn separators(
+  grouping: &[u8],
+  n: usize
+) -> usize {
+  (1..n).filter(|&right| is_boundary(grouping, right)).count()
+}
+
+fn bytes(s: *const c_char) -> &'static [u8] {
+  unsafe { slice::from_raw_parts(s.cast(), string::string_length(s)) }
+}
@@ -1 +1,284 @@
+use {
+  crate::{c_char, support::locale::format::Section},
+  core::{ffi::CStr, ptr}
+};
 
+// Strings of LC_TIME. Eras and alternative digits are lists separated by
+// ';', each era being "direction:offset:start:end:name:format" as POSIX
+// describes them for localedef.
+#[derive(Clone, Copy, Eq, PartialEq)]
+#[repr(C)]
+pub struct LocaleTime {
+  pub abday: [*const c_char; 7],
+  pub day: [*const c_char; 7],
+  pub abmon: [*const c_char; 12],
+  pub mon: [*const c_char; 12],
+  // Month names standing alone rather than in a date, for %Ob and %OB.
+  pub ab_alt_mon: [*const c_char; 12],
+  pub alt_mon: [*const c_char; 12],
+  pub am_pm: [*const c_char; 2],
+  pub d_t_fmt: *const c_char,
+  pub d_fmt: *const c_char,
+  pub t_fmt: *const c_char,
+  pub t_fmt_ampm: *const c_char,
+  pub era: *const c_char,
+  pub era_d_fmt: *const c_char,
+  pub era_t_fmt: *const c_char,
+  pub era_d_t_fmt: *const c_char,
+  pub alt_digits: *const c_char
+}
+
+impl LocaleTime {
+  pub fn new() -> Self {
+    Self {
+      abday: [ptr::null::<c_char>(); 7],
+      day: [ptr::null::<c_char>(); 7],
+      abmon: [ptr::null::<c_char>(); 12],
+      mon: [ptr::null::<c_char>(); 12],
+      ab_alt_mon: [ptr::null::<c_char>(); 12],
+      alt_mon: [ptr::null::<c_char>(); 12],
+      am_pm: [ptr::null::<c_char>(); 2],
+      d_t_fmt: ptr::null::<c_char>(),
+      d_fmt: ptr::null::<c_char>(),
+      t_fmt: ptr::null::<c_char>(),
+      t_fmt_ampm: ptr::null::<c_char>(),
+      era: ptr::null::<c_char>(),
+      era_d_fmt: ptr::null::<c_char>(),
+      era_t_fmt: ptr::null::<c_char>(),
+      era_d_t_fmt: ptr::null::<c_char>(),
+      alt_digits: ptr::null::<c_char>()
+    }
+  }
+
+  pub fn load(section: &Section<'static>) -> Option<Self> {
+    let string = |i| section.string(i).map(CStr::as_ptr);
+    let mut time = Self::new();
+    let mut i = 0;
+    for list in [
+      &mut time.abday[..],
+      &mut time.day[..],
+      &mut time.abmon[..],
+      &mut time.mon[..],
+      &mut time.ab_alt_mon[..],
+      &mut time.alt_mon[..],
+      &mut time.am_pm[..]
+    ] {
+      for s in list {
+        *s = string(i)?;
+        i += 1;
+      }
+    }
+    for s in [
+      &mut time.d_t_fmt,
+      &mut time.d_fmt,
+      &mut time.t_fmt,
+      &mut time.t_fmt_ampm,
+      &mut time.era,
+      &mut time.era_d_fmt,
+      &mut time.era_t_fmt,
+      &mut time.era_d_t_fmt,
+      &mut time.alt_digits
+    ] {
+      *s = string(i)?;
+      i += 1;
+    }
+    Some(time)
+  }
+}
+
+impl Default for LocaleTime {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+const ABMON_C: [*const c_char; 12] = [
+  c"Jan".as_ptr(),
+  c"Feb".as_ptr(),
+  c"Mar".as_ptr(),
+  c"Apr".as_ptr(),
+  c"May".as_ptr(),
+  c"Jun".as_ptr(),
+  c"Jul".as_ptr(),
+  c"Aug".as_ptr(),
+  c"Sep".as_ptr(),
+  c"Oct".as_ptr(),
+  c"Nov".as_ptr(),
+  c"Dec".as_ptr()
+];
+
+const MON_C: [*const c_char; 12] = [
+  c"January".as_ptr(),
+  c"February".as_ptr(),
+  c"March".as_ptr(),
+  c"April".as_ptr(),
+  c"May".as_ptr(),
+  c"June".as_ptr(),
+  c"July".as_ptr(),
+  c"August".as_ptr(),
+  c"September".as_ptr(),
+  c"October".as_ptr(),
+  c"November".as_ptr(),
+  c"December".as_ptr()
+];
+
+pub const LOCALE_TIME_C: LocaleTime = LocaleTime {
+  abday: [
+    c"Sun".as_ptr(),
+    c"Mon".as_ptr(),
+    c"Tue".as_ptr(),
+    c"Wed".as_ptr(),
+    c"Thu".as_ptr(),
+    c"Fri".as_ptr(),
+    c"Sat".as_ptr()
+  ],
+  day: [
+    c"Sunday".as_ptr(),
+    c"Monday".as_ptr(),
+    c"Tuesday".as_ptr(),
+    c"Wednesday".as_ptr(),
+    c"Thursday".as_ptr(),
+    c"Friday".as_ptr(),
+    c"Saturday".as_ptr()
+  ],
+  abmon: ABMON_C,
+  mon: MON_C,
+  ab_alt_mon: ABMON_C,
+  alt_mon: MON_C,
+  am_pm: [c"AM".as_ptr(), c"PM".as_ptr()],
+  d_t_fmt: c"%a %b %e %H:%M:%S %Y".as_ptr(),
+  d_fmt: c"%m/%d/%y".as_ptr(),
+  t_fmt: c"%H:%M:%S".as_ptr(),
+  t_fmt_ampm: c"%I:%M:%S %p".as_ptr(),
+  era: c"".as_ptr(),
+  era_d_fmt: c"".as_ptr(),
+  era_t_fmt: c"".as_ptr(),
+  era_d_t_fmt: c"".as_ptr(),
+  alt_digits: c"".as_ptr()
+};
+
+// A date as (year, month, day), with months from 1, ordered as dates are.
+pub type Date = (i64, i32, i32);
+
+#[derive(Clone, Copy)]
+enum Bound {
+  Date(Date),
+  // "-*" and "+*", the beginning and the end of time.
+  Past,
+  Future
+}
+
+// One era of the era list.
+pub struct Era<'a> {
+  forward: bool,
+  offset: i64,
+  start: Date,
+  end: Bound,
+  pub name: &'a [u8],
+  pub format: &'a [u8]
+}
+
+fn number(s: &[u8]) -> Option<i64> {
+  let (negative, digits) = match s.split_first() {
+    | Some((b'-', rest)) => (true, rest),
+    | Some((b'+', rest)) => (false, rest),
+    | _ => (false, s)
+  };
+  if digits.is_empty() || digits.len() > 18 {
+    return None;
+  }
+  let mut n = 0i64;
+  for &c in digits {
+    if !c.is_ascii_digit() {
+      return None;
+    }
+    n = n * 10 + i64::from(c - b'0');
+  }
+  Some(if negative { -n } else { n })
+}
+
+fn date(s: &[u8]) -> Option<Date> {
+  let mut parts = s.split(|&c| c == b'/');
+  let year = number(parts.next()?)?;
+  let mon = number(parts.next()?)?;
+  let day = number(parts.next()?)?;
+  if parts.next().is_some() ||
+    !(1..=12).contains(&mon) ||
+    !(1..=31).contains(&day)
+  {
+    return None;
+  }
+  Some((year, mon as i32, day as i32))
+}
+
+impl<'a> Era<'a> {
+  fn parse(s: &'a [u8]) -> Option<Self> {
+    let mut parts = s.splitn(6, |&c| c == b':');
+    let forward = match parts.next()? {
+      | b"+" => true,
+      | b"-" => false,
+      | _ => return None
+    };
+    let offset = number(parts.next()?)?;
+    let start = date(parts.next()?)?;
+    let end = match parts.next()? {
+      | b"-*" => Bound::Past,
+      | b"+*" => Bound::Future,
+      | end => Bound::Date(date(end)?)
+    };
+    let name = parts.next()?;
+    let format = parts.next()?;
+    Some(Era { forward, offset, start, end, name, format })
+  }
+
+  pub fn contains(
+    &self,
+    d: Date
+  ) -> bool {
+    match self.end {
+      | Bound::Past => d <= self.start,
+      | Bound::Future => self.start <= d,
+      | Bound::Date(end) => self.start.min(end) <= d && d <= self.start.max(end)
+    }
+  }
+
+  // The year within the era of a year of the Gregorian calendar.
+  pub fn year(
+    &self,
+    year: i64
+  ) -> i64 {
+    if self.forward {
+      self.offset + (year - self.start.0)
+    } else {
+      self.offset + (self.start.0 - year)
+    }
+  }
+
+  // The Gregorian year of a year within the era.
+  pub fn gregorian(
+    &self,
+    year: i64
+  ) -> i64 {
+    if self.forward {
+      self.start.0 + (year - self.offset)
+    } else {
+      self.start.0 - (year - self.offset)
+    }
+  }
+}
+
+// The well-formed eras of an era list.
+pub fn eras(list: &[u8]) -> impl Iterator<Item = Era<'_>> {
+  list.split(|&c| c == b';').filter_map(Era::parse)
+}
+
+// The alternative digits for `n`, if the list goes that far.
+pub fn alt_digits(
+  list: &[u8],
+  n: usize
+) -> Option<&[u8]> {
+  if list.is_empty() {
+    return None;
+  }
+  list.split(|&c| c == b';').nth(n)
+}
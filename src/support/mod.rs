@@ -1,4 +1,15 @@
+pub mod bignum;
+pub mod calendar;
+pub mod environ;
 pub mod locale;
+pub mod printf;
+pub mod scanf;
+pub mod sort;
+pub mod stream;
+pub mod strftime;
 pub mod string;
 pub mod stringstream;
+pub mod strptime;
+pub mod strtofloat;
 pub mod strtointeger;
+pub mod sync;
@@ -0,0 +1,106 @@
+use {crate::arch::float, core::ffi::VaList};
+
+// Highest argument number usable in `%n$` conversions.
+pub const NL_ARGMAX: usize = 64;
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum ArgKind {
+  None,
+  Int,
+  Long,
+  Ptr,
+  Double,
+  LongDouble
+}
+
+// Integer and pointer arguments are kept as raw 64-bit values. Conversions
+// narrow or sign extend them according to their length modifier.
+#[derive(Clone, Copy)]
+pub enum Arg {
+  Int(u64),
+  Double(f64),
+  // Raw bits of a long double, whose format depends on the target.
+  LongDouble(u128)
+}
+
+impl Arg {
+  pub fn int(self) -> u64 {
+    match self {
+      | Arg::Int(v) => v,
+      | Arg::Double(v) => v.to_bits(),
+      | Arg::LongDouble(v) => v as u64
+    }
+  }
+
+  pub fn double(self) -> f64 {
+    match self {
+      | Arg::Int(v) => f64::from_bits(v),
+      | Arg::Double(v) => v,
+      | Arg::LongDouble(v) => f64::from_bits(v as u64)
+    }
+  }
+
+  pub fn long_double(self) -> u128 {
+    match self {
+      | Arg::Int(v) => u128::from(v),
+      | Arg::Double(v) => u128::from(v.to_bits()),
+      | Arg::LongDouble(v) => v
+    }
+  }
+}
+
+// Arguments are read from the va_list in order. A format using `%n$` has all
+// of its arguments loaded up front, since the va_list can only be walked
+// once and only with the right types.
+pub struct Args<'a> {
+  ap: VaList<'a>,
+  slots: Option<[Arg; NL_ARGMAX]>
+}
+
+impl<'a> Args<'a> {
+  pub fn new(ap: VaList<'a>) -> Self {
+    Self { ap, slots: None }
+  }
+
+  pub fn positional(&self) -> bool {
+    self.slots.is_some()
+  }
+
+  pub fn load(
+    &mut self,
+    kinds: &[ArgKind]
+  ) {
+    let mut slots = [Arg::Int(0); NL_ARGMAX];
+    for (slot, &kind) in slots.iter_mut().zip(kinds) {
+      *slot = self.fetch(kind);
+    }
+    self.slots = Some(slots);
+  }
+
+  // `index` is zero based and only used once the arguments are loaded.
+  pub fn get(
+    &mut self,
+    index: Option<usize>,
+    kind: ArgKind
+  ) -> Arg {
+    match (&self.slots, index) {
+      | (Some(slots), Some(index)) => slots[index],
+      | _ => self.fetch(kind)
+    }
+  }
+
+  fn fetch(
+    &mut self,
+    kind: ArgKind
+  ) -> Arg {
+    let ap = &mut self.ap;
+    match kind {
+      | ArgKind::None => Arg::Int(0),
+      | ArgKind::Int => Arg::Int(unsafe { ap.next_arg::<i32>() } as u64),
+      | ArgKind::Long => Arg::Int(unsafe { ap.next_arg::<i64>() } as u64),
+      | ArgKind::Ptr => Arg::Int(unsafe { ap.next_arg::<usize>() } as u64),
+      | ArgKind::Double => Arg::Double(unsafe { ap.next_arg::<f64>() }),
+      | ArgKind::LongDouble => Arg::LongDouble(float::va_arg_long_double(ap))
+    }
+  }
+}
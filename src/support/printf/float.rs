@@ -0,0 +1,608 @@
+use {
+  super::{ALT, Flags, GROUP, LEFT, Output, PLUS, SPACE, Sink, ZERO},
+  crate::{
+    arch::float as fenv,
+    c_int,
+    support::{bignum::Big, locale::numeric}
+  },
+  core::cmp::Ordering
+};
+
+// Most significant digits a long double can need, reached by the largest
+// binary128 subnormal: every digit down to 2^-16494 is significant.
+const MAX_DIGITS: usize = 11_600;
+
+// log10(2) rounded up, as a fraction of 2^31.
+const LOG10_2: i64 = 646_456_994;
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Class {
+  Zero,
+  Finite,
+  Infinite,
+  Nan
+}
+
+// A binary floating-point value taken apart: mantissa * 2^exponent.
+pub struct Decoded {
+  negative: bool,
+  class: Class,
+  mantissa: u128,
+  exponent: i32,
+  // Mantissa width and exponent of the subnormals of the format.
+  bits: u32,
+  min_exponent: i32,
+  // The x87 format stores its integer bit, which %a prints as part of the
+  // leading hex digit like glibc does.
+  explicit: bool
+}
+
+impl Decoded {
+  pub fn from_f64(v: f64) -> Self {
+    let bits = v.to_bits();
+    let biased = ((bits >> 52) & 0x7ff) as i32;
+    let fraction = u128::from(bits & ((1 << 52) - 1));
+    Self::ieee(bits >> 63 != 0, biased, fraction, 53, 1023)
+  }
+
+  // Raw bits of the long double of the target, as read from a va_list.
+  pub fn from_long_double(bits: u128) -> Self {
+    if fenv::LDBL_MANT_DIG == 64 {
+      let negative = (bits >> 79) & 1 != 0;
+      let biased = ((bits >> 64) & 0x7fff) as i32;
+      let mantissa = bits & u128::from(u64::MAX);
+      let class = match biased {
+        | 0x7fff if mantissa << 65 == 0 => Class::Infinite,
+        | 0x7fff => Class::Nan,
+        | _ if mantissa == 0 => Class::Zero,
+        | _ => Class::Finite
+      };
+      Self {
+        negative,
+        class,
+        mantissa,
+        exponent: biased.max(1) - 16383 - 63,
+        bits: 64,
+        min_exponent: -16445,
+        explicit: true
+      }
+    } else {
+      let biased = ((bits >> 112) & 0x7fff) as i32;
+      let fraction = bits & ((1 << 112) - 1);
+      Self::ieee(bits >> 127 != 0, biased, fraction, 113, 16383)
+    }
+  }
+
+  fn ieee(
+    negative: bool,
+    biased: i32,
+    fraction: u128,
+    bits: u32,
+    bias: i32
+  ) -> Self {
+    let min_exponent = 2 - bias - bits as i32;
+    let (class, mantissa, exponent) = match biased {
+      | _ if biased == 2 * bias + 1 && fraction == 0 => (Class::Infinite, 0, 0),
+      | _ if biased == 2 * bias + 1 => (Class::Nan, 0, 0),
+      | 0 if fraction == 0 => (Class::Zero, 0, min_exponent),
+      | 0 => (Class::Finite, fraction, min_exponent),
+      | _ => (
+        Class::Finite,
+        fraction | 1 << (bits - 1),
+        biased - bias - bits as i32 + 1
+      )
+    };
+    Self {
+      negative,
+      class,
+      mantissa,
+      exponent,
+      bits,
+      min_exponent,
+      explicit: false
+    }
+  }
+}
+
+// Decimal digits of a value: 0.d1d2d3... * 10^k. Digits past `len` are
+// zero.
+pub struct Digits {
+  buf: [u8; MAX_DIGITS],
+  len: usize,
+  k: i32,
+  // Rounding carried into a new leading digit.
+  carried: bool
+}
+
+impl Digits {
+  pub const fn new() -> Self {
+    Self { buf: [0; MAX_DIGITS], len: 0, k: 1, carried: false }
+  }
+
+  pub fn digits(&self) -> &[u8] {
+    &self.buf[..self.len]
+  }
+
+  pub fn exponent(&self) -> i32 {
+    self.k
+  }
+
+  fn push(
+    &mut self,
+    d: u8
+  ) {
+    assert!(self.len < MAX_DIGITS, "printf: digit buffer overflow");
+    self.buf[self.len] = b'0' + d;
+    self.len += 1;
+  }
+
+  fn trim(&mut self) {
+    while self.len > 0 && self.buf[self.len - 1] == b'0' {
+      self.len -= 1;
+    }
+  }
+}
+
+impl Default for Digits {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// Where digit generation stops.
+#[derive(Clone, Copy)]
+enum Cutoff {
+  Significant(usize),
+  Fractional(usize)
+}
+
+// Set up r / s = v / 10^k with r / s in [0.1, 1). `gap` goes through the
+// same scaling as r.
+fn scale(
+  d: &Decoded,
+  mut gap: Option<&mut Big>
+) -> (Big, Big, i32) {
+  let mut r = Big::from_u128(d.mantissa);
+  let mut s = Big::from_u128(1);
+  if d.exponent >= 0 {
+    r.shl(d.exponent as usize);
+  } else {
+    s.shl(d.exponent.unsigned_abs() as usize);
+  }
+
+  // v < 2^n, so this is k or a little more than k.
+  let n = d.exponent + (128 - d.mantissa.leading_zeros()) as i32;
+  let mut k = ((i64::from(n) * LOG10_2 + (1 << 31) - 1) >> 31) as i32;
+  if k >= 0 {
+    s.mul_pow10(k as usize);
+  } else {
+    r.mul_pow10(k.unsigned_abs() as usize);
+    if let Some(gap) = gap.as_deref_mut() {
+      gap.mul_pow10(k.unsigned_abs() as usize);
+    }
+  }
+  loop {
+    let mut r10 = r.clone();
+    r10.mul_small(10);
+    if r10 >= s {
+      break;
+    }
+    k -= 1;
+    r = r10;
+    if let Some(gap) = gap.as_deref_mut() {
+      gap.mul_small(10);
+    }
+  }
+  (r, s, k)
+}
+
+// Exact digits up to the cutoff, rounded in the current rounding mode.
+fn generate(
+  d: &Decoded,
+  cutoff: Cutoff,
+  out: &mut Digits
+) {
+  out.len = 0;
+  out.k = 1;
+  out.carried = false;
+  if d.class != Class::Finite {
+    return;
+  }
+
+  let (mut r, s, k) = scale(d, None);
+  out.k = k;
+  let n = match cutoff {
+    | Cutoff::Significant(n) => n as i64,
+    | Cutoff::Fractional(n) => i64::from(k) + n as i64
+  };
+  while (out.len as i64) < n && !r.is_zero() {
+    r.mul_small(10);
+    let digit = r.div_digit(&s);
+    out.push(digit);
+  }
+  if r.is_zero() {
+    out.trim();
+    return;
+  }
+
+  // Anything past a negative cutoff is below half a unit.
+  let half = if n < 0 {
+    Ordering::Less
+  } else {
+    r.shl(1);
+    r.cmp(&s)
+  };
+  let odd = out.len > 0 && (out.buf[out.len - 1] - b'0') & 1 != 0;
+  let up = match fenv::get_rounding() {
+    | fenv::FE_UPWARD => !d.negative,
+    | fenv::FE_DOWNWARD => d.negative,
+    | fenv::FE_TOWARDZERO => false,
+    | _ => half == Ordering::Greater || half == Ordering::Equal && odd
+  };
+
+  if up {
+    while out.len > 0 && out.buf[out.len - 1] == b'9' {
+      out.len -= 1;
+    }
+    if out.len == 0 {
+      // Carry out of the first digit: the value is now 10^(k - n).
+      out.push(1);
+      out.k = k + 1 + (-n).max(0) as i32;
+      out.carried = true;
+      return;
+    }
+    out.buf[out.len - 1] += 1;
+  }
+  out.trim();
+}
+
+// Shortest digits that read back as the same value under round to nearest,
+// following Steele & White's free-format algorithm as refined by Burger and
+// Dybvig.
+pub fn shortest(
+  d: &Decoded,
+  out: &mut Digits
+) {
+  out.len = 0;
+  out.k = 1;
+  out.carried = false;
+  if d.class != Class::Finite {
+    return;
+  }
+
+  // Half the distance to the neighbours, in units of r. The one below is
+  // closer at a power of two.
+  let mut low = Big::from_u128(1);
+  if d.exponent > 0 {
+    low.shl(d.exponent as usize);
+  }
+  let (mut r, mut s, mut k) = scale(d, Some(&mut low));
+  let unequal = d.mantissa == 1 << (d.bits - 1) && d.exponent > d.min_exponent;
+  let shift = if unequal { 2 } else { 1 };
+  r.shl(shift);
+  s.shl(shift);
+  let mut high = low.clone();
+  if unequal {
+    high.shl(1);
+  }
+
+  // Values on a boundary round to the even mantissa, so it counts as ours.
+  let even = d.mantissa & 1 == 0;
+  let reaches = |r: &Big, high: &Big, s: &Big| {
+    let mut sum = r.clone();
+    sum.add(high);
+    sum > *s || even && sum == *s
+  };
+  if reaches(&r, &high, &s) {
+    k += 1;
+    s.mul_small(10);
+  }
+  out.k = k;
+
+  loop {
+    r.mul_small(10);
+    low.mul_small(10);
+    high.mul_small(10);
+    let digit = r.div_digit(&s);
+    let tc1 = r < low || even && r == low;
+    let tc2 = reaches(&r, &high, &s);
+    if !tc1 && !tc2 {
+      out.push(digit);
+      continue;
+    }
+    let up = if tc1 && tc2 {
+      r.shl(1);
+      r > s || r == s && digit & 1 != 0
+    } else {
+      tc2
+    };
+    out.push(digit + u8::from(up));
+    break;
+  }
+  out.trim();
+}
+
+fn sign(
+  flags: Flags,
+  negative: bool
+) -> &'static [u8] {
+  match () {
+    | () if negative => b"-",
+    | () if flags.has(PLUS) => b"+",
+    | () if flags.has(SPACE) => b" ",
+    | () => b""
+  }
+}
+
+// Write the padding and prefix of a field whose other `len` bytes follow,
+// returning the padding still owed on the right.
+fn open<S: Sink>(
+  out: &mut Output<S>,
+  flags: Flags,
+  width: usize,
+  prefix: &[u8],
+  len: usize,
+  zero: bool
+) -> Result<usize, c_int> {
+  let len = prefix.len() + len;
+  let pad = width.saturating_sub(len);
+  out.reserve(len + pad)?;
+  let zero = zero && flags.has(ZERO) && !flags.has(LEFT);
+  if !flags.has(LEFT) && !zero {
+    out.pad(b' ', pad)?;
+  }
+  out.write(prefix)?;
+  if zero {
+    out.pad(b'0', pad)?;
+  }
+  Ok(if flags.has(LEFT) { pad } else { 0 })
+}
+
+fn special<S: Sink>(
+  out: &mut Output<S>,
+  flags: Flags,
+  width: usize,
+  upper: bool,
+  d: &Decoded
+) -> Result<(), c_int> {
+  let body: &[u8] = match (d.class, upper) {
+    | (Class::Infinite, false) => b"inf",
+    | (Class::Infinite, true) => b"INF",
+    | (_, false) => b"nan",
+    | (_, true) => b"NAN"
+  };
+  let pad =
+    open(out, flags, width, sign(flags, d.negative), body.len(), false)?;
+  out.write(body)?;
+  out.pad(b' ', pad)
+}
+
+// Digits with index in [from, to), where those outside the generated ones
+// are zeros.
+fn run<S: Sink>(
+  out: &mut Output<S>,
+  digits: &[u8],
+  from: i64,
+  to: i64
+) -> Result<(), c_int> {
+  if from >= to {
+    return Ok(());
+  }
+  let len = digits.len() as i64;
+  let lead = to.min(0) - from.min(0);
+  let start = from.clamp(0, len);
+  let end = to.clamp(start, len);
+  let trail = to - from.max(len).min(to);
+  out.pad(b'0', lead as usize)?;
+  out.write(&digits[start as usize..end as usize])?;
+  out.pad(b'0', trail as usize)
+}
+
+// Digits in [from, to) as an integer part, with `sep` between its groups.
+fn grouped<S: Sink>(
+  out: &mut Output<S>,
+  digits: &[u8],
+  from: i64,
+  to: i64,
+  sep: &[u8],
+  grouping: &[u8]
+) -> Result<(), c_int> {
+  let mut start = from;
+  if !sep.is_empty() {
+    for i in from + 1..to {
+      if numeric::is_boundary(grouping, (to - i) as usize) {
+        run(out, digits, start, i)?;
+        out.write(sep)?;
+        start = i;
+      }
+    }
+  }
+  run(out, digits, start, to)
+}
+
+fn exponent_digits(
+  x: i32,
+  min: usize,
+  buf: &mut [u8; 8]
+) -> usize {
+  let mut v = x.unsigned_abs();
+  let mut len = 0;
+  while v != 0 || len < min {
+    buf[buf.len() - 1 - len] = b'0' + (v % 10) as u8;
+    v /= 10;
+    len += 1;
+  }
+  len
+}
+
+// %e, %f and %g.
+pub(super) fn decimal<S: Sink>(
+  out: &mut Output<S>,
+  flags: Flags,
+  width: usize,
+  precision: Option<usize>,
+  conv: u8,
+  d: &Decoded
+) -> Result<(), c_int> {
+  let upper = conv.is_ascii_uppercase();
+  if matches!(d.class, Class::Infinite | Class::Nan) {
+    return special(out, flags, width, upper, d);
+  }
+
+  let mut digits = Digits::new();
+  let mut prec = precision.unwrap_or(6);
+  let mut exp_style = conv.eq_ignore_ascii_case(&b'e');
+  match conv.to_ascii_lowercase() {
+    | b'e' => generate(d, Cutoff::Significant(prec + 1), &mut digits),
+    | b'f' => generate(d, Cutoff::Fractional(prec), &mut digits),
+    | _ => {
+      let p = prec.max(1);
+      generate(d, Cutoff::Significant(p), &mut digits);
+      let x = i64::from(digits.k) - 1;
+      if (p as i64) > x && x >= -4 {
+        prec = (p as i64 - 1 - x) as usize;
+      } else {
+        exp_style = true;
+        // glibc keeps the f-style precision chosen before rounding carried
+        // the value up to 10^P, which leaves no digits after the point.
+        prec = if digits.carried && x == p as i64 { 0 } else { p - 1 };
+      }
+      if !flags.has(ALT) {
+        let len = digits.len as i64;
+        let shown = if exp_style { len - 1 } else { len - i64::from(digits.k) };
+        prec = prec.min(shown.max(0) as usize);
+      }
+    }
+  }
+
+  let k = i64::from(digits.k);
+  let point = if prec > 0 || flags.has(ALT) { numeric::radix() } else { b"" };
+  let mut exp = [0; 8];
+  let (int, exp_len) = if exp_style {
+    (1, exponent_digits(digits.k - 1, 2, &mut exp))
+  } else {
+    (k.max(1) as usize, 0)
+  };
+  let (sep, grouping) = if flags.has(GROUP) && !exp_style {
+    numeric::separator()
+  } else {
+    (&b""[..], &b""[..])
+  };
+  let mut len = int + point.len() + prec;
+  if exp_style {
+    len += 2 + exp_len;
+  } else if !sep.is_empty() {
+    len += sep.len() * numeric::separators(grouping, int);
+  }
+
+  let pad = open(out, flags, width, sign(flags, d.negative), len, true)?;
+  let digits = digits.digits();
+  if exp_style {
+    run(out, digits, 0, 1)?;
+    out.write(point)?;
+    run(out, digits, 1, 1 + prec as i64)?;
+    out.write(if upper { b"E" } else { b"e" })?;
+    out.write(if k - 1 < 0 { b"-" } else { b"+" })?;
+    out.write(&exp[exp.len() - exp_len..])?;
+  } else {
+    grouped(out, digits, k.min(1) - 1, k, sep, grouping)?;
+    out.write(point)?;
+    run(out, digits, k, k + prec as i64)?;
+  }
+  out.pad(b' ', pad)
+}
+
+// %a, with the leading digit taken from the top of the mantissa so every
+// other digit is a whole nibble of it.
+pub(super) fn hex<S: Sink>(
+  out: &mut Output<S>,
+  flags: Flags,
+  width: usize,
+  precision: Option<usize>,
+  conv: u8,
+  d: &Decoded
+) -> Result<(), c_int> {
+  let upper = conv == b'A';
+  if matches!(d.class, Class::Infinite | Class::Nan) {
+    return special(out, flags, width, upper, d);
+  }
+
+  let lead_bits = if d.explicit { 4 } else { 1 };
+  let frac_bits = d.bits - lead_bits;
+  let nibbles = (frac_bits / 4) as usize;
+  let mut lead = d.mantissa >> frac_bits;
+  let mut frac = d.mantissa & ((1 << frac_bits) - 1);
+  let mut exp =
+    if d.class == Class::Zero { 0 } else { d.exponent + frac_bits as i32 };
+
+  let shown = match precision {
+    | Some(p) if p < nibbles => {
+      let cut = ((nibbles - p) * 4) as u32;
+      let rest = frac & ((1 << cut) - 1);
+      let half = 1 << (cut - 1);
+      frac >>= cut;
+      let last = if p == 0 { lead } else { frac };
+      let up = rest != 0 &&
+        match fenv::get_rounding() {
+          | fenv::FE_UPWARD => !d.negative,
+          | fenv::FE_DOWNWARD => d.negative,
+          | fenv::FE_TOWARDZERO => false,
+          | _ => rest > half || rest == half && last & 1 != 0
+        };
+      if up {
+        frac += 1;
+        if frac >> (p * 4) != 0 {
+          frac = 0;
+          lead += 1;
+          // A full x87 leading digit wraps around to 1 in the next
+          // nibble's exponent.
+          if lead == 16 {
+            lead = 1;
+            exp += 4;
+          }
+        }
+      }
+      p
+    },
+    | Some(p) => p,
+    | None => {
+      let mut n = nibbles;
+      while n > 0 && frac.trailing_zeros() >= 4 {
+        frac >>= 4;
+        n -= 1;
+      }
+      n
+    }
+  };
+
+  let digits = if upper { b"0123456789ABCDEF" } else { b"0123456789abcdef" };
+  let mut body = [0; 32];
+  let used = shown.min(nibbles);
+  for i in 0..used {
+    body[used - 1 - i] = digits[((frac >> (4 * i)) & 0xf) as usize];
+  }
+
+  let point = if shown > 0 || flags.has(ALT) { numeric::radix() } else { b"" };
+  let mut exp_buf = [0; 8];
+  let exp_len = exponent_digits(exp, 1, &mut exp_buf);
+  let len = 1 + point.len() + shown + 2 + exp_len;
+  let sign = sign(flags, d.negative);
+  let mut prefix = [0; 3];
+  prefix[..sign.len()].copy_from_slice(sign);
+  prefix[sign.len()..sign.len() + 2].copy_from_slice(if upper {
+    b"0X"
+  } else {
+    b"0x"
+  });
+  let prefix = &prefix[..sign.len() + 2];
+
+  let pad = open(out, flags, width, prefix, len, true)?;
+  out.write(&[digits[lead as usize]])?;
+  out.write(point)?;
+  out.write(&body[..used])?;
+  out.pad(b'0', shown - used)?;
+  out.write(if upper { b"P" } else { b"p" })?;
+  out.write(if exp < 0 { b"-" } else { b"+" })?;
+  out.write(&exp_buf[exp_buf.len() - exp_len..])?;
+  out.pad(b' ', pad)
+}
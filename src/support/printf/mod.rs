@@ -0,0 +1,677 @@
+pub mod args;
+pub mod float;
+pub mod sink;
+
+use {
+  crate::{
+    c_char,
+    c_int,
+    mbstate_t,
+    std::{errno, stdlib},
+    support::locale::{self, numeric},
+    wchar_t
+  },
+  args::{Arg, ArgKind, Args, NL_ARGMAX},
+  core::{ffi::VaList, slice},
+  float::Decoded
+};
+
+// Output of the conversion engine. A sink reports failures as an errno
+// value, which aborts the whole call.
+pub trait Sink {
+  fn write(
+    &mut self,
+    bytes: &[u8]
+  ) -> Result<(), c_int>;
+}
+
+const LEFT: u8 = 1;
+const PLUS: u8 = 2;
+const SPACE: u8 = 4;
+const ALT: u8 = 8;
+const ZERO: u8 = 16;
+const GROUP: u8 = 32;
+
+#[derive(Clone, Copy, Default)]
+struct Flags(u8);
+
+impl Flags {
+  #[inline]
+  fn has(
+    self,
+    flag: u8
+  ) -> bool {
+    self.0 & flag != 0
+  }
+}
+
+// Field width or precision.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Count {
+  None,
+  Fixed(usize),
+  // Taken from an int argument, with the argument number for `*m$`.
+  Arg(Option<usize>)
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum Length {
+  None,
+  Char,
+  Short,
+  Long,
+  LongLong,
+  IntMax,
+  Size,
+  PtrDiff,
+  LongDouble,
+  // C23 `wN` and `wfN`, in bits.
+  Bits(u32)
+}
+
+impl Length {
+  pub fn is_64bit(self) -> bool {
+    matches!(
+      self,
+      Length::Long |
+        Length::LongLong |
+        Length::IntMax |
+        Length::Size |
+        Length::PtrDiff |
+        Length::Bits(64)
+    )
+  }
+
+  fn signed(
+    self,
+    v: u64
+  ) -> i64 {
+    match self {
+      | Length::Char | Length::Bits(8) => v as i8 as i64,
+      | Length::Short | Length::Bits(16) => v as i16 as i64,
+      | _ if self.is_64bit() => v as i64,
+      | _ => v as i32 as i64
+    }
+  }
+
+  fn unsigned(
+    self,
+    v: u64
+  ) -> u64 {
+    match self {
+      | Length::Char | Length::Bits(8) => v as u8 as u64,
+      | Length::Short | Length::Bits(16) => v as u16 as u64,
+      | _ if self.is_64bit() => v,
+      | _ => v as u32 as u64
+    }
+  }
+}
+
+#[derive(Clone, Copy)]
+struct Spec {
+  // Zero based argument number from `%n$`.
+  index: Option<usize>,
+  flags: Flags,
+  width: Count,
+  precision: Count,
+  length: Length,
+  conv: u8
+}
+
+impl Spec {
+  fn kind(&self) -> ArgKind {
+    match self.conv {
+      | b'c' => ArgKind::Int,
+      | b'd' | b'i' | b'o' | b'u' | b'x' | b'X' | b'b' | b'B' => {
+        if self.length.is_64bit() { ArgKind::Long } else { ArgKind::Int }
+      },
+      | b's' | b'p' | b'n' => ArgKind::Ptr,
+      | b'e' | b'E' | b'f' | b'F' | b'g' | b'G' | b'a' | b'A' => {
+        if self.length == Length::LongDouble {
+          ArgKind::LongDouble
+        } else {
+          ArgKind::Double
+        }
+      },
+      | _ => ArgKind::None
+    }
+  }
+}
+
+// Tracks the number of bytes produced, which is what the printf functions
+// return even when a bounded sink drops some of them.
+struct Output<'s, S: Sink> {
+  sink: &'s mut S,
+  count: usize
+}
+
+impl<S: Sink> Output<'_, S> {
+  // Fields are checked before they are written so that a huge width fails
+  // with EOVERFLOW instead of producing gigabytes of padding.
+  fn reserve(
+    &self,
+    len: usize
+  ) -> Result<(), c_int> {
+    match self.count.checked_add(len) {
+      | Some(n) if n <= c_int::MAX as usize => Ok(()),
+      | _ => Err(errno::EOVERFLOW)
+    }
+  }
+
+  fn write(
+    &mut self,
+    bytes: &[u8]
+  ) -> Result<(), c_int> {
+    self.reserve(bytes.len())?;
+    self.count += bytes.len();
+    self.sink.write(bytes)
+  }
+
+  fn pad(
+    &mut self,
+    c: u8,
+    n: usize
+  ) -> Result<(), c_int> {
+    let chunk = [c; 32];
+    let mut left = n;
+    while left > 0 {
+      let len = left.min(chunk.len());
+      self.write(&chunk[..len])?;
+      left -= len;
+    }
+    Ok(())
+  }
+}
+
+// Parse a decimal number, failing with EOVERFLOW past INT_MAX.
+pub unsafe fn parse_number(p: &mut *const u8) -> Result<usize, c_int> {
+  let mut n: usize = 0;
+  while (**p).is_ascii_digit() {
+    n = n * 10 + (**p - b'0') as usize;
+    if n > c_int::MAX as usize {
+      return Err(errno::EOVERFLOW);
+    }
+    *p = p.add(1);
+  }
+  Ok(n)
+}
+
+// Parse `m$` after a `*` or at the start of a conversion.
+pub unsafe fn parse_index(p: &mut *const u8) -> Result<Option<usize>, c_int> {
+  if !(b'1'..=b'9').contains(&**p) {
+    return Ok(None);
+  }
+  let mut q = *p;
+  let n = parse_number(&mut q)?;
+  if *q != b'$' {
+    return Ok(None);
+  }
+  if n > NL_ARGMAX {
+    return Err(errno::EINVAL);
+  }
+  *p = q.add(1);
+  Ok(Some(n - 1))
+}
+
+unsafe fn parse_count(p: &mut *const u8) -> Result<Count, c_int> {
+  if **p == b'*' {
+    *p = p.add(1);
+    return Ok(Count::Arg(parse_index(p)?));
+  }
+  if (**p).is_ascii_digit() {
+    return Ok(Count::Fixed(parse_number(p)?));
+  }
+  Ok(Count::None)
+}
+
+pub unsafe fn parse_length(p: &mut *const u8) -> Result<Length, c_int> {
+  let c = **p;
+  let next = *p.add(1);
+  let (length, len) = match c {
+    | b'h' if next == b'h' => (Length::Char, 2),
+    | b'h' => (Length::Short, 1),
+    | b'l' if next == b'l' => (Length::LongLong, 2),
+    | b'l' => (Length::Long, 1),
+    | b'j' => (Length::IntMax, 1),
+    | b'z' => (Length::Size, 1),
+    | b't' => (Length::PtrDiff, 1),
+    | b'L' => (Length::LongDouble, 1),
+    | b'w' => {
+      // The fast types are 8 bits for wf8 and 64 bits otherwise, as in the
+      // LP64 <stdint.h>.
+      let fast = next == b'f';
+      let mut q = p.add(if fast { 2 } else { 1 });
+      let bits = match parse_number(&mut q)? {
+        | 8 => 8,
+        | 16 | 32 if fast => 64,
+        | n @ (16 | 32 | 64) => n as u32,
+        | _ => return Err(errno::EINVAL)
+      };
+      *p = q;
+      return Ok(Length::Bits(bits));
+    },
+    | _ => (Length::None, 0)
+  };
+  *p = p.add(len);
+  Ok(length)
+}
+
+// Parse a conversion specification following a '%'.
+unsafe fn parse_spec(p: &mut *const u8) -> Result<Spec, c_int> {
+  let index = parse_index(p)?;
+  let mut flags = Flags::default();
+  loop {
+    match **p {
+      | b'-' => flags.0 |= LEFT,
+      | b'+' => flags.0 |= PLUS,
+      | b' ' => flags.0 |= SPACE,
+      | b'#' => flags.0 |= ALT,
+      | b'0' => flags.0 |= ZERO,
+      | b'\'' => flags.0 |= GROUP,
+      | _ => break
+    }
+    *p = p.add(1);
+  }
+  let width = parse_count(p)?;
+  let precision = if **p == b'.' {
+    *p = p.add(1);
+    match parse_count(p)? {
+      | Count::None => Count::Fixed(0),
+      | count => count
+    }
+  } else {
+    Count::None
+  };
+  let mut length = parse_length(p)?;
+  let mut conv = **p;
+  match conv {
+    | b'C' | b'S' => {
+      conv = conv.to_ascii_lowercase();
+      length = Length::Long;
+    },
+    | b'd' | b'i' | b'o' | b'u' | b'x' | b'X' | b'b' | b'B' | b'c' | b's' |
+    b'p' | b'n' | b'e' | b'E' | b'f' | b'F' | b'g' | b'G' | b'a' | b'A' |
+    b'%' => {},
+    | _ => return Err(errno::EINVAL)
+  }
+  *p = p.add(1);
+  Ok(Spec { index, flags, width, precision, length, conv })
+}
+
+// Collect the argument types of a format using `%n$` and load them. Every
+// conversion has to be numbered and no argument may be skipped.
+unsafe fn load_positional(
+  fmt: *const u8,
+  args: &mut Args
+) -> Result<(), c_int> {
+  let mut kinds = [ArgKind::None; NL_ARGMAX];
+  let mut count = 0;
+  let mut record = |index: Option<usize>, kind: ArgKind| {
+    let Some(index) = index else {
+      return Err(errno::EINVAL);
+    };
+    kinds[index] = kind;
+    count = count.max(index + 1);
+    Ok(())
+  };
+
+  let mut p = fmt;
+  while *p != 0 {
+    if *p != b'%' {
+      p = p.add(1);
+      continue;
+    }
+    p = p.add(1);
+    let spec = parse_spec(&mut p)?;
+    if spec.conv == b'%' {
+      continue;
+    }
+    for count in [spec.width, spec.precision] {
+      if let Count::Arg(index) = count {
+        record(index, ArgKind::Int)?;
+      }
+    }
+    record(spec.index, spec.kind())?;
+  }
+
+  if kinds[..count].contains(&ArgKind::None) {
+    return Err(errno::EINVAL);
+  }
+  args.load(&kinds[..count]);
+  Ok(())
+}
+
+// Resolve a width or precision, negative values come back as None.
+unsafe fn resolve(
+  count: Count,
+  args: &mut Args
+) -> Option<usize> {
+  match count {
+    | Count::None => None,
+    | Count::Fixed(n) => Some(n),
+    | Count::Arg(index) => {
+      let n = args.get(index, ArgKind::Int).int() as i32;
+      usize::try_from(n).ok()
+    }
+  }
+}
+
+// Write `body` in a field of `width` bytes, preceded by `prefix` and
+// `zeros` zero digits.
+fn field<S: Sink>(
+  out: &mut Output<S>,
+  flags: Flags,
+  width: usize,
+  prefix: &[u8],
+  zeros: usize,
+  body: &[u8]
+) -> Result<(), c_int> {
+  let len = prefix.len() + zeros + body.len();
+  let pad = width.saturating_sub(len);
+  out.reserve(len + pad)?;
+  if !flags.has(LEFT) {
+    out.pad(b' ', pad)?;
+  }
+  out.write(prefix)?;
+  out.pad(b'0', zeros)?;
+  out.write(body)?;
+  if flags.has(LEFT) {
+    out.pad(b' ', pad)?;
+  }
+  Ok(())
+}
+
+// Digits of an integer with the thousands separator of the locale between
+// its groups, as the ' flag asks.
+fn group<'a>(
+  digits: &'a [u8],
+  buf: &'a mut [u8]
+) -> &'a [u8] {
+  let (sep, grouping) = numeric::separator();
+  if sep.is_empty() || sep.len() > stdlib::MB_LEN_MAX as usize {
+    return digits;
+  }
+  let mut len = 0;
+  for (i, &digit) in digits.iter().enumerate() {
+    if i > 0 && numeric::is_boundary(grouping, digits.len() - i) {
+      buf[len..len + sep.len()].copy_from_slice(sep);
+      len += sep.len();
+    }
+    buf[len] = digit;
+    len += 1;
+  }
+  &buf[..len]
+}
+
+fn integer<S: Sink>(
+  out: &mut Output<S>,
+  spec: &Spec,
+  width: usize,
+  precision: Option<usize>,
+  value: u64,
+  negative: bool
+) -> Result<(), c_int> {
+  let (base, digits): (u64, &[u8; 16]) = match spec.conv {
+    | b'o' => (8, b"0123456789abcdef"),
+    | b'x' | b'p' => (16, b"0123456789abcdef"),
+    | b'X' => (16, b"0123456789ABCDEF"),
+    | b'b' | b'B' => (2, b"0123456789abcdef"),
+    | _ => (10, b"0123456789abcdef")
+  };
+
+  let mut buf = [0u8; 64];
+  let mut start = buf.len();
+  let mut v = value;
+  if value != 0 || precision != Some(0) {
+    loop {
+      start -= 1;
+      buf[start] = digits[(v % base) as usize];
+      v /= base;
+      if v == 0 {
+        break;
+      }
+    }
+  }
+  let mut grouped = [0u8; 384];
+  let body = if spec.flags.has(GROUP) && matches!(spec.conv, b'd' | b'i' | b'u')
+  {
+    group(&buf[start..], &mut grouped)
+  } else {
+    &buf[start..]
+  };
+
+  let mut zeros = precision.unwrap_or(1).saturating_sub(body.len());
+  // The alternate form of %o makes sure the first digit is a zero.
+  if spec.conv == b'o' &&
+    spec.flags.has(ALT) &&
+    zeros == 0 &&
+    body.first() != Some(&b'0')
+  {
+    zeros = 1;
+  }
+
+  let prefix: &[u8] = match spec.conv {
+    | b'd' | b'i' if negative => b"-",
+    | b'd' | b'i' if spec.flags.has(PLUS) => b"+",
+    | b'd' | b'i' if spec.flags.has(SPACE) => b" ",
+    | b'x' if spec.flags.has(ALT) && value != 0 => b"0x",
+    | b'X' if spec.flags.has(ALT) && value != 0 => b"0X",
+    | b'b' if spec.flags.has(ALT) && value != 0 => b"0b",
+    | b'B' if spec.flags.has(ALT) && value != 0 => b"0B",
+    | b'p' => b"0x",
+    | _ => b""
+  };
+
+  // The 0 flag is ignored with a precision or the - flag.
+  if spec.flags.has(ZERO) && !spec.flags.has(LEFT) && precision.is_none() {
+    zeros = zeros.max(width.saturating_sub(prefix.len() + body.len()));
+  }
+  field(out, spec.flags, width, prefix, zeros, body)
+}
+
+unsafe fn string<S: Sink>(
+  out: &mut Output<S>,
+  flags: Flags,
+  width: usize,
+  precision: Option<usize>,
+  s: *const u8
+) -> Result<(), c_int> {
+  let max = precision.unwrap_or(usize::MAX);
+  let body: &[u8] = if s.is_null() {
+    // Like glibc, print nothing when "(null)" would be cut short.
+    if max < 6 { b"" } else { b"(null)" }
+  } else {
+    let mut len = 0;
+    while len < max && *s.add(len) != 0 {
+      len += 1;
+    }
+    slice::from_raw_parts(s, len)
+  };
+  field(out, flags, width, b"", 0, body)
+}
+
+// Convert one wide character with the c32tomb function of the thread's
+// locale.
+fn wide_char(
+  c: wchar_t,
+  buf: &mut [c_char; stdlib::MB_LEN_MAX as usize],
+  state: &mut mbstate_t
+) -> Result<usize, c_int> {
+  let loc = unsafe { *locale::get_thread_locale() };
+  let len = (loc.ctype.c32tomb)(buf.as_mut_ptr(), c as u32, state);
+  usize::try_from(len).map_err(|_| errno::EILSEQ)
+}
+
+// Multibyte characters are never cut, the precision limits the output to
+// the characters that fit entirely.
+unsafe fn wide_string<S: Sink>(
+  out: &mut Output<S>,
+  flags: Flags,
+  width: usize,
+  precision: Option<usize>,
+  s: *const wchar_t
+) -> Result<(), c_int> {
+  if s.is_null() {
+    return string(out, flags, width, precision, c"(null)".as_ptr().cast());
+  }
+
+  let max = precision.unwrap_or(usize::MAX);
+  let mut buf = [0; stdlib::MB_LEN_MAX as usize];
+  let mut state = mbstate_t::new();
+  let mut len = 0;
+  let mut p = s;
+  while *p != 0 {
+    let n = wide_char(*p, &mut buf, &mut state)?;
+    if n > max - len {
+      break;
+    }
+    len += n;
+    p = p.add(1);
+  }
+
+  let pad = width.saturating_sub(len);
+  out.reserve(len + pad)?;
+  if !flags.has(LEFT) {
+    out.pad(b' ', pad)?;
+  }
+  let mut state = mbstate_t::new();
+  for i in 0..p.offset_from(s) as usize {
+    let n = wide_char(*s.add(i), &mut buf, &mut state)?;
+    out.write(slice::from_raw_parts(buf.as_ptr().cast(), n))?;
+  }
+  if flags.has(LEFT) {
+    out.pad(b' ', pad)?;
+  }
+  Ok(())
+}
+
+unsafe fn store_count(
+  spec: &Spec,
+  p: usize,
+  count: usize
+) {
+  if p == 0 {
+    return;
+  }
+  match spec.length {
+    | Length::Char | Length::Bits(8) => *(p as *mut i8) = count as i8,
+    | Length::Short | Length::Bits(16) => *(p as *mut i16) = count as i16,
+    | length if length.is_64bit() => *(p as *mut i64) = count as i64,
+    | _ => *(p as *mut i32) = count as i32
+  }
+}
+
+fn decode(
+  spec: &Spec,
+  arg: Arg
+) -> Decoded {
+  if spec.length == Length::LongDouble {
+    Decoded::from_long_double(arg.long_double())
+  } else {
+    Decoded::from_f64(arg.double())
+  }
+}
+
+unsafe fn convert<S: Sink>(
+  out: &mut Output<S>,
+  spec: &Spec,
+  args: &mut Args
+) -> Result<(), c_int> {
+  if spec.conv == b'%' {
+    return out.write(b"%");
+  }
+  if args.positional() != spec.index.is_some() {
+    return Err(errno::EINVAL);
+  }
+
+  let mut flags = spec.flags;
+  let width = match spec.width {
+    | Count::Arg(index) => {
+      // A negative width is taken as the - flag.
+      let n = args.get(index, ArgKind::Int).int() as i32;
+      if n < 0 {
+        flags.0 |= LEFT;
+      }
+      n.unsigned_abs() as usize
+    },
+    | count => resolve(count, args).unwrap_or(0)
+  };
+  let precision = resolve(spec.precision, args);
+  let spec = Spec { flags, ..*spec };
+  let arg: Arg = args.get(spec.index, spec.kind());
+
+  match spec.conv {
+    | b'd' | b'i' => {
+      let v = spec.length.signed(arg.int());
+      integer(out, &spec, width, precision, v.unsigned_abs(), v < 0)
+    },
+    | b'o' | b'u' | b'x' | b'X' | b'b' | b'B' => {
+      let v = spec.length.unsigned(arg.int());
+      integer(out, &spec, width, precision, v, false)
+    },
+    | b'p' if arg.int() == 0 => {
+      string(out, flags, width, None, c"(nil)".as_ptr().cast())
+    },
+    | b'p' => integer(out, &spec, width, precision, arg.int(), false),
+    | b'c' if spec.length == Length::Long => {
+      let mut buf = [0; stdlib::MB_LEN_MAX as usize];
+      let mut state = mbstate_t::new();
+      let n = wide_char(arg.int() as wchar_t, &mut buf, &mut state)?;
+      let body = slice::from_raw_parts(buf.as_ptr().cast(), n);
+      field(out, flags, width, b"", 0, body)
+    },
+    | b'c' => field(out, flags, width, b"", 0, &[arg.int() as u8]),
+    | b's' if spec.length == Length::Long => {
+      wide_string(out, flags, width, precision, arg.int() as *const wchar_t)
+    },
+    | b's' => string(out, flags, width, precision, arg.int() as *const u8),
+    | b'n' => {
+      store_count(&spec, arg.int() as usize, out.count);
+      Ok(())
+    },
+    | b'a' | b'A' => {
+      float::hex(out, flags, width, precision, spec.conv, &decode(&spec, arg))
+    },
+    | _ => {
+      let d = decode(&spec, arg);
+      float::decimal(out, flags, width, precision, spec.conv, &d)
+    }
+  }
+}
+
+// Run the conversions in `fmt` and return the number of bytes produced.
+pub fn format<S: Sink>(
+  sink: &mut S,
+  fmt: *const c_char,
+  ap: VaList
+) -> Result<usize, c_int> {
+  unsafe {
+    let fmt = fmt.cast::<u8>();
+    let mut out = Output { sink, count: 0 };
+    let mut args = Args::new(ap);
+
+    let mut p = fmt;
+    while *p != 0 && *p != b'%' {
+      p = p.add(1);
+    }
+    if *p == b'%' && parse_index(&mut p.add(1))?.is_some() {
+      load_positional(fmt, &mut args)?;
+    }
+
+    let mut p = fmt;
+    loop {
+      let start = p;
+      while *p != 0 && *p != b'%' {
+        p = p.add(1);
+      }
+      out.write(slice::from_raw_parts(start, p.offset_from(start) as usize))?;
+      if *p == 0 {
+        break;
+      }
+      p = p.add(1);
+      let spec = parse_spec(&mut p)?;
+      convert(&mut out, &spec, &mut args)?;
+    }
+    Ok(out.count)
+  }
+}
@@ -0,0 +1,112 @@
+use {
+  super::Sink,
+  crate::{c_char, c_int, std::errno, support::stringstream::StringStream},
+  allocator::vec::Vec,
+  syscalls::{Errno, Sysno, syscall}
+};
+
+// snprintf keeps what fits and drops the rest.
+impl Sink for StringStream<'_> {
+  fn write(
+    &mut self,
+    bytes: &[u8]
+  ) -> Result<(), c_int> {
+    self.from_bytes(bytes);
+    Ok(())
+  }
+}
+
+// Unbounded output to a caller provided buffer, for sprintf.
+pub struct RawSink {
+  buf: *mut c_char,
+  len: usize
+}
+
+impl RawSink {
+  pub fn new(buf: *mut c_char) -> Self {
+    Self { buf, len: 0 }
+  }
+
+  pub fn terminate(&mut self) {
+    unsafe { *self.buf.add(self.len) = 0 };
+  }
+}
+
+impl Sink for RawSink {
+  fn write(
+    &mut self,
+    bytes: &[u8]
+  ) -> Result<(), c_int> {
+    unsafe {
+      self
+        .buf
+        .add(self.len)
+        .copy_from_nonoverlapping(bytes.as_ptr().cast(), bytes.len());
+    }
+    self.len += bytes.len();
+    Ok(())
+  }
+}
+
+// Growing output for asprintf. The buffer comes from malloc, so it can be
+// handed to the caller as is.
+pub struct VecSink(pub Vec<u8>);
+
+impl Sink for VecSink {
+  fn write(
+    &mut self,
+    bytes: &[u8]
+  ) -> Result<(), c_int> {
+    self.0.try_reserve(bytes.len()).map_err(|_| errno::ENOMEM)?;
+    self.0.extend_from_slice(bytes);
+    Ok(())
+  }
+}
+
+// Buffered output to a file descriptor, for dprintf.
+pub struct FdSink {
+  fd: c_int,
+  buf: [u8; 512],
+  len: usize
+}
+
+impl FdSink {
+  pub fn new(fd: c_int) -> Self {
+    Self { fd, buf: [0; 512], len: 0 }
+  }
+
+  pub fn flush(&mut self) -> Result<(), c_int> {
+    let mut done = 0;
+    while done < self.len {
+      let rest = &self.buf[done..self.len];
+      match unsafe {
+        syscall!(Sysno::write, self.fd, rest.as_ptr(), rest.len())
+      } {
+        | Ok(n) => done += n,
+        | Err(Errno::EINTR) => {},
+        | Err(e) => {
+          self.len = 0;
+          return Err(e.into_raw());
+        }
+      }
+    }
+    self.len = 0;
+    Ok(())
+  }
+}
+
+impl Sink for FdSink {
+  fn write(
+    &mut self,
+    bytes: &[u8]
+  ) -> Result<(), c_int> {
+    for chunk in bytes.chunks(self.buf.len()) {
+      if self.len + chunk.len() > self.buf.len() {
+        self.flush()?;
+      }
+      self.buf[self.len..self.len + chunk.len()].copy_from_slice(chunk);
+      self.len += chunk.len();
+    }
+    Ok(())
+  }
+}
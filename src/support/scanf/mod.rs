@@ -0,0 +1,678 @@
+pub mod source;
+
+use {
+  crate::{
+    c_char,
+    c_int,
+    char32_t,
+    mbstate_t,
+    std::{ctype, errno, stdio::EOF},
+    support::{
+      locale::{self, numeric},
+      printf::{
+        self,
+        Length,
+        args::{ArgKind, Args, NL_ARGMAX}
+      },
+      strtofloat,
+      strtointeger::strtointeger
+    },
+    wchar_t
+  },
+  allocator::vec::Vec,
+  core::{ffi::VaList, mem, ptr}
+};
+
+// Input of the conversion engine, read a byte at a time. Only the byte
+// that was just read is ever pushed back.
+pub trait Source {
+  fn get(&mut self) -> Option<u8>;
+
+  fn unget(
+    &mut self,
+    c: u8
+  );
+}
+
+// Why a call stops before the end of the format.
+enum Stop {
+  // The input ended before a directive could read anything.
+  Input,
+  // The input does not match the directive.
+  Matching,
+  // A failure reported through errno, such as ENOMEM for the `m` modifier.
+  Error(c_int)
+}
+
+// Tracks the number of bytes consumed for %n, and how many more the field
+// being converted may take.
+struct Input<'s, S: Source> {
+  src: &'s mut S,
+  count: usize,
+  left: usize
+}
+
+impl<S: Source> Input<'_, S> {
+  fn next(&mut self) -> Option<u8> {
+    if self.left == 0 {
+      return None;
+    }
+    let c = self.src.get()?;
+    self.count += 1;
+    self.left -= 1;
+    Some(c)
+  }
+
+  fn back(
+    &mut self,
+    c: u8
+  ) {
+    self.src.unget(c);
+    self.count -= 1;
+    self.left += 1;
+  }
+
+  // The first byte of a field, which is missing only at the end of input.
+  fn first(&mut self) -> Result<u8, Stop> {
+    self.next().ok_or(Stop::Input)
+  }
+
+  fn skip_space(&mut self) {
+    while let Some(c) = self.next() {
+      if !is_space(c) {
+        self.back(c);
+        break;
+      }
+    }
+  }
+
+  fn literal(
+    &mut self,
+    c: u8
+  ) -> Result<(), Stop> {
+    match self.next() {
+      | None => Err(Stop::Input),
+      | Some(d) if d == c => Ok(()),
+      | Some(d) => {
+        self.back(d);
+        Err(Stop::Matching)
+      }
+    }
+  }
+}
+
+fn is_space(c: u8) -> bool {
+  ctype::ouma_isspace(c_int::from(c)) != 0
+}
+
+fn push<T>(
+  v: &mut Vec<T>,
+  x: T
+) -> Result<(), Stop> {
+  v.try_reserve(1).map_err(|_| Stop::Error(errno::ENOMEM))?;
+  v.push(x);
+  Ok(())
+}
+
+// The bytes accepted by %[, one bit each.
+#[derive(Clone, Copy, Default)]
+struct Scanset([u64; 4]);
+
+impl Scanset {
+  fn insert(
+    &mut self,
+    c: u8
+  ) {
+    self.0[usize::from(c >> 6)] |= 1 << (c & 63);
+  }
+
+  fn contains(
+    &self,
+    c: u8
+  ) -> bool {
+    self.0[usize::from(c >> 6)] >> (c & 63) & 1 != 0
+  }
+}
+
+#[derive(Clone, Copy)]
+struct Spec {
+  // Zero based argument number from `%n$`.
+  index: Option<usize>,
+  // `*`: the field is read but not stored.
+  suppress: bool,
+  // `m`: strings go to a buffer from malloc.
+  alloc: bool,
+  width: Option<usize>,
+  length: Length,
+  conv: u8,
+  set: Scanset
+}
+
+// Parse the scanset of a %[ conversion, up to and including the ']'. As in
+// glibc, a '-' between two bytes in order makes a range and is taken as is
+// anywhere else.
+unsafe fn parse_set(p: &mut *const u8) -> Option<Scanset> {
+  let mut set = Scanset::default();
+  let invert = **p == b'^';
+  if invert {
+    *p = p.add(1);
+  }
+  let mut prev = None;
+  if **p == b']' {
+    set.insert(b']');
+    prev = Some(b']');
+    *p = p.add(1);
+  }
+  loop {
+    let c = **p;
+    match c {
+      | 0 => return None,
+      | b']' => break,
+      | _ => {}
+    }
+    *p = p.add(1);
+    let next = **p;
+    match prev {
+      | Some(low) if c == b'-' && next != 0 && next != b']' && low <= next => {
+        (low..next).for_each(|b| set.insert(b));
+      },
+      | _ => set.insert(c)
+    }
+    prev = Some(c);
+  }
+  *p = p.add(1);
+  if invert {
+    set.0.iter_mut().for_each(|w| *w = !*w);
+  }
+  Some(set)
+}
+
+// Parse a conversion specification following a '%'.
+unsafe fn parse_spec(p: &mut *const u8) -> Option<Spec> {
+  let index = printf::parse_index(p).ok()?;
+  let suppress = **p == b'*';
+  if suppress {
+    *p = p.add(1);
+  }
+  // Thousands grouping, a no-op as no locale defines a separator.
+  while **p == b'\'' {
+    *p = p.add(1);
+  }
+  let width = match printf::parse_number(p).ok()? {
+    | 0 => None,
+    | n => Some(n)
+  };
+  let alloc = **p == b'm';
+  if alloc {
+    *p = p.add(1);
+  }
+  let mut length = printf::parse_length(p).ok()?;
+  let mut conv = **p;
+  if conv == 0 {
+    return None;
+  }
+  *p = p.add(1);
+  let mut set = Scanset::default();
+  match conv {
+    | b'C' | b'S' => {
+      conv = conv.to_ascii_lowercase();
+      length = Length::Long;
+    },
+    | b'p' => length = Length::Long,
+    | b'[' => set = parse_set(p)?,
+    | b'd' | b'i' | b'o' | b'u' | b'x' | b'X' | b'b' | b'c' | b's' | b'n' |
+    b'e' | b'E' | b'f' | b'F' | b'g' | b'G' | b'a' | b'A' | b'%' => {},
+    | _ => return None
+  }
+  Some(Spec { index, suppress, alloc, width, length, conv, set })
+}
+
+// Load the arguments of a format using `%n$`, which are all pointers. No
+// argument may be skipped.
+unsafe fn load_positional(
+  fmt: *const u8,
+  args: &mut Args
+) -> Result<(), c_int> {
+  let mut kinds = [ArgKind::None; NL_ARGMAX];
+  let mut count = 0;
+  let mut p = fmt;
+  while *p != 0 {
+    if *p != b'%' {
+      p = p.add(1);
+      continue;
+    }
+    p = p.add(1);
+    let Some(spec) = parse_spec(&mut p) else {
+      break;
+    };
+    if spec.suppress || spec.conv == b'%' {
+      continue;
+    }
+    if let Some(index) = spec.index {
+      kinds[index] = ArgKind::Ptr;
+      count = count.max(index + 1);
+    }
+  }
+
+  if kinds[..count].contains(&ArgKind::None) {
+    return Err(errno::EINVAL);
+  }
+  if count != 0 {
+    args.load(&kinds[..count]);
+  }
+  Ok(())
+}
+
+// The pointer a conversion stores to.
+fn target(
+  spec: &Spec,
+  args: &mut Args
+) -> Result<usize, Stop> {
+  if args.positional() != spec.index.is_some() {
+    return Err(Stop::Error(errno::EINVAL));
+  }
+  Ok(args.get(spec.index, ArgKind::Ptr).int() as usize)
+}
+
+unsafe fn store_int(
+  length: Length,
+  p: usize,
+  v: u64
+) {
+  match length {
+    | Length::Char | Length::Bits(8) => *(p as *mut u8) = v as u8,
+    | Length::Short | Length::Bits(16) => *(p as *mut u16) = v as u16,
+    | length if length.is_64bit() => *(p as *mut u64) = v,
+    | _ => *(p as *mut u32) = v as u32
+  }
+}
+
+// Read an integer and convert it with strtol or strtoul. The digits are
+// collected first since the input can only go back by one byte. A prefix
+// is consumed even when no digit follows it, leaving just the "0".
+fn integer<S: Source>(
+  input: &mut Input<S>,
+  conv: u8
+) -> Result<u64, Stop> {
+  let mut base = match conv {
+    | b'd' | b'u' => 10,
+    | b'o' => 8,
+    | b'x' | b'X' | b'p' => 16,
+    | b'b' => 2,
+    | _ => 0
+  };
+  let mut buf = Vec::new();
+  let mut c = Some(input.first()?);
+  if let Some(sign @ (b'+' | b'-')) = c {
+    push(&mut buf, sign)?;
+    c = input.next();
+  }
+  let start = buf.len();
+  if c == Some(b'0') && matches!(base, 0 | 2 | 16) {
+    push(&mut buf, b'0')?;
+    c = input.next();
+    match c {
+      | Some(b'x' | b'X') if base != 2 => {
+        base = 16;
+        c = input.next();
+      },
+      | Some(b'b' | b'B') if base != 16 => {
+        base = 2;
+        c = input.next();
+      },
+      | _ if base == 0 => base = 8,
+      | _ => {}
+    }
+  }
+  if base == 0 {
+    base = 10;
+  }
+  while let Some(d) = c.filter(|d| (*d as char).is_digit(base)) {
+    push(&mut buf, d)?;
+    c = input.next();
+  }
+  if let Some(c) = c {
+    input.back(c);
+  }
+  if buf.len() == start {
+    return Err(Stop::Matching);
+  }
+
+  push(&mut buf, 0)?;
+  let s = buf.as_ptr().cast::<c_char>();
+  let base = base as c_int;
+  let v = if matches!(conv, b'd' | b'i') {
+    strtointeger::<u64, { i64::MIN as u64 }, { i64::MAX as u64 }>(s, base).0
+  } else {
+    strtointeger::<u64, 0, { u64::MAX }>(s, base).0
+  };
+  Ok(v)
+}
+
+// Append `c` to the text of a number and read the next byte.
+fn advance<S: Source>(
+  input: &mut Input<S>,
+  buf: &mut Vec<u8>,
+  c: u8
+) -> Result<Option<u8>, Stop> {
+  push(buf, c)?;
+  Ok(input.next())
+}
+
+// Match the rest of "inf", "infinity" or "nan", in any case.
+fn word<S: Source>(
+  input: &mut Input<S>,
+  buf: &mut Vec<u8>,
+  mut c: Option<u8>,
+  word: &[u8]
+) -> Result<Option<u8>, Stop> {
+  for &w in word {
+    match c {
+      | Some(d) if d.to_ascii_lowercase() == w => c = advance(input, buf, d)?,
+      | Some(d) => {
+        input.back(d);
+        return Err(Stop::Matching);
+      },
+      | None => return Err(Stop::Matching)
+    }
+  }
+  Ok(c)
+}
+
+// Read the longest prefix of a floating-point number and convert it with
+// strtod. Like glibc, a prefix that is a number on its own is enough, so
+// "1e+" gives 1.
+fn float<S: Source>(
+  input: &mut Input<S>,
+  length: Length
+) -> Result<u128, Stop> {
+  let mut buf = Vec::new();
+  let mut c = Some(input.first()?);
+  if let Some(sign @ (b'+' | b'-')) = c {
+    c = advance(input, &mut buf, sign)?;
+  }
+
+  match c.map(|c| c.to_ascii_lowercase()) {
+    | Some(b'i') => {
+      c = word(input, &mut buf, c, b"inf")?;
+      if c.is_some_and(|c| c.eq_ignore_ascii_case(&b'i')) {
+        c = word(input, &mut buf, c, b"inity")?;
+      }
+    },
+    | Some(b'n') => {
+      // Like glibc, the "(n-char-sequence)" of a NaN is left unread.
+      c = word(input, &mut buf, c, b"nan")?;
+    },
+    | _ => {
+      let radix = numeric::radix();
+      let (mut hex, mut digits, mut point) = (false, false, false);
+      if c == Some(b'0') {
+        c = advance(input, &mut buf, b'0')?;
+        digits = true;
+        if let Some(x @ (b'x' | b'X')) = c {
+          c = advance(input, &mut buf, x)?;
+          (hex, digits) = (true, false);
+        }
+      }
+      let base = if hex { 16 } else { 10 };
+      loop {
+        match c {
+          | Some(d) if (d as char).is_digit(base) => {
+            c = advance(input, &mut buf, d)?;
+            digits = true;
+          },
+          | Some(d) if !point && radix.first() == Some(&d) => {
+            point = true;
+            c = advance(input, &mut buf, d)?;
+            for &r in &radix[1..] {
+              if c != Some(r) {
+                break;
+              }
+              c = advance(input, &mut buf, r)?;
+            }
+          },
+          | _ => break
+        }
+      }
+      let marker = if hex { b'p' } else { b'e' };
+      if let Some(e) = c.filter(|e| digits && e.to_ascii_lowercase() == marker)
+      {
+        c = advance(input, &mut buf, e)?;
+        if let Some(sign @ (b'+' | b'-')) = c {
+          c = advance(input, &mut buf, sign)?;
+        }
+        while let Some(d) = c.filter(u8::is_ascii_digit) {
+          c = advance(input, &mut buf, d)?;
+        }
+      }
+      if hex && !digits {
+        return Err(Stop::Matching);
+      }
+    }
+  }
+  if let Some(c) = c {
+    input.back(c);
+  }
+
+  push(&mut buf, 0)?;
+  let format = match length {
+    | Length::LongDouble => &strtofloat::LONG_DOUBLE,
+    | Length::Long => &strtofloat::F64,
+    | _ => &strtofloat::F32
+  };
+  let radix = numeric::radix();
+  let (bits, parsed, _) =
+    strtofloat::strtofloat(buf.as_ptr().cast(), format, radix);
+  if parsed == 0 {
+    return Err(Stop::Matching);
+  }
+  Ok(bits)
+}
+
+unsafe fn store_float(
+  length: Length,
+  p: usize,
+  bits: u128
+) {
+  match length {
+    | Length::LongDouble => (p as *mut u128).write_unaligned(bits),
+    | Length::Long => *(p as *mut u64) = bits as u64,
+    | _ => *(p as *mut u32) = bits as u32
+  }
+}
+
+fn accepts(
+  spec: &Spec,
+  c: u8
+) -> bool {
+  match spec.conv {
+    | b's' => !is_space(c),
+    | b'[' => spec.set.contains(c),
+    | _ => true
+  }
+}
+
+// The bytes of %c, %s and %[.
+fn chars<S: Source>(
+  input: &mut Input<S>,
+  spec: &Spec
+) -> Result<Vec<u8>, Stop> {
+  let mut out = Vec::new();
+  let mut c = Some(input.first()?);
+  while let Some(d) = c {
+    if !accepts(spec, d) {
+      input.back(d);
+      break;
+    }
+    push(&mut out, d)?;
+    c = input.next();
+  }
+  if out.is_empty() {
+    return Err(Stop::Matching);
+  }
+  Ok(out)
+}
+
+// The characters of %lc, %ls and %l[, converted with the mbtoc32 function
+// of the thread's locale. The field width counts characters, and %ls and
+// %l[ check each byte of the multibyte sequence.
+fn wide_chars<S: Source>(
+  input: &mut Input<S>,
+  spec: &Spec
+) -> Result<Vec<wchar_t>, Stop> {
+  let mbtoc32 = unsafe { (*locale::get_thread_locale()).ctype.mbtoc32 };
+  let mut left = mem::replace(&mut input.left, usize::MAX);
+  let mut state = mbstate_t::new();
+  let mut out = Vec::new();
+  let mut c = Some(input.first()?);
+  while left > 0 {
+    let Some(mut d) = c else {
+      break;
+    };
+    if !accepts(spec, d) {
+      input.back(d);
+      break;
+    }
+    let mut wc: char32_t = 0;
+    while mbtoc32(&raw mut wc, (&raw const d).cast(), 1, &raw mut state) != 1 {
+      // The rest of a multibyte character, which must be there whole.
+      match input.next() {
+        | Some(next) if accepts(spec, next) => d = next,
+        | _ => {
+          errno::set_errno(errno::EILSEQ);
+          return Err(Stop::Matching);
+        }
+      }
+    }
+    push(&mut out, wc as wchar_t)?;
+    left -= 1;
+    if left > 0 {
+      c = input.next();
+    }
+  }
+  if out.is_empty() {
+    return Err(Stop::Matching);
+  }
+  Ok(out)
+}
+
+// Store a string to the argument, or to a new buffer with the `m`
+// modifier. %s and %[ add a terminator, %c does not.
+fn deliver<T: Copy + Default>(
+  spec: &Spec,
+  args: &mut Args,
+  mut v: Vec<T>
+) -> Result<(), Stop> {
+  if spec.suppress {
+    return Ok(());
+  }
+  if spec.conv != b'c' {
+    push(&mut v, T::default())?;
+  }
+  let dst = target(spec, args)?;
+  unsafe {
+    if spec.alloc {
+      *(dst as *mut *mut T) = mem::ManuallyDrop::new(v).as_mut_ptr();
+    } else {
+      ptr::copy_nonoverlapping(v.as_ptr(), dst as *mut T, v.len());
+    }
+  }
+  Ok(())
+}
+
+// Run one conversion and tell whether it assigned a value.
+fn convert<S: Source>(
+  input: &mut Input<S>,
+  spec: &Spec,
+  args: &mut Args
+) -> Result<bool, Stop> {
+  if !matches!(spec.conv, b'[' | b'c' | b'n') {
+    input.skip_space();
+  }
+  match spec.conv {
+    | b'%' => return input.literal(b'%').map(|()| false),
+    | b'n' => {
+      if !spec.suppress {
+        let p = target(spec, args)?;
+        unsafe { store_int(spec.length, p, input.count as u64) };
+      }
+      return Ok(false);
+    },
+    | _ => {}
+  }
+
+  let default = if spec.conv == b'c' { 1 } else { usize::MAX };
+  input.left = spec.width.unwrap_or(default);
+  let wide = spec.length == Length::Long;
+  match spec.conv {
+    | b'c' | b's' | b'[' if wide => {
+      let v = wide_chars(input, spec)?;
+      deliver(spec, args, v)?;
+    },
+    | b'c' | b's' | b'[' => {
+      let v = chars(input, spec)?;
+      deliver(spec, args, v)?;
+    },
+    | b'e' | b'E' | b'f' | b'F' | b'g' | b'G' | b'a' | b'A' => {
+      let bits = float(input, spec.length)?;
+      if !spec.suppress {
+        let p = target(spec, args)?;
+        unsafe { store_float(spec.length, p, bits) };
+      }
+    },
+    | _ => {
+      let v = integer(input, spec.conv)?;
+      if !spec.suppress {
+        let p = target(spec, args)?;
+        unsafe { store_int(spec.length, p, v) };
+      }
+    }
+  }
+  input.left = usize::MAX;
+  Ok(!spec.suppress)
+}
+
+// Run the directives in `fmt` and return the number of assignments, or EOF
+// when the input ends before the first one.
+pub fn scan<S: Source>(
+  src: &mut S,
+  fmt: *const c_char,
+  ap: VaList
+) -> Result<c_int, c_int> {
+  unsafe {
+    let fmt = fmt.cast::<u8>();
+    let mut input = Input { src, count: 0, left: usize::MAX };
+    let mut args = Args::new(ap);
+    load_positional(fmt, &mut args)?;
+
+    let mut done = 0;
+    let mut p = fmt;
+    let stop = loop {
+      let c = *p;
+      if c == 0 {
+        break None;
+      }
+      p = p.add(1);
+      let res = if is_space(c) {
+        input.skip_space();
+        Ok(false)
+      } else if c != b'%' {
+        input.literal(c).map(|()| false)
+      } else if let Some(spec) = parse_spec(&mut p) {
+        convert(&mut input, &spec, &mut args)
+      } else {
+        Err(Stop::Matching)
+      };
+      match res {
+        | Ok(assigned) => done += c_int::from(assigned),
+        | Err(stop) => break Some(stop)
+      }
+    };
+
+    match stop {
+      | Some(Stop::Input) if done == 0 => Ok(EOF),
+      | Some(Stop::Error(e)) => Err(e),
+      | _ => Ok(done)
+    }
+  }
+}
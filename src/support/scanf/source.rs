@@ -0,0 +1,30 @@
+use {super::Source, crate::c_char};
+
+// Input from a NUL terminated string, for sscanf.
+pub struct StrSource {
+  p: *const u8
+}
+
+impl StrSource {
+  pub fn new(s: *const c_char) -> Self {
+    Self { p: s.cast() }
+  }
+}
+
+impl Source for StrSource {
+  fn get(&mut self) -> Option<u8> {
+    let c = unsafe { *self.p };
+    if c == 0 {
+      return None;
+    }
+    self.p = self.p.wrapping_add(1);
+    Some(c)
+  }
+
+  fn unget(
+    &mut self,
+    _: u8
+  ) {
+    self.p = self.p.wrapping_sub(1);
+  }
+}
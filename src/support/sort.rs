@@ -0,0 +1,231 @@
+use core::{cmp::Ordering, mem, ptr};
+
+// Below this many elements insertion sort beats partitioning.
+const INSERTION_MAX: usize = 16;
+// Above this many elements the pivot is the median of three medians.
+const NINTHER_MIN: usize = 128;
+
+// Elements are exchanged in chunks of this type, which moves like a word.
+type Word = [u8; mem::size_of::<usize>()];
+
+// An array of `n` elements of `size` bytes ordered by a C comparator, which
+// may be inconsistent without the sort leaving the array.
+struct Slice<F> {
+  base: *mut u8,
+  size: usize,
+  cmp: F
+}
+
+impl<F: FnMut(*const u8, *const u8) -> Ordering> Slice<F> {
+  fn at(
+    &self,
+    i: usize
+  ) -> *mut u8 {
+    self.base.wrapping_add(i * self.size)
+  }
+
+  fn compare(
+    &mut self,
+    i: usize,
+    j: usize
+  ) -> Ordering {
+    let (a, b) = (self.at(i), self.at(j));
+    (self.cmp)(a, b)
+  }
+
+  // Exchange two elements a word at a time, then the bytes left over.
+  fn swap(
+    &self,
+    i: usize,
+    j: usize
+  ) {
+    if i == j {
+      return;
+    }
+    let (a, b) = (self.at(i), self.at(j));
+    let mut k = 0;
+    unsafe {
+      while k + mem::size_of::<Word>() <= self.size {
+        let (x, y) = (a.add(k).cast::<Word>(), b.add(k).cast::<Word>());
+        let t = x.read_unaligned();
+        x.write_unaligned(y.read_unaligned());
+        y.write_unaligned(t);
+        k += mem::size_of::<Word>();
+      }
+      while k < self.size {
+        ptr::swap(a.add(k), b.add(k));
+        k += 1;
+      }
+    }
+  }
+
+  fn insertion_sort(
+    &mut self,
+    lo: usize,
+    n: usize
+  ) {
+    for i in lo + 1..lo + n {
+      let mut j = i;
+      while j > lo && self.compare(j - 1, j) == Ordering::Greater {
+        self.swap(j - 1, j);
+        j -= 1;
+      }
+    }
+  }
+
+  fn sift_down(
+    &mut self,
+    lo: usize,
+    mut root: usize,
+    n: usize
+  ) {
+    loop {
+      let mut child = 2 * root + 1;
+      if child >= n {
+        return;
+      }
+      if child + 1 < n &&
+        self.compare(lo + child, lo + child + 1) == Ordering::Less
+      {
+        child += 1;
+      }
+      if self.compare(lo + root, lo + child) != Ordering::Less {
+        return;
+      }
+      self.swap(lo + root, lo + child);
+      root = child;
+    }
+  }
+
+  fn heapsort(
+    &mut self,
+    lo: usize,
+    n: usize
+  ) {
+    for root in (0..n / 2).rev() {
+      self.sift_down(lo, root, n);
+    }
+    for end in (1..n).rev() {
+      self.swap(lo, lo + end);
+      self.sift_down(lo, 0, end);
+    }
+  }
+
+  // Index of the median of three elements.
+  fn median(
+    &mut self,
+    a: usize,
+    b: usize,
+    c: usize
+  ) -> usize {
+    let (a, b) =
+      if self.compare(a, b) == Ordering::Greater { (b, a) } else { (a, b) };
+    if self.compare(b, c) != Ordering::Greater {
+      b
+    } else if self.compare(a, c) == Ordering::Greater {
+      a
+    } else {
+      c
+    }
+  }
+
+  // Move the pivot to `lo` and partition the rest around it, giving its
+  // final index. Elements equal to the pivot are exchanged too, which
+  // splits runs of equal keys evenly.
+  fn partition(
+    &mut self,
+    lo: usize,
+    n: usize
+  ) -> usize {
+    let (mid, last) = (lo + n / 2, lo + n - 1);
+    let pivot = if n > NINTHER_MIN {
+      let d = n / 8;
+      let a = self.median(lo, lo + d, lo + 2 * d);
+      let b = self.median(mid - d, mid, mid + d);
+      let c = self.median(last - 2 * d, last - d, last);
+      self.median(a, b, c)
+    } else {
+      self.median(lo, mid, last)
+    };
+    self.swap(lo, pivot);
+
+    let (mut i, mut j) = (lo + 1, last);
+    loop {
+      while i <= j && self.compare(i, lo) == Ordering::Less {
+        i += 1;
+      }
+      while i <= j && self.compare(j, lo) == Ordering::Greater {
+        j -= 1;
+      }
+      if i >= j {
+        break;
+      }
+      self.swap(i, j);
+      i += 1;
+      j -= 1;
+    }
+    self.swap(lo, j);
+    j
+  }
+
+  // Quicksort that recurses into the smaller side only, so the stack stays
+  // logarithmic, and falls back to heapsort once `depth` runs out.
+  fn introsort(
+    &mut self,
+    mut lo: usize,
+    mut n: usize,
+    mut depth: u32
+  ) {
+    while n > INSERTION_MAX {
+      if depth == 0 {
+        self.heapsort(lo, n);
+        return;
+      }
+      depth -= 1;
+      let p = self.partition(lo, n);
+      let (left, right) = (p - lo, lo + n - p - 1);
+      if left < right {
+        self.introsort(lo, left, depth);
+        (lo, n) = (p + 1, right);
+      } else {
+        self.introsort(p + 1, right, depth);
+        n = left;
+      }
+    }
+    self.insertion_sort(lo, n);
+  }
+}
+
+// Sort `n` elements of `size` bytes at `base` in O(n log n) comparisons.
+pub fn sort(
+  base: *mut u8,
+  n: usize,
+  size: usize,
+  cmp: impl FnMut(*const u8, *const u8) -> Ordering
+) {
+  if n < 2 || size == 0 {
+    return;
+  }
+  let mut s = Slice { base, size, cmp };
+  s.introsort(0, n, 2 * n.ilog2());
+}
+
+// Find an element equal to `key` in a sorted array, as bsearch does.
+pub fn search(
+  base: *const u8,
+  n: usize,
+  size: usize,
+  mut cmp: impl FnMut(*const u8) -> Ordering
+) -> Option<*const u8> {
+  let (mut lo, mut hi) = (0, n);
+  while lo < hi {
+    let mid = lo + (hi - lo) / 2;
+    let p = base.wrapping_add(mid * size);
+    match cmp(p) {
+      | Ordering::Less => hi = mid,
+      | Ordering::Greater => lo = mid + 1,
+      | Ordering::Equal => return Some(p)
+    }
+  }
+  None
+}
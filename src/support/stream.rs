@@ -0,0 +1,769 @@
+use {
+  crate::{
+    FILE,
+    alloc,
+    c_char,
+    c_int,
+    off_t,
+    std::{
+      errno,
+      stdio::{_IOFBF, _IOLBF, _IONBF, BUFSIZ, SEEK_CUR, SEEK_END, SEEK_SET}
+    },
+    support::{
+      printf::Sink,
+      scanf::Source,
+      string,
+      sync::{Mutex, MutexGuard}
+    }
+  },
+  core::{cell::UnsafeCell, mem, ptr, slice},
+  syscalls::{Errno, Sysno, syscall}
+};
+
+const O_RDONLY: c_int = 0;
+const O_WRONLY: c_int = 1;
+const O_RDWR: c_int = 2;
+const O_ACCMODE: c_int = 3;
+const O_CREAT: c_int = 0o100;
+const O_EXCL: c_int = 0o200;
+const O_TRUNC: c_int = 0o1000;
+const O_APPEND: c_int = 0o2000;
+const O_CLOEXEC: c_int = 0o2000000;
+
+const AT_FDCWD: c_int = -100;
+const F_SETFD: c_int = 2;
+const F_GETFL: c_int = 3;
+const F_SETFL: c_int = 4;
+const FD_CLOEXEC: c_int = 1;
+const TCGETS: usize = 0x5401;
+
+// Bytes kept in front of the buffer so ungetc always has room.
+const UNGET: usize = 8;
+
+const READ: u8 = 1;
+const WRITE: u8 = 2;
+const APPEND: u8 = 4;
+const EOF: u8 = 8;
+const ERROR: u8 = 16;
+// The buffer came from malloc.
+const OWN_BUF: u8 = 32;
+// The stream itself came from malloc, which is not the case for stdin,
+// stdout and stderr.
+const OWN_FILE: u8 = 64;
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum State {
+  Idle,
+  Reading,
+  Writing
+}
+
+// The buffer is `buf[UNGET..UNGET + size]`, or `small` for an unbuffered
+// stream. While reading, the unread input is `buf[rpos..rend]` and may
+// start in the ungetc area. While writing, the pending output is
+// `buf[UNGET..wpos]`.
+pub struct Stream {
+  fd: c_int,
+  flags: u8,
+  // _IOFBF, _IOLBF or _IONBF, or -1 until the first operation looks at
+  // whether the file is a terminal.
+  mode: c_int,
+  state: State,
+  buf: *mut u8,
+  // Zero until the buffer is set up.
+  size: usize,
+  rpos: usize,
+  rend: usize,
+  wpos: usize,
+  small: [u8; UNGET + 1]
+}
+
+unsafe impl Send for Stream {}
+
+impl Stream {
+  const fn new(
+    fd: c_int,
+    flags: u8,
+    mode: c_int,
+    buf: *mut u8,
+    size: usize
+  ) -> Self {
+    Self {
+      fd,
+      flags,
+      mode,
+      state: State::Idle,
+      buf,
+      size,
+      rpos: UNGET,
+      rend: UNGET,
+      wpos: UNGET,
+      small: [0; UNGET + 1]
+    }
+  }
+
+  pub fn fd(&self) -> c_int {
+    self.fd
+  }
+
+  pub fn eof(&self) -> bool {
+    self.flags & EOF != 0
+  }
+
+  pub fn error(&self) -> bool {
+    self.flags & ERROR != 0
+  }
+
+  pub fn clear(&mut self) {
+    self.flags &= !(EOF | ERROR);
+  }
+
+  fn base(&mut self) -> *mut u8 {
+    if self.buf.is_null() { self.small.as_mut_ptr() } else { self.buf }
+  }
+
+  fn fail(
+    &mut self,
+    e: c_int
+  ) -> c_int {
+    self.flags |= ERROR;
+    errno::set_errno(e);
+    e
+  }
+
+  // Pick the buffering on first use: terminals are line buffered, other
+  // files fully buffered. A buffer that cannot be allocated leaves the
+  // stream unbuffered.
+  fn setup(&mut self) {
+    if self.mode < 0 {
+      self.mode = if isatty(self.fd) { _IOLBF } else { _IOFBF };
+    }
+    if self.size != 0 {
+      return;
+    }
+    if self.mode != _IONBF {
+      let size = BUFSIZ as usize;
+      self.buf = alloc::allocate(UNGET + size, 1);
+      if !self.buf.is_null() {
+        self.flags |= OWN_BUF;
+        self.size = size;
+        return;
+      }
+      self.mode = _IONBF;
+    }
+    self.buf = ptr::null_mut();
+    self.size = 1;
+  }
+
+  fn release(&mut self) {
+    if self.flags & OWN_BUF != 0 {
+      alloc::deallocate(self.buf);
+      self.flags &= !OWN_BUF;
+    }
+    self.buf = ptr::null_mut();
+    self.size = 0;
+  }
+
+  fn write_fd(
+    &mut self,
+    data: &[u8]
+  ) -> Result<(), c_int> {
+    let mut done = 0;
+    while done < data.len() {
+      let rest = &data[done..];
+      match unsafe {
+        syscall!(Sysno::write, self.fd, rest.as_ptr(), rest.len())
+      } {
+        | Ok(n) => done += n,
+        | Err(Errno::EINTR) => {},
+        | Err(e) => return Err(self.fail(e.into_raw()))
+      }
+    }
+    Ok(())
+  }
+
+  fn read_fd(
+    &mut self,
+    data: *mut u8,
+    len: usize
+  ) -> usize {
+    // Input stays at end of file until clearerr, like C requires.
+    if self.eof() {
+      return 0;
+    }
+    if !self.is_stdout() {
+      flush_line_buffered_stdout();
+    }
+    loop {
+      match unsafe { syscall!(Sysno::read, self.fd, data, len) } {
+        | Ok(0) => {
+          self.flags |= EOF;
+          return 0;
+        },
+        | Ok(n) => return n,
+        | Err(Errno::EINTR) => {},
+        | Err(e) => {
+          self.fail(e.into_raw());
+          return 0;
+        }
+      }
+    }
+  }
+
+  fn is_stdout(&self) -> bool {
+    ptr::eq(self, STDOUT.stream.as_ptr().cast_const())
+  }
+
+  // Give back unread input to the file so its offset matches the stream.
+  fn drop_input(&mut self) {
+    let unread = self.rend - self.rpos;
+    if unread != 0 {
+      let _ = unsafe {
+        syscall!(Sysno::lseek, self.fd, -(unread as isize), SEEK_CUR)
+      };
+    }
+    self.rpos = UNGET;
+    self.rend = UNGET;
+    self.state = State::Idle;
+  }
+
+  pub fn flush(&mut self) -> Result<(), c_int> {
+    match self.state {
+      | State::Writing => {
+        let len = self.wpos - UNGET;
+        self.wpos = UNGET;
+        self.state = State::Idle;
+        let data =
+          unsafe { slice::from_raw_parts(self.base().add(UNGET), len) };
+        self.write_fd(data)
+      },
+      | State::Reading => {
+        self.drop_input();
+        Ok(())
+      },
+      | State::Idle => Ok(())
+    }
+  }
+
+  fn prepare_read(&mut self) -> bool {
+    if self.flags & READ == 0 {
+      self.fail(errno::EBADF);
+      return false;
+    }
+    if self.state == State::Writing && self.flush().is_err() {
+      return false;
+    }
+    self.setup();
+    self.state = State::Reading;
+    true
+  }
+
+  fn prepare_write(&mut self) -> bool {
+    if self.flags & WRITE == 0 {
+      self.fail(errno::EBADF);
+      return false;
+    }
+    if self.state == State::Reading {
+      self.drop_input();
+    }
+    self.setup();
+    self.state = State::Writing;
+    true
+  }
+
+  fn fill(&mut self) -> bool {
+    let base = self.base();
+    let n = self.read_fd(unsafe { base.add(UNGET) }, self.size);
+    self.rpos = UNGET;
+    self.rend = UNGET + n;
+    n != 0
+  }
+
+  fn unread(&mut self) -> &[u8] {
+    let (rpos, rend) = (self.rpos, self.rend);
+    unsafe { slice::from_raw_parts(self.base().add(rpos), rend - rpos) }
+  }
+
+  // Returns the number of bytes read, which is short at end of file or on
+  // an error.
+  pub fn read(
+    &mut self,
+    dst: &mut [u8]
+  ) -> usize {
+    if !self.prepare_read() {
+      return 0;
+    }
+    let mut done = 0;
+    while done < dst.len() {
+      let avail = self.unread();
+      if !avail.is_empty() {
+        let n = avail.len().min(dst.len() - done);
+        dst[done..done + n].copy_from_slice(&avail[..n]);
+        self.rpos += n;
+        done += n;
+        continue;
+      }
+      // Large reads go straight to the destination.
+      let rest = dst.len() - done;
+      if rest >= self.size {
+        let n = self.read_fd(dst[done..].as_mut_ptr(), rest);
+        if n == 0 {
+          break;
+        }
+        done += n;
+      } else if !self.fill() {
+        break;
+      }
+    }
+    done
+  }
+
+  pub fn getc(&mut self) -> Option<u8> {
+    if !self.prepare_read() {
+      return None;
+    }
+    if self.rpos == self.rend && !self.fill() {
+      return None;
+    }
+    let c = unsafe { *self.base().add(self.rpos) };
+    self.rpos += 1;
+    Some(c)
+  }
+
+  pub fn ungetc(
+    &mut self,
+    c: u8
+  ) -> bool {
+    if !self.prepare_read() || self.rpos == 0 {
+      return false;
+    }
+    self.rpos -= 1;
+    let rpos = self.rpos;
+    unsafe { *self.base().add(rpos) = c };
+    self.flags &= !EOF;
+    true
+  }
+
+  // Read a line of at most `dst.len()` bytes, returning its length.
+  pub fn gets(
+    &mut self,
+    dst: &mut [u8]
+  ) -> usize {
+    if !self.prepare_read() {
+      return 0;
+    }
+    let mut done = 0;
+    while done < dst.len() {
+      if self.rpos == self.rend && !self.fill() {
+        break;
+      }
+      let avail = self.unread();
+      let max = avail.len().min(dst.len() - done);
+      let (n, end) = match avail[..max].iter().position(|&c| c == b'\n') {
+        | Some(i) => (i + 1, true),
+        | None => (max, false)
+      };
+      dst[done..done + n].copy_from_slice(&avail[..n]);
+      self.rpos += n;
+      done += n;
+      if end {
+        break;
+      }
+    }
+    done
+  }
+
+  // Returns the number of bytes accepted, which is short on an error.
+  pub fn write(
+    &mut self,
+    src: &[u8]
+  ) -> usize {
+    if !self.prepare_write() {
+      return 0;
+    }
+    let room = self.size - (self.wpos - UNGET);
+    if src.len() > room || self.mode == _IONBF {
+      if self.flush().is_err() {
+        return 0;
+      }
+      self.state = State::Writing;
+      // Anything the buffer cannot hold is written directly.
+      if src.len() >= self.size {
+        return if self.write_fd(src).is_ok() { src.len() } else { 0 };
+      }
+    }
+    let wpos = self.wpos;
+    unsafe {
+      self.base().add(wpos).copy_from_nonoverlapping(src.as_ptr(), src.len());
+    }
+    self.wpos += src.len();
+    if self.mode == _IOLBF && src.contains(&b'\n') && self.flush().is_err() {
+      return 0;
+    }
+    src.len()
+  }
+
+  pub fn seek(
+    &mut self,
+    offset: off_t,
+    whence: c_int
+  ) -> Result<(), c_int> {
+    if !matches!(whence, SEEK_SET | SEEK_CUR | SEEK_END) {
+      return Err(errno::EINVAL);
+    }
+    let mut offset = offset;
+    if whence == SEEK_CUR && self.state == State::Reading {
+      offset -= (self.rend - self.rpos) as off_t;
+    }
+    if self.state == State::Writing {
+      self.flush()?;
+    }
+    unsafe { syscall!(Sysno::lseek, self.fd, offset, whence) }
+      .map_err(Errno::into_raw)?;
+    self.rpos = UNGET;
+    self.rend = UNGET;
+    self.state = State::Idle;
+    self.flags &= !EOF;
+    Ok(())
+  }
+
+  pub fn tell(&mut self) -> Result<off_t, c_int> {
+    let pending = self.wpos - UNGET;
+    let whence = if self.state == State::Writing &&
+      pending != 0 &&
+      self.flags & APPEND != 0
+    {
+      SEEK_END
+    } else {
+      SEEK_CUR
+    };
+    let pos = unsafe { syscall!(Sysno::lseek, self.fd, 0, whence) }
+      .map_err(Errno::into_raw)? as off_t;
+    Ok(match self.state {
+      | State::Reading => pos - (self.rend - self.rpos) as off_t,
+      | State::Writing => pos + pending as off_t,
+      | State::Idle => pos
+    })
+  }
+
+  pub fn setvbuf(
+    &mut self,
+    buf: *mut c_char,
+    mode: c_int,
+    size: usize
+  ) -> Result<(), c_int> {
+    if !matches!(mode, _IOFBF | _IOLBF | _IONBF) {
+      return Err(errno::EINVAL);
+    }
+    self.flush()?;
+    self.release();
+    self.mode = mode;
+    // The start of a caller provided buffer holds the ungetc area.
+    if mode != _IONBF && !buf.is_null() && size > UNGET {
+      self.buf = buf.cast();
+      self.size = size - UNGET;
+    }
+    Ok(())
+  }
+
+  fn close(&mut self) -> Result<(), c_int> {
+    let flushed = self.flush();
+    self.release();
+    let closed = unsafe { syscall!(Sysno::close, self.fd) }
+      .map(|_| ())
+      .map_err(Errno::into_raw);
+    self.fd = -1;
+    self.flags &= OWN_FILE;
+    flushed.and(closed)
+  }
+}
+
+// Formatted output keeps writing after a short write, the error is
+// reported once the call is done.
+impl Sink for Stream {
+  fn write(
+    &mut self,
+    bytes: &[u8]
+  ) -> Result<(), c_int> {
+    if self.write(bytes) < bytes.len() {
+      return Err(errno::get_errno());
+    }
+    Ok(())
+  }
+}
+
+// Formatted input pushes back at most the byte it just read, which always
+// fits in front of the read position.
+impl Source for Stream {
+  fn get(&mut self) -> Option<u8> {
+    self.getc()
+  }
+
+  fn unget(
+    &mut self,
+    c: u8
+  ) {
+    self.ungetc(c);
+  }
+}
+
+pub struct FileStruct {
+  stream: Mutex<Stream>,
+  // Next entry in the list of open streams, guarded by OPEN.
+  next: UnsafeCell<*mut FileStruct>
+}
+
+unsafe impl Sync for FileStruct {}
+
+impl FileStruct {
+  pub fn lock(&self) -> MutexGuard<'_, Stream> {
+    self.stream.lock()
+  }
+}
+
+struct OpenList(*mut FileStruct);
+
+unsafe impl Send for OpenList {}
+
+static mut STDIN_BUF: [u8; UNGET + BUFSIZ as usize] =
+  [0; UNGET + BUFSIZ as usize];
+static mut STDOUT_BUF: [u8; UNGET + BUFSIZ as usize] =
+  [0; UNGET + BUFSIZ as usize];
+
+pub static STDIN: FileStruct = FileStruct {
+  stream: Mutex::new(Stream::new(
+    0,
+    READ,
+    _IOFBF,
+    (&raw mut STDIN_BUF).cast(),
+    BUFSIZ as usize
+  )),
+  next: UnsafeCell::new((&raw const STDOUT).cast_mut())
+};
+
+pub static STDOUT: FileStruct = FileStruct {
+  stream: Mutex::new(Stream::new(
+    1,
+    WRITE,
+    -1,
+    (&raw mut STDOUT_BUF).cast(),
+    BUFSIZ as usize
+  )),
+  next: UnsafeCell::new((&raw const STDERR).cast_mut())
+};
+
+pub static STDERR: FileStruct = FileStruct {
+  stream: Mutex::new(Stream::new(2, WRITE, _IONBF, ptr::null_mut(), 1)),
+  next: UnsafeCell::new(ptr::null_mut())
+};
+
+static OPEN: Mutex<OpenList> =
+  Mutex::new(OpenList((&raw const STDIN).cast_mut()));
+
+fn isatty(fd: c_int) -> bool {
+  let mut termios = [0u8; 64];
+  unsafe { syscall!(Sysno::ioctl, fd, TCGETS, termios.as_mut_ptr()) }.is_ok()
+}
+
+// Reading input from the system first pushes out a line buffered stdout,
+// so prompts show up before the program waits for an answer.
+fn flush_line_buffered_stdout() {
+  let mut out = STDOUT.lock();
+  if out.mode == _IOLBF && out.state == State::Writing {
+    let _ = out.flush();
+  }
+}
+
+// Open flags for an fopen mode string.
+fn parse_mode(mode: *const c_char) -> Result<c_int, c_int> {
+  let mode = unsafe {
+    slice::from_raw_parts(mode.cast::<u8>(), string::string_length(mode))
+  };
+  let mut flags = match mode.first() {
+    | Some(b'r') => O_RDONLY,
+    | Some(b'w') => O_WRONLY | O_CREAT | O_TRUNC,
+    | Some(b'a') => O_WRONLY | O_CREAT | O_APPEND,
+    | _ => return Err(errno::EINVAL)
+  };
+  for &c in &mode[1..] {
+    match c {
+      | b'+' => flags = flags & !O_ACCMODE | O_RDWR,
+      | b'x' => flags |= O_EXCL,
+      | b'e' => flags |= O_CLOEXEC,
+      | _ => {}
+    }
+  }
+  Ok(flags)
+}
+
+fn stream_flags(flags: c_int) -> u8 {
+  let access = match flags & O_ACCMODE {
+    | O_RDONLY => READ,
+    | O_WRONLY => WRITE,
+    | _ => READ | WRITE
+  };
+  if flags & O_APPEND != 0 { access | APPEND } else { access }
+}
+
+fn link(f: *mut FileStruct) {
+  let mut open = OPEN.lock();
+  unsafe { *(*f).next.get() = open.0 };
+  open.0 = f;
+}
+
+fn unlink(f: *mut FileStruct) {
+  let mut open = OPEN.lock();
+  let mut p: *mut *mut FileStruct = &raw mut open.0;
+  unsafe {
+    while !(*p).is_null() {
+      if *p == f {
+        *p = *(*f).next.get();
+        break;
+      }
+      p = (**p).next.get();
+    }
+  }
+}
+
+fn new_file(
+  fd: c_int,
+  flags: c_int
+) -> Result<*mut FILE, c_int> {
+  let f = alloc::allocate(
+    mem::size_of::<FileStruct>(),
+    mem::align_of::<FileStruct>()
+  ) as usize as *mut FileStruct;
+  if f.is_null() {
+    return Err(errno::ENOMEM);
+  }
+  unsafe {
+    f.write(FileStruct {
+      stream: Mutex::new(Stream::new(
+        fd,
+        stream_flags(flags) | OWN_FILE,
+        -1,
+        ptr::null_mut(),
+        0
+      )),
+      next: UnsafeCell::new(ptr::null_mut())
+    });
+  }
+  link(f);
+  Ok(f)
+}
+
+fn open_path(
+  path: *const c_char,
+  flags: c_int
+) -> Result<c_int, c_int> {
+  unsafe { syscall!(Sysno::openat, AT_FDCWD, path, flags, 0o666) }
+    .map(|fd| fd as c_int)
+    .map_err(Errno::into_raw)
+}
+
+pub fn open(
+  path: *const c_char,
+  mode: *const c_char
+) -> Result<*mut FILE, c_int> {
+  let flags = parse_mode(mode)?;
+  let fd = open_path(path, flags)?;
+  new_file(fd, flags).inspect_err(|_| {
+    let _ = unsafe { syscall!(Sysno::close, fd) };
+  })
+}
+
+pub fn fdopen(
+  fd: c_int,
+  mode: *const c_char
+) -> Result<*mut FILE, c_int> {
+  let flags = parse_mode(mode)?;
+  let current = unsafe { syscall!(Sysno::fcntl, fd, F_GETFL) }
+    .map_err(Errno::into_raw)? as c_int;
+  // The descriptor has to allow what the mode asks for.
+  let want = stream_flags(flags) & (READ | WRITE);
+  if want & !stream_flags(current) & (READ | WRITE) != 0 {
+    return Err(errno::EINVAL);
+  }
+  if flags & O_APPEND != 0 && current & O_APPEND == 0 {
+    unsafe { syscall!(Sysno::fcntl, fd, F_SETFL, current | O_APPEND) }
+      .map_err(Errno::into_raw)?;
+  }
+  if flags & O_CLOEXEC != 0 {
+    unsafe { syscall!(Sysno::fcntl, fd, F_SETFD, FD_CLOEXEC) }
+      .map_err(Errno::into_raw)?;
+  }
+  new_file(fd, flags | current & O_APPEND)
+}
+
+// Reopen `f` on another file, or change the mode of its descriptor when
+// `path` is null. The stream keeps its descriptor number, so reopening
+// stdout keeps fd 1. On failure the stream is closed.
+pub fn reopen(
+  path: *const c_char,
+  mode: *const c_char,
+  f: *mut FILE
+) -> Result<*mut FILE, c_int> {
+  let res = {
+    let mut s = unsafe { (*f).lock() };
+    let _ = s.flush();
+    parse_mode(mode).and_then(|flags| {
+      if path.is_null() {
+        unsafe {
+          syscall!(Sysno::fcntl, s.fd, F_SETFL, flags & !O_CLOEXEC)
+            .map_err(Errno::into_raw)?;
+          if flags & O_CLOEXEC != 0 {
+            syscall!(Sysno::fcntl, s.fd, F_SETFD, FD_CLOEXEC)
+              .map_err(Errno::into_raw)?;
+          }
+        }
+      } else {
+        let fd = open_path(path, flags)?;
+        if fd != s.fd {
+          let dup =
+            unsafe { syscall!(Sysno::dup3, fd, s.fd, flags & O_CLOEXEC) };
+          let _ = unsafe { syscall!(Sysno::close, fd) };
+          dup.map_err(Errno::into_raw)?;
+        }
+      }
+      s.flags = s.flags & (OWN_BUF | OWN_FILE) | stream_flags(flags);
+      s.state = State::Idle;
+      s.rpos = UNGET;
+      s.rend = UNGET;
+      s.wpos = UNGET;
+      Ok(f)
+    })
+  };
+  res.inspect_err(|_| {
+    let _ = close(f);
+  })
+}
+
+pub fn close(f: *mut FILE) -> Result<(), c_int> {
+  unlink(f);
+  let (res, owned) = {
+    let mut s = unsafe { (*f).lock() };
+    (s.close(), s.flags & OWN_FILE != 0)
+  };
+  if owned {
+    unsafe { ptr::drop_in_place(f) };
+    alloc::deallocate(f.cast());
+  }
+  res
+}
+
+// Flush every open stream, as fflush(NULL) and exit do.
+pub fn flush_all() -> Result<(), c_int> {
+  let open = OPEN.lock();
+  let mut res = Ok(());
+  let mut f = open.0;
+  while !f.is_null() {
+    unsafe {
+      let mut s = (*f).lock();
+      if s.state == State::Writing {
+        res = res.and(s.flush());
+      }
+      f = *(*f).next.get();
+    }
+  }
+  res
+}
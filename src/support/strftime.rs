@@ -0,0 +1,426 @@
+use {
+  crate::{
+    c_char,
+    c_int,
+    std::{errno, time::tm},
+    support::{
+      calendar,
+      locale::time::{self, LocaleTime},
+      printf::Sink,
+      string
+    }
+  },
+  core::slice
+};
+
+// Formats nest through %c, %EY and the like; a locale whose formats refer
+// to each other stops here.
+const MAX_DEPTH: u32 = 8;
+
+// Output to a caller provided array of `max` bytes, leaving room for the
+// terminating NUL.
+pub struct ArraySink {
+  buf: *mut u8,
+  max: usize,
+  len: usize
+}
+
+impl ArraySink {
+  pub fn new(
+    buf: *mut u8,
+    max: usize
+  ) -> Self {
+    Self { buf, max, len: 0 }
+  }
+
+  // Terminate the output, giving its length.
+  pub fn terminate(&mut self) -> usize {
+    unsafe { *self.buf.add(self.len) = 0 };
+    self.len
+  }
+}
+
+impl Sink for ArraySink {
+  fn write(
+    &mut self,
+    bytes: &[u8]
+  ) -> Result<(), c_int> {
+    if self.max - self.len <= bytes.len() {
+      return Err(errno::ERANGE);
+    }
+    unsafe {
+      self
+        .buf
+        .add(self.len)
+        .copy_from_nonoverlapping(bytes.as_ptr(), bytes.len());
+    }
+    self.len += bytes.len();
+    Ok(())
+  }
+}
+
+pub fn bytes<'a>(s: *const c_char) -> &'a [u8] {
+  unsafe { slice::from_raw_parts(s.cast(), string::string_length(s)) }
+}
+
+// Whether C and POSIX allow the E or O modifier on a conversion.
+pub fn modifiable(
+  modifier: u8,
+  conv: u8
+) -> bool {
+  match modifier {
+    | b'E' => b"cCxXyY".contains(&conv),
+    | b'O' => b"bBdeHImMSuUVwWy".contains(&conv),
+    | _ => true
+  }
+}
+
+// A conversion specification: an optional '0' or '+' flag, a minimum field
+// width, an E or O modifier and the conversion character.
+#[derive(Clone, Copy, Default)]
+struct Spec {
+  flag: u8,
+  width: Option<usize>,
+  modifier: u8,
+  conv: u8
+}
+
+struct Formatter<'a, S: Sink> {
+  out: &'a mut S,
+  tm: &'a tm,
+  time: &'a LocaleTime
+}
+
+// `n` as decimal digits, most significant first.
+fn decimal(
+  n: u64,
+  buf: &mut [u8; 20]
+) -> &[u8] {
+  let mut v = n;
+  let mut start = buf.len();
+  loop {
+    start -= 1;
+    buf[start] = b'0' + (v % 10) as u8;
+    v /= 10;
+    if v == 0 {
+      break;
+    }
+  }
+  &buf[start..]
+}
+
+impl<'a, S: Sink> Formatter<'a, S> {
+  fn pad(
+    &mut self,
+    c: u8,
+    n: usize
+  ) -> Result<(), c_int> {
+    let chunk = [c; 32];
+    let mut left = n;
+    while left > 0 {
+      let len = left.min(chunk.len());
+      self.out.write(&chunk[..len])?;
+      left -= len;
+    }
+    Ok(())
+  }
+
+  // A string, right aligned in the field width.
+  fn text(
+    &mut self,
+    spec: &Spec,
+    s: &[u8]
+  ) -> Result<(), c_int> {
+    self.pad(b' ', spec.width.unwrap_or(0).saturating_sub(s.len()))?;
+    self.out.write(s)
+  }
+
+  fn name(
+    &mut self,
+    spec: &Spec,
+    names: &[*const c_char],
+    i: c_int
+  ) -> Result<(), c_int> {
+    match usize::try_from(i).ok().and_then(|i| names.get(i)) {
+      | Some(&name) => self.text(spec, bytes(name)),
+      | None => self.text(spec, b"?")
+    }
+  }
+
+  fn number(
+    &mut self,
+    spec: &Spec,
+    value: i64,
+    min: usize,
+    fill: u8
+  ) -> Result<(), c_int> {
+    self.signed(spec, value, min, min, fill)
+  }
+
+  // A number of at least `min` digits, padded with `fill` unless a flag
+  // asks for zeros. The + flag signs values of more than `digits` digits,
+  // or all of them in a field wider than that, as in glibc, so that years
+  // past 9999 stay apart from the fields after them.
+  fn signed(
+    &mut self,
+    spec: &Spec,
+    value: i64,
+    min: usize,
+    digits: usize,
+    fill: u8
+  ) -> Result<(), c_int> {
+    if spec.modifier == b'O' && value >= 0 {
+      let list = bytes(self.time.alt_digits);
+      if let Some(alt) = time::alt_digits(list, value as usize) {
+        return self.text(spec, alt);
+      }
+    }
+    let mut buf = [0; 20];
+    let body = decimal(value.unsigned_abs(), &mut buf);
+    let sign: &[u8] = if value < 0 {
+      b"-"
+    } else if spec.flag == b'+' &&
+      (body.len() > digits || spec.width.is_some_and(|w| w > digits))
+    {
+      b"+"
+    } else {
+      b""
+    };
+    let fill = if spec.flag == 0 { fill } else { b'0' };
+    let width = spec.width.unwrap_or(min);
+    let pad = width.saturating_sub(sign.len() + body.len());
+    if fill == b'0' {
+      self.out.write(sign)?;
+      self.pad(b'0', pad)?;
+    } else {
+      self.pad(fill, pad)?;
+      self.out.write(sign)?;
+    }
+    self.out.write(body)
+  }
+
+  fn year(&self) -> i64 {
+    i64::from(self.tm.tm_year) + 1900
+  }
+
+  // The era holding the date, if the locale has eras.
+  fn era(&self) -> Option<time::Era<'a>> {
+    let date = (self.year(), self.tm.tm_mon + 1, self.tm.tm_mday);
+    let time: &'a LocaleTime = self.time;
+    time::eras(bytes(time.era)).find(|era| era.contains(date))
+  }
+
+  fn iso_week(&self) -> (i64, i32) {
+    calendar::iso_week(self.year(), self.tm.tm_yday, self.tm.tm_wday)
+  }
+
+  // Seconds since the epoch of the broken-down time, which is
+  // `tm_gmtoff` ahead of UTC.
+  fn seconds(&self) -> i64 {
+    let tm = self.tm;
+    let days = calendar::days_from_civil(self.year(), tm.tm_mon + 1, 1) +
+      i64::from(tm.tm_mday) -
+      1;
+    days * calendar::SECONDS_PER_DAY +
+      i64::from(tm.tm_hour) * 3600 +
+      i64::from(tm.tm_min) * 60 +
+      i64::from(tm.tm_sec) -
+      tm.tm_gmtoff
+  }
+
+  fn nested(
+    &mut self,
+    fmt: &[u8],
+    depth: u32
+  ) -> Result<(), c_int> {
+    if depth < MAX_DEPTH { self.format(fmt, depth + 1) } else { Ok(()) }
+  }
+
+  // A format string of the locale, or the default one when it has none.
+  fn locale_format(
+    &mut self,
+    alternative: *const c_char,
+    default: *const c_char,
+    depth: u32
+  ) -> Result<(), c_int> {
+    let fmt = bytes(alternative);
+    let fmt = if fmt.is_empty() { bytes(default) } else { fmt };
+    self.nested(fmt, depth)
+  }
+
+  fn convert(
+    &mut self,
+    spec: &Spec,
+    depth: u32
+  ) -> Result<(), c_int> {
+    let tm = self.tm;
+    let time = self.time;
+    if !modifiable(spec.modifier, spec.conv) {
+      return Err(errno::EINVAL);
+    }
+    let e = spec.modifier == b'E';
+    match spec.conv {
+      | b'a' => self.name(spec, &time.abday, tm.tm_wday),
+      | b'A' => self.name(spec, &time.day, tm.tm_wday),
+      | b'b' | b'h' if spec.modifier == b'O' => {
+        self.name(spec, &time.ab_alt_mon, tm.tm_mon)
+      },
+      | b'b' | b'h' => self.name(spec, &time.abmon, tm.tm_mon),
+      | b'B' if spec.modifier == b'O' => {
+        self.name(spec, &time.alt_mon, tm.tm_mon)
+      },
+      | b'B' => self.name(spec, &time.mon, tm.tm_mon),
+      | b'c' if e => self.locale_format(time.era_d_t_fmt, time.d_t_fmt, depth),
+      | b'c' => self.nested(bytes(time.d_t_fmt), depth),
+      | b'C' if e && self.era().is_some() => {
+        let name = self.era().map_or(&b""[..], |era| era.name);
+        self.text(spec, name)
+      },
+      | b'C' => self.number(spec, self.year().div_euclid(100), 2, b'0'),
+      | b'd' => self.number(spec, i64::from(tm.tm_mday), 2, b'0'),
+      | b'D' => self.nested(b"%m/%d/%y", depth),
+      | b'e' => self.number(spec, i64::from(tm.tm_mday), 2, b' '),
+      | b'F' => {
+        // Without flag or width this is "%+4Y-%m-%d", and otherwise the year
+        // takes the flag and what the width leaves to "-mm-dd".
+        let year = Spec {
+          flag: if spec.flag == 0 && spec.width.is_none() {
+            b'+'
+          } else {
+            spec.flag
+          },
+          width: Some(spec.width.map_or(4, |w| w.saturating_sub(6))),
+          modifier: 0,
+          conv: b'Y'
+        };
+        self.signed(&year, self.year(), 4, 4, b'0')?;
+        self.nested(b"-%m-%d", depth)
+      },
+      | b'g' => self.number(spec, self.iso_week().0.rem_euclid(100), 2, b'0'),
+      | b'G' => self.signed(spec, self.iso_week().0, 1, 4, b'0'),
+      | b'H' => self.number(spec, i64::from(tm.tm_hour), 2, b'0'),
+      | b'I' => {
+        let hour = (tm.tm_hour + 11).rem_euclid(12) + 1;
+        self.number(spec, i64::from(hour), 2, b'0')
+      },
+      | b'j' => self.number(spec, i64::from(tm.tm_yday) + 1, 3, b'0'),
+      | b'm' => self.number(spec, i64::from(tm.tm_mon) + 1, 2, b'0'),
+      | b'M' => self.number(spec, i64::from(tm.tm_min), 2, b'0'),
+      | b'n' => self.out.write(b"\n"),
+      | b'p' => self.name(spec, &time.am_pm, c_int::from(tm.tm_hour >= 12)),
+      | b'r' => self.nested(bytes(time.t_fmt_ampm), depth),
+      | b'R' => self.nested(b"%H:%M", depth),
+      | b's' => self.number(spec, self.seconds(), 1, b'0'),
+      | b'S' => self.number(spec, i64::from(tm.tm_sec), 2, b'0'),
+      | b't' => self.out.write(b"\t"),
+      | b'T' => self.nested(b"%H:%M:%S", depth),
+      | b'u' => {
+        let day = (tm.tm_wday + 6).rem_euclid(7) + 1;
+        self.number(spec, i64::from(day), 1, b'0')
+      },
+      | b'U' => {
+        let week = (tm.tm_yday + 7 - tm.tm_wday) / 7;
+        self.number(spec, i64::from(week), 2, b'0')
+      },
+      | b'V' => self.number(spec, i64::from(self.iso_week().1), 2, b'0'),
+      | b'w' => self.number(spec, i64::from(tm.tm_wday), 1, b'0'),
+      | b'W' => {
+        let week = (tm.tm_yday + 7 - (tm.tm_wday + 6).rem_euclid(7)) / 7;
+        self.number(spec, i64::from(week), 2, b'0')
+      },
+      | b'x' if e => self.locale_format(time.era_d_fmt, time.d_fmt, depth),
+      | b'x' => self.nested(bytes(time.d_fmt), depth),
+      | b'X' if e => self.locale_format(time.era_t_fmt, time.t_fmt, depth),
+      | b'X' => self.nested(bytes(time.t_fmt), depth),
+      | b'y' if e && self.era().is_some() => {
+        let year = self.era().map_or(0, |era| era.year(self.year()));
+        self.number(spec, year, 1, b'0')
+      },
+      | b'y' => self.number(spec, self.year().rem_euclid(100), 2, b'0'),
+      | b'Y' if e && self.era().is_some_and(|era| !era.format.is_empty()) => {
+        let format = self.era().map_or(&b""[..], |era| era.format);
+        self.nested(format, depth)
+      },
+      | b'Y' => self.signed(spec, self.year(), 1, 4, b'0'),
+      | b'z' => {
+        if tm.tm_isdst < 0 {
+          return Ok(());
+        }
+        let minutes = tm.tm_gmtoff / 60;
+        let offset = minutes / 60 * 100 + minutes % 60;
+        self.out.write(if minutes < 0 { b"-" } else { b"+" })?;
+        let mut buf = [0; 20];
+        let body = decimal(offset.unsigned_abs(), &mut buf);
+        self.pad(b'0', 4usize.saturating_sub(body.len()))?;
+        self.out.write(body)
+      },
+      | b'Z' if tm.tm_isdst < 0 || tm.tm_zone.is_null() => Ok(()),
+      | b'Z' => self.text(spec, bytes(tm.tm_zone)),
+      | b'%' => self.out.write(b"%"),
+      | _ => Err(errno::EINVAL)
+    }
+  }
+
+  fn format(
+    &mut self,
+    fmt: &[u8],
+    depth: u32
+  ) -> Result<(), c_int> {
+    let mut i = 0;
+    while i < fmt.len() {
+      let start = i;
+      while i < fmt.len() && fmt[i] != b'%' {
+        i += 1;
+      }
+      self.out.write(&fmt[start..i])?;
+      if i == fmt.len() {
+        break;
+      }
+
+      let spec_start = i;
+      i += 1;
+      let mut spec = Spec::default();
+      if let Some(&c @ (b'0' | b'+')) = fmt.get(i) {
+        spec.flag = c;
+        i += 1;
+      }
+      let mut width = None::<usize>;
+      while let Some(&c @ b'0'..=b'9') = fmt.get(i) {
+        let w = width.unwrap_or(0);
+        width =
+          Some(w.saturating_mul(10).saturating_add(usize::from(c - b'0')));
+        i += 1;
+      }
+      spec.width = width;
+      if let Some(&c @ (b'E' | b'O')) = fmt.get(i) {
+        spec.modifier = c;
+        i += 1;
+      }
+      let Some(&conv) = fmt.get(i) else {
+        // A lone '%' at the end stays as it is.
+        self.out.write(&fmt[spec_start..])?;
+        break;
+      };
+      spec.conv = conv;
+      i += 1;
+      match self.convert(&spec, depth) {
+        // Unknown conversions are copied as they were written. Sinks fail
+        // with other errors.
+        | Err(errno::EINVAL) => self.out.write(&fmt[spec_start..i])?,
+        | result => result?
+      }
+    }
+    Ok(())
+  }
+}
+
+// Format `tm` as the C string `fmt` describes.
+pub fn format<S: Sink>(
+  out: &mut S,
+  fmt: *const u8,
+  tm: &tm,
+  time: &LocaleTime
+) -> Result<(), c_int> {
+  let fmt = bytes(fmt.cast());
+  Formatter { out, tm, time }.format(fmt, 0)
+}
@@ -53,6 +53,17 @@ impl<'a> StringStream<'a> {
     self.write(s.as_ptr().cast(), s.len());
   }
 
+  pub fn from_bytes(
+    &mut self,
+    b: &[u8]
+  ) {
+    self.write(b.as_ptr().cast(), b.len());
+  }
+
+  pub fn written(&self) -> size_t {
+    self.writeptr
+  }
+
   pub fn as_str(&mut self) -> &str {
     let s = unsafe {
       slice::from_raw_parts(self.data.as_ptr().cast::<u8>(), self.data.len())
@@ -0,0 +1,497 @@
+use crate::{
+  c_char,
+  c_int,
+  std::time::tm,
+  support::{
+    calendar,
+    locale::time::{self, LocaleTime},
+    strftime::{bytes, modifiable}
+  }
+};
+
+// Formats nest through %c, %EY and the like, as in strftime.
+const MAX_DEPTH: u32 = 8;
+
+// What the conversions found, which the fields derived from them wait for.
+#[derive(Default)]
+struct Found {
+  century: Option<i64>,
+  // %y, the year within the century.
+  short_year: Option<i64>,
+  // %I, and whether %p said afternoon.
+  hour12: Option<i32>,
+  pm: Option<bool>,
+  era: Option<usize>,
+  era_year: Option<i64>,
+  // %U or %W, with whether weeks start on Monday.
+  week: Option<(i32, bool)>,
+  // The fields set directly, as WDAY and the like.
+  fields: u8
+}
+
+const WDAY: u8 = 1;
+const YDAY: u8 = 2;
+const MON: u8 = 4;
+const MDAY: u8 = 8;
+
+impl Found {
+  fn has(
+    &self,
+    fields: u8
+  ) -> bool {
+    self.fields & fields == fields
+  }
+}
+
+struct Parser<'a> {
+  input: &'a [u8],
+  pos: usize,
+  tm: &'a mut tm,
+  time: &'a LocaleTime,
+  found: Found
+}
+
+fn is_space(c: u8) -> bool {
+  matches!(c, b' ' | b'\t' | b'\n' | b'\x0b' | b'\x0c' | b'\r')
+}
+
+impl Parser<'_> {
+  fn skip_space(&mut self) {
+    while self.pos < self.input.len() && is_space(self.input[self.pos]) {
+      self.pos += 1;
+    }
+  }
+
+  fn rest(&self) -> &[u8] {
+    &self.input[self.pos..]
+  }
+
+  // Length of `s` if the input starts with it, ignoring case.
+  fn starts_with(
+    &self,
+    s: &[u8]
+  ) -> Option<usize> {
+    let rest = self.rest();
+    (!s.is_empty() &&
+      rest.len() >= s.len() &&
+      rest[..s.len()].eq_ignore_ascii_case(s))
+    .then_some(s.len())
+  }
+
+  // Index of the longest of the names the input starts with.
+  fn name(
+    &mut self,
+    lists: &[&[*const c_char]]
+  ) -> Option<usize> {
+    let mut best: Option<(usize, usize)> = None;
+    for list in lists {
+      for (i, &name) in list.iter().enumerate() {
+        if let Some(len) = self.starts_with(bytes(name)) {
+          if best.is_none_or(|(_, l)| len > l) {
+            best = Some((i, len));
+          }
+        }
+      }
+    }
+    let (i, len) = best?;
+    self.pos += len;
+    Some(i)
+  }
+
+  // A number of at most `width` digits in [min, max], after any white
+  // space, with a sign if `signed`. The O modifier also takes the
+  // alternative digits of the locale.
+  fn number(
+    &mut self,
+    modifier: u8,
+    width: usize,
+    min: i64,
+    max: i64,
+    signed: bool
+  ) -> Option<i64> {
+    self.skip_space();
+    if modifier == b'O' {
+      let list = bytes(self.time.alt_digits);
+      let mut best: Option<(i64, usize)> = None;
+      for (n, alt) in list.split(|&c| c == b';').enumerate() {
+        if let Some(len) = self.starts_with(alt) {
+          if best.is_none_or(|(_, l)| len > l) {
+            best = Some((n as i64, len));
+          }
+        }
+      }
+      if let Some((n, len)) = best.filter(|&(n, _)| (min..=max).contains(&n)) {
+        self.pos += len;
+        return Some(n);
+      }
+    }
+    let start = self.pos;
+    let negative = match self.input.get(self.pos) {
+      | Some(b'-') if signed => true,
+      | Some(b'+') if signed => false,
+      | _ => false
+    };
+    if signed && matches!(self.input.get(self.pos), Some(b'-' | b'+')) {
+      self.pos += 1;
+    }
+    let mut n = 0i64;
+    let mut len = 0;
+    while len < width {
+      match self.input.get(self.pos) {
+        | Some(&c @ b'0'..=b'9') => {
+          let Some(next) =
+            n.checked_mul(10).and_then(|n| n.checked_add(i64::from(c - b'0')))
+          else {
+            self.pos = start;
+            return None;
+          };
+          n = next;
+          self.pos += 1;
+          len += 1;
+        },
+        | _ => break
+      }
+    }
+    let n = if negative { -n } else { n };
+    if len == 0 || !(min..=max).contains(&n) {
+      self.pos = start;
+      return None;
+    }
+    Some(n)
+  }
+
+  fn nested(
+    &mut self,
+    fmt: *const c_char,
+    depth: u32
+  ) -> Option<()> {
+    if depth >= MAX_DEPTH {
+      return None;
+    }
+    self.format(bytes(fmt), depth + 1)
+  }
+
+  fn nested_or(
+    &mut self,
+    alternative: *const c_char,
+    default: *const c_char,
+    depth: u32
+  ) -> Option<()> {
+    let fmt = if bytes(alternative).is_empty() { default } else { alternative };
+    self.nested(fmt, depth)
+  }
+
+  // Seconds since the epoch, which set every field as in UTC.
+  fn seconds(
+    &mut self,
+    seconds: i64
+  ) {
+    let days = seconds.div_euclid(calendar::SECONDS_PER_DAY);
+    let secs = seconds.rem_euclid(calendar::SECONDS_PER_DAY) as c_int;
+    let (year, mon, mday) = calendar::civil_from_days(days);
+    let tm = &mut *self.tm;
+    tm.tm_year = (year - 1900) as c_int;
+    tm.tm_mon = mon - 1;
+    tm.tm_mday = mday;
+    tm.tm_yday = calendar::yday(year, mon, mday);
+    tm.tm_wday = calendar::weekday(days);
+    tm.tm_hour = secs / 3600;
+    tm.tm_min = secs / 60 % 60;
+    tm.tm_sec = secs % 60;
+    self.found.fields |= WDAY | YDAY | MON | MDAY;
+  }
+
+  // An offset from UTC: Z, or a sign, two digits of hours and optionally
+  // two of minutes, with or without a colon.
+  fn offset(&mut self) -> Option<()> {
+    self.skip_space();
+    if self.input.get(self.pos) == Some(&b'Z') {
+      self.pos += 1;
+      self.tm.tm_gmtoff = 0;
+      return Some(());
+    }
+    let negative = match self.input.get(self.pos)? {
+      | b'+' => false,
+      | b'-' => true,
+      | _ => return None
+    };
+    self.pos += 1;
+    let digit = |p: &Self, i: usize| {
+      p.input.get(p.pos + i).filter(|c| c.is_ascii_digit()).map(|c| c - b'0')
+    };
+    let hours = i64::from(digit(self, 0)? * 10 + digit(self, 1)?);
+    self.pos += 2;
+    let colon = self.input.get(self.pos) == Some(&b':');
+    let skip = usize::from(colon);
+    let minutes = match (digit(self, skip), digit(self, skip + 1)) {
+      | (Some(a), Some(b)) => {
+        self.pos += skip + 2;
+        i64::from(a * 10 + b)
+      },
+      | _ => 0
+    };
+    if hours > 24 || minutes > 59 {
+      return None;
+    }
+    let offset = (hours * 60 + minutes) * 60;
+    self.tm.tm_gmtoff = if negative { -offset } else { offset };
+    Some(())
+  }
+
+  fn convert(
+    &mut self,
+    modifier: u8,
+    conv: u8,
+    depth: u32
+  ) -> Option<()> {
+    if !modifiable(modifier, conv) {
+      return None;
+    }
+    let time = self.time;
+    let e = modifier == b'E';
+    match conv {
+      | b'a' | b'A' => {
+        self.skip_space();
+        self.tm.tm_wday = self.name(&[&time.day, &time.abday])? as c_int;
+        self.found.fields |= WDAY;
+      },
+      | b'b' | b'B' | b'h' => {
+        self.skip_space();
+        let lists: [&[_]; 4] =
+          [&time.mon, &time.abmon, &time.alt_mon, &time.ab_alt_mon];
+        self.tm.tm_mon = self.name(&lists)? as c_int;
+        self.found.fields |= MON;
+      },
+      | b'c' if e => self.nested_or(time.era_d_t_fmt, time.d_t_fmt, depth)?,
+      | b'c' => self.nested(time.d_t_fmt, depth)?,
+      | b'C' if e && !bytes(time.era).is_empty() => {
+        self.skip_space();
+        let mut best: Option<(usize, usize)> = None;
+        for (i, era) in time::eras(bytes(time.era)).enumerate() {
+          if let Some(len) = self.starts_with(era.name) {
+            if best.is_none_or(|(_, l)| len > l) {
+              best = Some((i, len));
+            }
+          }
+        }
+        let (i, len) = best?;
+        self.pos += len;
+        self.found.era = Some(i);
+      },
+      | b'C' => {
+        self.found.century = Some(self.number(modifier, 2, 0, 99, false)?);
+      },
+      | b'd' | b'e' => {
+        self.tm.tm_mday = self.number(modifier, 2, 1, 31, false)? as c_int;
+        self.found.fields |= MDAY;
+      },
+      | b'D' => self.format(b"%m/%d/%y", depth + 1)?,
+      | b'F' => self.format(b"%Y-%m-%d", depth + 1)?,
+      // The week-based year and week only check their input, as they do
+      // not fix a date without the day of the week.
+      | b'g' => _ = self.number(modifier, 2, 0, 99, false)?,
+      | b'G' => _ = self.number(modifier, 4, -9999, 9999, true)?,
+      | b'V' => _ = self.number(modifier, 2, 1, 53, false)?,
+      | b'H' => {
+        self.tm.tm_hour = self.number(modifier, 2, 0, 23, false)? as c_int;
+        self.found.hour12 = None;
+      },
+      | b'I' => {
+        let hour = self.number(modifier, 2, 1, 12, false)?;
+        self.found.hour12 = Some(hour as i32);
+      },
+      | b'j' => {
+        self.tm.tm_yday = self.number(modifier, 3, 1, 366, false)? as c_int - 1;
+        self.found.fields |= YDAY;
+      },
+      | b'm' => {
+        self.tm.tm_mon = self.number(modifier, 2, 1, 12, false)? as c_int - 1;
+        self.found.fields |= MON;
+      },
+      | b'M' => {
+        self.tm.tm_min = self.number(modifier, 2, 0, 59, false)? as c_int;
+      },
+      | b'n' | b't' => self.skip_space(),
+      | b'p' => {
+        self.skip_space();
+        self.found.pm = Some(self.name(&[&time.am_pm])? == 1);
+      },
+      | b'r' => self.nested(time.t_fmt_ampm, depth)?,
+      | b'R' => self.format(b"%H:%M", depth + 1)?,
+      | b's' => {
+        let seconds = self.number(modifier, 19, i64::MIN, i64::MAX, true)?;
+        self.seconds(seconds);
+      },
+      | b'S' => {
+        self.tm.tm_sec = self.number(modifier, 2, 0, 60, false)? as c_int;
+      },
+      | b'T' => self.format(b"%H:%M:%S", depth + 1)?,
+      | b'u' => {
+        self.tm.tm_wday = self.number(modifier, 1, 1, 7, false)? as c_int % 7;
+        self.found.fields |= WDAY;
+      },
+      | b'U' | b'W' => {
+        let week = self.number(modifier, 2, 0, 53, false)?;
+        self.found.week = Some((week as i32, conv == b'W'));
+      },
+      | b'w' => {
+        self.tm.tm_wday = self.number(modifier, 1, 0, 6, false)? as c_int;
+        self.found.fields |= WDAY;
+      },
+      | b'x' if e => self.nested_or(time.era_d_fmt, time.d_fmt, depth)?,
+      | b'x' => self.nested(time.d_fmt, depth)?,
+      | b'X' if e => self.nested_or(time.era_t_fmt, time.t_fmt, depth)?,
+      | b'X' => self.nested(time.t_fmt, depth)?,
+      | b'y' if e && !bytes(time.era).is_empty() => {
+        let year = self.number(modifier, 9, i64::MIN, i64::MAX, true)?;
+        self.found.era_year = Some(year);
+      },
+      | b'y' => {
+        self.found.short_year = Some(self.number(modifier, 2, 0, 99, false)?);
+      },
+      | b'Y' if e && !bytes(time.era).is_empty() => {
+        // Any era whose format matches will do.
+        let start = self.pos;
+        let eras = bytes(time.era);
+        let matched = time::eras(eras).any(|era| {
+          self.pos = start;
+          !era.format.is_empty() && self.format(era.format, depth + 1).is_some()
+        });
+        if !matched {
+          self.pos = start;
+          return self.convert(0, b'Y', depth);
+        }
+      },
+      | b'Y' => {
+        let year = self.number(modifier, 4, -9999, 9999, true)?;
+        self.tm.tm_year = (year - 1900) as c_int;
+        (self.found.century, self.found.short_year) = (None, None);
+      },
+      | b'z' => self.offset()?,
+      | b'Z' => {
+        // Without a time zone database any name is taken as it is.
+        while self.pos < self.input.len() &&
+          self.input[self.pos].is_ascii_alphabetic()
+        {
+          self.pos += 1;
+        }
+      },
+      | b'%' => {
+        self.skip_space();
+        if self.input.get(self.pos) != Some(&b'%') {
+          return None;
+        }
+        self.pos += 1;
+      },
+      | _ => return None
+    }
+    Some(())
+  }
+
+  fn format(
+    &mut self,
+    fmt: &[u8],
+    depth: u32
+  ) -> Option<()> {
+    let mut i = 0;
+    while i < fmt.len() {
+      let c = fmt[i];
+      i += 1;
+      if is_space(c) {
+        self.skip_space();
+        continue;
+      }
+      if c != b'%' {
+        if self.input.get(self.pos) != Some(&c) {
+          return None;
+        }
+        self.pos += 1;
+        continue;
+      }
+      // Flags and widths as strftime writes them are taken and ignored.
+      while matches!(fmt.get(i), Some(b'0' | b'+' | b'0'..=b'9')) {
+        i += 1;
+      }
+      let modifier = match fmt.get(i) {
+        | Some(&m @ (b'E' | b'O')) => {
+          i += 1;
+          m
+        },
+        | _ => 0
+      };
+      let &conv = fmt.get(i)?;
+      i += 1;
+      self.convert(modifier, conv, depth)?;
+    }
+    Some(())
+  }
+
+  // Fill in the fields that follow from what was found.
+  fn finish(&mut self) {
+    let found = &self.found;
+    let tm = &mut *self.tm;
+
+    if let Some(hour) = found.hour12 {
+      tm.tm_hour = hour % 12 + if found.pm == Some(true) { 12 } else { 0 };
+    }
+
+    match (found.century, found.short_year) {
+      | (Some(c), y) => tm.tm_year = (c * 100 + y.unwrap_or(0) - 1900) as c_int,
+      | (None, Some(y)) => {
+        // As POSIX asks, 69 to 99 are in the 20th century and the rest in
+        // the 21st.
+        tm.tm_year = if y < 69 { y + 100 } else { y } as c_int;
+      },
+      | (None, None) => {}
+    }
+
+    if let (Some(i), Some(y)) = (found.era, found.era_year) {
+      if let Some(era) = time::eras(bytes(self.time.era)).nth(i) {
+        tm.tm_year = (era.gregorian(y) - 1900) as c_int;
+      }
+    }
+
+    let year = i64::from(tm.tm_year) + 1900;
+    let jan1 = calendar::days_from_civil(year, 1, 1);
+    let mut yday = found.has(YDAY);
+    if found.has(MON | MDAY) {
+      tm.tm_yday = calendar::yday(year, tm.tm_mon + 1, tm.tm_mday);
+      yday = true;
+    } else if let (Some((week, monday)), true, false) =
+      (found.week, found.has(WDAY), found.has(YDAY))
+    {
+      // Week 1 starts on the year's first Sunday, or Monday, and the days
+      // before it are in week 0.
+      let first = calendar::weekday(jan1);
+      let start = i32::from(monday);
+      let first_week = (7 + start - first) % 7;
+      let day_in_week = (tm.tm_wday - start + 7) % 7;
+      tm.tm_yday = first_week + (week - 1) * 7 + day_in_week;
+      yday = true;
+    }
+    if yday && !found.has(MON | MDAY) {
+      let (_, mon, mday) =
+        calendar::civil_from_days(jan1 + i64::from(tm.tm_yday));
+      tm.tm_mon = mon - 1;
+      tm.tm_mday = mday;
+    }
+    if yday && !found.has(WDAY) {
+      tm.tm_wday = calendar::weekday(jan1 + i64::from(tm.tm_yday));
+    }
+  }
+}
+
+// Parse the C string `s` as the C string `fmt` describes into `tm`, giving
+// the first byte not taken.
+pub fn parse(
+  s: *const u8,
+  fmt: *const u8,
+  tm: &mut tm,
+  time: &LocaleTime
+) -> Option<*const u8> {
+  let input = bytes(s.cast());
+  let mut parser = Parser { input, pos: 0, tm, time, found: Found::default() };
+  parser.format(bytes(fmt.cast()), 0)?;
+  parser.finish();
+  Some(unsafe { s.add(parser.pos) })
+}
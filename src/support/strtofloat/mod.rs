@@ -0,0 +1,608 @@
+mod table;
+
+use {
+  self::table::{
+    LARGEST_POWER_OF_FIVE,
+    POWER_OF_FIVE_128,
+    SMALLEST_POWER_OF_FIVE
+  },
+  crate::{
+    arch::float as fenv,
+    c_char,
+    c_int,
+    std::{ctype, errno},
+    support::{bignum::Big, string}
+  },
+  core::slice
+};
+
+// Binary interchange format of a result.
+pub struct Format {
+  // Mantissa bits, including the leading one.
+  bits: u32,
+  // Exponent bias, which is also the largest exponent.
+  bias: i32,
+  // The x87 format stores the leading one.
+  explicit: bool
+}
+
+pub const F32: Format = Format { bits: 24, bias: 127, explicit: false };
+pub const F64: Format = Format { bits: 53, bias: 1023, explicit: false };
+pub const LONG_DOUBLE: Format = if fenv::LDBL_MANT_DIG == 64 {
+  Format { bits: 64, bias: 16383, explicit: true }
+} else {
+  Format { bits: 113, bias: 16383, explicit: false }
+};
+
+// Significant digits that can decide the rounding of a result: those of the
+// exact midpoint between two subnormals. Anything past them only matters
+// for being nonzero.
+const MAX_DIGITS_DOUBLE: usize = 800;
+const MAX_DIGITS_LONG_DOUBLE: usize = 11_600;
+
+// Limbs for the scaled digits and powers of ten of each format.
+const LIMBS_DOUBLE: usize = 72;
+const LIMBS_LONG_DOUBLE: usize = 900;
+
+impl Format {
+  // Exponent of the last bit of the smallest subnormal.
+  fn min_lsb(&self) -> i32 {
+    2 - self.bias - self.bits as i32
+  }
+
+  fn max_digits(&self) -> usize {
+    if self.bits > 53 { MAX_DIGITS_LONG_DOUBLE } else { MAX_DIGITS_DOUBLE }
+  }
+
+  fn sign_bit(&self) -> u32 {
+    if self.explicit { 79 } else { self.bits - 1 + self.exponent_bits() }
+  }
+
+  fn exponent_bits(&self) -> u32 {
+    (2 * self.bias + 1).count_ones()
+  }
+
+  fn fraction_bits(&self) -> u32 {
+    if self.explicit { self.bits } else { self.bits - 1 }
+  }
+
+  fn encode(
+    &self,
+    negative: bool,
+    biased: i32,
+    fraction: u128
+  ) -> u128 {
+    u128::from(negative) << self.sign_bit() |
+      (biased as u128) << self.fraction_bits() |
+      fraction
+  }
+
+  fn infinity(
+    &self,
+    negative: bool
+  ) -> u128 {
+    let one = if self.explicit { 1 << 63 } else { 0 };
+    self.encode(negative, 2 * self.bias + 1, one)
+  }
+
+  // glibc makes quiet NaNs with only the quiet bit set.
+  fn nan(
+    &self,
+    negative: bool
+  ) -> u128 {
+    let quiet = if self.explicit { 3 << 62 } else { 1 << (self.bits - 2) };
+    self.encode(negative, 2 * self.bias + 1, quiet)
+  }
+}
+
+// An exact binary value q * 2^lsb and the bits below it: the first one and
+// whether any other is set.
+#[derive(Clone, Copy)]
+struct Rounding {
+  q: u128,
+  lsb: i32,
+  half: bool,
+  sticky: bool
+}
+
+// Round to the format in the current rounding mode and encode, returning
+// ERANGE along with results that overflow or are inexact subnormals.
+fn finish(
+  format: &Format,
+  negative: bool,
+  r: Rounding
+) -> (u128, c_int) {
+  let Rounding { mut q, mut lsb, half, sticky } = r;
+  let inexact = half || sticky;
+  let up = match fenv::get_rounding() {
+    | fenv::FE_UPWARD => inexact && !negative,
+    | fenv::FE_DOWNWARD => inexact && negative,
+    | fenv::FE_TOWARDZERO => false,
+    | _ => half && (sticky || q & 1 != 0)
+  };
+  if up {
+    q += 1;
+    if q >> format.bits != 0 {
+      q >>= 1;
+      lsb += 1;
+    }
+  }
+
+  let p = format.bits as i32;
+  if lsb + p - 1 > format.bias {
+    // Directed rounding toward zero stops at the largest finite value.
+    let max = match fenv::get_rounding() {
+      | fenv::FE_UPWARD => negative,
+      | fenv::FE_DOWNWARD => !negative,
+      | fenv::FE_TOWARDZERO => true,
+      | _ => false
+    };
+    let bits = if max {
+      let all = (1 << format.bits) - 1;
+      let fraction = if format.explicit { all } else { all >> 1 };
+      format.encode(negative, 2 * format.bias, fraction)
+    } else {
+      format.infinity(negative)
+    };
+    return (bits, errno::ERANGE);
+  }
+
+  let normal = q >> (format.bits - 1) != 0;
+  let err = if !normal && inexact { errno::ERANGE } else { 0 };
+  let bits = if normal {
+    let fraction =
+      if format.explicit { q } else { q & ((1 << (format.bits - 1)) - 1) };
+    format.encode(negative, lsb + p - 1 + format.bias, fraction)
+  } else {
+    format.encode(negative, 0, q)
+  };
+  (bits, err)
+}
+
+// Round m * 2^e, with `sticky` standing for nonzero bits below m.
+fn round_binary(
+  format: &Format,
+  m: u128,
+  e: i32,
+  sticky: bool
+) -> Rounding {
+  let top = e + 127 - m.leading_zeros() as i32;
+  let lsb = (top - format.bits as i32 + 1).max(format.min_lsb());
+  let shift = lsb - e;
+  if shift <= 0 {
+    return Rounding { q: m << -shift, lsb, half: false, sticky };
+  }
+  if shift > 128 {
+    return Rounding { q: 0, lsb, half: false, sticky: true };
+  }
+  let rest = if shift == 128 { m } else { m & ((1 << shift) - 1) };
+  let half = 1u128 << (shift - 1);
+  Rounding {
+    q: if shift == 128 { 0 } else { m >> shift },
+    lsb,
+    half: rest & half != 0,
+    sticky: sticky || rest & (half - 1) != 0
+  }
+}
+
+// Round d * 10^e exactly, by long division of big integers.
+fn round_decimal<const N: usize>(
+  format: &Format,
+  d: &Big<N>,
+  e: i32
+) -> Rounding {
+  let mut r = d.clone();
+  let mut s = Big::<N>::from_u128(1);
+  if e >= 0 {
+    r.mul_pow10(e as usize);
+  } else {
+    s.mul_pow10(e.unsigned_abs() as usize);
+  }
+
+  // Line up the leading bits so that r / s is in [1, 2) and the value is
+  // r / s * 2^top.
+  let mut top = r.bit_length() as i32 - s.bit_length() as i32;
+  if top >= 0 {
+    s.shl(top as usize);
+  } else {
+    r.shl(top.unsigned_abs() as usize);
+  }
+  if r < s {
+    r.shl(1);
+    top -= 1;
+  }
+
+  let lsb = (top - format.bits as i32 + 1).max(format.min_lsb());
+  let bits = top - lsb + 1;
+  if bits < 0 {
+    return Rounding { q: 0, lsb, half: false, sticky: true };
+  }
+  if bits == 0 {
+    // The value is in [2^(lsb - 1), 2^lsb), so at least half the last bit.
+    r.sub(&s);
+    return Rounding { q: 0, lsb, half: true, sticky: !r.is_zero() };
+  }
+
+  let mut q: u128 = 0;
+  for _ in 0..bits {
+    q <<= 1;
+    if r >= s {
+      r.sub(&s);
+      q |= 1;
+    }
+    r.shl(1);
+  }
+  let half = r >= s;
+  if half {
+    r.sub(&s);
+  }
+  Rounding { q, lsb, half, sticky: !r.is_zero() }
+}
+
+// The upper 128 bits of w * 5^q, where w has its top bit set. The low half
+// is only refined when the bits kept by `precision` could still change.
+fn product(
+  q: i32,
+  w: u64,
+  precision: u32
+) -> (u64, u64) {
+  let mask = u64::MAX >> precision;
+  let (hi5, lo5) = POWER_OF_FIVE_128[(q - SMALLEST_POWER_OF_FIVE) as usize];
+  let first = u128::from(w) * u128::from(hi5);
+  let (mut hi, mut lo) = ((first >> 64) as u64, first as u64);
+  if hi & mask == mask {
+    let second = ((u128::from(w) * u128::from(lo5)) >> 64) as u64;
+    lo = lo.wrapping_add(second);
+    if second > lo {
+      hi += 1;
+    }
+  }
+  (hi, lo)
+}
+
+// Eisel and Lemire: w * 10^q rounded to nearest as biased exponent and
+// fraction, from a 128-bit approximation of 5^q. None when the
+// approximation cannot settle the rounding or the result is not normal,
+// which the exact path then handles.
+fn lemire(
+  format: &Format,
+  q: i32,
+  w: u64
+) -> Option<(i32, u64)> {
+  if !(SMALLEST_POWER_OF_FIVE..=LARGEST_POWER_OF_FIVE).contains(&q) {
+    return None;
+  }
+  let explicit = format.bits - 1;
+  let lz = w.leading_zeros();
+  let w = w << lz;
+  let (hi, lo) = product(q, w, explicit + 3);
+  if lo == u64::MAX && !(-27..=55).contains(&q) {
+    return None;
+  }
+
+  let upper = (hi >> 63) as u32;
+  let shift = upper + 64 - explicit - 3;
+  let mut m = hi >> shift;
+  // floor(log2(10^q)) + 63, exact over the whole table.
+  let power = ((q * (152_170 + 65_536)) >> 16) + 63;
+  let mut biased = power + upper as i32 - lz as i32 + format.bias;
+  if biased <= 0 {
+    return None;
+  }
+
+  // An exact product half way between two values rounds to even. Only
+  // powers small enough for w * 5^q to fit in 128 bits can be exact.
+  let (min_even, max_even) =
+    if format.bits == 24 { (-17, 10) } else { (-4, 23) };
+  if lo <= 1 &&
+    (min_even..=max_even).contains(&q) &&
+    m & 3 == 1 &&
+    m << shift == hi
+  {
+    m &= !1;
+  }
+  m += m & 1;
+  m >>= 1;
+  if m >= 2 << explicit {
+    m = 1 << explicit;
+    biased += 1;
+  }
+  if biased > 2 * format.bias {
+    return None;
+  }
+  Some((biased, m & !(1 << explicit)))
+}
+
+// Decimal input for float and double rounded to nearest, which is almost
+// always settled by the first 19 significant digits without big integers.
+fn fast_decimal(
+  format: &Format,
+  negative: bool,
+  s: &[u8],
+  radix: &[u8]
+) -> Option<(u128, usize)> {
+  if format.bits > 53 || fenv::get_rounding() != fenv::FE_TONEAREST {
+    return None;
+  }
+  let (mut w, mut count, mut e, mut truncated) = (0u64, 0, 0i32, false);
+  let mantissa = scan_mantissa(s, 10, radix, |v, point| {
+    if count == 0 && v == 0 {
+      if point {
+        e -= 1;
+      }
+      return;
+    }
+    if count < 19 {
+      w = w * 10 + u64::from(v);
+      count += 1;
+      if point {
+        e -= 1;
+      }
+    } else {
+      truncated |= v != 0;
+      if !point {
+        e += 1;
+      }
+    }
+  });
+  if !mantissa.digits || w == 0 {
+    return None;
+  }
+  let (exp, len) = scan_exponent(&s[mantissa.end..], b'e');
+  let q = e.saturating_add(exp);
+
+  // Dropped digits put the value between w and w + 1 units, so both
+  // bounds must round alike.
+  let (biased, fraction) = lemire(format, q, w)?;
+  if truncated && lemire(format, q, w + 1)? != (biased, fraction) {
+    return None;
+  }
+  let bits = format.encode(negative, biased, u128::from(fraction));
+  Some((bits, mantissa.end + len))
+}
+
+fn digit(
+  c: u8,
+  base: u32
+) -> Option<u32> {
+  (c as char).to_digit(base)
+}
+
+// Case-insensitive match of `word` at the start of `s`.
+fn starts_with(
+  s: &[u8],
+  word: &[u8]
+) -> bool {
+  s.len() >= word.len() && s[..word.len()].eq_ignore_ascii_case(word)
+}
+
+// Digits of a mantissa and whether there were any.
+struct Mantissa {
+  end: usize,
+  digits: bool
+}
+
+fn scan_mantissa(
+  s: &[u8],
+  base: u32,
+  radix: &[u8],
+  mut each: impl FnMut(u32, bool)
+) -> Mantissa {
+  let mut i = 0;
+  let mut digits = false;
+  let mut point = false;
+  loop {
+    if let Some(d) = s.get(i).and_then(|&c| digit(c, base)) {
+      each(d, point);
+      digits = true;
+      i += 1;
+    } else if !point && !radix.is_empty() && s[i..].starts_with(radix) {
+      point = true;
+      i += radix.len();
+    } else {
+      break;
+    }
+  }
+  Mantissa { end: i, digits }
+}
+
+// Optional exponent with at least one digit after `marker` and the sign,
+// giving its value clamped far beyond any format and its length.
+fn scan_exponent(
+  s: &[u8],
+  marker: u8
+) -> (i32, usize) {
+  if s.first().map(u8::to_ascii_lowercase) != Some(marker) {
+    return (0, 0);
+  }
+  let mut i = 1;
+  let negative = s.get(i) == Some(&b'-');
+  if matches!(s.get(i), Some(b'+' | b'-')) {
+    i += 1;
+  }
+  if !s.get(i).is_some_and(u8::is_ascii_digit) {
+    return (0, 0);
+  }
+  let mut e: i32 = 0;
+  while let Some(&c) = s.get(i).filter(|c| c.is_ascii_digit()) {
+    e = (e * 10 + i32::from(c - b'0')).min(100_000_000);
+    i += 1;
+  }
+  (if negative { -e } else { e }, i)
+}
+
+fn hexadecimal(
+  format: &Format,
+  s: &[u8],
+  radix: &[u8]
+) -> Option<(Rounding, usize)> {
+  let mut m: u128 = 0;
+  let mut e: i32 = 0;
+  let mut sticky = false;
+  let mantissa = scan_mantissa(s, 16, radix, |d, point| {
+    if m >> 124 == 0 {
+      m = m << 4 | u128::from(d);
+      if point {
+        e -= 4;
+      }
+    } else {
+      sticky |= d != 0;
+      if !point {
+        e += 4;
+      }
+    }
+  });
+  if !mantissa.digits {
+    return None;
+  }
+  let (exp, len) = scan_exponent(&s[mantissa.end..], b'p');
+  let end = mantissa.end + len;
+  if m == 0 {
+    return Some((Rounding { q: 0, lsb: 0, half: false, sticky: false }, end));
+  }
+  let e = e.saturating_add(exp).clamp(-100_000, 100_000);
+  Some((round_binary(format, m, e, sticky), end))
+}
+
+fn decimal<const N: usize>(
+  format: &Format,
+  s: &[u8],
+  radix: &[u8]
+) -> Option<(Rounding, usize)> {
+  let max = format.max_digits();
+  let mut d = Big::<N>::from_u128(0);
+  let (mut chunk, mut chunk_len) = (0u64, 0);
+  let (mut count, mut e, mut sticky) = (0, 0i32, false);
+  let mantissa = scan_mantissa(s, 10, radix, |v, point| {
+    if count == 0 && v == 0 {
+      // Leading zeros only move the point.
+      if point {
+        e -= 1;
+      }
+      return;
+    }
+    if count < max {
+      chunk = chunk * 10 + u64::from(v);
+      chunk_len += 1;
+      if chunk_len == 19 {
+        d.mul_pow10(19);
+        d.add_small(chunk);
+        (chunk, chunk_len) = (0, 0);
+      }
+      count += 1;
+      if point {
+        e -= 1;
+      }
+    } else {
+      sticky |= v != 0;
+      if !point {
+        e += 1;
+      }
+    }
+  });
+  if !mantissa.digits {
+    return None;
+  }
+  d.mul_pow10(chunk_len);
+  d.add_small(chunk);
+  let (exp, len) = scan_exponent(&s[mantissa.end..], b'e');
+  let end = mantissa.end + len;
+  if d.is_zero() {
+    return Some((Rounding { q: 0, lsb: 0, half: false, sticky: false }, end));
+  }
+
+  // A nonzero digit past the last one kept stands for all dropped digits.
+  if sticky {
+    d.mul_small(10);
+    d.add_small(1);
+    e -= 1;
+  }
+  let e = e.saturating_add(exp);
+
+  // Values far outside the format need no arithmetic: the digits give the
+  // decimal exponent within one.
+  let magnitude = i64::from(e) + count as i64 + i64::from(sticky);
+  let max_exp = i64::from(format.bias) * 30_103 / 100_000 + 2;
+  let min_exp = i64::from(format.min_lsb()) * 30_103 / 100_000 - 2;
+  if magnitude > max_exp {
+    let lsb = format.bias + 1;
+    return Some((Rounding { q: 1, lsb, half: false, sticky: false }, end));
+  }
+  if magnitude < min_exp {
+    let lsb = format.min_lsb();
+    return Some((Rounding { q: 0, lsb, half: false, sticky: true }, end));
+  }
+  Some((round_decimal(format, &d, e), end))
+}
+
+// Parse a floating-point number like strtod into the raw bits of `format`,
+// with `radix` as the decimal point.
+// (result, parsed, err)
+pub fn strtofloat(
+  src: *const c_char,
+  format: &Format,
+  radix: &[u8]
+) -> (u128, isize, c_int) {
+  let mut p = src;
+  unsafe {
+    while ctype::ouma_isspace(c_int::from(*p)) != 0 {
+      p = p.add(1);
+    }
+  }
+  let s =
+    unsafe { slice::from_raw_parts(p.cast::<u8>(), string::string_length(p)) };
+  let start = unsafe { p.offset_from(src) } as usize;
+
+  let negative = s.first() == Some(&b'-');
+  let mut i = usize::from(matches!(s.first(), Some(b'+' | b'-')));
+  let rest = &s[i..];
+
+  if starts_with(rest, b"inf") {
+    i += if starts_with(rest, b"infinity") { 8 } else { 3 };
+    return (format.infinity(negative), (start + i) as isize, 0);
+  }
+  if starts_with(rest, b"nan") {
+    i += 3;
+    // An optional "(n-char-sequence)" is accepted and ignored.
+    if rest.get(3) == Some(&b'(') {
+      let chars = rest[4..]
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric() || **c == b'_')
+        .count();
+      if rest.get(4 + chars) == Some(&b')') {
+        i += chars + 2;
+      }
+    }
+    return (format.nan(negative), (start + i) as isize, 0);
+  }
+
+  let hex = starts_with(rest, b"0x");
+  if !hex {
+    if let Some((bits, end)) = fast_decimal(format, negative, rest, radix) {
+      return (bits, (start + i + end) as isize, 0);
+    }
+  }
+  let parsed = if hex {
+    hexadecimal(format, &rest[2..], radix).map(|(r, end)| (r, end + 2))
+  } else if format.bits > 53 {
+    decimal::<LIMBS_LONG_DOUBLE>(format, rest, radix)
+  } else {
+    decimal::<LIMBS_DOUBLE>(format, rest, radix)
+  };
+
+  match parsed {
+    | Some((r, end)) => {
+      let (bits, err) = if r.q == 0 && !r.half && !r.sticky {
+        (format.encode(negative, 0, 0), 0)
+      } else {
+        finish(format, negative, r)
+      };
+      (bits, (start + i + end) as isize, err)
+    },
+    // "0x" without hex digits is the number 0 followed by "x".
+    | None if hex => {
+      (format.encode(negative, 0, 0), (start + i + 1) as isize, 0)
+    },
+    | None => (0, 0, 0)
+  }
+}
@@ -2,69 +2,82 @@ use {
   crate::{
     c_char,
     c_int,
-    std::{ctype, errno}
+    std::{ctype, errno, wctype},
+    wchar_t,
+    wint_t
   },
   num_traits::{cast, identities}
 };
 
-fn b36_char_to_int(input: c_char) -> c_int {
-  if ctype::ouma_isdigit(c_int::from(input)) != 0 {
-    return c_int::from(input - b'0' as c_char);
+// A character of the string being parsed: a byte for strtol, a wide
+// character for wcstol. Only ASCII characters can be part of a number.
+pub trait Char: Copy {
+  fn is_space(self) -> bool;
+  // The character as ASCII, or 0 if it is not.
+  fn ascii(self) -> u8;
+}
+
+impl Char for c_char {
+  fn is_space(self) -> bool {
+    ctype::ouma_isspace(c_int::from(self)) != 0
   }
-  if ctype::ouma_isalpha(c_int::from(input)) != 0 {
-    return c_int::from(
-      (input | 32).wrapping_add(10).wrapping_sub(b'a' as c_char)
-    );
+
+  fn ascii(self) -> u8 {
+    if self as u8 > 0x7f { 0 } else { self as u8 }
   }
-  0
 }
 
-fn is_hex_start(src: *const c_char) -> bool {
-  unsafe {
-    if *src == b'0' as c_char &&
-      ((*(src.wrapping_add(1)) | 32) == b'x' as c_char ||
-        (*(src.wrapping_add(1)) | 32) == b'X' as c_char) &&
-      b36_char_to_int(*(src.wrapping_add(2))) < 16 &&
-      ctype::ouma_isalnum(c_int::from(*(src.wrapping_add(2)))) != 0
-    {
-      return true;
-    }
+impl Char for wchar_t {
+  fn is_space(self) -> bool {
+    wctype::ouma_iswspace(self as wint_t) != 0
   }
-  false
-}
 
-fn is_bin_start(src: *const c_char) -> bool {
-  unsafe {
-    if *src == b'0' as c_char &&
-      ((*(src.wrapping_add(1)) | 32) == b'b' as c_char ||
-        (*(src.wrapping_add(1)) | 32) == b'B' as c_char) &&
-      b36_char_to_int(*(src.wrapping_add(2))) < 16 &&
-      ctype::ouma_isalnum(c_int::from(*(src.wrapping_add(2)))) != 0
-    {
-      return true;
-    }
+  fn ascii(self) -> u8 {
+    u8::try_from(self).ok().filter(u8::is_ascii).unwrap_or(0)
   }
-  false
 }
 
-fn infer_base(src: *mut *const c_char) -> c_int {
-  unsafe {
-    if is_hex_start(*src) {
-      *src = (*src).wrapping_offset(2);
-      return 16;
-    } else if is_bin_start(*src) {
-      *src = (*src).wrapping_offset(2);
-      return 2;
-    } else if **src == b'0' as c_char {
-      return 8;
-    }
+fn b36_char_to_int(input: u8) -> u32 {
+  (input as char).to_digit(36).unwrap_or(0)
+}
+
+fn at<C: Char>(
+  src: *const C,
+  i: usize
+) -> u8 {
+  unsafe { (*src.wrapping_add(i)).ascii() }
+}
+
+fn is_hex_start<C: Char>(src: *const C) -> bool {
+  at(src, 0) == b'0' &&
+    at(src, 1) | 32 == b'x' &&
+    at(src, 2).is_ascii_alphanumeric() &&
+    b36_char_to_int(at(src, 2)) < 16
+}
+
+fn is_bin_start<C: Char>(src: *const C) -> bool {
+  at(src, 0) == b'0' &&
+    at(src, 1) | 32 == b'b' &&
+    at(src, 2).is_ascii_alphanumeric() &&
+    b36_char_to_int(at(src, 2)) < 2
+}
+
+fn infer_base<C: Char>(src: &mut *const C) -> c_int {
+  if is_hex_start(*src) {
+    *src = src.wrapping_add(2);
+    return 16;
+  } else if is_bin_start(*src) {
+    *src = src.wrapping_add(2);
+    return 2;
+  } else if at(*src, 0) == b'0' {
+    return 8;
   }
   10
 }
 
 // (result, parsed, err)
 pub fn strtointeger<T, const MIN: u64, const MAX: u64>(
-  src: *const c_char,
+  src: *const impl Char,
   base: c_int
 ) -> (T, isize, c_int)
 where
@@ -79,56 +92,52 @@ where
   }
 
   unsafe {
-    while ctype::ouma_isspace(c_int::from(*s)) != 0 {
+    while (*s).is_space() {
       s = s.wrapping_add(1);
     }
   }
 
-  let mut result_sign: c_char = b'+' as c_char;
-  unsafe {
-    if *s == b'+' as c_char || *s == b'-' as c_char {
-      result_sign = *s;
-      s = s.wrapping_add(1);
-    }
+  let mut result_sign = b'+';
+  if matches!(at(s, 0), b'+' | b'-') {
+    result_sign = at(s, 0);
+    s = s.wrapping_add(1);
   }
 
   if b == 0 {
     b = infer_base(&mut s);
-  } else if b == 16 && is_hex_start(s) {
+  } else if (b == 16 && is_hex_start(s)) || (b == 2 && is_bin_start(s)) {
     s = s.wrapping_add(2);
   }
 
   let is_unsigned = MIN == 0;
-  let is_positive = result_sign == b'+' as c_char;
+  let is_positive = result_sign == b'+';
   let negative_max = if !is_unsigned { MAX + 1 } else { MAX };
   let abs_max = if is_positive { MAX } else { negative_max };
   let abs_max_div = abs_max / b as u64;
-  unsafe {
-    while ctype::ouma_isalnum(c_int::from(*s)) != 0 {
-      let cur_digit = b36_char_to_int(*s) as u64;
-      if cur_digit >= b as u64 {
-        break;
-      }
+  while at(s, 0).is_ascii_alphanumeric() {
+    let cur_digit = u64::from(b36_char_to_int(at(s, 0)));
+    if cur_digit >= b as u64 {
+      break;
+    }
 
-      is_number = true;
-      s = s.wrapping_add(1);
+    is_number = true;
+    s = s.wrapping_add(1);
 
-      if result == abs_max {
-        errno = errno::ERANGE;
-        continue;
-      }
-      if result > abs_max_div {
-        result = abs_max;
-        errno = errno::ERANGE;
-      } else {
-        result *= b as u64;
-      }
-      if result > abs_max - cur_digit {
-        result = abs_max;
-        errno = errno::ERANGE;
-      } else {
-        result += cur_digit;
-      }
+    if result == abs_max {
+      errno = errno::ERANGE;
+      continue;
+    }
+    if result > abs_max_div {
+      result = abs_max;
+      errno = errno::ERANGE;
+    } else {
+      result *= b as u64;
+    }
+    if result > abs_max - cur_digit {
+      result = abs_max;
+      errno = errno::ERANGE;
+    } else {
+      result += cur_digit;
     }
   }
 
@@ -0,0 +1,81 @@
+use {
+  core::{
+    cell::UnsafeCell,
+    hint,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, Ordering}
+  },
+  syscalls::{Sysno, syscall}
+};
+
+const SPIN_LIMIT: usize = 100;
+
+// Simple spin lock which yields the CPU when it cannot get the lock quickly.
+// Used where the C library needs a process-wide lock before threads exist.
+pub struct Mutex<T> {
+  locked: AtomicBool,
+  data: UnsafeCell<T>
+}
+
+unsafe impl<T: Send> Sync for Mutex<T> {}
+unsafe impl<T: Send> Send for Mutex<T> {}
+
+pub struct MutexGuard<'a, T> {
+  mutex: &'a Mutex<T>
+}
+
+impl<T> Mutex<T> {
+  pub const fn new(data: T) -> Self {
+    Self { locked: AtomicBool::new(false), data: UnsafeCell::new(data) }
+  }
+
+  pub fn lock(&self) -> MutexGuard<'_, T> {
+    let mut spins = 0;
+    while self
+      .locked
+      .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+      .is_err()
+    {
+      if spins < SPIN_LIMIT {
+        hint::spin_loop();
+        spins += 1;
+      } else {
+        let _ = unsafe { syscall!(Sysno::sched_yield) };
+      }
+    }
+    MutexGuard { mutex: self }
+  }
+
+  // Address of the protected data, for identity checks without locking.
+  pub fn as_ptr(&self) -> *mut T {
+    self.data.get()
+  }
+
+  pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+    self
+      .locked
+      .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+      .ok()
+      .map(|_| MutexGuard { mutex: self })
+  }
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+  type Target = T;
+
+  fn deref(&self) -> &T {
+    unsafe { &*self.mutex.data.get() }
+  }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+  fn deref_mut(&mut self) -> &mut T {
+    unsafe { &mut *self.mutex.data.get() }
+  }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+  fn drop(&mut self) {
+    self.mutex.locked.store(false, Ordering::Release);
+  }
+}
@@ -6,7 +6,9 @@ extern "C" {
     const char *codeset;
     ssize_t (*mbtoc32)(char32_t*, const char*, size_t, mbstate_t*);
     ssize_t (*c32tomb)(char*, char32_t, mbstate_t*);
+    ssize_t (*unshift)(char*, mbstate_t*);
     int mb_cur_max;
+    bool stateful;
   };
 
   struct LocaleStruct {
@@ -121,3 +123,56 @@ TEST(ctype, ascii_to_wchar) {
     ASSERT_EQ(ch, ouma_toupper(ch));
   }
 }
+
+TEST(ctype, latin1) {
+  ouma_locale_t locale = ouma_newlocale(0x3f, "C.ISO-8859-1", nullptr);
+  ASSERT_NE(nullptr, locale);
+  EXPECT_STREQ("ISO-8859-1", locale->ctype.codeset);
+  EXPECT_EQ(1, locale->ctype.mb_cur_max);
+
+  EXPECT_NE(0, ouma_isalpha_l(0xe9, locale));
+  EXPECT_NE(0, ouma_islower_l(0xe9, locale));
+  EXPECT_NE(0, ouma_isupper_l(0xc9, locale));
+  EXPECT_NE(0, ouma_iscntrl_l(0x85, locale));
+  EXPECT_NE(0, ouma_ispunct_l(0xa7, locale));
+  EXPECT_EQ(0, ouma_isalpha_l(0xd7, locale));
+  EXPECT_EQ(0xe9, ouma_tolower_l(0xc9, locale));
+  EXPECT_EQ(0xc9, ouma_toupper_l(0xe9, locale));
+  // The uppercase of ÿ is not in Latin-1.
+  EXPECT_EQ(0xff, ouma_toupper_l(0xff, locale));
+  ouma_freelocale(locale);
+}
+
+TEST(ctype, koi8r) {
+  ouma_locale_t locale = ouma_newlocale(0x3f, "C.KOI8-R", nullptr);
+  ASSERT_NE(nullptr, locale);
+  EXPECT_STREQ("KOI8-R", locale->ctype.codeset);
+
+  // а and А.
+  EXPECT_NE(0, ouma_islower_l(0xc1, locale));
+  EXPECT_NE(0, ouma_isupper_l(0xe1, locale));
+  EXPECT_EQ(0xe1, ouma_toupper_l(0xc1, locale));
+  EXPECT_EQ(0xc1, ouma_tolower_l(0xe1, locale));
+  // A box drawing character.
+  EXPECT_EQ(0, ouma_isalpha_l(0xa0, locale));
+  EXPECT_NE(0, ouma_isgraph_l(0xa0, locale));
+  ouma_freelocale(locale);
+}
+
+TEST(ctype, cp1252) {
+  ouma_locale_t locale = ouma_newlocale(0x3f, "C.windows-1252", nullptr);
+  ASSERT_NE(nullptr, locale);
+  EXPECT_STREQ("CP1252", locale->ctype.codeset);
+
+  // €, and a byte that encodes nothing.
+  EXPECT_NE(0, ouma_isprint_l(0x80, locale));
+  EXPECT_EQ(0, ouma_isprint_l(0x81, locale));
+  EXPECT_EQ(0x81, ouma_toupper_l(0x81, locale));
+  EXPECT_EQ(0x9a, ouma_tolower_l(0x8a, locale));
+  ouma_freelocale(locale);
+}
+
+TEST(ctype, unknown_codeset) {
+  EXPECT_EQ(nullptr, ouma_newlocale(0x3f, "C.ISO-8859-12", nullptr));
+  EXPECT_EQ(nullptr, ouma_newlocale(0x3f, "C.CP1249", nullptr));
+}
@@ -0,0 +1,87 @@
+#include <gtest/gtest.h>
+
+#include <csignal>
+#include <cstdint>
+#include <cstring>
+
+#include <sys/mman.h>
+
+// Built with the hardened-malloc and malloc-guard-pages features, where every
+// check below ends the process.
+
+extern "C" {
+  void ouma_free(void *ptr);
+  void *ouma_malloc(size_t size);
+}
+
+TEST(malloc_DeathTest, double_free) {
+  void *ptr = ouma_malloc(32);
+  ASSERT_NE(nullptr, ptr);
+  ouma_free(ptr);
+  EXPECT_DEATH(ouma_free(ptr), "double free detected");
+}
+
+TEST(malloc_DeathTest, invalid_free) {
+  char *ptr = static_cast<char *>(ouma_malloc(64));
+  ASSERT_NE(nullptr, ptr);
+  EXPECT_DEATH(ouma_free(ptr + 16), "invalid pointer");
+  ouma_free(ptr);
+}
+
+TEST(malloc_DeathTest, canary_overflow) {
+  unsigned char *ptr = static_cast<unsigned char *>(ouma_malloc(20));
+  ASSERT_NE(nullptr, ptr);
+  EXPECT_DEATH(
+    {
+      ptr[20] ^= 0xff;
+      ouma_free(ptr);
+    },
+    "heap buffer overflow detected");
+  ouma_free(ptr);
+}
+
+TEST(malloc_DeathTest, large_canary_overflow) {
+  size_t size = 100000;
+  unsigned char *ptr = static_cast<unsigned char *>(ouma_malloc(size));
+  ASSERT_NE(nullptr, ptr);
+  EXPECT_DEATH(
+    {
+      ptr[size] ^= 0xff;
+      ouma_free(ptr);
+    },
+    "heap buffer overflow detected");
+  ouma_free(ptr);
+}
+
+TEST(malloc_DeathTest, use_after_free) {
+  char *ptr = static_cast<char *>(ouma_malloc(32));
+  ASSERT_NE(nullptr, ptr);
+  ouma_free(ptr);
+  // The write shows once the block drops out of the quarantine, after as
+  // many other frees as it has slots at most.
+  EXPECT_DEATH(
+    {
+      ptr[0] = 'x';
+      for (int i = 0; i < 2048; i++) {
+        ouma_free(ouma_malloc(32));
+      }
+    },
+    "use after free detected");
+}
+
+TEST(malloc_DeathTest, guard_page) {
+  size_t size = 1024 * 1024;
+  char *ptr = static_cast<char *>(ouma_malloc(size));
+  ASSERT_NE(nullptr, ptr);
+  // The first byte of the page after the block, past its canaries.
+  char *end = reinterpret_cast<char *>(
+    (reinterpret_cast<uintptr_t>(ptr + size) + 4095) & ~uintptr_t(4095));
+  memset(ptr, 'A', size);
+  // That page is mapped, so that nothing else is mapped right behind the
+  // block, but cannot be touched.
+  unsigned char resident;
+  EXPECT_EQ(0, mincore(end, 4096, &resident));
+  EXPECT_EXIT(*(volatile char *)end = 'A', testing::KilledBySignal(SIGSEGV),
+              "");
+  ouma_free(ptr);
+}
@@ -0,0 +1,350 @@
+#include <gtest/gtest.h>
+
+#include <cerrno>
+#include <cstring>
+#include <string>
+
+#include <sys/types.h>
+
+extern "C" {
+  struct IconvStruct;
+  struct LocaleStruct;
+
+  typedef struct LocaleStruct *ouma_locale_t;
+
+  typedef struct IconvStruct *ouma_iconv_t;
+
+  ouma_iconv_t ouma_iconv_open(const char *tocode, const char *fromcode);
+  size_t ouma_iconv(ouma_iconv_t cd, char **inbuf, size_t *inbytesleft,
+                    char **outbuf, size_t *outbytesleft);
+  int ouma_iconv_close(ouma_iconv_t cd);
+
+  struct ouma_encoding {
+    const char *name;
+    const char *const *aliases;
+    int mb_cur_max;
+    bool stateful;
+    ssize_t (*mbtoc32)(char32_t *pc32, const char *s, size_t n, void *ps);
+    ssize_t (*c32tomb)(char *s, char32_t c32, void *ps);
+    ssize_t (*unshift)(char *s, void *ps);
+  };
+
+  int ouma_register_encoding(const struct ouma_encoding *encoding);
+  ouma_locale_t ouma_newlocale(int category_mask, const char *locale,
+                               ouma_locale_t base);
+  void ouma_freelocale(ouma_locale_t locale);
+  char *ouma_nl_langinfo_l(int item, ouma_locale_t locale);
+
+  extern _Thread_local int __oumalibc_errno;
+}
+
+#define OUMA_ICONV_ERROR ((ouma_iconv_t)-1)
+
+#define OUMA_LC_ALL_MASK 0x3f
+#define OUMA_CODESET 14
+
+// Converts all of the input in one call, giving the output or the errno.
+static std::string Convert(const char *tocode, const char *fromcode,
+                           const std::string &input, size_t *result = nullptr) {
+  ouma_iconv_t cd = ouma_iconv_open(tocode, fromcode);
+  EXPECT_NE(OUMA_ICONV_ERROR, cd) << tocode << " " << fromcode;
+  std::string in = input;
+  char out[256];
+  char *inbuf = in.data(), *outbuf = out;
+  size_t inleft = in.size(), outleft = sizeof(out);
+  size_t r = ouma_iconv(cd, &inbuf, &inleft, &outbuf, &outleft);
+  if (result != nullptr)
+    *result = r;
+  EXPECT_EQ(0, inleft);
+  EXPECT_EQ(0, ouma_iconv(cd, nullptr, nullptr, &outbuf, &outleft));
+  EXPECT_EQ(0, ouma_iconv_close(cd));
+  return std::string(out, outbuf);
+}
+
+TEST(iconv_open, unknown) {
+  __oumalibc_errno = 0;
+  EXPECT_EQ(OUMA_ICONV_ERROR, ouma_iconv_open("UTF-9", "UTF-8"));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(OUMA_ICONV_ERROR, ouma_iconv_open("UTF-8", "UTF-9"));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+
+  ouma_iconv_t cd = ouma_iconv_open("latin1", "utf8");
+  ASSERT_NE(OUMA_ICONV_ERROR, cd);
+  EXPECT_EQ(0, ouma_iconv_close(cd));
+}
+
+TEST(iconv, convert) {
+  EXPECT_EQ("Gr\xfc\xdf" "e", Convert("ISO-8859-1", "UTF-8", "Grüße"));
+  EXPECT_EQ("Grüße", Convert("UTF-8", "ISO-8859-1", "Gr\xfc\xdf" "e"));
+  EXPECT_EQ("\xa4", Convert("ISO-8859-15", "UTF-8", "€"));
+  EXPECT_EQ("\x80", Convert("CP1252", "ISO-8859-15", "\xa4"));
+  EXPECT_EQ("\xe1\xc2\xd7\xc7", Convert("KOI8-R", "UTF-8", "Абвг"));
+  EXPECT_EQ("\xa4\xa2\xb0\xa1", Convert("EUC-JP", "Shift_JIS", "\x82\xa0\x88\x9f"));
+  EXPECT_EQ("\xa4\x40", Convert("BIG5", "UTF-8", "一"));
+  // The empty name is the codeset of the current locale.
+  EXPECT_EQ("é", Convert("", "ISO-8859-1", "\xe9"));
+}
+
+TEST(iconv, unicode) {
+  using namespace std::string_literals;
+  EXPECT_EQ("\xfe\xff\0a"s, Convert("UTF-16", "UTF-8", "a"));
+  EXPECT_EQ("\0a"s, Convert("UTF-16BE", "UTF-8", "a"));
+  EXPECT_EQ("a\0"s, Convert("UTF-16LE", "UTF-8", "a"));
+  EXPECT_EQ("\x3d\xd8\x00\xde"s, Convert("UTF-16LE", "UTF-8", "😀"));
+  EXPECT_EQ("\xd8\x3d\xde\x00"s, Convert("UTF-16BE", "UTF-8", "😀"));
+  EXPECT_EQ("\0\0\xfe\xff\0\x01\xf6\0"s, Convert("UTF-32", "UTF-8", "😀"));
+  EXPECT_EQ("\0\x01\xf6\0"s, Convert("UTF-32BE", "UTF-8", "😀"));
+  EXPECT_EQ("\0\xf6\x01\0"s, Convert("UTF-32LE", "UTF-8", "😀"));
+  EXPECT_EQ("\x30\x42"s, Convert("UCS-2", "UTF-8", "あ"));
+  EXPECT_EQ("\x42\x30"s, Convert("UCS-2LE", "UTF-8", "あ"));
+
+  // Decoding follows the byte order mark, taking big endian without one.
+  EXPECT_EQ("ab", Convert("UTF-8", "UTF-16", "\xff\xfe" "a\0b\0"s));
+  EXPECT_EQ("ab", Convert("UTF-8", "UTF-16", "\xfe\xff\0a\0b"s));
+  EXPECT_EQ("ab", Convert("UTF-8", "UTF-16", "\0a\0b"s));
+  EXPECT_EQ("😀", Convert("UTF-8", "UTF-16LE", "\x3d\xd8\x00\xde"s));
+  EXPECT_EQ("😀", Convert("UTF-8", "UTF-32", "\xff\xfe\0\0\0\xf6\x01\0"s));
+  EXPECT_EQ("😀", Convert("UTF-8", "UCS-4", "\0\x01\xf6\0"s));
+
+  // UCS-2 has no surrogates.
+  ouma_iconv_t cd = ouma_iconv_open("UCS-2", "UTF-8");
+  ASSERT_NE(OUMA_ICONV_ERROR, cd);
+  char in[] = "😀", out[4];
+  char *inbuf = in, *outbuf = out;
+  size_t inleft = 4, outleft = sizeof(out);
+  __oumalibc_errno = 0;
+  EXPECT_EQ((size_t)-1, ouma_iconv(cd, &inbuf, &inleft, &outbuf, &outleft));
+  EXPECT_EQ(EILSEQ, __oumalibc_errno);
+  EXPECT_EQ(in, inbuf);
+  EXPECT_EQ(0, ouma_iconv_close(cd));
+}
+
+TEST(iconv, e2big) {
+  ouma_iconv_t cd = ouma_iconv_open("UTF-16BE", "UTF-8");
+  ASSERT_NE(OUMA_ICONV_ERROR, cd);
+  char in[] = "äöü", out[16];
+  char *inbuf = in, *outbuf = out;
+  size_t inleft = 6, outleft = 5;
+  __oumalibc_errno = 0;
+  EXPECT_EQ((size_t)-1, ouma_iconv(cd, &inbuf, &inleft, &outbuf, &outleft));
+  EXPECT_EQ(E2BIG, __oumalibc_errno);
+  EXPECT_EQ(in + 4, inbuf);
+  EXPECT_EQ(2, inleft);
+  EXPECT_EQ(out + 4, outbuf);
+  EXPECT_EQ(1, outleft);
+
+  outleft = 2;
+  EXPECT_EQ(0, ouma_iconv(cd, &inbuf, &inleft, &outbuf, &outleft));
+  EXPECT_EQ(0, inleft);
+  EXPECT_EQ(0, outleft);
+  EXPECT_EQ(0, memcmp("\0\xe4\0\xf6\0\xfc", out, 6));
+  EXPECT_EQ(0, ouma_iconv_close(cd));
+}
+
+TEST(iconv, eilseq) {
+  ouma_iconv_t cd = ouma_iconv_open("ISO-8859-1", "UTF-8");
+  ASSERT_NE(OUMA_ICONV_ERROR, cd);
+  char in[] = "ab€c", out[16];
+  char *inbuf = in, *outbuf = out;
+  size_t inleft = strlen(in), outleft = sizeof(out);
+  __oumalibc_errno = 0;
+  EXPECT_EQ((size_t)-1, ouma_iconv(cd, &inbuf, &inleft, &outbuf, &outleft));
+  EXPECT_EQ(EILSEQ, __oumalibc_errno);
+  EXPECT_EQ(in + 2, inbuf);
+  EXPECT_EQ(4, inleft);
+  EXPECT_EQ(out + 2, outbuf);
+
+  // Invalid input stops it just the same.
+  char bad[] = "d\xff";
+  inbuf = bad;
+  inleft = 2;
+  __oumalibc_errno = 0;
+  EXPECT_EQ((size_t)-1, ouma_iconv(cd, &inbuf, &inleft, &outbuf, &outleft));
+  EXPECT_EQ(EILSEQ, __oumalibc_errno);
+  EXPECT_EQ(bad + 1, inbuf);
+  EXPECT_EQ(0, memcmp("abd", out, 3));
+  EXPECT_EQ(0, ouma_iconv_close(cd));
+}
+
+TEST(iconv, einval) {
+  ouma_iconv_t cd = ouma_iconv_open("UTF-16BE", "UTF-8");
+  ASSERT_NE(OUMA_ICONV_ERROR, cd);
+  char in[] = "a\xe2\x82\xac", out[16];
+  char *inbuf = in, *outbuf = out;
+  size_t inleft = 3, outleft = sizeof(out);
+  __oumalibc_errno = 0;
+  EXPECT_EQ((size_t)-1, ouma_iconv(cd, &inbuf, &inleft, &outbuf, &outleft));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+  EXPECT_EQ(in + 1, inbuf);
+  EXPECT_EQ(2, inleft);
+  EXPECT_EQ(out + 2, outbuf);
+
+  // The rest of the character comes with the next call.
+  inleft = 3;
+  EXPECT_EQ(0, ouma_iconv(cd, &inbuf, &inleft, &outbuf, &outleft));
+  EXPECT_EQ(0, inleft);
+  EXPECT_EQ(0, memcmp("\0a\x20\xac", out, 4));
+  EXPECT_EQ(0, ouma_iconv_close(cd));
+}
+
+TEST(iconv, pieces) {
+  using namespace std::string_literals;
+  struct {
+    const char *tocode;
+    const char *fromcode;
+    std::string input;
+    std::string output;
+  } tests[] = {
+    {"UTF-16BE", "UTF-8", "aé€😀", "\0a\0\xe9\x20\xac\xd8\x3d\xde\x00"s},
+    {"UTF-8", "UTF-16", "\xff\xfe" "a\0=\xd8\0\xde"s, "a😀"},
+    {"UTF-8", "ISO-2022-JP", "a\x1b$B$\"$$\x1b(Bb\x1b$B$&\x1b(B", "aあいbう"},
+    {"UTF-8", "UTF-7", "a+MEIwRA-b+2D3eAA-", "aあいb😀"},
+    {"UTF-8", "GB18030", "a\x81\x30\x81\x30\xb0\xa1", "a\u0080啊"},
+  };
+  for (auto &test : tests) {
+    // Feeds the input a byte more at a time, keeping what was not taken.
+    ouma_iconv_t cd = ouma_iconv_open(test.tocode, test.fromcode);
+    ASSERT_NE(OUMA_ICONV_ERROR, cd) << test.fromcode;
+    std::string in = test.input;
+    char out[64];
+    char *outbuf = out;
+    size_t outleft = sizeof(out), start = 0;
+    for (size_t end = 1; end <= in.size(); ++end) {
+      char *inbuf = in.data() + start;
+      size_t inleft = end - start;
+      __oumalibc_errno = 0;
+      size_t r = ouma_iconv(cd, &inbuf, &inleft, &outbuf, &outleft);
+      if (r == (size_t)-1)
+        EXPECT_EQ(EINVAL, __oumalibc_errno) << test.fromcode << " " << end;
+      start = inbuf - in.data();
+    }
+    EXPECT_EQ(in.size(), start) << test.fromcode;
+    EXPECT_EQ(test.output, std::string(out, outbuf)) << test.fromcode;
+    EXPECT_EQ(0, ouma_iconv_close(cd));
+  }
+}
+
+TEST(iconv, stateful) {
+  EXPECT_EQ("\x1b$B$\"\x1b(Ba", Convert("ISO-2022-JP", "UTF-8", "あa"));
+  EXPECT_EQ("a+MEI-", Convert("UTF-7", "UTF-8", "aあ"));
+
+  ouma_iconv_t cd = ouma_iconv_open("ISO-2022-JP", "UTF-8");
+  ASSERT_NE(OUMA_ICONV_ERROR, cd);
+  char in[] = "あ", out[16];
+  char *inbuf = in, *outbuf = out;
+  size_t inleft = 3, outleft = sizeof(out);
+  EXPECT_EQ(0, ouma_iconv(cd, &inbuf, &inleft, &outbuf, &outleft));
+  EXPECT_EQ(out + 5, outbuf);
+
+  // Returning to the initial state needs room for the escape sequence.
+  outleft = 2;
+  __oumalibc_errno = 0;
+  EXPECT_EQ((size_t)-1, ouma_iconv(cd, nullptr, nullptr, &outbuf, &outleft));
+  EXPECT_EQ(E2BIG, __oumalibc_errno);
+  EXPECT_EQ(out + 5, outbuf);
+  outleft = 3;
+  EXPECT_EQ(0, ouma_iconv(cd, nullptr, nullptr, &outbuf, &outleft));
+  EXPECT_EQ(0, outleft);
+  EXPECT_EQ(0, memcmp("\x1b$B$\"\x1b(B", out, 8));
+
+  // Without an output buffer the states are only reset.
+  inbuf = in;
+  inleft = 3;
+  outbuf = out;
+  outleft = sizeof(out);
+  EXPECT_EQ(0, ouma_iconv(cd, &inbuf, &inleft, &outbuf, &outleft));
+  EXPECT_EQ(0, ouma_iconv(cd, nullptr, nullptr, nullptr, nullptr));
+  inbuf = in;
+  inleft = 3;
+  EXPECT_EQ(0, ouma_iconv(cd, &inbuf, &inleft, &outbuf, &outleft));
+  EXPECT_EQ(0, memcmp("\x1b$B$\"\x1b$B$\"", out, 10));
+  EXPECT_EQ(0, ouma_iconv_close(cd));
+}
+
+TEST(iconv, translit) {
+  size_t r;
+  EXPECT_EQ("Grusse EUR", Convert("ASCII//TRANSLIT", "UTF-8", "Grüße €", &r));
+  EXPECT_EQ(3, r);
+  EXPECT_EQ("\"fi\" -- (C)  1/2",
+            Convert("ASCII//TRANSLIT", "UTF-8", "“ﬁ” –– © ½", &r));
+  EXPECT_EQ("Gr\xfc\xdf" "e EUR",
+            Convert("ISO-8859-1//TRANSLIT", "UTF-8", "Grüße €", &r));
+  EXPECT_EQ(1, r);
+  // Without a stand-in, a question mark.
+  EXPECT_EQ("a?b", Convert("ASCII//TRANSLIT", "UTF-8", "a日b", &r));
+  EXPECT_EQ(1, r);
+  EXPECT_EQ("EUR", Convert("ISO-2022-JP//TRANSLIT", "UTF-8", "€", &r));
+}
+
+TEST(iconv, ignore) {
+  size_t r;
+  EXPECT_EQ("ab", Convert("ISO-8859-1//IGNORE", "UTF-8", "a€b", &r));
+  EXPECT_EQ(1, r);
+  EXPECT_EQ("ab", Convert("ISO-8859-1//IGNORE", "UTF-8", "a\xff" "b", &r));
+  EXPECT_EQ(1, r);
+  EXPECT_EQ("a?bEUR",
+            Convert("ASCII//TRANSLIT//IGNORE", "UTF-8", "a日b€", &r));
+  EXPECT_EQ(2, r);
+  EXPECT_EQ("aEUR", Convert("ascii//translit,ignore", "UTF-8", "a€", &r));
+}
+
+// ASCII with its letters rotated by 13, as an encoding registered from C.
+static char32_t rot13(char32_t c) {
+  if ((c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z')) {
+    char32_t base = c >= 'a' ? 'a' : 'A';
+    return base + (c - base + 13) % 26;
+  }
+  return c;
+}
+
+static ssize_t rot13_mbtoc32(char32_t *pc32, const char *s, size_t n,
+                             void *) {
+  if (n < 1) {
+    return -2;
+  }
+  unsigned char b = *s;
+  if (b >= 0x80) {
+    __oumalibc_errno = EILSEQ;
+    return -1;
+  }
+  *pc32 = rot13(b);
+  return 1;
+}
+
+static ssize_t rot13_c32tomb(char *s, char32_t c32, void *) {
+  if (c32 >= 0x80) {
+    __oumalibc_errno = EILSEQ;
+    return -1;
+  }
+  *s = rot13(c32);
+  return 1;
+}
+
+static const char *const kRot13Aliases[] = {"ROT13", "rot-13", nullptr};
+
+static const ouma_encoding kRot13 = {
+  "X-ROT13", kRot13Aliases, 1, false, rot13_mbtoc32, rot13_c32tomb, nullptr
+};
+
+TEST(ouma_register_encoding, example) {
+  ouma_encoding bad = kRot13;
+  bad.c32tomb = nullptr;
+  __oumalibc_errno = 0;
+  EXPECT_EQ(-1, ouma_register_encoding(&bad));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+  EXPECT_EQ(OUMA_ICONV_ERROR, ouma_iconv_open("UTF-8", "X-ROT13"));
+
+  ASSERT_EQ(0, ouma_register_encoding(&kRot13));
+  EXPECT_EQ("Hello, World", Convert("UTF-8", "X-ROT13", "Uryyb, Jbeyq"));
+  EXPECT_EQ("Uryyb", Convert("rot13", "ISO-8859-1", "Hello"));
+  // Stand-ins are written in the encoding as well.
+  size_t result;
+  EXPECT_EQ("RHE", Convert("ROT-13//TRANSLIT", "UTF-8", "€", &result));
+  EXPECT_EQ(1, result);
+
+  ouma_locale_t locale = ouma_newlocale(OUMA_LC_ALL_MASK, "C.rot13", nullptr);
+  ASSERT_NE(nullptr, locale);
+  EXPECT_STREQ("X-ROT13", ouma_nl_langinfo_l(OUMA_CODESET, locale));
+  ouma_freelocale(locale);
+}
@@ -0,0 +1,112 @@
+#include <gtest/gtest.h>
+#include <gmock/gmock.h>
+
+#include <cstring>
+
+extern "C" {
+  struct LocaleStruct;
+
+  typedef struct LocaleStruct *ouma_locale_t;
+  typedef int ouma_nl_item;
+
+  char *ouma_nl_langinfo(ouma_nl_item item);
+  char *ouma_nl_langinfo_l(ouma_nl_item item, ouma_locale_t locale);
+  ouma_locale_t ouma_newlocale(int category_mask, const char *locale,
+                               ouma_locale_t base);
+  void ouma_freelocale(ouma_locale_t locale);
+  ouma_locale_t ouma_uselocale(ouma_locale_t locale);
+}
+
+#define OUMA_LC_ALL_MASK 0x3f
+
+#define OUMA_CODESET 14
+#define OUMA_RADIXCHAR 0x10000
+#define OUMA_THOUSEP 0x10001
+#define OUMA_CRNCYSTR 0x4000f
+#define OUMA_ABDAY_1 0x20000
+#define OUMA_DAY_7 0x2000d
+#define OUMA_ABMON_1 0x2000e
+#define OUMA_MON_12 0x20025
+#define OUMA_AM_STR 0x20026
+#define OUMA_PM_STR 0x20027
+#define OUMA_D_T_FMT 0x20028
+#define OUMA_D_FMT 0x20029
+#define OUMA_T_FMT 0x2002a
+#define OUMA_T_FMT_AMPM 0x2002b
+#define OUMA_ERA 0x2002c
+#define OUMA_ALT_DIGITS 0x2002f
+#define OUMA_YESEXPR 0x50000
+#define OUMA_NOEXPR 0x50001
+
+TEST(nl_langinfo, codeset) {
+  EXPECT_STREQ("UTF-8", ouma_nl_langinfo(OUMA_CODESET));
+
+  ouma_locale_t c = ouma_newlocale(OUMA_LC_ALL_MASK, "C", nullptr);
+  ASSERT_NE(nullptr, c);
+  EXPECT_STREQ("US-ASCII", ouma_nl_langinfo_l(OUMA_CODESET, c));
+
+  ouma_locale_t old = ouma_uselocale(c);
+  EXPECT_STREQ("US-ASCII", ouma_nl_langinfo(OUMA_CODESET));
+  ouma_uselocale(old);
+  ouma_freelocale(c);
+
+  ouma_locale_t koi8 = ouma_newlocale(OUMA_LC_ALL_MASK, "C.koi8r", nullptr);
+  ASSERT_NE(nullptr, koi8);
+  EXPECT_STREQ("KOI8-R", ouma_nl_langinfo_l(OUMA_CODESET, koi8));
+  ouma_freelocale(koi8);
+}
+
+TEST(nl_langinfo, codeset_aliases) {
+  struct {
+    const char *name;
+    const char *codeset;
+  } aliases[] = {
+    {"C.UTF8", "UTF-8"},
+    {"C.utf-8", "UTF-8"},
+    {"C.ANSI_X3.4-1968", "US-ASCII"},
+    {"C.sjis", "SHIFT_JIS"},
+    {"C.ujis", "EUC-JP"},
+    {"C.latin1", "ISO-8859-1"},
+    {"C.ISO_8859-15", "ISO-8859-15"},
+  };
+  for (auto &alias : aliases) {
+    ouma_locale_t locale = ouma_newlocale(OUMA_LC_ALL_MASK, alias.name, nullptr);
+    ASSERT_NE(nullptr, locale) << alias.name;
+    EXPECT_STREQ(alias.codeset, ouma_nl_langinfo_l(OUMA_CODESET, locale));
+    ouma_freelocale(locale);
+  }
+  EXPECT_EQ(nullptr, ouma_newlocale(OUMA_LC_ALL_MASK, "C.UTF-9", nullptr));
+  // Left to iconv, as its characters hold null bytes.
+  EXPECT_EQ(nullptr, ouma_newlocale(OUMA_LC_ALL_MASK, "C.UTF-16", nullptr));
+}
+
+TEST(nl_langinfo, numeric) {
+  EXPECT_STREQ(".", ouma_nl_langinfo(OUMA_RADIXCHAR));
+  EXPECT_STREQ("", ouma_nl_langinfo(OUMA_THOUSEP));
+  EXPECT_STREQ("-", ouma_nl_langinfo(OUMA_CRNCYSTR));
+}
+
+TEST(nl_langinfo, time) {
+  EXPECT_STREQ("Sun", ouma_nl_langinfo(OUMA_ABDAY_1));
+  EXPECT_STREQ("Saturday", ouma_nl_langinfo(OUMA_DAY_7));
+  EXPECT_STREQ("Jan", ouma_nl_langinfo(OUMA_ABMON_1));
+  EXPECT_STREQ("December", ouma_nl_langinfo(OUMA_MON_12));
+  EXPECT_STREQ("AM", ouma_nl_langinfo(OUMA_AM_STR));
+  EXPECT_STREQ("PM", ouma_nl_langinfo(OUMA_PM_STR));
+  EXPECT_STREQ("%a %b %e %H:%M:%S %Y", ouma_nl_langinfo(OUMA_D_T_FMT));
+  EXPECT_STREQ("%m/%d/%y", ouma_nl_langinfo(OUMA_D_FMT));
+  EXPECT_STREQ("%H:%M:%S", ouma_nl_langinfo(OUMA_T_FMT));
+  EXPECT_STREQ("%I:%M:%S %p", ouma_nl_langinfo(OUMA_T_FMT_AMPM));
+  EXPECT_STREQ("", ouma_nl_langinfo(OUMA_ERA));
+  EXPECT_STREQ("", ouma_nl_langinfo(OUMA_ALT_DIGITS));
+}
+
+TEST(nl_langinfo, messages) {
+  EXPECT_STREQ("^[yY]", ouma_nl_langinfo(OUMA_YESEXPR));
+  EXPECT_STREQ("^[nN]", ouma_nl_langinfo(OUMA_NOEXPR));
+}
+
+TEST(nl_langinfo, unknown) {
+  EXPECT_STREQ("", ouma_nl_langinfo(-1));
+  EXPECT_STREQ("", ouma_nl_langinfo(0x7fff0000));
+}
@@ -0,0 +1,535 @@
+#include <gtest/gtest.h>
+#include <gmock/gmock.h>
+
+#include <cerrno>
+#include <climits>
+#include <cstring>
+#include <cwchar>
+#include <fstream>
+#include <string>
+#include <vector>
+
+#include <stdlib.h>
+
+extern "C" {
+  struct LocaleStruct;
+
+  typedef struct LocaleStruct *ouma_locale_t;
+
+  struct ouma_lconv {
+    char *decimal_point;
+    char *thousands_sep;
+    char *grouping;
+    char *int_curr_symbol;
+    char *currency_symbol;
+    char *mon_decimal_point;
+    char *mon_thousands_sep;
+    char *mon_grouping;
+    char *positive_sign;
+    char *negative_sign;
+    char int_frac_digits;
+    char frac_digits;
+    char p_cs_precedes;
+    char p_sep_by_space;
+    char n_cs_precedes;
+    char n_sep_by_space;
+    char p_sign_posn;
+    char n_sign_posn;
+    char int_p_cs_precedes;
+    char int_p_sep_by_space;
+    char int_n_cs_precedes;
+    char int_n_sep_by_space;
+    char int_p_sign_posn;
+    char int_n_sign_posn;
+  };
+
+  char *ouma_setlocale(int category, const char *locale);
+  ouma_locale_t ouma_newlocale(int category_mask, const char *locale,
+                               ouma_locale_t base);
+  ouma_locale_t ouma_duplocale(ouma_locale_t locale);
+  void ouma_freelocale(ouma_locale_t locale);
+  ouma_locale_t ouma_uselocale(ouma_locale_t locale);
+  struct ouma_lconv *ouma_localeconv(void);
+
+  extern _Thread_local int __oumalibc_errno;
+
+  int ouma_setenv(const char *name, const char *value, int overwrite);
+  int ouma_unsetenv(const char *name);
+  size_t __oumalibc_get_mb_cur_max(void);
+  int ouma_iswalpha_l(wint_t wc, ouma_locale_t locale);
+  int ouma_iswdigit_l(wint_t wc, ouma_locale_t locale);
+  int ouma_iswlower_l(wint_t wc, ouma_locale_t locale);
+  int ouma_iswupper_l(wint_t wc, ouma_locale_t locale);
+  wint_t ouma_towlower(wint_t wc);
+  wint_t ouma_towupper(wint_t wc);
+  wint_t ouma_towlower_l(wint_t wc, ouma_locale_t locale);
+  wint_t ouma_towupper_l(wint_t wc, ouma_locale_t locale);
+  char *ouma_nl_langinfo_l(int item, ouma_locale_t locale);
+  int ouma_strcoll_l(const char *s1, const char *s2, ouma_locale_t locale);
+  size_t ouma_strxfrm_l(char *s1, const char *s2, size_t n,
+                        ouma_locale_t locale);
+  int ouma_wcscoll_l(const wchar_t *s1, const wchar_t *s2,
+                     ouma_locale_t locale);
+}
+
+#define OUMA_LC_CTYPE 0
+#define OUMA_LC_NUMERIC 1
+#define OUMA_LC_TIME 2
+#define OUMA_LC_ALL 6
+#define OUMA_LC_CTYPE_MASK (1 << OUMA_LC_CTYPE)
+#define OUMA_LC_NUMERIC_MASK (1 << OUMA_LC_NUMERIC)
+#define OUMA_LC_COLLATE 3
+#define OUMA_LC_MONETARY 4
+#define OUMA_LC_MESSAGES 5
+#define OUMA_LC_ALL_MASK 0x3f
+#define OUMA_LC_MONETARY_MASK (1 << 4)
+#define OUMA_LC_GLOBAL_LOCALE ((ouma_locale_t)-1)
+
+#define OUMA_CODESET 14
+#define OUMA_RADIXCHAR 0x10000
+#define OUMA_DAY_2 0x20008
+#define OUMA_MON_1 0x2001a
+#define OUMA_D_FMT 0x20029
+#define OUMA_CRNCYSTR 0x4000f
+#define OUMA_YESEXPR 0x50000
+#define OUMA_NOSTR 0x50003
+
+// Writes a locale file in the format newlocale maps from LOCPATH. Section
+// words with the high bit set are offsets into the data that follows the
+// sections, until write() knows where it starts.
+class LocaleFile {
+ public:
+  explicit LocaleFile(const char *codeset) : codeset_(string(codeset)) {}
+
+  uint32_t string(const char *s) {
+    uint32_t at = data_.size();
+    data_.append(s, strlen(s) + 1);
+    return kData | at;
+  }
+
+  // Append a table of records of `words` words each to `section`.
+  void table(std::vector<uint32_t> &section,
+             const std::vector<uint32_t> &records, size_t words) {
+    data_.resize((data_.size() + 3) & ~3);
+    section.push_back(kData | data_.size());
+    section.push_back(records.size() / words);
+    for (uint32_t word : records) {
+      put(data_, word);
+    }
+  }
+
+  void section(uint32_t category, std::vector<uint32_t> words) {
+    sections_.push_back({category, words});
+  }
+
+  void write(const std::string &path) const {
+    std::string out = "ouma-loc";
+    size_t start = 20 + 12 * sections_.size();
+    size_t base = start;
+    for (auto &section : sections_) {
+      base += 4 * section.second.size();
+    }
+    put(out, 1);
+    put(out, base + (codeset_ & ~kData));
+    put(out, sections_.size());
+    size_t offset = start;
+    for (auto &section : sections_) {
+      put(out, section.first);
+      put(out, offset);
+      put(out, section.second.size());
+      offset += 4 * section.second.size();
+    }
+    for (auto &section : sections_) {
+      for (uint32_t word : section.second) {
+        put(out, word & kData ? base + (word & ~kData) : word);
+      }
+    }
+    std::ofstream(path, std::ios::binary) << out << data_;
+  }
+
+ private:
+  static constexpr uint32_t kData = 0x80000000;
+
+  static void put(std::string &out, uint32_t word) {
+    for (int i = 0; i < 4; i++) {
+      out.push_back(word >> (8 * i));
+    }
+  }
+
+  std::string data_;
+  uint32_t codeset_;
+  std::vector<std::pair<uint32_t, std::vector<uint32_t>>> sections_;
+};
+
+// A directory of locales for LOCPATH: tr_TR with Turkish case mapping, de_DE
+// with German conventions and collation, and a corrupt xx_XX.
+static std::string locale_dir() {
+  static std::string dir;
+  if (!dir.empty()) {
+    return dir;
+  }
+  char name[] = "/tmp/ouma-locale-XXXXXX";
+  dir = mkdtemp(name);
+
+  LocaleFile tr("UTF-8");
+  std::vector<std::vector<uint32_t>> classes = {
+    {0x30, 0x39, 0x41, 0x5a, 0x61, 0x7a, 0xc0, 0x24f},  // alnum
+    {0x41, 0x5a, 0x61, 0x7a, 0xc0, 0x24f},              // alpha
+    {0x9, 0x9, 0x20, 0x20},                             // blank
+    {0x0, 0x1f, 0x7f, 0x7f},                            // cntrl
+    {0x30, 0x39},                                       // digit
+    {0x21, 0x7e, 0xa1, 0x24f},                          // graph
+    {0x61, 0x7a, 0x131, 0x131},                         // lower
+    {0x20, 0x7e, 0xa0, 0x24f},                          // print
+    {0x21, 0x2f, 0x3a, 0x40, 0x5b, 0x60, 0x7b, 0x7e},   // punct
+    {0x9, 0xd, 0x20, 0x20},                             // space
+    {0x41, 0x5a, 0x130, 0x130},                         // upper
+    {0x30, 0x39, 0x41, 0x46, 0x61, 0x66}                // xdigit
+  };
+  std::vector<uint32_t> ctype, toupper, tolower;
+  for (auto &ranges : classes) {
+    tr.table(ctype, ranges, 2);
+  }
+  for (uint32_t c = 'a'; c <= 'z'; c++) {
+    toupper.insert(toupper.end(), {c, c == 'i' ? 0x130 : c - 0x20});
+  }
+  toupper.insert(toupper.end(), {0x131, 'I'});
+  for (uint32_t c = 'A'; c <= 'Z'; c++) {
+    tolower.insert(tolower.end(), {c, c == 'I' ? 0x131 : c + 0x20});
+  }
+  tolower.insert(tolower.end(), {0x130, 'i'});
+  tr.table(ctype, toupper, 2);
+  tr.table(ctype, tolower, 2);
+  tr.section(OUMA_LC_CTYPE, ctype);
+  tr.write(dir + "/tr_TR.utf8");
+
+  LocaleFile de("UTF-8");
+  de.section(OUMA_LC_NUMERIC,
+             {de.string(","), de.string("."), de.string("\3\3")});
+  std::vector<uint32_t> monetary = {
+    de.string("EUR "), de.string("\u20ac"), de.string(","), de.string("."),
+    de.string("\3\3"), de.string(""), de.string("-"), de.string("+\u20ac"),
+    2, 2, 0, 1, 0, 1, 1, 1, 0, 1, 0, 1, 1, 1
+  };
+  de.section(OUMA_LC_MONETARY, monetary);
+  const char *days[] = {"Sonntag", "Montag", "Dienstag", "Mittwoch",
+                        "Donnerstag", "Freitag", "Samstag"};
+  const char *months[] = {"Januar", "Februar", "M\u00e4rz", "April", "Mai",
+                          "Juni", "Juli", "August", "September", "Oktober",
+                          "November", "Dezember"};
+  std::vector<uint32_t> time;
+  for (const char *day : days) {
+    time.push_back(de.string(std::string(day, 2).c_str()));
+  }
+  for (const char *day : days) {
+    time.push_back(de.string(day));
+  }
+  for (int i = 0; i < 3; i++) {
+    for (const char *month : months) {
+      time.push_back(de.string(i == 0 ? std::string(month, 3).c_str() : month));
+    }
+    if (i == 1) {
+      for (const char *month : months) {
+        time.push_back(de.string(std::string(month, 3).c_str()));
+      }
+    }
+  }
+  for (const char *s : {"", "", "%a %d %b %Y %T", "%d.%m.%Y", "%T", "", "",
+                        "", "", "", ""}) {
+    time.push_back(de.string(s));
+  }
+  de.section(OUMA_LC_TIME, time);
+  de.section(OUMA_LC_MESSAGES, {de.string("^[jJyY]"), de.string("^[nN]"),
+                                de.string("ja"), de.string("nein")});
+  // Letters first by base letter, then by accent, then by case; "ch" sorts
+  // as a letter between c and d, and the rest follow in code point order.
+  std::vector<uint32_t> collate = {3, 200};
+  de.table(collate, {
+    'A', 10, 1, 2, 'B', 20, 1, 2, 'C', 30, 1, 2, 'a', 10, 1, 1,
+    'b', 20, 1, 1, 'c', 30, 1, 1, 0xe4, 10, 2, 1
+  }, 4);
+  de.table(collate, {'c', 'h', 0, 0, 35, 1, 1}, 7);
+  de.section(OUMA_LC_COLLATE, collate);
+  de.write(dir + "/de_DE");
+
+  std::ofstream(dir + "/xx_XX") << "not a locale";
+  return dir;
+}
+
+static void set_locpath() {
+  ASSERT_EQ(0, ouma_setenv("LOCPATH", ("/nonexistent::" + locale_dir()).c_str(),
+                           1));
+}
+
+TEST(setlocale, query) {
+  const char *name = ouma_setlocale(OUMA_LC_ALL, nullptr);
+  ASSERT_NE(nullptr, name);
+  EXPECT_STREQ("C.UTF-8", name);
+  EXPECT_STREQ("C.UTF-8", ouma_setlocale(OUMA_LC_CTYPE, nullptr));
+}
+
+TEST(setlocale, categories) {
+  ASSERT_NE(nullptr, ouma_setlocale(OUMA_LC_ALL, "C"));
+  EXPECT_STREQ("C", ouma_setlocale(OUMA_LC_ALL, nullptr));
+  EXPECT_EQ(1, __oumalibc_get_mb_cur_max());
+
+  ASSERT_NE(nullptr, ouma_setlocale(OUMA_LC_CTYPE, "C.UTF-8"));
+  const char *composite = "LC_CTYPE=C.UTF-8;LC_NUMERIC=C;LC_TIME=C;"
+                          "LC_COLLATE=C;LC_MONETARY=C;LC_MESSAGES=C";
+  EXPECT_STREQ(composite, ouma_setlocale(OUMA_LC_ALL, nullptr));
+
+  char saved[512];
+  strcpy(saved, ouma_setlocale(OUMA_LC_ALL, nullptr));
+  ASSERT_NE(nullptr, ouma_setlocale(OUMA_LC_ALL, "POSIX"));
+  EXPECT_STREQ("POSIX", ouma_setlocale(OUMA_LC_ALL, nullptr));
+  ASSERT_NE(nullptr, ouma_setlocale(OUMA_LC_ALL, saved));
+  EXPECT_STREQ(composite, ouma_setlocale(OUMA_LC_ALL, nullptr));
+  EXPECT_STREQ("C.UTF-8", ouma_setlocale(OUMA_LC_CTYPE, nullptr));
+
+  ASSERT_NE(nullptr, ouma_setlocale(OUMA_LC_ALL, "C.UTF-8"));
+}
+
+TEST(setlocale, invalid) {
+  __oumalibc_errno = 0;
+  EXPECT_EQ(nullptr, ouma_setlocale(OUMA_LC_ALL, "en_US.NOPE"));
+  EXPECT_EQ(ENOENT, __oumalibc_errno);
+  EXPECT_EQ(nullptr, ouma_setlocale(OUMA_LC_ALL, "../../etc"));
+  EXPECT_EQ(nullptr, ouma_setlocale(OUMA_LC_ALL, "LC_CTYPE=C"));
+  EXPECT_EQ(nullptr, ouma_setlocale(42, "C"));
+  EXPECT_STREQ("C.UTF-8", ouma_setlocale(OUMA_LC_ALL, nullptr));
+}
+
+TEST(setlocale, environment) {
+  set_locpath();
+  ouma_setenv("LANG", "de_DE.utf8", 1);
+  ouma_setenv("LC_NUMERIC", "C", 1);
+  ouma_unsetenv("LC_ALL");
+  ASSERT_NE(nullptr, ouma_setlocale(OUMA_LC_ALL, ""));
+  EXPECT_STREQ("de_DE.utf8", ouma_setlocale(OUMA_LC_CTYPE, nullptr));
+  EXPECT_STREQ("C", ouma_setlocale(OUMA_LC_NUMERIC, nullptr));
+  EXPECT_STREQ("de_DE.utf8", ouma_setlocale(OUMA_LC_TIME, nullptr));
+
+  ouma_setenv("LC_ALL", "POSIX", 1);
+  ASSERT_NE(nullptr, ouma_setlocale(OUMA_LC_ALL, ""));
+  EXPECT_STREQ("POSIX", ouma_setlocale(OUMA_LC_ALL, nullptr));
+
+  ouma_unsetenv("LC_ALL");
+  ouma_unsetenv("LC_NUMERIC");
+  ouma_unsetenv("LANG");
+  ASSERT_NE(nullptr, ouma_setlocale(OUMA_LC_ALL, ""));
+  EXPECT_STREQ("C", ouma_setlocale(OUMA_LC_ALL, nullptr));
+  ASSERT_NE(nullptr, ouma_setlocale(OUMA_LC_ALL, "C.UTF-8"));
+}
+
+TEST(newlocale, example) {
+  ouma_locale_t c = ouma_newlocale(OUMA_LC_ALL_MASK, "C", nullptr);
+  ASSERT_NE(nullptr, c);
+  EXPECT_FALSE(ouma_iswalpha_l(L'é', c));
+  EXPECT_TRUE(ouma_iswalpha_l(L'a', c));
+
+  ouma_locale_t utf8 = ouma_newlocale(OUMA_LC_CTYPE_MASK, "C.UTF-8", c);
+  EXPECT_EQ(c, utf8);
+  EXPECT_TRUE(ouma_iswalpha_l(L'é', utf8));
+  ouma_freelocale(utf8);
+}
+
+TEST(newlocale, invalid) {
+  __oumalibc_errno = 0;
+  EXPECT_EQ(nullptr, ouma_newlocale(OUMA_LC_ALL_MASK, "xx_XX.BOGUS", nullptr));
+  EXPECT_EQ(ENOENT, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(nullptr, ouma_newlocale(1 << 20, "C", nullptr));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(nullptr, ouma_newlocale(OUMA_LC_ALL_MASK, nullptr, nullptr));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+
+  // A failed change leaves the base as it was.
+  ouma_locale_t base = ouma_newlocale(OUMA_LC_ALL_MASK, "C", nullptr);
+  ASSERT_NE(nullptr, base);
+  EXPECT_EQ(nullptr, ouma_newlocale(OUMA_LC_ALL_MASK, "@@", base));
+  EXPECT_FALSE(ouma_iswalpha_l(L'é', base));
+  ouma_freelocale(base);
+}
+
+TEST(duplocale, global) {
+  ouma_locale_t copy = ouma_duplocale(OUMA_LC_GLOBAL_LOCALE);
+  ASSERT_NE(nullptr, copy);
+  ASSERT_NE(nullptr, ouma_setlocale(OUMA_LC_ALL, "C"));
+  EXPECT_EQ(1, __oumalibc_get_mb_cur_max());
+  EXPECT_TRUE(ouma_iswalpha_l(L'é', copy));
+  ASSERT_NE(nullptr, ouma_setlocale(OUMA_LC_ALL, "C.UTF-8"));
+  ouma_freelocale(copy);
+}
+
+TEST(uselocale, example) {
+  EXPECT_EQ(OUMA_LC_GLOBAL_LOCALE, ouma_uselocale(nullptr));
+
+  ouma_locale_t c = ouma_newlocale(OUMA_LC_ALL_MASK, "C", nullptr);
+  ASSERT_NE(nullptr, c);
+  EXPECT_EQ(OUMA_LC_GLOBAL_LOCALE, ouma_uselocale(c));
+  EXPECT_EQ(c, ouma_uselocale(nullptr));
+  EXPECT_EQ(1, __oumalibc_get_mb_cur_max());
+
+  EXPECT_EQ(c, ouma_uselocale(OUMA_LC_GLOBAL_LOCALE));
+  EXPECT_EQ(4, __oumalibc_get_mb_cur_max());
+  EXPECT_EQ(OUMA_LC_GLOBAL_LOCALE, ouma_uselocale(nullptr));
+  ouma_freelocale(c);
+}
+
+TEST(localeconv, c) {
+  struct ouma_lconv *lc = ouma_localeconv();
+  ASSERT_NE(nullptr, lc);
+  EXPECT_STREQ(".", lc->decimal_point);
+  EXPECT_STREQ("", lc->thousands_sep);
+  EXPECT_STREQ("", lc->grouping);
+  EXPECT_STREQ("", lc->int_curr_symbol);
+  EXPECT_STREQ("", lc->currency_symbol);
+  EXPECT_STREQ("", lc->mon_decimal_point);
+  EXPECT_STREQ("", lc->mon_thousands_sep);
+  EXPECT_STREQ("", lc->mon_grouping);
+  EXPECT_STREQ("", lc->positive_sign);
+  EXPECT_STREQ("", lc->negative_sign);
+  EXPECT_EQ(CHAR_MAX, lc->int_frac_digits);
+  EXPECT_EQ(CHAR_MAX, lc->frac_digits);
+  EXPECT_EQ(CHAR_MAX, lc->p_cs_precedes);
+  EXPECT_EQ(CHAR_MAX, lc->n_sign_posn);
+  EXPECT_EQ(CHAR_MAX, lc->int_p_cs_precedes);
+  EXPECT_EQ(CHAR_MAX, lc->int_n_sign_posn);
+}
+
+TEST(localeconv, uselocale) {
+  ouma_locale_t c = ouma_newlocale(OUMA_LC_MONETARY_MASK, "POSIX", nullptr);
+  ASSERT_NE(nullptr, c);
+  ouma_locale_t old = ouma_uselocale(c);
+  EXPECT_STREQ(".", ouma_localeconv()->decimal_point);
+  EXPECT_EQ(CHAR_MAX, ouma_localeconv()->frac_digits);
+  ouma_uselocale(old);
+  ouma_freelocale(c);
+}
+
+TEST(newlocale, locpath_ctype) {
+  set_locpath();
+  ouma_locale_t tr = ouma_newlocale(OUMA_LC_ALL_MASK, "tr_TR.UTF-8", nullptr);
+  ASSERT_NE(nullptr, tr);
+  EXPECT_EQ(0x130, ouma_towupper_l(L'i', tr));
+  EXPECT_EQ(L'I', ouma_towupper_l(0x131, tr));
+  EXPECT_EQ(0x131, ouma_towlower_l(L'I', tr));
+  EXPECT_EQ(L'Q', ouma_towupper_l(L'q', tr));
+  // The file decides, even where Unicode says otherwise.
+  EXPECT_EQ(L'é', ouma_towupper_l(L'é', tr));
+  EXPECT_FALSE(ouma_iswlower_l(L'é', tr));
+  EXPECT_TRUE(ouma_iswupper_l(0x130, tr));
+  EXPECT_TRUE(ouma_iswdigit_l(L'5', tr));
+  EXPECT_FALSE(ouma_iswalpha_l(L'5', tr));
+
+  ouma_locale_t old = ouma_uselocale(tr);
+  EXPECT_EQ(0x130, ouma_towupper(L'i'));
+  EXPECT_EQ(0x131, ouma_towlower(L'I'));
+  ouma_uselocale(old);
+  EXPECT_EQ(L'I', ouma_towupper(L'i'));
+  ouma_freelocale(tr);
+
+  // Only LC_CTYPE is in the file; the rest keep the conventions of C.
+  tr = ouma_newlocale(OUMA_LC_ALL_MASK, "tr_TR.utf8", nullptr);
+  ASSERT_NE(nullptr, tr);
+  EXPECT_STREQ(".", ouma_nl_langinfo_l(OUMA_RADIXCHAR, tr));
+  EXPECT_STREQ("^[yY]", ouma_nl_langinfo_l(OUMA_YESEXPR, tr));
+  ouma_freelocale(tr);
+}
+
+TEST(newlocale, locpath_conventions) {
+  set_locpath();
+  ouma_locale_t de = ouma_newlocale(OUMA_LC_ALL_MASK, "de_DE.UTF-8", nullptr);
+  ASSERT_NE(nullptr, de);
+  EXPECT_STREQ("UTF-8", ouma_nl_langinfo_l(OUMA_CODESET, de));
+  EXPECT_STREQ(",", ouma_nl_langinfo_l(OUMA_RADIXCHAR, de));
+  EXPECT_STREQ("+\u20ac", ouma_nl_langinfo_l(OUMA_CRNCYSTR, de));
+  EXPECT_STREQ("Montag", ouma_nl_langinfo_l(OUMA_DAY_2, de));
+  EXPECT_STREQ("Januar", ouma_nl_langinfo_l(OUMA_MON_1, de));
+  EXPECT_STREQ("%d.%m.%Y", ouma_nl_langinfo_l(OUMA_D_FMT, de));
+  EXPECT_STREQ("^[jJyY]", ouma_nl_langinfo_l(OUMA_YESEXPR, de));
+  EXPECT_STREQ("nein", ouma_nl_langinfo_l(OUMA_NOSTR, de));
+
+  ouma_locale_t old = ouma_uselocale(de);
+  struct ouma_lconv *lc = ouma_localeconv();
+  EXPECT_STREQ(".", lc->thousands_sep);
+  EXPECT_STREQ("\u20ac", lc->currency_symbol);
+  EXPECT_EQ(2, lc->frac_digits);
+  EXPECT_EQ(0, lc->p_cs_precedes);
+  EXPECT_EQ(1, lc->p_sep_by_space);
+  ouma_uselocale(old);
+
+  // Locales loaded from one file share its mapping.
+  ouma_locale_t again = ouma_newlocale(OUMA_LC_NUMERIC_MASK, "de_DE", nullptr);
+  ASSERT_NE(nullptr, again);
+  EXPECT_EQ(ouma_nl_langinfo_l(OUMA_RADIXCHAR, de),
+            ouma_nl_langinfo_l(OUMA_RADIXCHAR, again));
+  ouma_freelocale(again);
+  ouma_freelocale(de);
+
+  // A file in another codeset than the one asked for does not serve.
+  __oumalibc_errno = 0;
+  EXPECT_EQ(nullptr,
+            ouma_newlocale(OUMA_LC_ALL_MASK, "de_DE.ISO-8859-1", nullptr));
+  EXPECT_EQ(ENOENT, __oumalibc_errno);
+}
+
+TEST(newlocale, locpath_collate) {
+  set_locpath();
+  ouma_locale_t de = ouma_newlocale(OUMA_LC_ALL_MASK, "de_DE.UTF-8", nullptr);
+  ASSERT_NE(nullptr, de);
+  EXPECT_LT(ouma_strcoll_l("a", "B", de), 0);
+  EXPECT_LT(ouma_strcoll_l("b", "B", de), 0);
+  EXPECT_LT(ouma_strcoll_l("ab", "Ab", de), 0);
+  EXPECT_LT(ouma_strcoll_l("Ab", "ac", de), 0);
+  EXPECT_LT(ouma_strcoll_l("a", "\u00e4", de), 0);
+  EXPECT_LT(ouma_strcoll_l("\u00e4", "b", de), 0);
+  EXPECT_LT(ouma_strcoll_l("cz", "ch", de), 0);
+  EXPECT_LT(ouma_strcoll_l("ch", "d", de), 0);
+  EXPECT_LT(ouma_strcoll_l("d", "e", de), 0);
+  EXPECT_EQ(0, ouma_strcoll_l("ch", "ch", de));
+  EXPECT_LT(ouma_wcscoll_l(L"a", L"B", de), 0);
+  EXPECT_LT(ouma_wcscoll_l(L"cz", L"ch", de), 0);
+
+  const char *words[] = {"a", "A", "\u00e4", "b", "B", "c", "cz", "ch", "d"};
+  for (const char *s1 : words) {
+    for (const char *s2 : words) {
+      char x1[64], x2[64];
+      size_t n1 = ouma_strxfrm_l(x1, s1, sizeof x1, de);
+      size_t n2 = ouma_strxfrm_l(x2, s2, sizeof x2, de);
+      ASSERT_LT(n1, sizeof x1);
+      ASSERT_LT(n2, sizeof x2);
+      int coll = ouma_strcoll_l(s1, s2, de);
+      int cmp = strcmp(x1, x2);
+      EXPECT_EQ(coll < 0, cmp < 0);
+      EXPECT_EQ(coll == 0, cmp == 0);
+    }
+  }
+  char small[2];
+  EXPECT_EQ(ouma_strxfrm_l(nullptr, "ab", 0, de),
+            ouma_strxfrm_l(small, "ab", sizeof small, de));
+  ouma_freelocale(de);
+
+  ouma_locale_t c = ouma_newlocale(OUMA_LC_ALL_MASK, "C", nullptr);
+  ASSERT_NE(nullptr, c);
+  EXPECT_GT(ouma_strcoll_l("a", "B", c), 0);
+  ouma_freelocale(c);
+}
+
+TEST(newlocale, locpath_invalid) {
+  set_locpath();
+  __oumalibc_errno = 0;
+  EXPECT_EQ(nullptr, ouma_newlocale(OUMA_LC_ALL_MASK, "xx_XX", nullptr));
+  EXPECT_EQ(ENOENT, __oumalibc_errno);
+  // Only C and POSIX are there without a file.
+  __oumalibc_errno = 0;
+  EXPECT_EQ(nullptr, ouma_newlocale(OUMA_LC_ALL_MASK, "fr_FR.UTF-8", nullptr));
+  EXPECT_EQ(ENOENT, __oumalibc_errno);
+  EXPECT_EQ(nullptr, ouma_setlocale(OUMA_LC_ALL, "fr_FR.UTF-8"));
+  EXPECT_STREQ("C.UTF-8", ouma_setlocale(OUMA_LC_ALL, nullptr));
+  ouma_locale_t c = ouma_newlocale(OUMA_LC_ALL_MASK, "C.ISO-8859-1", nullptr);
+  ASSERT_NE(nullptr, c);
+  EXPECT_STREQ(".", ouma_nl_langinfo_l(OUMA_RADIXCHAR, c));
+  ouma_freelocale(c);
+}
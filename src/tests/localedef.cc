@@ -0,0 +1,237 @@
+#include <gtest/gtest.h>
+#include <gmock/gmock.h>
+
+#include <cstdlib>
+#include <fstream>
+#include <string>
+
+#include <stdlib.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+extern "C" {
+  struct LocaleStruct;
+
+  typedef struct LocaleStruct *ouma_locale_t;
+
+  ouma_locale_t ouma_newlocale(int category_mask, const char *locale,
+                               ouma_locale_t base);
+  void ouma_freelocale(ouma_locale_t locale);
+
+  int ouma_setenv(const char *name, const char *value, int overwrite);
+  int ouma_iswupper_l(wint_t wc, ouma_locale_t locale);
+  int ouma_iswlower_l(wint_t wc, ouma_locale_t locale);
+  int ouma_iswalpha_l(wint_t wc, ouma_locale_t locale);
+  int ouma_iswpunct_l(wint_t wc, ouma_locale_t locale);
+  wint_t ouma_towupper_l(wint_t wc, ouma_locale_t locale);
+  wint_t ouma_towlower_l(wint_t wc, ouma_locale_t locale);
+  char *ouma_nl_langinfo_l(int item, ouma_locale_t locale);
+  int ouma_strcoll_l(const char *s1, const char *s2, ouma_locale_t locale);
+}
+
+#define OUMA_LC_ALL_MASK 0x3f
+
+#define OUMA_CODESET 14
+#define OUMA_RADIXCHAR 0x10000
+#define OUMA_THOUSEP 0x10001
+#define OUMA_ABDAY_1 0x20000
+#define OUMA_MON_3 0x2001c
+#define OUMA_D_FMT 0x20029
+#define OUMA_CRNCYSTR 0x4000f
+#define OUMA_YESEXPR 0x50000
+#define OUMA_NOSTR 0x50003
+
+// A source in the form the glibc sources take, with a category copied from
+// another file and a line continued after a comment.
+static const char kSource[] = R"(comment_char %
+escape_char /
+% A locale for the tests of localedef.
+
+LC_CTYPE
+upper <U0041>..<U005A>;<U00C4>
+lower <U0061>..<U007A>;<U00E4>
+digit <U0030>..<U0039>
+punct <U0021>..<U002F>
+toupper (<U0061>,<U0041>);(<U0062>,<U0042>);(<U00E4>,<U00C4>)
+tolower (<U0041>,<U0061>);(<U0042>,<U0062>);(<U00C4>,<U00E4>)
+translit_start
+<U00E4> "<U0061><U0065>"
+translit_end
+END LC_CTYPE
+
+LC_NUMERIC
+copy "xx_numeric"
+END LC_NUMERIC
+
+LC_TIME
+mon "Januar";"Februar";"M<U00E4>rz";"April";"Mai";"Juni";/
+    "Juli";"August";"September";"Oktober";"November";"Dezember"
+d_fmt "%d.%m.%Y"
+END LC_TIME
+
+LC_COLLATE
+collating-element <ch> from "<U0063><U0068>"
+order_start forward;forward
+<U0061> <U0061>;<U0061>
+<U00E4> <U0061>;<U00E4>
+<U0062> <U0062>;<U0062>
+<U0063> <U0063>;<U0063>
+<ch> <ch>;<ch>
+<U0064> <U0064>;<U0064>
+order_end
+END LC_COLLATE
+
+LC_MONETARY
+int_curr_symbol "EUR "
+currency_symbol "<U20AC>"
+frac_digits 2
+p_cs_precedes 0
+END LC_MONETARY
+
+LC_MESSAGES
+yesexpr "^[jJyY]"
+noexpr "^[nN]"
+yesstr "ja"
+nostr "nein"
+END LC_MESSAGES
+)";
+
+static const char kNumeric[] = R"(LC_NUMERIC
+decimal_point "<U002C>"
+thousands_sep "<U002E>"
+grouping 3;3
+END LC_NUMERIC
+)";
+
+// Latin-1, as far as ASCII and a few letters go.
+static const char kCharmap[] = R"(<code_set_name> ISO-8859-1
+<comment_char> %
+<escape_char> /
+CHARMAP
+<U0000>..<U007F> /x00
+<U00C4> /xc4 LATIN CAPITAL LETTER A WITH DIAERESIS
+<U00E4> /xe4 LATIN SMALL LETTER A WITH DIAERESIS
+END CHARMAP
+)";
+
+// A directory for LOCPATH, with the sources above and what localedef, at
+// LOCALEDEF or as cargo builds it, compiles from them.
+static std::string locale_dir() {
+  static std::string dir;
+  if (!dir.empty()) {
+    return dir;
+  }
+  char name[] = "/tmp/ouma-localedef-XXXXXX";
+  dir = mkdtemp(name);
+  std::ofstream(dir + "/xx_XX") << kSource;
+  std::ofstream(dir + "/xx_numeric") << kNumeric;
+  std::ofstream(dir + "/latin1") << kCharmap;
+  std::ofstream(dir + "/bad") << "LC_NUMERIC\ndecimal_point \",\"\n";
+  return dir;
+}
+
+static int localedef(const std::string &args) {
+  const char *program = getenv("LOCALEDEF");
+  std::string command =
+    program ? program : "tools/localedef/target/debug/localedef";
+  command += " " + args + " 2>/dev/null";
+  return std::system(command.c_str());
+}
+
+static void set_locpath() {
+  ASSERT_EQ(0, ouma_setenv("LOCPATH", locale_dir().c_str(), 1));
+}
+
+TEST(localedef, utf8) {
+  std::string dir = locale_dir();
+  ASSERT_EQ(0, localedef("-i " + dir + "/xx_XX " + dir + "/xx_XX.UTF-8"));
+  set_locpath();
+  ouma_locale_t xx = ouma_newlocale(OUMA_LC_ALL_MASK, "xx_XX.UTF-8", nullptr);
+  ASSERT_NE(nullptr, xx);
+  EXPECT_STREQ("UTF-8", ouma_nl_langinfo_l(OUMA_CODESET, xx));
+
+  EXPECT_TRUE(ouma_iswupper_l(0xc4, xx));
+  EXPECT_TRUE(ouma_iswlower_l(0xe4, xx));
+  EXPECT_TRUE(ouma_iswalpha_l(0xe4, xx));
+  EXPECT_TRUE(ouma_iswpunct_l(L',', xx));
+  EXPECT_EQ(0xc4, ouma_towupper_l(0xe4, xx));
+  EXPECT_EQ(L'b', ouma_towlower_l(L'B', xx));
+  // What the source does not map stays as it is.
+  EXPECT_EQ(L'c', ouma_towupper_l(L'c', xx));
+
+  EXPECT_STREQ(",", ouma_nl_langinfo_l(OUMA_RADIXCHAR, xx));
+  EXPECT_STREQ(".", ouma_nl_langinfo_l(OUMA_THOUSEP, xx));
+  EXPECT_STREQ("März", ouma_nl_langinfo_l(OUMA_MON_3, xx));
+  EXPECT_STREQ("%d.%m.%Y", ouma_nl_langinfo_l(OUMA_D_FMT, xx));
+  // What LC_TIME leaves out is as in C.
+  EXPECT_STREQ("Sun", ouma_nl_langinfo_l(OUMA_ABDAY_1, xx));
+  EXPECT_STREQ("+€", ouma_nl_langinfo_l(OUMA_CRNCYSTR, xx));
+  EXPECT_STREQ("^[jJyY]", ouma_nl_langinfo_l(OUMA_YESEXPR, xx));
+  EXPECT_STREQ("nein", ouma_nl_langinfo_l(OUMA_NOSTR, xx));
+
+  EXPECT_LT(ouma_strcoll_l("a", "ä", xx), 0);
+  EXPECT_LT(ouma_strcoll_l("ä", "b", xx), 0);
+  EXPECT_LT(ouma_strcoll_l("äb", "ac", xx), 0);
+  EXPECT_LT(ouma_strcoll_l("cx", "ch", xx), 0);
+  EXPECT_LT(ouma_strcoll_l("ch", "d", xx), 0);
+  // Characters the order leaves out follow it.
+  EXPECT_LT(ouma_strcoll_l("d", "e", xx), 0);
+  EXPECT_LT(ouma_strcoll_l("d", "A", xx), 0);
+  ouma_freelocale(xx);
+}
+
+TEST(localedef, charmap) {
+  std::string dir = locale_dir();
+  ASSERT_EQ(0, localedef("-f " + dir + "/latin1 -i " + dir + "/xx_XX " + dir +
+                         "/xx_XX.ISO-8859-1"));
+  set_locpath();
+  ouma_locale_t xx =
+    ouma_newlocale(OUMA_LC_ALL_MASK, "xx_XX.ISO-8859-1", nullptr);
+  ASSERT_NE(nullptr, xx);
+  EXPECT_STREQ("ISO-8859-1", ouma_nl_langinfo_l(OUMA_CODESET, xx));
+  EXPECT_STREQ("M\xe4rz", ouma_nl_langinfo_l(OUMA_MON_3, xx));
+  // The charmap lacks the euro sign, which is left out.
+  EXPECT_STREQ("+", ouma_nl_langinfo_l(OUMA_CRNCYSTR, xx));
+  EXPECT_LT(ouma_strcoll_l("a", "\xe4", xx), 0);
+  EXPECT_LT(ouma_strcoll_l("\xe4", "b", xx), 0);
+  ouma_freelocale(xx);
+}
+
+TEST(localedef, locpath) {
+  std::string dir = locale_dir();
+  ASSERT_EQ(0, localedef("-i " + dir + "/xx_XX " + dir + "/xx_XX.UTF-8"));
+  // Directories before the one with the locale hold, under names it is
+  // looked for by, a file that is not a locale, a directory and a locale in
+  // a codeset the library does not know.
+  std::string junk = dir + "/junk", nested = dir + "/nested";
+  std::string unknown = dir + "/unknown";
+  ASSERT_EQ(0, mkdir(junk.c_str(), 0755));
+  ASSERT_EQ(0, mkdir(nested.c_str(), 0755));
+  ASSERT_EQ(0, mkdir(unknown.c_str(), 0755));
+  std::ofstream(junk + "/xx_XX.UTF-8") << "not a locale";
+  ASSERT_EQ(0, mkdir((nested + "/xx_XX.UTF-8").c_str(), 0755));
+  std::string charmap = kCharmap;
+  charmap.replace(charmap.find("ISO-8859-1"), 10, "X-UNKNOWN");
+  std::ofstream(unknown + "/charmap") << charmap;
+  ASSERT_EQ(0, localedef("-f " + unknown + "/charmap -i " + dir + "/xx_XX " +
+                         unknown + "/xx_XX"));
+
+  std::string locpath = junk + ":" + nested + ":" + unknown + ":" + dir;
+  ASSERT_EQ(0, ouma_setenv("LOCPATH", locpath.c_str(), 1));
+  ouma_locale_t xx = ouma_newlocale(OUMA_LC_ALL_MASK, "xx_XX.UTF-8", nullptr);
+  ASSERT_NE(nullptr, xx);
+  EXPECT_STREQ("UTF-8", ouma_nl_langinfo_l(OUMA_CODESET, xx));
+  EXPECT_STREQ(",", ouma_nl_langinfo_l(OUMA_RADIXCHAR, xx));
+  ouma_freelocale(xx);
+}
+
+TEST(localedef, errors) {
+  std::string dir = locale_dir();
+  EXPECT_NE(0, localedef("-i " + dir + "/bad " + dir + "/bad.UTF-8"));
+  EXPECT_NE(0, localedef("-i " + dir + "/missing " + dir + "/missing.UTF-8"));
+  EXPECT_NE(0, localedef("-f " + dir + "/missing -i " + dir + "/xx_XX " +
+                         dir + "/xx_XX.missing"));
+  EXPECT_NE(0, localedef(dir + "/a " + dir + "/b"));
+  EXPECT_NE(0, access((dir + "/bad.UTF-8").c_str(), F_OK));
+  EXPECT_NE(0, access((dir + "/xx_XX.missing").c_str(), F_OK));
+}
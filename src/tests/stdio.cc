@@ -0,0 +1,696 @@
+#include <gmock/gmock.h>
+#include <gtest/gtest.h>
+
+#include <cfenv>
+#include <cfloat>
+#include <climits>
+#include <cmath>
+#include <cstdint>
+#include <cstdio>
+#include <cstdlib>
+#include <cstring>
+#include <cwchar>
+#include <fcntl.h>
+#include <unistd.h>
+
+extern "C" {
+  typedef struct ouma_FILE ouma_FILE;
+
+  int ouma_asprintf(char **__restrict, const char *__restrict, ...);
+  int ouma_dprintf(int, const char *__restrict, ...);
+  int ouma_snprintf(char *__restrict, size_t, const char *__restrict, ...);
+  int ouma_sprintf(char *__restrict, const char *__restrict, ...);
+  void ouma_free(void *ptr);
+
+  ouma_FILE *ouma_fopen(const char *__restrict, const char *__restrict);
+  ouma_FILE *ouma_fdopen(int, const char *);
+  ouma_FILE *ouma_freopen(const char *__restrict, const char *__restrict,
+                          ouma_FILE *__restrict);
+  int ouma_fclose(ouma_FILE *);
+  int ouma_fflush(ouma_FILE *);
+  int ouma_setvbuf(ouma_FILE *__restrict, char *__restrict, int, size_t);
+  size_t ouma_fread(void *__restrict, size_t, size_t, ouma_FILE *__restrict);
+  size_t ouma_fwrite(const void *__restrict, size_t, size_t,
+                     ouma_FILE *__restrict);
+  int ouma_fgetc(ouma_FILE *);
+  int ouma_ungetc(int, ouma_FILE *);
+  char *ouma_fgets(char *__restrict, int, ouma_FILE *__restrict);
+  int ouma_fputs(const char *__restrict, ouma_FILE *__restrict);
+  int ouma_fseek(ouma_FILE *, long, int);
+  int ouma_fseeko(ouma_FILE *, off_t, int);
+  long ouma_ftell(ouma_FILE *);
+  int ouma_feof(ouma_FILE *);
+  int ouma_ferror(ouma_FILE *);
+  void ouma_clearerr(ouma_FILE *);
+  int ouma_fileno(ouma_FILE *);
+  int ouma_fprintf(ouma_FILE *__restrict, const char *__restrict, ...);
+  int ouma_sscanf(const char *__restrict, const char *__restrict, ...);
+  int ouma_fscanf(ouma_FILE *__restrict, const char *__restrict, ...);
+
+  extern _Thread_local int __oumalibc_errno;
+
+  struct LocaleCtype {
+    const char *codeset;
+    ssize_t (*mbtoc32)(char32_t*, const char*, size_t, mbstate_t*);
+    ssize_t (*c32tomb)(char*, char32_t, mbstate_t*);
+    ssize_t (*unshift)(char*, mbstate_t*);
+    int mb_cur_max;
+    bool stateful;
+  };
+
+  struct LocaleNumeric {
+    const char *decimal_point;
+    const char *thousands_sep;
+    const char *grouping;
+  };
+
+  struct LocaleStruct {
+    LocaleCtype ctype;
+    LocaleNumeric numeric;
+  };
+
+  typedef struct LocaleStruct *ouma_locale_t;
+
+  ouma_locale_t ouma_newlocale(int, const char *, ouma_locale_t);
+  void ouma_freelocale(ouma_locale_t);
+  ouma_locale_t ouma_uselocale(ouma_locale_t);
+}
+
+// Streams work on a fresh temporary file, removed again on destruction.
+class TempFile {
+ public:
+  TempFile() {
+    strcpy(path_, "/tmp/ouma-stdio-XXXXXX");
+    close(mkstemp(path_));
+  }
+  ~TempFile() { unlink(path_); }
+  const char *path() const { return path_; }
+
+ private:
+  char path_[32];
+};
+
+#define EXPECT_SNPRINTF(expected, ...)                                       \
+  do {                                                                       \
+    char buf[256];                                                           \
+    EXPECT_EQ((int)strlen(expected),                                         \
+              ouma_snprintf(buf, sizeof(buf), __VA_ARGS__));                 \
+    EXPECT_STREQ(expected, buf);                                             \
+  } while (0)
+
+TEST(snprintf, integers) {
+  EXPECT_SNPRINTF("42 -42", "%d %i", 42, -42);
+  EXPECT_SNPRINTF("   42|42   |00042", "%5d|%-5d|%05d", 42, 42, 42);
+  EXPECT_SNPRINTF("+5  5 -5", "%+d % d %+d", 5, 5, -5);
+  EXPECT_SNPRINTF("007||     |-007    |     007", "%.3d|%.0d|%5.0d|%-8.3d|%08.3d",
+                  7, 0, 0, -7, 7);
+  EXPECT_SNPRINTF("ff FF 0xff 0 010 0 0", "%x %X %#x %#X %#o %o %#o", 255, 255,
+                  255, 0, 8, 0, 0);
+  EXPECT_SNPRINTF("44 4464 44 4464", "%hhd %hd %hhu %hu", 300, 70000, 300,
+                  70000);
+  EXPECT_SNPRINTF("-9223372036854775808 18446744073709551615", "%lld %zu",
+                  LLONG_MIN, SIZE_MAX);
+}
+
+TEST(snprintf, binary) {
+  EXPECT_SNPRINTF("101 0b101 0B101 00000101 ", "%b %#b %#B %08b %.0b", 5, 5, 5,
+                  5, 0);
+}
+
+TEST(snprintf, bit_width) {
+  EXPECT_SNPRINTF("44 4464 -1 -1", "%w8d %w16d %w32d %w64d", 300, 70000, -1,
+                  (int64_t)-1);
+  EXPECT_SNPRINTF("44 -1 -1", "%wf8d %wf16d %wf64d", 300, (int64_t)-1,
+                  (int64_t)-1);
+  char buf[16];
+  __oumalibc_errno = 0;
+  EXPECT_EQ(-1, ouma_snprintf(buf, sizeof(buf), "%w12d", 1));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+}
+
+TEST(snprintf, strings) {
+  EXPECT_SNPRINTF("abc|  x|y  |", "%c%c%c|%3c|%-3c|", 'a', 'b', 'c', 'x', 'y');
+  EXPECT_SNPRINTF("str|       str|str       |st|        st", "%s|%10s|%-10s|%.2s|%10.2s",
+                  "str", "str", "str", "str", "str");
+  EXPECT_SNPRINTF("(null)|", "%s|%.3s", (char *)nullptr, (char *)nullptr);
+  EXPECT_SNPRINTF("0x1234|     (nil)", "%p|%10p", (void *)0x1234, nullptr);
+}
+
+TEST(snprintf, wide) {
+  EXPECT_SNPRINTF("h\xc3\xa9llo|   ab|h\xc3\xa9|\xc3\xa9", "%ls|%5ls|%.3ls|%lc",
+                  L"héllo", L"ab", L"héllo", (wint_t)L'é');
+  // Characters are never cut by the precision.
+  EXPECT_SNPRINTF("|\xc3\xa9", "%.1ls|%.4ls", L"é€", L"é€");
+}
+
+TEST(snprintf, star) {
+  EXPECT_SNPRINTF("    1|2    |3    |004|5", "%*d|%-*d|%*d|%.*d|%.*d", 5, 1, 5, 2,
+                  -5, 3, 3, 4, -1, 5);
+}
+
+TEST(snprintf, positional) {
+  EXPECT_SNPRINTF("b a b", "%2$s %1$s %2$s", "a", "b");
+  EXPECT_SNPRINTF("    5|007", "%1$*2$d|%3$.*4$d", 5, 5, 7, 3);
+  char buf[16];
+  __oumalibc_errno = 0;
+  EXPECT_EQ(-1, ouma_snprintf(buf, sizeof(buf), "%1$d %d", 1, 2));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(-1, ouma_snprintf(buf, sizeof(buf), "%2$d", 1, 2));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+}
+
+TEST(snprintf, count) {
+  int n1 = 0;
+  signed char n2 = 0;
+  long n3 = 0;
+  EXPECT_SNPRINTF("abc12345", "abc%n12%hhn345%ln", &n1, &n2, &n3);
+  EXPECT_EQ(3, n1);
+  EXPECT_EQ(5, n2);
+  EXPECT_EQ(8, n3);
+}
+
+TEST(snprintf, truncation) {
+  char buf[4];
+  EXPECT_EQ(8, ouma_snprintf(buf, sizeof(buf), "%s", "abcdefgh"));
+  EXPECT_STREQ("abc", buf);
+  EXPECT_EQ(11, ouma_snprintf(nullptr, 0, "%s%d", "abcdefgh", 123));
+}
+
+TEST(snprintf, overflow) {
+  char buf[4];
+  __oumalibc_errno = 0;
+  EXPECT_EQ(-1, ouma_snprintf(buf, sizeof(buf), "%s%2147483647d", "ab", 1));
+  EXPECT_EQ(EOVERFLOW, __oumalibc_errno);
+}
+
+TEST(snprintf, fixed) {
+  EXPECT_SNPRINTF("1.000000 -0.500000 0 2 2", "%f %f %.0f %.0f %.0f", 1.0,
+                  -0.5, 0.5, 1.5, 2.5);
+  EXPECT_SNPRINTF("0.10000000000000000555", "%.20f", 0.1);
+  EXPECT_SNPRINTF("   3.142|3.142   |0003.142|+3.142| 3.142",
+                  "%8.3f|%-8.3f|%08.3f|%+.3f|% .3f", 3.14159, 3.14159, 3.14159,
+                  3.14159, 3.14159);
+  EXPECT_SNPRINTF("1. 100000000000000000000", "%#.0f %.0f", 1.0, 1e20);
+  EXPECT_SNPRINTF("-0.000000", "%f", -0.0);
+}
+
+TEST(snprintf, exponent) {
+  EXPECT_SNPRINTF("1.000000e+00 1.234560E-05 1e+100", "%e %E %.0e", 1.0,
+                  1.23456e-5, 1e100);
+  EXPECT_SNPRINTF("4.9406564584124654e-324", "%.16e", 4.9e-324);
+  EXPECT_SNPRINTF("1.797693e+308 0.000000e+00", "%e %e",
+                  1.7976931348623157e308, 0.0);
+}
+
+TEST(snprintf, general) {
+  EXPECT_SNPRINTF("100000 1e+06 0.0001 1e-05", "%g %g %g %g", 100000.0, 1e6,
+                  1e-4, 1e-5);
+  EXPECT_SNPRINTF("0.1 0.30000000000000004 1.5", "%g %.17g %G", 0.1, 0.1 + 0.2,
+                  1.5);
+  EXPECT_SNPRINTF("1.00000 100. 1.e+04", "%#g %#.3g %#.4g", 1.0, 99.95, 9999.7);
+}
+
+TEST(snprintf, hex) {
+  EXPECT_SNPRINTF("0x1p+0 0x1.8p+1 -0x0p+0 0X1.FP-1", "%a %a %a %A", 1.0, 3.0,
+                  -0.0, 0.96875);
+  EXPECT_SNPRINTF("0x2p+0 0x1.0p+0 0x1.000p+0", "%.0a %.1a %.3a", 1.5, 1.0,
+                  1.0);
+  EXPECT_SNPRINTF("0x0.0000000000001p-1022", "%a", 4.9e-324);
+  EXPECT_SNPRINTF("0x0001p+0 0x1.p+0", "%09a %#a", 1.0, 1.0);
+}
+
+TEST(snprintf, long_double) {
+  EXPECT_SNPRINTF("1.000000 0.1 2.500000e+00", "%Lf %Lg %Le", 1.0L, 0.1L, 2.5L);
+  EXPECT_SNPRINTF("1 2.000000 3 4.000000", "%d %Lf %d %f", 1, 2.0L, 3, 4.0);
+  EXPECT_SNPRINTF("1.18973e+4932", "%Lg", LDBL_MAX);
+#if LDBL_MANT_DIG == 64
+  EXPECT_SNPRINTF("0x8p-3 0xcp-2", "%La %La", 1.0L, 3.0L);
+#else
+  EXPECT_SNPRINTF("0x1p+0 0x1.8p+1", "%La %La", 1.0L, 3.0L);
+#endif
+}
+
+TEST(snprintf, special) {
+  EXPECT_SNPRINTF("inf -inf INF nan -nan NAN", "%f %e %E %g %a %A", INFINITY,
+                  -INFINITY, INFINITY, NAN, -NAN, NAN);
+  EXPECT_SNPRINTF("  inf|+inf |  nan", "%05f|%-+5f|% 5Lf", INFINITY, INFINITY,
+                  (long double)NAN);
+}
+
+TEST(snprintf, rounding_mode) {
+  ASSERT_EQ(0, fesetround(FE_UPWARD));
+  EXPECT_SNPRINTF("0.2 -0.1 0x1.1p+0", "%.1f %.1f %.1a", 0.1, -0.1, 1.0001);
+  ASSERT_EQ(0, fesetround(FE_DOWNWARD));
+  EXPECT_SNPRINTF("0.1 -0.2 0x1.0p+0", "%.1f %.1f %.1a", 0.19, -0.11, 1.0001);
+  ASSERT_EQ(0, fesetround(FE_TOWARDZERO));
+  EXPECT_SNPRINTF("0.9 -0.9", "%.1f %.1f", 0.99, -0.99);
+  ASSERT_EQ(0, fesetround(FE_TONEAREST));
+  EXPECT_SNPRINTF("1.0 -1.0", "%.1f %.1f", 0.99, -0.99);
+}
+
+TEST(snprintf, grouping) {
+  EXPECT_SNPRINTF("1234567 1234567.50", "%'d %'.2f", 1234567, 1234567.5);
+
+  ouma_locale_t locale = ouma_newlocale(0x3f, "C", nullptr);
+  ASSERT_NE(nullptr, locale);
+  locale->numeric = {",", ".", "\3"};
+  ouma_locale_t old = ouma_uselocale(locale);
+  EXPECT_SNPRINTF("1.234.567 -1.234 123 0", "%'d %'d %'d %'u", 1234567, -1234,
+                  123, 0);
+  EXPECT_SNPRINTF("     1.234|1.234     |0001.234", "%'10d|%'-10d|%'08d", 1234,
+                  1234, 1234);
+  EXPECT_SNPRINTF("12d687 1234567", "%'x %d", 1234567, 1234567);
+  EXPECT_SNPRINTF("1.234.567,89 0,50 1,234568e+06", "%'.2f %'.2f %'e",
+                  1234567.891, 0.5, 1234567.891);
+  EXPECT_SNPRINTF("123.456 1,23457e+06", "%'g %'g", 123456.0, 1234567.0);
+  EXPECT_SNPRINTF("  1.234,0", "%'9.1f", 1234.0);
+
+  locale->numeric.grouping = "\3\2";
+  EXPECT_SNPRINTF("12.34.56.789", "%'d", 123456789);
+  locale->numeric.grouping = "\3\x7f";
+  EXPECT_SNPRINTF("1234.567", "%'d", 1234567);
+
+  ouma_uselocale(old);
+  ouma_freelocale(locale);
+}
+
+TEST(sprintf, example) {
+  char buf[16];
+  EXPECT_EQ(6, ouma_sprintf(buf, "%s %d", "spr", 12));
+  EXPECT_STREQ("spr 12", buf);
+}
+
+TEST(asprintf, example) {
+  char *s = nullptr;
+  EXPECT_EQ(9, ouma_asprintf(&s, "%s-%d-%03d", "xyz", 9, 1));
+  EXPECT_STREQ("xyz-9-001", s);
+  ouma_free(s);
+}
+
+TEST(dprintf, example) {
+  int fds[2];
+  ASSERT_EQ(0, pipe(fds));
+  EXPECT_EQ(6, ouma_dprintf(fds[1], "%s %d", "fd", 123));
+  char buf[16] = {};
+  EXPECT_EQ(6, read(fds[0], buf, sizeof(buf)));
+  EXPECT_STREQ("fd 123", buf);
+  close(fds[0]);
+  close(fds[1]);
+
+  __oumalibc_errno = 0;
+  EXPECT_EQ(-1, ouma_dprintf(-1, "x"));
+  EXPECT_EQ(EBADF, __oumalibc_errno);
+}
+
+TEST(fopen, modes) {
+  TempFile tmp;
+  __oumalibc_errno = 0;
+  EXPECT_EQ(nullptr, ouma_fopen(tmp.path(), "wx"));
+  EXPECT_EQ(EEXIST, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(nullptr, ouma_fopen(tmp.path(), "q"));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(nullptr, ouma_fopen("/nonexistent/file", "r"));
+  EXPECT_EQ(ENOENT, __oumalibc_errno);
+
+  ouma_FILE *f = ouma_fopen(tmp.path(), "we");
+  ASSERT_NE(nullptr, f);
+  EXPECT_EQ(FD_CLOEXEC, fcntl(ouma_fileno(f), F_GETFD));
+  EXPECT_EQ(EOF, ouma_fgetc(f));
+  EXPECT_TRUE(ouma_ferror(f));
+  EXPECT_EQ(0, ouma_fclose(f));
+}
+
+TEST(fwrite, fread) {
+  TempFile tmp;
+  ouma_FILE *f = ouma_fopen(tmp.path(), "w+");
+  ASSERT_NE(nullptr, f);
+  int values[1000];
+  for (int i = 0; i < 1000; i++) {
+    values[i] = i * 7;
+  }
+  EXPECT_EQ(1000u, ouma_fwrite(values, sizeof(int), 1000, f));
+  EXPECT_EQ(4000, ouma_ftell(f));
+  EXPECT_EQ(0, ouma_fseek(f, 0, SEEK_SET));
+  int read_back[1001];
+  EXPECT_EQ(1000u, ouma_fread(read_back, sizeof(int), 1001, f));
+  EXPECT_TRUE(ouma_feof(f));
+  EXPECT_EQ(0, memcmp(values, read_back, sizeof(values)));
+  ouma_clearerr(f);
+  EXPECT_FALSE(ouma_feof(f));
+  EXPECT_EQ(0, ouma_fclose(f));
+}
+
+TEST(fgets, lines) {
+  TempFile tmp;
+  ouma_FILE *f = ouma_fopen(tmp.path(), "w");
+  ASSERT_NE(nullptr, f);
+  EXPECT_LE(0, ouma_fputs("first line\nsecond\nno newline", f));
+  EXPECT_EQ(0, ouma_fclose(f));
+
+  f = ouma_fopen(tmp.path(), "r");
+  ASSERT_NE(nullptr, f);
+  char buf[8];
+  EXPECT_EQ(buf, ouma_fgets(buf, sizeof(buf), f));
+  EXPECT_STREQ("first l", buf);
+  EXPECT_EQ(buf, ouma_fgets(buf, sizeof(buf), f));
+  EXPECT_STREQ("ine\n", buf);
+  EXPECT_EQ(buf, ouma_fgets(buf, sizeof(buf), f));
+  EXPECT_STREQ("second\n", buf);
+  EXPECT_EQ(buf, ouma_fgets(buf, sizeof(buf), f));
+  EXPECT_STREQ("no newl", buf);
+  EXPECT_EQ(buf, ouma_fgets(buf, sizeof(buf), f));
+  EXPECT_STREQ("ine", buf);
+  EXPECT_EQ(nullptr, ouma_fgets(buf, sizeof(buf), f));
+  EXPECT_STREQ("ine", buf);
+  EXPECT_TRUE(ouma_feof(f));
+  EXPECT_EQ(0, ouma_fclose(f));
+}
+
+TEST(ungetc, pushback) {
+  TempFile tmp;
+  ouma_FILE *f = ouma_fopen(tmp.path(), "w+");
+  ASSERT_NE(nullptr, f);
+  EXPECT_LE(0, ouma_fputs("abc", f));
+  EXPECT_EQ(0, ouma_fseek(f, 0, SEEK_SET));
+  EXPECT_EQ('a', ouma_fgetc(f));
+  EXPECT_EQ('b', ouma_fgetc(f));
+  EXPECT_EQ('X', ouma_ungetc('X', f));
+  EXPECT_EQ(1, ouma_ftell(f));
+  EXPECT_EQ(EOF, ouma_ungetc(EOF, f));
+  EXPECT_EQ('X', ouma_fgetc(f));
+  EXPECT_EQ('c', ouma_fgetc(f));
+  EXPECT_EQ(EOF, ouma_fgetc(f));
+  EXPECT_EQ('c', ouma_ungetc('c', f));
+  EXPECT_FALSE(ouma_feof(f));
+  EXPECT_EQ('c', ouma_fgetc(f));
+  EXPECT_EQ(0, ouma_fclose(f));
+}
+
+TEST(fseek, update) {
+  TempFile tmp;
+  ouma_FILE *f = ouma_fopen(tmp.path(), "w+");
+  ASSERT_NE(nullptr, f);
+  EXPECT_EQ(11, ouma_fprintf(f, "%s %d", "hello", 12345));
+  EXPECT_EQ(0, ouma_fseek(f, -5, SEEK_END));
+  EXPECT_EQ(6, ouma_ftell(f));
+  EXPECT_EQ('1', ouma_fgetc(f));
+  EXPECT_EQ(0, ouma_fseeko(f, 0, SEEK_CUR));
+  EXPECT_LE(0, ouma_fputs("X", f));
+  EXPECT_EQ(0, ouma_fseek(f, 0, SEEK_SET));
+  char buf[16] = {};
+  EXPECT_EQ(11u, ouma_fread(buf, 1, sizeof(buf), f));
+  EXPECT_STREQ("hello 1X345", buf);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(-1, ouma_fseek(f, 0, 42));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+  EXPECT_EQ(0, ouma_fclose(f));
+
+  f = ouma_fopen(tmp.path(), "a");
+  ASSERT_NE(nullptr, f);
+  EXPECT_LE(0, ouma_fputs("!", f));
+  EXPECT_EQ(12, ouma_ftell(f));
+  EXPECT_EQ(0, ouma_fclose(f));
+}
+
+TEST(setvbuf, modes) {
+  int fds[2];
+  ASSERT_EQ(0, pipe(fds));
+  ASSERT_EQ(0, fcntl(fds[0], F_SETFL, O_NONBLOCK));
+  ouma_FILE *f = ouma_fdopen(fds[1], "w");
+  ASSERT_NE(nullptr, f);
+  static char buf[64];
+  EXPECT_EQ(0, ouma_setvbuf(f, buf, _IOLBF, sizeof(buf)));
+  EXPECT_EQ(-1, ouma_setvbuf(f, nullptr, 42, 0));
+  char out[16] = {};
+
+  EXPECT_LE(0, ouma_fputs("partial", f));
+  EXPECT_EQ(-1, read(fds[0], out, sizeof(out)));
+  EXPECT_LE(0, ouma_fputs(" line\n", f));
+  EXPECT_EQ(13, read(fds[0], out, sizeof(out)));
+
+  EXPECT_EQ(0, ouma_setvbuf(f, nullptr, _IONBF, 0));
+  EXPECT_LE(0, ouma_fputs("now", f));
+  EXPECT_EQ(3, read(fds[0], out, sizeof(out)));
+
+  EXPECT_EQ(0, ouma_setvbuf(f, nullptr, _IOFBF, 0));
+  EXPECT_LE(0, ouma_fputs("full\n", f));
+  EXPECT_EQ(0, ouma_fflush(nullptr));
+  EXPECT_EQ(5, read(fds[0], out, sizeof(out)));
+
+  EXPECT_EQ(0, ouma_fclose(f));
+  close(fds[0]);
+}
+
+TEST(fdopen, access) {
+  int fds[2];
+  ASSERT_EQ(0, pipe(fds));
+  __oumalibc_errno = 0;
+  EXPECT_EQ(nullptr, ouma_fdopen(fds[0], "w"));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(nullptr, ouma_fdopen(-1, "r"));
+  EXPECT_EQ(EBADF, __oumalibc_errno);
+  close(fds[0]);
+  close(fds[1]);
+}
+
+TEST(freopen, keeps_descriptor) {
+  TempFile first, second;
+  ouma_FILE *f = ouma_fopen(first.path(), "w");
+  ASSERT_NE(nullptr, f);
+  int fd = ouma_fileno(f);
+  EXPECT_LE(0, ouma_fputs("one", f));
+  EXPECT_EQ(f, ouma_freopen(second.path(), "w", f));
+  EXPECT_EQ(fd, ouma_fileno(f));
+  EXPECT_LE(0, ouma_fputs("two", f));
+  EXPECT_EQ(0, ouma_fclose(f));
+
+  char buf[8] = {};
+  int in = open(first.path(), O_RDONLY);
+  EXPECT_EQ(3, read(in, buf, sizeof(buf)));
+  EXPECT_STREQ("one", buf);
+  close(in);
+  in = open(second.path(), O_RDONLY);
+  EXPECT_EQ(3, read(in, buf, sizeof(buf)));
+  EXPECT_STREQ("two", buf);
+  close(in);
+}
+
+TEST(sscanf, integers) {
+  int a = 0, b = 0, n = 0;
+  EXPECT_EQ(2, ouma_sscanf("  -42 17", "%d %d%n", &a, &b, &n));
+  EXPECT_EQ(-42, a);
+  EXPECT_EQ(17, b);
+  EXPECT_EQ(8, n);
+  unsigned u = 0, x = 0, o = 0;
+  EXPECT_EQ(3, ouma_sscanf("4294967295 0x1f 017", "%u %x %o", &u, &x, &o));
+  EXPECT_EQ(UINT_MAX, u);
+  EXPECT_EQ(31u, x);
+  EXPECT_EQ(15u, o);
+  EXPECT_EQ(2, ouma_sscanf("12345", "%3d%d", &a, &b));
+  EXPECT_EQ(123, a);
+  EXPECT_EQ(45, b);
+  signed char hh = 0;
+  short h = 0;
+  long long ll = 0;
+  EXPECT_EQ(3, ouma_sscanf("300 70000 99999999999999999999", "%hhd %hd %lld",
+                           &hh, &h, &ll));
+  EXPECT_EQ(44, hh);
+  EXPECT_EQ(4464, h);
+  EXPECT_EQ(LLONG_MAX, ll);
+  void *p = nullptr;
+  EXPECT_EQ(1, ouma_sscanf("0x1234", "%p", &p));
+  EXPECT_EQ((void *)0x1234, p);
+}
+
+TEST(sscanf, prefixes) {
+  int i = 0, n = 0;
+  EXPECT_EQ(1, ouma_sscanf("0x1f", "%i", &i));
+  EXPECT_EQ(31, i);
+  EXPECT_EQ(1, ouma_sscanf("017", "%i", &i));
+  EXPECT_EQ(15, i);
+  EXPECT_EQ(1, ouma_sscanf("0b101", "%i", &i));
+  EXPECT_EQ(5, i);
+  EXPECT_EQ(1, ouma_sscanf("-0b11", "%b", &i));
+  EXPECT_EQ(-3, i);
+  // A prefix without digits is consumed and leaves the value 0.
+  EXPECT_EQ(1, ouma_sscanf("0xg", "%x%n", &i, &n));
+  EXPECT_EQ(0, i);
+  EXPECT_EQ(2, n);
+  EXPECT_EQ(1, ouma_sscanf("08", "%i%n", &i, &n));
+  EXPECT_EQ(0, i);
+  EXPECT_EQ(1, n);
+}
+
+TEST(sscanf, floats) {
+  double d = 0;
+  float f = 0;
+  int n = 0;
+  EXPECT_EQ(2, ouma_sscanf("0.1 3.4028235e38", "%lf %f", &d, &f));
+  EXPECT_EQ(0.1, d);
+  EXPECT_EQ(FLT_MAX, f);
+  EXPECT_EQ(1, ouma_sscanf("0x1.8p-1074", "%la", &d));
+  EXPECT_EQ(2 * DBL_TRUE_MIN, d);
+  EXPECT_EQ(1, ouma_sscanf("2.4703282292062328e-324", "%le", &d));
+  EXPECT_EQ(DBL_TRUE_MIN, d);
+  EXPECT_EQ(1, ouma_sscanf("1e400", "%lg", &d));
+  EXPECT_EQ(INFINITY, d);
+  EXPECT_EQ(1, ouma_sscanf("-Infinity", "%lf", &d));
+  EXPECT_EQ(-INFINITY, d);
+  EXPECT_EQ(1, ouma_sscanf("nan(1)", "%lf%n", &d, &n));
+  EXPECT_TRUE(std::isnan(d));
+  EXPECT_EQ(3, n);
+  // The longest prefix of a number is read even if it is not one as a whole.
+  EXPECT_EQ(1, ouma_sscanf("1e+x", "%lf%n", &d, &n));
+  EXPECT_EQ(1.0, d);
+  EXPECT_EQ(3, n);
+  EXPECT_EQ(1, ouma_sscanf("1.5e3", "%3lf", &d));
+  EXPECT_EQ(1.5, d);
+  EXPECT_EQ(0, ouma_sscanf("0x", "%lf", &d));
+  EXPECT_EQ(0, ouma_sscanf("-.e1", "%lf", &d));
+  EXPECT_EQ(0, ouma_sscanf("infin", "%lf", &d));
+}
+
+TEST(sscanf, long_double) {
+  long double l = 0;
+  EXPECT_EQ(1, ouma_sscanf("1.1", "%Lf", &l));
+  EXPECT_EQ(1.1L, l);
+  EXPECT_EQ(1, ouma_sscanf("1e4932", "%Lg", &l));
+  EXPECT_EQ(1e4932L, l);
+  EXPECT_EQ(1, ouma_sscanf("3.36210314311209350626e-4932", "%Le", &l));
+  EXPECT_EQ(LDBL_MIN, l);
+}
+
+TEST(sscanf, rounding_mode) {
+  double d = 0;
+  ASSERT_EQ(0, fesetround(FE_UPWARD));
+  EXPECT_EQ(1, ouma_sscanf("0.1", "%lf", &d));
+  EXPECT_EQ(0x1.999999999999ap-4, d);
+  EXPECT_EQ(1, ouma_sscanf("1e-400", "%lf", &d));
+  EXPECT_EQ(DBL_TRUE_MIN, d);
+  ASSERT_EQ(0, fesetround(FE_DOWNWARD));
+  EXPECT_EQ(1, ouma_sscanf("0.1", "%lf", &d));
+  EXPECT_EQ(0x1.9999999999999p-4, d);
+  ASSERT_EQ(0, fesetround(FE_TOWARDZERO));
+  EXPECT_EQ(1, ouma_sscanf("1e400", "%lf", &d));
+  EXPECT_EQ(DBL_MAX, d);
+  ASSERT_EQ(0, fesetround(FE_TONEAREST));
+}
+
+TEST(sscanf, strings) {
+  char a[16] = {}, b[16] = {};
+  EXPECT_EQ(2, ouma_sscanf("hello world", "%s %s", a, b));
+  EXPECT_STREQ("hello", a);
+  EXPECT_STREQ("world", b);
+  EXPECT_EQ(2, ouma_sscanf("hello", "%3s%s", a, b));
+  EXPECT_STREQ("hel", a);
+  EXPECT_STREQ("lo", b);
+  char c[4] = {};
+  EXPECT_EQ(1, ouma_sscanf("  abc", "%c", c));
+  EXPECT_EQ(' ', c[0]);
+  EXPECT_EQ(1, ouma_sscanf("abc", "%3c", c));
+  EXPECT_EQ(0, memcmp("abc", c, 3));
+  EXPECT_EQ(1, ouma_sscanf("  x", " %c", c));
+  EXPECT_EQ('x', c[0]);
+}
+
+TEST(sscanf, scanset) {
+  char a[16] = {}, b[16] = {};
+  EXPECT_EQ(2, ouma_sscanf("abc]def", "%[]a-c]%s", a, b));
+  EXPECT_STREQ("abc]", a);
+  EXPECT_STREQ("def", b);
+  EXPECT_EQ(2, ouma_sscanf("abcdef", "%[^d]%s", a, b));
+  EXPECT_STREQ("abc", a);
+  EXPECT_STREQ("def", b);
+  EXPECT_EQ(1, ouma_sscanf("a-b", "%[a-]", a));
+  EXPECT_STREQ("a-", a);
+  // A reversed range is three separate bytes.
+  EXPECT_EQ(1, ouma_sscanf("c-ab", "%[c-a]", a));
+  EXPECT_STREQ("c-a", a);
+  EXPECT_EQ(0, ouma_sscanf("xyz", "%[a-c]", a));
+}
+
+TEST(sscanf, alloc) {
+  char *a = nullptr, *b = nullptr;
+  EXPECT_EQ(2, ouma_sscanf("dyn alloc", "%ms %m[a-z]", &a, &b));
+  EXPECT_STREQ("dyn", a);
+  EXPECT_STREQ("alloc", b);
+  ouma_free(a);
+  ouma_free(b);
+  wchar_t *w = nullptr;
+  EXPECT_EQ(1, ouma_sscanf("wide", "%mls", &w));
+  EXPECT_EQ(0, wcscmp(L"wide", w));
+  ouma_free(w);
+}
+
+TEST(sscanf, wide) {
+  wchar_t w[16] = {};
+  wchar_t c = 0;
+  EXPECT_EQ(2, ouma_sscanf("h\xc3\xa9llo w\xc3\xb6rld", "%ls %lc", w, &c));
+  EXPECT_EQ(0, wcscmp(L"h\u00e9llo", w));
+  EXPECT_EQ(L'w', c);
+  // The width counts characters rather than bytes.
+  EXPECT_EQ(1, ouma_sscanf("h\xc3\xa9llo", "%3S", w));
+  EXPECT_EQ(0, wcscmp(L"h\u00e9l", w));
+  wchar_t two[2] = {};
+  EXPECT_EQ(1, ouma_sscanf("\xc3\xa9\xc3\xa0x", "%2C", two));
+  EXPECT_EQ(L'\u00e9', two[0]);
+  EXPECT_EQ(L'\u00e0', two[1]);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(0, ouma_sscanf("\xff", "%ls", w));
+  EXPECT_EQ(EILSEQ, __oumalibc_errno);
+}
+
+TEST(sscanf, positional) {
+  int a = 0, b = 0;
+  EXPECT_EQ(2, ouma_sscanf("12 34", "%2$d %1$d", &a, &b));
+  EXPECT_EQ(34, a);
+  EXPECT_EQ(12, b);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(-1, ouma_sscanf("12 34", "%1$d %d", &a, &b));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+}
+
+TEST(sscanf, failures) {
+  int a = 0, b = 0, n = 0;
+  EXPECT_EQ(EOF, ouma_sscanf("", "%d", &a));
+  EXPECT_EQ(EOF, ouma_sscanf("   ", " %d", &a));
+  EXPECT_EQ(EOF, ouma_sscanf("", "a"));
+  EXPECT_EQ(0, ouma_sscanf("", ""));
+  EXPECT_EQ(0, ouma_sscanf("", "%n", &n));
+  EXPECT_EQ(0, ouma_sscanf("x", "%d", &a));
+  EXPECT_EQ(0, ouma_sscanf("+-1", "%d", &a));
+  EXPECT_EQ(0, ouma_sscanf("b1", "a%d", &a));
+  // Suppressed conversions do not count, so the input ends before the first
+  // assignment.
+  EXPECT_EQ(EOF, ouma_sscanf("1", "%*d %d", &a));
+  EXPECT_EQ(1, ouma_sscanf("1 2", "%*d %d", &a));
+  EXPECT_EQ(2, a);
+  EXPECT_EQ(1, ouma_sscanf("5 %", "%d%%%d", &a, &b));
+}
+
+TEST(fscanf, fields) {
+  TempFile tmp;
+  ouma_FILE *f = ouma_fopen(tmp.path(), "w+");
+  ASSERT_NE(nullptr, f);
+  EXPECT_LE(0, ouma_fputs("point 12 -3.5\nnext", f));
+  EXPECT_EQ(0, ouma_fseek(f, 0, SEEK_SET));
+  char name[16] = {};
+  int x = 0;
+  double y = 0;
+  EXPECT_EQ(3, ouma_fscanf(f, "%15s %d %lf", name, &x, &y));
+  EXPECT_STREQ("point", name);
+  EXPECT_EQ(12, x);
+  EXPECT_EQ(-3.5, y);
+  // The byte that ended the last field is still there to read.
+  EXPECT_EQ('\n', ouma_fgetc(f));
+  EXPECT_EQ(0, ouma_fscanf(f, "%d", &x));
+  EXPECT_EQ(1, ouma_fscanf(f, "%s", name));
+  EXPECT_STREQ("next", name);
+  EXPECT_EQ(EOF, ouma_fscanf(f, "%s", name));
+  EXPECT_NE(0, ouma_feof(f));
+  EXPECT_EQ(0, ouma_fclose(f));
+}
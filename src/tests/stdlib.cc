@@ -1,7 +1,29 @@
 #include <gtest/gtest.h>
 #include <gmock/gmock.h>
 
+#include <cerrno>
+#include <cmath>
 #include <climits>
+#include <algorithm>
+#include <cstdint>
+#include <cstring>
+#include <cwchar>
+#include <iterator>
+#include <utility>
+#include <vector>
+
+struct mallinfo2 {
+  size_t arena;
+  size_t ordblks;
+  size_t smblks;
+  size_t hblks;
+  size_t hblkhd;
+  size_t usmblks;
+  size_t fsmblks;
+  size_t uordblks;
+  size_t fordblks;
+  size_t keepcost;
+};
 
 extern "C" {
   void *ouma_aligned_alloc(size_t alignment, size_t size);
@@ -10,9 +32,56 @@ extern "C" {
   void ouma_free_sized(void *ptr, size_t size);
   void ouma_free_aligned_sized(void *ptr, size_t alignment, size_t size);
   void *ouma_malloc(size_t size);
+  size_t ouma_malloc_usable_size(void *ptr);
+  int ouma_malloc_trim(size_t pad);
+  struct mallinfo2 ouma_mallinfo2(void);
+  void *ouma_memalign(size_t alignment, size_t size);
+  void *ouma_pvalloc(size_t size);
+  void *ouma_valloc(size_t size);
   void *ouma_realloc(void *ptr, size_t size);
+  void *ouma_reallocarray(void *ptr, size_t nmemb, size_t size);
   int ouma_posix_memalign(void **memptr, size_t alignment, size_t size);
+  long ouma_strtol(const char *src, char **endptr, int base);
   unsigned long ouma_strtoul(const char *src, char **endptr, int base);
+  long long ouma_strtoll(const char *src, char **endptr, int base);
+  unsigned long long ouma_strtoull(const char *src, char **endptr, int base);
+  intmax_t ouma_strtoimax(const char *src, char **endptr, int base);
+  uintmax_t ouma_strtoumax(const char *src, char **endptr, int base);
+  long ouma_strtol_l(const char *src, char **endptr, int base, void *locale);
+  int ouma_atoi(const char *src);
+  long ouma_atol(const char *src);
+  long long ouma_atoll(const char *src);
+  float ouma_strtof(const char *src, char **endptr);
+  double ouma_strtod(const char *src, char **endptr);
+  long double ouma_strtold(const char *src, char **endptr);
+  double ouma_strtod_l(const char *src, char **endptr, void *locale);
+  long double ouma_strtold_l(const char *src, char **endptr, void *locale);
+  double ouma_atof(const char *src);
+  void ouma_qsort(void *base, size_t nel, size_t width,
+    int (*compar)(const void *, const void *));
+  void ouma_qsort_r(void *base, size_t nel, size_t width,
+    int (*compar)(const void *, const void *, void *), void *arg);
+  void *ouma_bsearch(const void *key, const void *base, size_t nel,
+    size_t width, int (*compar)(const void *, const void *));
+  int ouma_mblen(const char *s, size_t n);
+  int ouma_mbtowc(wchar_t *pwc, const char *s, size_t n);
+  int ouma_wctomb(char *s, wchar_t wc);
+  size_t ouma_mbstowcs(wchar_t *dst, const char *src, size_t n);
+  size_t ouma_wcstombs(char *dst, const wchar_t *src, size_t n);
+  void *ouma_newlocale(int mask, const char *name, void *base);
+  void ouma_freelocale(void *locale);
+  void *ouma_uselocale(void *locale);
+  char *ouma_getenv(const char *name);
+  char *ouma_secure_getenv(const char *name);
+  int ouma_setenv(const char *name, const char *value, int overwrite);
+  int ouma_unsetenv(const char *name);
+  int ouma_putenv(char *string);
+  int ouma_clearenv(void);
+
+  extern char **ouma_environ;
+
+  struct LocaleStruct;
+  extern LocaleStruct OLOCALE_C;
 
   extern _Thread_local int __oumalibc_errno;
 }
@@ -151,6 +220,22 @@ TEST(malloc, realloc_overflow) {
   ouma_free(ptr);
 }
 
+TEST(reallocarray, example) {
+  int *ptr = (int *)ouma_reallocarray(nullptr, 10, sizeof(int));
+  ASSERT_TRUE(ptr != nullptr);
+  ptr[9] = 42;
+  ptr = (int *)ouma_reallocarray(ptr, 1000, sizeof(int));
+  ASSERT_TRUE(ptr != nullptr);
+  ASSERT_EQ(42, ptr[9]);
+  ouma_free(ptr);
+}
+
+TEST(reallocarray, overflow) {
+  __oumalibc_errno = 0;
+  ASSERT_EQ(nullptr, ouma_reallocarray(nullptr, SIZE_MAX / 2, 3));
+  ASSERT_EQ(ENOMEM, __oumalibc_errno);
+}
+
 TEST(calloc, example) {
   size_t alloc_len = 100;
   char *ptr = (char *)ouma_calloc(1, alloc_len);
@@ -202,6 +287,74 @@ TEST(realloc, example) {
   ouma_free(buf);
 }
 
+TEST(malloc, size_classes) {
+  for (size_t size = 1; size <= 20000; size += 37) {
+    unsigned char *ptr = (unsigned char *)ouma_malloc(size);
+    ASSERT_TRUE(ptr != nullptr);
+    ASSERT_EQ(0, (uintptr_t)ptr % alignof(max_align_t));
+    memset(ptr, 0x5a, size);
+    ASSERT_EQ(0x5a, ptr[size - 1]);
+    ouma_free(ptr);
+  }
+}
+
+TEST(malloc, large) {
+  size_t size = 8 * 1024 * 1024;
+  char *ptr = (char *)ouma_malloc(size);
+  ASSERT_TRUE(ptr != nullptr);
+  memset(ptr, 'A', size);
+  ptr = (char *)ouma_realloc(ptr, 2 * size);
+  ASSERT_TRUE(ptr != nullptr);
+  for (size_t i = 0; i < size; i += 4096) {
+    ASSERT_EQ('A', ptr[i]);
+  }
+  ouma_free(ptr);
+}
+
+// The malloc of the C library the test runs on grows the program break too,
+// which must not make either hand out memory of the other.
+TEST(malloc, host_malloc) {
+  std::vector<std::pair<unsigned char *, unsigned char *>> blocks;
+  for (size_t i = 0; i < 2000; i++) {
+    size_t size = 1 + (i * 7919) % 16384;
+    unsigned char *host = (unsigned char *)malloc(2 * size);
+    unsigned char *ouma = (unsigned char *)ouma_malloc(size);
+    ASSERT_TRUE(host != nullptr);
+    ASSERT_TRUE(ouma != nullptr);
+    memset(host, 0x67, 2 * size);
+    memset(ouma, i & 0xff, size);
+    blocks.push_back({host, ouma});
+  }
+  for (size_t i = 0; i < blocks.size(); i++) {
+    size_t size = 1 + (i * 7919) % 16384;
+    for (size_t j = 0; j < 2 * size; j++) {
+      ASSERT_EQ(0x67, blocks[i].first[j]);
+    }
+    for (size_t j = 0; j < size; j++) {
+      ASSERT_EQ(i & 0xff, blocks[i].second[j]);
+    }
+  }
+  for (auto &block : blocks) {
+    free(block.first);
+    ouma_free(block.second);
+  }
+}
+
+TEST(aligned_alloc, example) {
+  for (size_t i = 1; i <= 4 * 1024 * 1024; i *= 2) {
+    void *buf = ouma_aligned_alloc(i, 100);
+    ASSERT_TRUE(buf != nullptr);
+    ASSERT_EQ(0, (uintptr_t)buf % i);
+    ouma_free_aligned_sized(buf, i, 100);
+  }
+}
+
+TEST(aligned_alloc, bad) {
+  __oumalibc_errno = 0;
+  ASSERT_EQ(nullptr, ouma_aligned_alloc(3, 100));
+  ASSERT_EQ(EINVAL, __oumalibc_errno);
+}
+
 TEST(posix_memalign, bad) {
   for (size_t i = 0; i < sizeof(void *); ++i) {
     ASSERT_EQ(EINVAL, ouma_posix_memalign(nullptr, i, 1));
@@ -226,3 +379,455 @@ TEST(free, example) {
   ASSERT_NE(nullptr, buf);
   ouma_free(buf);
 }
+
+TEST(malloc_usable_size, example) {
+  ASSERT_EQ(0U, ouma_malloc_usable_size(nullptr));
+  for (size_t size : {1, 100, 4000, 100000}) {
+    void *ptr = ouma_malloc(size);
+    ASSERT_TRUE(ptr != nullptr);
+    ASSERT_LE(size, ouma_malloc_usable_size(ptr));
+    ouma_free(ptr);
+  }
+}
+
+TEST(memalign, example) {
+  for (size_t alignment = 1; alignment <= 65536; alignment <<= 1) {
+    void *ptr = ouma_memalign(alignment, 100);
+    ASSERT_TRUE(ptr != nullptr);
+    ASSERT_EQ(0U, reinterpret_cast<uintptr_t>(ptr) % alignment);
+    ouma_free(ptr);
+  }
+  __oumalibc_errno = 0;
+  ASSERT_EQ(nullptr, ouma_memalign(3, 100));
+  ASSERT_EQ(EINVAL, __oumalibc_errno);
+}
+
+TEST(valloc, example) {
+  void *ptr = ouma_valloc(100);
+  ASSERT_TRUE(ptr != nullptr);
+  ASSERT_EQ(0U, reinterpret_cast<uintptr_t>(ptr) % 4096);
+  ouma_free(ptr);
+
+  ptr = ouma_pvalloc(100);
+  ASSERT_TRUE(ptr != nullptr);
+  ASSERT_EQ(0U, reinterpret_cast<uintptr_t>(ptr) % 4096);
+  ASSERT_LE(4096U, ouma_malloc_usable_size(ptr));
+  ouma_free(ptr);
+}
+
+TEST(mallinfo2, example) {
+  struct mallinfo2 before = ouma_mallinfo2();
+  // Sizes above 4096 bypass the thread cache, whose objects count as used.
+  void *small = ouma_malloc(8000);
+  void *large = ouma_malloc(1 << 20);
+  struct mallinfo2 after = ouma_mallinfo2();
+  ASSERT_LE(before.uordblks + 8000, after.uordblks);
+  ASSERT_EQ(before.hblks + 1, after.hblks);
+  ASSERT_LE(before.hblkhd + (1 << 20), after.hblkhd);
+  ASSERT_EQ(after.arena, after.uordblks + after.fordblks);
+  ouma_free(small);
+  ouma_free(large);
+  ASSERT_EQ(before.hblks, ouma_mallinfo2().hblks);
+}
+
+TEST(malloc_trim, example) {
+  void *ptrs[1000];
+  for (void *&ptr : ptrs) {
+    ptr = ouma_malloc(256);
+    ASSERT_TRUE(ptr != nullptr);
+  }
+  for (void *ptr : ptrs) {
+    ouma_free(ptr);
+  }
+  ASSERT_EQ(1, ouma_malloc_trim(0));
+  ASSERT_EQ(0U, ouma_mallinfo2().keepcost);
+}
+
+TEST(strtol, endptr) {
+  char *end = nullptr;
+  const char *src = "  -42x";
+  EXPECT_EQ(-42, ouma_strtol(src, &end, 10));
+  EXPECT_EQ(src + 5, end);
+  src = "  +";
+  EXPECT_EQ(0, ouma_strtol(src, &end, 10));
+  EXPECT_EQ(src, end);
+  src = "0x1g";
+  EXPECT_EQ(1, ouma_strtol(src, &end, 0));
+  EXPECT_EQ(src + 3, end);
+  // A prefix without digits leaves just the "0".
+  src = "0xg";
+  EXPECT_EQ(0, ouma_strtol(src, &end, 16));
+  EXPECT_EQ(src + 1, end);
+  src = "09";
+  EXPECT_EQ(0, ouma_strtol(src, &end, 0));
+  EXPECT_EQ(src + 1, end);
+}
+
+TEST(strtol, bases) {
+  EXPECT_EQ(255, ouma_strtol("0XfF", nullptr, 0));
+  EXPECT_EQ(63, ouma_strtol("077", nullptr, 0));
+  EXPECT_EQ(35, ouma_strtol("z", nullptr, 36));
+  EXPECT_EQ(5, ouma_strtol("101", nullptr, 2));
+  __oumalibc_errno = 0;
+  EXPECT_EQ(0, ouma_strtol("1", nullptr, 37));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+}
+
+TEST(strtol, binary_prefix) {
+  char *end = nullptr;
+  const char *src = "0b101";
+  EXPECT_EQ(5, ouma_strtol(src, &end, 0));
+  EXPECT_EQ(src + 5, end);
+  EXPECT_EQ(-5, ouma_strtol("-0B101", nullptr, 2));
+  src = "0b2";
+  EXPECT_EQ(0, ouma_strtol(src, &end, 0));
+  EXPECT_EQ(src + 1, end);
+  EXPECT_EQ(0xb1, ouma_strtol("0b1", nullptr, 16));
+}
+
+TEST(strtol, range) {
+  char *end = nullptr;
+  const char *src = "9223372036854775808";
+  __oumalibc_errno = 0;
+  EXPECT_EQ(LONG_MAX, ouma_strtol(src, &end, 10));
+  EXPECT_EQ(ERANGE, __oumalibc_errno);
+  EXPECT_EQ(src + 19, end);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(LLONG_MIN, ouma_strtoll("-9223372036854775809", nullptr, 10));
+  EXPECT_EQ(ERANGE, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(INTMAX_MIN, ouma_strtoimax("-9223372036854775808", nullptr, 10));
+  EXPECT_EQ(0, __oumalibc_errno);
+}
+
+TEST(strtoul, range) {
+  __oumalibc_errno = 0;
+  EXPECT_EQ(ULONG_MAX, ouma_strtoul("-1", nullptr, 10));
+  EXPECT_EQ(ULLONG_MAX, ouma_strtoull("18446744073709551615", nullptr, 10));
+  EXPECT_EQ(1ULL, ouma_strtoull("-18446744073709551615", nullptr, 10));
+  EXPECT_EQ(0, __oumalibc_errno);
+  EXPECT_EQ(UINTMAX_MAX, ouma_strtoumax("18446744073709551616", nullptr, 10));
+  EXPECT_EQ(ERANGE, __oumalibc_errno);
+}
+
+TEST(strtol_l, example) {
+  char *end = nullptr;
+  const char *src = " 12 ";
+  EXPECT_EQ(12, ouma_strtol_l(src, &end, 10, nullptr));
+  EXPECT_EQ(src + 3, end);
+}
+
+TEST(atoi, example) {
+  EXPECT_EQ(12, ouma_atoi(" 12abc"));
+  EXPECT_EQ(-7, ouma_atol("-7"));
+  EXPECT_EQ(0, ouma_atoi("x"));
+  EXPECT_EQ(LLONG_MAX, ouma_atoll("9223372036854775807"));
+}
+
+TEST(strtod, endptr) {
+  char *end = nullptr;
+  const char *src = " \t-1.5e3x";
+  EXPECT_EQ(-1500.0, ouma_strtod(src, &end));
+  EXPECT_EQ(src + 8, end);
+  src = "1e+";
+  EXPECT_EQ(1.0, ouma_strtod(src, &end));
+  EXPECT_EQ(src + 1, end);
+  src = "0x";
+  EXPECT_EQ(0.0, ouma_strtod(src, &end));
+  EXPECT_EQ(src + 1, end);
+  src = "-.";
+  EXPECT_EQ(0.0, ouma_strtod(src, &end));
+  EXPECT_EQ(src, end);
+}
+
+TEST(strtod, rounding) {
+  EXPECT_EQ(0.1, ouma_strtod("0.1", nullptr));
+  EXPECT_EQ(0x1p53, ouma_strtod("9007199254740993", nullptr));
+  EXPECT_EQ(0x1.0000000000001p53,
+    ouma_strtod("9007199254740993.0000000000000000000001", nullptr));
+  EXPECT_EQ(0x1.fffffffffffffp1023,
+    ouma_strtod("1.7976931348623157e308", nullptr));
+  EXPECT_EQ(0x1p-1074, ouma_strtod("4.9406564584124654e-324", nullptr));
+  EXPECT_EQ(0x1.921fb6p1f, ouma_strtof("3.14159265358979", nullptr));
+  EXPECT_EQ(0x1.0000000000000002p0L,
+    ouma_strtold("1.00000000000000000011", nullptr));
+  EXPECT_EQ(0x1.8p-3, ouma_strtod("0x1.8p-3", nullptr));
+}
+
+TEST(strtod, special) {
+  char *end = nullptr;
+  const char *src = "-Infinity";
+  EXPECT_EQ(-HUGE_VAL, ouma_strtod(src, &end));
+  EXPECT_EQ(src + 9, end);
+  src = "nan(123)";
+  EXPECT_TRUE(std::isnan(ouma_strtod(src, &end)));
+  EXPECT_EQ(src + 8, end);
+  src = "nan(";
+  EXPECT_TRUE(std::isnan(ouma_strtold(src, &end)));
+  EXPECT_EQ(src + 3, end);
+}
+
+TEST(strtod, range) {
+  __oumalibc_errno = 0;
+  EXPECT_EQ(0x1p-1074, ouma_strtod("5e-324", nullptr));
+  EXPECT_EQ(ERANGE, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(0x1p-1022, ouma_strtod("0x1p-1022", nullptr));
+  EXPECT_EQ(0, __oumalibc_errno);
+  EXPECT_EQ(HUGE_VAL, ouma_strtod("1e309", nullptr));
+  EXPECT_EQ(ERANGE, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(-0.0, ouma_strtod("-1e-400", nullptr));
+  EXPECT_EQ(ERANGE, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(HUGE_VALF, ouma_strtof("3.5e38", nullptr));
+  EXPECT_EQ(ERANGE, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(0x1p16383L * 1.5L, ouma_strtold("0x3p16382", nullptr));
+  EXPECT_EQ(0, __oumalibc_errno);
+}
+
+TEST(strtod_l, example) {
+  char *end = nullptr;
+  const char *src = "2.5,";
+  EXPECT_EQ(2.5, ouma_strtod_l(src, &end, &OLOCALE_C));
+  EXPECT_EQ(src + 3, end);
+  EXPECT_EQ(2.5L, ouma_strtold_l(src, &end, &OLOCALE_C));
+  EXPECT_EQ(src + 3, end);
+}
+
+TEST(atof, example) {
+  EXPECT_EQ(3.25, ouma_atof("  3.25xyz"));
+  EXPECT_EQ(0.0, ouma_atof("x"));
+}
+
+static int compare_int(const void *a, const void *b) {
+  int x = *(const int *)a, y = *(const int *)b;
+  return (x > y) - (x < y);
+}
+
+TEST(qsort, example) {
+  int a[] = {5, -3, 9, 0, 5, 1, -7, 2, 8, 3, 3, 6, 4, -1, 7, 0, 11, 10, 2};
+  ouma_qsort(a, std::size(a), sizeof(int), compare_int);
+  EXPECT_TRUE(std::is_sorted(std::begin(a), std::end(a)));
+  ouma_qsort(nullptr, 0, sizeof(int), compare_int);
+}
+
+TEST(qsort, many) {
+  static int v[10000], expected[10000];
+  for (size_t i = 0; i < std::size(v); i++) {
+    v[i] = expected[i] = (i * 7919) % 1000;
+  }
+  std::sort(std::begin(expected), std::end(expected));
+  ouma_qsort(v, std::size(v), sizeof(int), compare_int);
+  EXPECT_TRUE(std::equal(std::begin(v), std::end(v), std::begin(expected)));
+}
+
+struct record {
+  char name[13];
+  double weight;
+};
+
+static int compare_record(const void *a, const void *b) {
+  return strcmp(((const record *)a)->name, ((const record *)b)->name);
+}
+
+TEST(qsort, wide_elements) {
+  record r[] = {{"pear", 1.5}, {"apple", 2}, {"quince", 3}, {"fig", 4}};
+  ouma_qsort(r, std::size(r), sizeof(record), compare_record);
+  EXPECT_STREQ("apple", r[0].name);
+  EXPECT_EQ(2, r[0].weight);
+  EXPECT_STREQ("fig", r[1].name);
+  EXPECT_STREQ("pear", r[2].name);
+  EXPECT_EQ(1.5, r[2].weight);
+  EXPECT_STREQ("quince", r[3].name);
+}
+
+static int compare_mod(const void *a, const void *b, void *arg) {
+  int m = *(int *)arg;
+  return *(const int *)a % m - *(const int *)b % m;
+}
+
+TEST(qsort_r, example) {
+  int a[] = {13, 21, 7, 10, 4};
+  int m = 10;
+  ouma_qsort_r(a, std::size(a), sizeof(int), compare_mod, &m);
+  int expected[] = {10, 21, 13, 4, 7};
+  EXPECT_TRUE(std::equal(std::begin(a), std::end(a), std::begin(expected)));
+}
+
+TEST(bsearch, example) {
+  int a[] = {1, 3, 5, 7, 9, 11};
+  int key = 7;
+  EXPECT_EQ(&a[3], ouma_bsearch(&key, a, std::size(a), sizeof(int), compare_int));
+  key = 1;
+  EXPECT_EQ(&a[0], ouma_bsearch(&key, a, std::size(a), sizeof(int), compare_int));
+  key = 11;
+  EXPECT_EQ(&a[5], ouma_bsearch(&key, a, std::size(a), sizeof(int), compare_int));
+  key = 4;
+  EXPECT_EQ(nullptr, ouma_bsearch(&key, a, std::size(a), sizeof(int), compare_int));
+  EXPECT_EQ(nullptr, ouma_bsearch(&key, a, 0, sizeof(int), compare_int));
+}
+
+TEST(getenv, example) {
+  char a[] = "A=1", b[] = "B=", ab[] = "AB=3";
+  char *env[] = {ab, a, b, nullptr};
+  ouma_environ = env;
+  EXPECT_STREQ("1", ouma_getenv("A"));
+  EXPECT_STREQ("", ouma_getenv("B"));
+  EXPECT_STREQ("3", ouma_getenv("AB"));
+  EXPECT_EQ(nullptr, ouma_getenv("C"));
+  EXPECT_EQ(nullptr, ouma_getenv("A=1"));
+  EXPECT_EQ(nullptr, ouma_getenv(""));
+  EXPECT_STREQ("1", ouma_secure_getenv("A"));
+  ouma_environ = nullptr;
+  EXPECT_EQ(nullptr, ouma_getenv("A"));
+}
+
+TEST(setenv, example) {
+  ouma_clearenv();
+  EXPECT_EQ(0, ouma_setenv("A", "1", 0));
+  EXPECT_EQ(0, ouma_setenv("A", "2", 0));
+  EXPECT_STREQ("1", ouma_getenv("A"));
+  EXPECT_EQ(0, ouma_setenv("A", "3", 1));
+  EXPECT_STREQ("3", ouma_getenv("A"));
+  EXPECT_EQ(0, ouma_setenv("B", "4", 1));
+  EXPECT_STREQ("A=3", ouma_environ[0]);
+  EXPECT_STREQ("B=4", ouma_environ[1]);
+  EXPECT_EQ(nullptr, ouma_environ[2]);
+  ouma_clearenv();
+}
+
+TEST(setenv, invalid) {
+  __oumalibc_errno = 0;
+  EXPECT_EQ(-1, ouma_setenv("", "1", 1));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(-1, ouma_setenv("A=B", "1", 1));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(-1, ouma_unsetenv(nullptr));
+  EXPECT_EQ(EINVAL, __oumalibc_errno);
+}
+
+TEST(setenv, own_array) {
+  char x[] = "X=1", y[] = "Y=2";
+  char *env[] = {x, y, nullptr};
+  ouma_environ = env;
+  EXPECT_EQ(0, ouma_setenv("Y", "5", 1));
+  EXPECT_NE(env, ouma_environ);
+  EXPECT_STREQ("Y=2", env[1]);
+  EXPECT_STREQ("X=1", ouma_environ[0]);
+  EXPECT_STREQ("5", ouma_getenv("Y"));
+  ouma_clearenv();
+}
+
+TEST(unsetenv, example) {
+  ouma_clearenv();
+  EXPECT_EQ(0, ouma_unsetenv("A"));
+  ouma_setenv("A", "1", 1);
+  ouma_setenv("B", "2", 1);
+  EXPECT_EQ(0, ouma_unsetenv("A"));
+  EXPECT_EQ(nullptr, ouma_getenv("A"));
+  EXPECT_STREQ("B=2", ouma_environ[0]);
+  EXPECT_EQ(nullptr, ouma_environ[1]);
+  ouma_clearenv();
+}
+
+TEST(putenv, example) {
+  static char s[] = "P=1";
+  ouma_clearenv();
+  EXPECT_EQ(0, ouma_putenv(s));
+  EXPECT_EQ(s, ouma_environ[0]);
+  s[2] = '2';
+  EXPECT_STREQ("2", ouma_getenv("P"));
+  ouma_setenv("P", "3", 1);
+  EXPECT_STREQ("P=2", s);
+  EXPECT_STREQ("3", ouma_getenv("P"));
+  EXPECT_EQ(0, ouma_putenv(s));
+  EXPECT_STREQ("2", ouma_getenv("P"));
+  char name[] = "P";
+  EXPECT_EQ(0, ouma_putenv(name));
+  EXPECT_EQ(nullptr, ouma_getenv("P"));
+  ouma_clearenv();
+}
+
+TEST(clearenv, example) {
+  ouma_setenv("A", "1", 1);
+  EXPECT_EQ(0, ouma_clearenv());
+  EXPECT_EQ(nullptr, ouma_environ);
+  EXPECT_EQ(nullptr, ouma_getenv("A"));
+}
+
+TEST(mblen, example) {
+  EXPECT_EQ(0, ouma_mblen(nullptr, 0));
+  EXPECT_EQ(1, ouma_mblen("a", 1));
+  EXPECT_EQ(0, ouma_mblen("", 1));
+  EXPECT_EQ(3, ouma_mblen("\u20ac", 3));
+  __oumalibc_errno = 0;
+  EXPECT_EQ(-1, ouma_mblen("\u20ac", 2));
+  EXPECT_EQ(EILSEQ, __oumalibc_errno);
+  EXPECT_EQ(-1, ouma_mblen("\xff", 1));
+  EXPECT_EQ(4, ouma_mblen("\U0001f600", 4));
+}
+
+TEST(mbtowc, example) {
+  wchar_t wc = 0;
+  EXPECT_EQ(0, ouma_mbtowc(nullptr, nullptr, 0));
+  EXPECT_EQ(2, ouma_mbtowc(&wc, "\u00e9t\u00e9", 4));
+  EXPECT_EQ(L'\u00e9', wc);
+  EXPECT_EQ(0, ouma_mbtowc(&wc, "", 1));
+  EXPECT_EQ(L'\0', wc);
+  EXPECT_EQ(1, ouma_mbtowc(nullptr, "x", 1));
+  EXPECT_EQ(-1, ouma_mbtowc(&wc, "\xc3", 1));
+  EXPECT_EQ(1, ouma_mbtowc(&wc, "y", 1));
+  EXPECT_EQ(L'y', wc);
+}
+
+TEST(wctomb, example) {
+  char buf[8] = {};
+  EXPECT_EQ(0, ouma_wctomb(nullptr, 0));
+  EXPECT_EQ(1, ouma_wctomb(buf, L'a'));
+  EXPECT_EQ('a', buf[0]);
+  EXPECT_EQ(3, ouma_wctomb(buf, L'\u20ac'));
+  EXPECT_EQ(0, memcmp(buf, "\u20ac", 3));
+  EXPECT_EQ(-1, ouma_wctomb(buf, 0xd800));
+}
+
+TEST(wctomb, stateful) {
+  void *locale = ouma_newlocale(0x3f, "C.ISO-2022-JP", nullptr);
+  ASSERT_NE(nullptr, locale);
+  void *old = ouma_uselocale(locale);
+  char buf[8] = {};
+  EXPECT_NE(0, ouma_wctomb(nullptr, 0));
+  EXPECT_NE(0, ouma_mbtowc(nullptr, nullptr, 0));
+  EXPECT_EQ(5, ouma_wctomb(buf, L'\u65e5'));
+  EXPECT_EQ(2, ouma_wctomb(buf, L'\u672c'));
+  EXPECT_EQ(4, ouma_wctomb(buf, L'\0'));
+  EXPECT_EQ(0, memcmp(buf, "\x1b(B", 4));
+  // A null string starts over in the initial state.
+  EXPECT_EQ(5, ouma_wctomb(buf, L'\u65e5'));
+  EXPECT_NE(0, ouma_wctomb(nullptr, 0));
+  EXPECT_EQ(5, ouma_wctomb(buf, L'\u65e5'));
+  ouma_uselocale(old);
+  ouma_freelocale(locale);
+}
+
+TEST(mbstowcs, example) {
+  wchar_t buf[8];
+  EXPECT_EQ(3U, ouma_mbstowcs(nullptr, "a\u00e9\u20ac", 0));
+  EXPECT_EQ(3U, ouma_mbstowcs(buf, "a\u00e9\u20ac", 8));
+  EXPECT_EQ(0, wcscmp(L"a\u00e9\u20ac", buf));
+  EXPECT_EQ(2U, ouma_mbstowcs(buf, "xyz", 2));
+  EXPECT_EQ(L'y', buf[1]);
+  EXPECT_EQ((size_t)-1, ouma_mbstowcs(buf, "a\xff", 8));
+}
+
+TEST(wcstombs, example) {
+  char buf[8];
+  EXPECT_EQ(6U, ouma_wcstombs(nullptr, L"a\u00e9\u20ac", 0));
+  EXPECT_EQ(6U, ouma_wcstombs(buf, L"a\u00e9\u20ac", 8));
+  EXPECT_STREQ("a\u00e9\u20ac", buf);
+  EXPECT_EQ(3U, ouma_wcstombs(buf, L"a\u00e9\u20ac", 5));
+  EXPECT_EQ((size_t)-1, ouma_wcstombs(buf, L"a\xd800", 8));
+}
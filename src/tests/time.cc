@@ -0,0 +1,302 @@
+#include <gtest/gtest.h>
+#include <gmock/gmock.h>
+
+#include <cstring>
+#include <ctime>
+
+extern "C" {
+  struct LocaleCtype {
+    const char *codeset;
+    ssize_t (*mbtoc32)(char32_t*, const char*, size_t, mbstate_t*);
+    ssize_t (*c32tomb)(char*, char32_t, mbstate_t*);
+    ssize_t (*unshift)(char*, mbstate_t*);
+    int mb_cur_max;
+    bool stateful;
+  };
+
+  struct LocaleNumeric {
+    const char *decimal_point;
+    const char *thousands_sep;
+    const char *grouping;
+  };
+
+  struct LocaleMonetary {
+    const char *int_curr_symbol;
+    const char *currency_symbol;
+    const char *mon_decimal_point;
+    const char *mon_thousands_sep;
+    const char *mon_grouping;
+    const char *positive_sign;
+    const char *negative_sign;
+    const char *crncystr;
+    char frac_digits[14];
+  };
+
+  struct LocaleTime {
+    const char *abday[7];
+    const char *day[7];
+    const char *abmon[12];
+    const char *mon[12];
+    const char *ab_alt_mon[12];
+    const char *alt_mon[12];
+    const char *am_pm[2];
+    const char *d_t_fmt;
+    const char *d_fmt;
+    const char *t_fmt;
+    const char *t_fmt_ampm;
+    const char *era;
+    const char *era_d_fmt;
+    const char *era_t_fmt;
+    const char *era_d_t_fmt;
+    const char *alt_digits;
+  };
+
+  struct LocaleStruct {
+    LocaleCtype ctype;
+    LocaleNumeric numeric;
+    LocaleMonetary monetary;
+    LocaleTime time;
+  };
+
+  typedef struct LocaleStruct *ouma_locale_t;
+
+  size_t ouma_strftime(char *__restrict, size_t, const char *__restrict,
+                       const struct tm *__restrict);
+  size_t ouma_strftime_l(char *__restrict, size_t, const char *__restrict,
+                         const struct tm *__restrict, ouma_locale_t);
+  char *ouma_strptime(const char *__restrict, const char *__restrict,
+                      struct tm *__restrict);
+  ouma_locale_t ouma_newlocale(int, const char *, ouma_locale_t);
+  void ouma_freelocale(ouma_locale_t);
+  ouma_locale_t ouma_uselocale(ouma_locale_t);
+}
+
+// Tuesday 5 March 2024, 14:07:09 at UTC+1.
+static struct tm example() {
+  struct tm tm = {};
+  tm.tm_year = 124;
+  tm.tm_mon = 2;
+  tm.tm_mday = 5;
+  tm.tm_hour = 14;
+  tm.tm_min = 7;
+  tm.tm_sec = 9;
+  tm.tm_wday = 2;
+  tm.tm_yday = 64;
+  tm.tm_isdst = 0;
+  tm.tm_gmtoff = 3600;
+  tm.tm_zone = "CET";
+  return tm;
+}
+
+#define EXPECT_STRFTIME(expected, format, tm)                       \
+  do {                                                              \
+    char buf[256];                                                  \
+    EXPECT_EQ(strlen(expected), ouma_strftime(buf, sizeof(buf),     \
+                                              format, &(tm)));      \
+    EXPECT_STREQ(expected, buf);                                    \
+  } while (0)
+
+TEST(strftime, example) {
+  struct tm tm = example();
+  EXPECT_STRFTIME("Tue Tuesday Mar March Mar", "%a %A %b %B %h", tm);
+  EXPECT_STRFTIME("Tue Mar  5 14:07:09 2024", "%c", tm);
+  EXPECT_STRFTIME("03/05/24 14:07:09", "%x %X", tm);
+  EXPECT_STRFTIME("03/05/24 2024-03-05 14:07:09", "%D %F %T", tm);
+  EXPECT_STRFTIME("02 PM 02:07:09 PM 14:07", "%I %p %r %R", tm);
+  EXPECT_STRFTIME("20 24 05  5 14 07 09", "%C %y %d %e %H %M %S", tm);
+  EXPECT_STRFTIME("065 2 2 09 10", "%j %u %w %U %W", tm);
+  EXPECT_STRFTIME("10 2024 24", "%V %G %g", tm);
+  EXPECT_STRFTIME("+0100 CET %", "%z %Z %%", tm);
+  EXPECT_STRFTIME("1709644029", "%s", tm);
+  EXPECT_STRFTIME("\n\t", "%n%t", tm);
+  EXPECT_STRFTIME("", "", tm);
+}
+
+TEST(strftime, week_based_year) {
+  struct tm tm = {};
+  // Friday 1 January 2021 belongs to the last week of 2020.
+  tm.tm_year = 121;
+  tm.tm_wday = 5;
+  tm.tm_yday = 0;
+  EXPECT_STRFTIME("2020 20 53 00 00", "%G %g %V %U %W", tm);
+
+  // Monday 30 December 2024 starts the first week of 2025.
+  tm.tm_year = 124;
+  tm.tm_wday = 1;
+  tm.tm_yday = 364;
+  EXPECT_STRFTIME("2025 25 01 52 53", "%G %g %V %U %W", tm);
+}
+
+TEST(strftime, flags) {
+  struct tm tm = example();
+  EXPECT_STRFTIME("2024 2024 +02024 00002024", "%Y %+Y %+6Y %08Y", tm);
+  EXPECT_STRFTIME("002024-03-05 +02024-03-05", "%12F %+12F", tm);
+  EXPECT_STRFTIME("   Tuesday 005     5", "%10A %3d %5e", tm);
+
+  tm.tm_year = 12345 - 1900;
+  EXPECT_STRFTIME("12345 +12345 +12345-03-05", "%Y %+Y %F", tm);
+
+  tm.tm_year = -1 - 1900;
+  EXPECT_STRFTIME("-0001 -1 99", "%05Y %C %y", tm);
+}
+
+TEST(strftime, modifiers) {
+  struct tm tm = example();
+  EXPECT_STRFTIME("Tue Mar  5 14:07:09 2024 20 03/05/24 14:07:09 24 2024",
+                  "%Ec %EC %Ex %EX %Ey %EY", tm);
+  EXPECT_STRFTIME("05  5 14 02 03 07 09 2 09 10 2 10 24 Mar March",
+                  "%Od %Oe %OH %OI %Om %OM %OS %Ou %OU %OV %Ow %OW %Oy "
+                  "%Ob %OB", tm);
+}
+
+TEST(strftime, unknown) {
+  struct tm tm = example();
+  EXPECT_STRFTIME("%Q %Ed 2024%", "%Q %Ed %Y%", tm);
+}
+
+TEST(strftime, overflow) {
+  struct tm tm = example();
+  char buf[8];
+  EXPECT_EQ(7, ouma_strftime(buf, 8, "%Y-%m", &tm));
+  EXPECT_STREQ("2024-03", buf);
+  EXPECT_EQ(0, ouma_strftime(buf, 7, "%Y-%m", &tm));
+  EXPECT_EQ(0, ouma_strftime(buf, 0, "", &tm));
+}
+
+TEST(strftime_l, era) {
+  ouma_locale_t locale = ouma_newlocale(0x3f, "C", nullptr);
+  ASSERT_NE(nullptr, locale);
+  locale->time.era = "+:1:2019/05/01:+*:Reiwa:%EC %Ey;"
+                     "+:1:1989/01/08:2019/04/30:Heisei:%EC %Ey";
+  locale->time.era_d_fmt = "%EY %m/%d";
+  locale->time.alt_digits = "zero;one;two;three;four;five;six;seven";
+  locale->time.alt_mon[2] = "Marzo";
+
+  struct tm tm = example();
+  char buf[64];
+  EXPECT_EQ(21, ouma_strftime_l(buf, sizeof(buf), "%Ex|%EC|%Ey", &tm,
+                                locale));
+  EXPECT_STREQ("Reiwa 6 03/05|Reiwa|6", buf);
+  EXPECT_EQ(22, ouma_strftime_l(buf, sizeof(buf), "%Od %OM %OB %Om", &tm,
+                                locale));
+  EXPECT_STREQ("five seven Marzo three", buf);
+
+  tm.tm_year = 95;
+  EXPECT_EQ(8, ouma_strftime_l(buf, sizeof(buf), "%EY", &tm, locale));
+  EXPECT_STREQ("Heisei 7", buf);
+
+  tm.tm_year = 88;
+  EXPECT_EQ(4, ouma_strftime_l(buf, sizeof(buf), "%EY", &tm, locale));
+  EXPECT_STREQ("1988", buf);
+
+  ouma_locale_t old = ouma_uselocale(locale);
+  struct tm parsed = {};
+  const char *s = "Heisei 7 five";
+  EXPECT_EQ(s + strlen(s), ouma_strptime(s, "%EY %Od", &parsed));
+  EXPECT_EQ(95, parsed.tm_year);
+  EXPECT_EQ(5, parsed.tm_mday);
+  ouma_uselocale(old);
+  ouma_freelocale(locale);
+}
+
+TEST(strptime, example) {
+  struct tm tm = {};
+  const char *s = "2024-03-05 14:07:09 rest";
+  EXPECT_EQ(s + 19, ouma_strptime(s, "%Y-%m-%d %H:%M:%S", &tm));
+  EXPECT_EQ(124, tm.tm_year);
+  EXPECT_EQ(2, tm.tm_mon);
+  EXPECT_EQ(5, tm.tm_mday);
+  EXPECT_EQ(14, tm.tm_hour);
+  EXPECT_EQ(7, tm.tm_min);
+  EXPECT_EQ(9, tm.tm_sec);
+  EXPECT_EQ(2, tm.tm_wday);
+  EXPECT_EQ(64, tm.tm_yday);
+}
+
+TEST(strptime, names) {
+  struct tm tm = {};
+  const char *s = "tuesday MARCH  5 2024";
+  EXPECT_EQ(s + strlen(s), ouma_strptime(s, "%A %B %e %Y", &tm));
+  EXPECT_EQ(2, tm.tm_mon);
+  EXPECT_EQ(2, tm.tm_wday);
+
+  s = "Tue Mar  5 14:07:09 2024";
+  EXPECT_EQ(s + strlen(s), ouma_strptime(s, "%c", &tm));
+  EXPECT_EQ(64, tm.tm_yday);
+}
+
+TEST(strptime, hours) {
+  struct tm tm = {};
+  const char *s = "02:07 pm";
+  EXPECT_EQ(s + strlen(s), ouma_strptime(s, "%I:%M %p", &tm));
+  EXPECT_EQ(14, tm.tm_hour);
+  s = "12 AM";
+  EXPECT_EQ(s + strlen(s), ouma_strptime(s, "%I %p", &tm));
+  EXPECT_EQ(0, tm.tm_hour);
+  s = "12:30:00 PM";
+  EXPECT_EQ(s + strlen(s), ouma_strptime(s, "%r", &tm));
+  EXPECT_EQ(12, tm.tm_hour);
+}
+
+TEST(strptime, years) {
+  struct tm tm = {};
+  EXPECT_NE(nullptr, ouma_strptime("68", "%y", &tm));
+  EXPECT_EQ(168, tm.tm_year);
+  EXPECT_NE(nullptr, ouma_strptime("69", "%y", &tm));
+  EXPECT_EQ(69, tm.tm_year);
+  EXPECT_NE(nullptr, ouma_strptime("19 05", "%C %y", &tm));
+  EXPECT_EQ(5, tm.tm_year);
+  EXPECT_NE(nullptr, ouma_strptime("-0044", "%Y", &tm));
+  EXPECT_EQ(-1944, tm.tm_year);
+}
+
+TEST(strptime, derived) {
+  struct tm tm = {};
+  EXPECT_NE(nullptr, ouma_strptime("2024 065", "%Y %j", &tm));
+  EXPECT_EQ(2, tm.tm_mon);
+  EXPECT_EQ(5, tm.tm_mday);
+  EXPECT_EQ(2, tm.tm_wday);
+
+  tm = {};
+  EXPECT_NE(nullptr, ouma_strptime("2024 09 2", "%Y %U %w", &tm));
+  EXPECT_EQ(64, tm.tm_yday);
+  EXPECT_EQ(5, tm.tm_mday);
+
+  tm = {};
+  EXPECT_NE(nullptr, ouma_strptime("2024 10 Tue", "%Y %W %a", &tm));
+  EXPECT_EQ(64, tm.tm_yday);
+}
+
+TEST(strptime, offsets) {
+  struct tm tm = {};
+  EXPECT_NE(nullptr, ouma_strptime("+0530", "%z", &tm));
+  EXPECT_EQ(19800, tm.tm_gmtoff);
+  EXPECT_NE(nullptr, ouma_strptime("-08:00", "%z", &tm));
+  EXPECT_EQ(-28800, tm.tm_gmtoff);
+  EXPECT_NE(nullptr, ouma_strptime("Z", "%z", &tm));
+  EXPECT_EQ(0, tm.tm_gmtoff);
+
+  EXPECT_NE(nullptr, ouma_strptime("86461", "%s", &tm));
+  EXPECT_EQ(70, tm.tm_year);
+  EXPECT_EQ(2, tm.tm_mday);
+  EXPECT_EQ(1, tm.tm_min);
+  EXPECT_EQ(1, tm.tm_sec);
+}
+
+TEST(strptime, spaces) {
+  struct tm tm = {};
+  const char *s = "  2024 \n 03%05";
+  EXPECT_EQ(s + strlen(s), ouma_strptime(s, " %Y%n%m%%%d", &tm));
+  EXPECT_EQ(5, tm.tm_mday);
+}
+
+TEST(strptime, invalid) {
+  struct tm tm = {};
+  EXPECT_EQ(nullptr, ouma_strptime("2024-13", "%Y-%m", &tm));
+  EXPECT_EQ(nullptr, ouma_strptime("abc", "%d", &tm));
+  EXPECT_EQ(nullptr, ouma_strptime("Tue", "%B", &tm));
+  EXPECT_EQ(nullptr, ouma_strptime("24:00", "%H:%M", &tm));
+  EXPECT_EQ(nullptr, ouma_strptime("2024", "%Y-", &tm));
+  EXPECT_EQ(nullptr, ouma_strptime("5", "%Q", &tm));
+  EXPECT_EQ(nullptr, ouma_strptime("5", "%Ed", &tm));
+}
@@ -5,10 +5,12 @@
 #include <wchar.h>
 #include <errno.h>
 #include <stdlib.h>
+#include <string.h>
 
 extern "C" {
   typedef struct {
     char16_t __surrogate;
+    unsigned short __shift;
     unsigned int __bytesleft;
     char32_t __partial;
     char32_t __lowerbound;
@@ -21,6 +23,12 @@ extern "C" {
   size_t ouma_mbrtoc32(char32_t *__restrict, const char *__restrict, size_t, ouma_mbstate_t *__restrict);
   int ouma_mbsinit(const ouma_mbstate_t *);
 
+  typedef struct LocaleStruct *ouma_locale_t;
+
+  ouma_locale_t ouma_newlocale(int, const char *, ouma_locale_t);
+  void ouma_freelocale(ouma_locale_t);
+  ouma_locale_t ouma_uselocale(ouma_locale_t);
+
   extern thread_local int __oumalibc_errno;
 }
 
@@ -101,3 +109,68 @@ TEST(mbrtoc32, unicode) {
             ouma_mbrtoc32(&c32, "Some text", 0, &mbs));
   ASSERT_NE(0, ouma_mbsinit(&mbs));
 }
+
+TEST(mbrtoc16, gb18030) {
+  ouma_locale_t locale = ouma_newlocale(0x3f, "C.GB18030", nullptr);
+  ASSERT_NE(nullptr, locale);
+  ouma_locale_t old = ouma_uselocale(locale);
+
+  ouma_mbstate_t mbs{};
+  char16_t c16;
+  // 中 split after its lead byte.
+  ASSERT_EQ((size_t)-2, ouma_mbrtoc16(&c16, "\xd6", 1, &mbs));
+  ASSERT_EQ(0, ouma_mbsinit(&mbs));
+  ASSERT_EQ(1, ouma_mbrtoc16(&c16, "\xd0", 1, &mbs));
+  ASSERT_EQ(u'\u4e2d', c16);
+  ASSERT_NE(0, ouma_mbsinit(&mbs));
+  // U+1F600 in four bytes, split in the middle.
+  ASSERT_EQ((size_t)-2, ouma_mbrtoc16(&c16, "\x94\x39", 2, &mbs));
+  ASSERT_EQ(0, ouma_mbsinit(&mbs));
+  ASSERT_EQ((size_t)-2, ouma_mbrtoc16(&c16, "\xfc", 1, &mbs));
+  ASSERT_EQ(1, ouma_mbrtoc16(&c16, "\x36", 1, &mbs));
+  ASSERT_EQ(0xd83d, c16);
+  ASSERT_EQ((size_t)-3, ouma_mbrtoc16(&c16, "", 1, &mbs));
+  ASSERT_EQ(0xde00, c16);
+  ASSERT_NE(0, ouma_mbsinit(&mbs));
+  // A two byte code with a four byte trail.
+  ASSERT_EQ((size_t)-1, ouma_mbrtoc16(&c16, "\x81\x30\x20", 3, &mbs));
+  ASSERT_EQ(EILSEQ, __oumalibc_errno);
+
+  char buf[MB_LEN_MAX];
+  mbs = {};
+  ASSERT_EQ(0, ouma_c16rtomb(buf, 0xd83d, &mbs));
+  ASSERT_EQ(4, ouma_c16rtomb(buf, 0xde00, &mbs));
+  ASSERT_EQ(0, memcmp("\x94\x39\xfc\x36", buf, 4));
+  ASSERT_EQ(4, ouma_c32rtomb(buf, U'\u0080', &mbs));
+  ASSERT_EQ(0, memcmp("\x81\x30\x81\x30", buf, 4));
+
+  ouma_uselocale(old);
+  ouma_freelocale(locale);
+}
+
+TEST(mbrtoc32, eucjp) {
+  ouma_locale_t locale = ouma_newlocale(0x3f, "C.eucJP", nullptr);
+  ASSERT_NE(nullptr, locale);
+  ouma_locale_t old = ouma_uselocale(locale);
+
+  ouma_mbstate_t mbs{};
+  char32_t c32;
+  // 丂 from JIS X 0212, one byte at a time.
+  ASSERT_EQ((size_t)-2, ouma_mbrtoc32(&c32, "\x8f", 1, &mbs));
+  ASSERT_EQ((size_t)-2, ouma_mbrtoc32(&c32, "\xb0", 1, &mbs));
+  ASSERT_EQ(1, ouma_mbrtoc32(&c32, "\xa1", 1, &mbs));
+  ASSERT_EQ(U'\u4e02', c32);
+  ASSERT_NE(0, ouma_mbsinit(&mbs));
+  ASSERT_EQ(2, ouma_mbrtoc32(&c32, "\x8e\xb1", 2, &mbs));
+  ASSERT_EQ(U'\uff71', c32);
+  ASSERT_EQ((size_t)-1, ouma_mbrtoc32(&c32, "\xa4\x41", 2, &mbs));
+  ASSERT_EQ((size_t)-1, ouma_mbrtoc32(&c32, "\x80", 1, &mbs));
+
+  char buf[MB_LEN_MAX];
+  ASSERT_EQ(3, ouma_c32rtomb(buf, U'\u4e02', &mbs));
+  ASSERT_EQ(0, memcmp("\x8f\xb0\xa1", buf, 3));
+  ASSERT_EQ((size_t)-1, ouma_c32rtomb(buf, U'\u0e01', &mbs));
+
+  ouma_uselocale(old);
+  ouma_freelocale(locale);
+}
@@ -1,6 +1,12 @@
 #include <gtest/gtest.h>
 #include <gmock/gmock.h>
 
+#include <algorithm>
+#include <cerrno>
+#include <climits>
+#include <cmath>
+#include <cstdint>
+#include <ctime>
 #include <iterator>
 
 #include <wchar.h>
@@ -11,6 +17,7 @@
 extern "C" {
   typedef struct {
     char16_t __surrogate;
+    unsigned short __shift;
     unsigned int __bytesleft;
     char32_t __partial;
     char32_t __lowerbound;
@@ -56,6 +63,17 @@ extern "C" {
   wint_t ouma_btowc(int);
   int ouma_wcwidth(wchar_t);
   int ouma_wcswidth(const wchar_t *, size_t);
+  long ouma_wcstol(const wchar_t *__restrict, wchar_t **__restrict, int);
+  unsigned long ouma_wcstoul(const wchar_t *__restrict, wchar_t **__restrict, int);
+  long long ouma_wcstoll(const wchar_t *__restrict, wchar_t **__restrict, int);
+  unsigned long long ouma_wcstoull(const wchar_t *__restrict, wchar_t **__restrict, int);
+  intmax_t ouma_wcstoimax(const wchar_t *__restrict, wchar_t **__restrict, int);
+  uintmax_t ouma_wcstoumax(const wchar_t *__restrict, wchar_t **__restrict, int);
+  float ouma_wcstof(const wchar_t *__restrict, wchar_t **__restrict);
+  double ouma_wcstod(const wchar_t *__restrict, wchar_t **__restrict);
+  long double ouma_wcstold(const wchar_t *__restrict, wchar_t **__restrict);
+  size_t ouma_wcsftime(wchar_t *__restrict, size_t, const wchar_t *__restrict,
+                       const struct tm *__restrict);
 
 /* When ctype support gets added
   int ouma_wcscasecmp(const wchar_t *, const wchar_t *);
@@ -64,6 +82,13 @@ extern "C" {
   int ouma_wcsncasecmp_l(const wchar_t *, const wchar_t *, size_t, locale_t);
 */
 
+  typedef struct LocaleStruct *ouma_locale_t;
+
+  ouma_locale_t ouma_newlocale(int, const char *, ouma_locale_t);
+  void ouma_freelocale(ouma_locale_t);
+  ouma_locale_t ouma_uselocale(ouma_locale_t);
+  size_t __oumalibc_get_mb_cur_max(void);
+
   extern _Thread_local int __oumalibc_errno;
   void ouma_free(void *ptr);
 }
@@ -423,6 +448,16 @@ TEST(wcsrtombs, unicode) {
   ASSERT_STREQ("ℕ ⊆ ℕ₀ ⊂ ℤ ⊂ ℚ ⊂ ℝ ⊂ ℂ", dst);
 }
 
+TEST(wcsrtombs, partial) {
+  // Conversion stops before a character that does not fit.
+  const wchar_t *src = L"aé€";
+  char dst[5];
+  ouma_mbstate_t mbs{};
+  ASSERT_EQ(3U, ouma_wcsrtombs(dst, &src, sizeof(dst), &mbs));
+  ASSERT_EQ(0, wcscmp(L"€", src));
+  ASSERT_EQ(0, memcmp("aé", dst, 3));
+}
+
 TEST(mbrlen, unicode) {
   // Parse all bytes of a Euro symbol separately.
   char euro[] = "€";
@@ -448,6 +483,240 @@ TEST(btowc, example) {
   }
 }
 
+TEST(btowc, single_byte) {
+  ouma_locale_t latin1 = ouma_newlocale(0x3f, "C.ISO-8859-1", nullptr);
+  ouma_locale_t koi8r = ouma_newlocale(0x3f, "C.KOI8-R", nullptr);
+  ASSERT_NE(nullptr, latin1);
+  ASSERT_NE(nullptr, koi8r);
+
+  ouma_locale_t old = ouma_uselocale(latin1);
+  for (int i = 0; i < 256; ++i) {
+    SCOPED_TRACE(i);
+    ASSERT_EQ(wint_t(i), ouma_btowc(i));
+    ASSERT_EQ(i, ouma_wctob(i));
+  }
+  ASSERT_EQ(EOF, ouma_wctob(0x100));
+  ASSERT_EQ(EOF, ouma_wctob(WEOF));
+
+  ouma_uselocale(koi8r);
+  ASSERT_EQ(wint_t(0x430), ouma_btowc(0xc1));
+  ASSERT_EQ(wint_t(0x2550), ouma_btowc(0xa0));
+  ASSERT_EQ(0xe1, ouma_wctob(0x410));
+  ASSERT_EQ(EOF, ouma_wctob(0xe9));
+  ASSERT_EQ(wint_t('A'), ouma_btowc('A'));
+
+  wchar_t buf[8];
+  const char *src = "\xf0\xd2\xc9\xd7\xc5\xd4";
+  ouma_mbstate_t mbs{};
+  ASSERT_EQ(6U, ouma_mbsrtowcs(buf, &src, 8, &mbs));
+  ASSERT_EQ(0, wmemcmp(L"\u041f\u0440\u0438\u0432\u0435\u0442", buf, 6));
+  char out[8];
+  const wchar_t *wsrc = buf;
+  ASSERT_EQ(6U, ouma_wcsrtombs(out, &wsrc, sizeof(out), &mbs));
+  ASSERT_EQ(0, memcmp("\xf0\xd2\xc9\xd7\xc5\xd4", out, 7));
+
+  ouma_uselocale(old);
+  ouma_freelocale(latin1);
+  ouma_freelocale(koi8r);
+}
+
+TEST(mbsnrtowcs, multibyte) {
+  struct {
+    const char *name;
+    size_t mb_cur_max;
+    const char *mbs;
+    const wchar_t *wcs;
+  } codesets[] = {
+    {"C.EUC-JP", 3, "\xc6\xfc\xcb\xdc\xb8\xec\x8e\xb1\x8f\xb0\xa1",
+     L"\u65e5\u672c\u8a9e\uff71\u4e02"},
+    {"C.SJIS", 2, "\x93\xfa\x96\x7b\x8c\xea\xb1",
+     L"\u65e5\u672c\u8a9e\uff71"},
+    {"C.GB18030", 4, "\xd6\xd0\xce\xc4\xa2\xe3\x94\x39\xfc\x36",
+     L"\u4e2d\u6587\u20ac\U0001f600"},
+    {"C.Big5", 2, "\xa4\xa4\xa4\xe5", L"\u4e2d\u6587"},
+    {"C.EUC-KR", 2, "\xc7\xd1\xb1\xb9\xbe\xee",
+     L"\ud55c\uad6d\uc5b4"},
+  };
+  for (auto &codeset : codesets) {
+    SCOPED_TRACE(codeset.name);
+    ouma_locale_t locale = ouma_newlocale(0x3f, codeset.name, nullptr);
+    ASSERT_NE(nullptr, locale);
+    ouma_locale_t old = ouma_uselocale(locale);
+    ASSERT_EQ(codeset.mb_cur_max, __oumalibc_get_mb_cur_max());
+
+    // Feed the input in every possible piece size.
+    size_t len = strlen(codeset.mbs), wlen = wcslen(codeset.wcs);
+    for (size_t piece = 1; piece <= len; ++piece) {
+      SCOPED_TRACE(piece);
+      wchar_t out[8];
+      size_t n = 0;
+      ouma_mbstate_t mbs{};
+      const char *src = codeset.mbs;
+      while (src != nullptr && n < wlen) {
+        const char *end = codeset.mbs + len + 1;
+        size_t take = std::min(piece, size_t(end - src));
+        size_t r = ouma_mbsnrtowcs(out + n, &src, take, 8 - n, &mbs);
+        ASSERT_NE((size_t)-1, r);
+        n += r;
+      }
+      ASSERT_EQ(wlen, n);
+      ASSERT_EQ(0, wmemcmp(codeset.wcs, out, wlen));
+    }
+
+    char out[16];
+    const wchar_t *wsrc = codeset.wcs;
+    ouma_mbstate_t mbs{};
+    ASSERT_EQ(len, ouma_wcsnrtombs(out, &wsrc, wlen + 1, sizeof(out), &mbs));
+    ASSERT_EQ(nullptr, wsrc);
+    ASSERT_STREQ(codeset.mbs, out);
+
+    ouma_uselocale(old);
+    ouma_freelocale(locale);
+  }
+}
+
+TEST(wcrtomb, iso2022jp) {
+  ouma_locale_t locale = ouma_newlocale(0x3f, "C.ISO-2022-JP", nullptr);
+  ASSERT_NE(nullptr, locale);
+  ouma_locale_t old = ouma_uselocale(locale);
+  ASSERT_EQ(5, __oumalibc_get_mb_cur_max());
+
+  char buf[MB_LEN_MAX];
+  ouma_mbstate_t mbs{};
+  ASSERT_EQ(1, ouma_wcrtomb(buf, L'A', &mbs));
+  ASSERT_NE(0, ouma_mbsinit(&mbs));
+  ASSERT_EQ(5, ouma_wcrtomb(buf, L'日', &mbs));
+  ASSERT_EQ(0, memcmp("\x1b$BF|", buf, 5));
+  ASSERT_EQ(0, ouma_mbsinit(&mbs));
+  ASSERT_EQ(2, ouma_wcrtomb(buf, L'本', &mbs));
+  ASSERT_EQ(0, memcmp("K\\", buf, 2));
+  ASSERT_EQ((size_t)-1, ouma_wcrtomb(buf, L'é', &mbs));
+  ASSERT_EQ(EILSEQ, __oumalibc_errno);
+
+  // Back to ASCII before the null character.
+  ouma_mbstate_t copy = mbs;
+  ASSERT_EQ(4, ouma_wcrtomb(nullptr, L'x', &copy));
+  ASSERT_NE(0, ouma_mbsinit(&copy));
+  ASSERT_EQ(4, ouma_wcrtomb(buf, L'\0', &mbs));
+  ASSERT_EQ(0, memcmp("\x1b(B", buf, 4));
+  ASSERT_NE(0, ouma_mbsinit(&mbs));
+  ASSERT_EQ(4, ouma_wcrtomb(buf, L'¥', &mbs));
+  ASSERT_EQ(0, memcmp("\x1b(J\\", buf, 4));
+
+  const wchar_t *wsrc = L"日本語 ok";
+  char out[32];
+  mbs = {};
+  ASSERT_EQ(15, ouma_wcsrtombs(nullptr, &wsrc, 0, &mbs));
+  ASSERT_EQ(15, ouma_wcsrtombs(out, &wsrc, sizeof(out), &mbs));
+  ASSERT_EQ(nullptr, wsrc);
+  ASSERT_STREQ("\x1b$BF|K\\8l\x1b(B ok", out);
+  ASSERT_NE(0, ouma_mbsinit(&mbs));
+
+  // Stopping short of a character leaves the state as it was.
+  wsrc = L"A日";
+  ASSERT_EQ(1, ouma_wcsrtombs(out, &wsrc, 5, &mbs));
+  ASSERT_NE(0, ouma_mbsinit(&mbs));
+
+  ouma_uselocale(old);
+  ouma_freelocale(locale);
+}
+
+TEST(mbrtowc, iso2022jp) {
+  ouma_locale_t locale = ouma_newlocale(0x3f, "C.ISO-2022-JP", nullptr);
+  ASSERT_NE(nullptr, locale);
+  ouma_locale_t old = ouma_uselocale(locale);
+
+  // Escape sequences go with the character after them.
+  const char *mbs = "\x1b$BF|K\\\x1b(BA";
+  const wchar_t expected[] = {L'日', L'本', L'A'};
+  ouma_mbstate_t state{};
+  wchar_t wc;
+  size_t n = 0;
+  for (const char *p = mbs; *p != '\0'; ++p) {
+    size_t r = ouma_mbrtowc(&wc, p, 1, &state);
+    if (r == (size_t)-2) {
+      continue;
+    }
+    ASSERT_EQ(1, r);
+    ASSERT_EQ(expected[n++], wc);
+    ASSERT_EQ(n == 3, ouma_mbsinit(&state) != 0);
+  }
+  ASSERT_EQ(3, n);
+
+  state = {};
+  ASSERT_EQ(4, ouma_mbrtowc(&wc, "\x1b(J\\", 4, &state));
+  ASSERT_EQ(L'¥', wc);
+  ASSERT_EQ(0, ouma_mbsinit(&state));
+  ASSERT_EQ(0, ouma_mbrtowc(&wc, "", 1, &state));
+  ASSERT_NE(0, ouma_mbsinit(&state));
+  ASSERT_EQ((size_t)-1, ouma_mbrtowc(&wc, "\x1b(Z", 3, &state));
+  ASSERT_EQ(EILSEQ, __oumalibc_errno);
+
+  ouma_uselocale(old);
+  ouma_freelocale(locale);
+}
+
+TEST(wcrtomb, utf7) {
+  ouma_locale_t locale = ouma_newlocale(0x3f, "C.UTF-7", nullptr);
+  ASSERT_NE(nullptr, locale);
+  ouma_locale_t old = ouma_uselocale(locale);
+
+  char buf[MB_LEN_MAX];
+  ouma_mbstate_t mbs{};
+  ASSERT_EQ(1, ouma_wcrtomb(buf, L'A', &mbs));
+  ASSERT_EQ(3, ouma_wcrtomb(buf, L'日', &mbs));
+  ASSERT_EQ(0, memcmp("+Ze", buf, 3));
+  ASSERT_EQ(0, ouma_mbsinit(&mbs));
+  ASSERT_EQ(3, ouma_wcrtomb(buf, L'+', &mbs));
+  ASSERT_EQ(0, memcmp("UAK", buf, 3));
+  ASSERT_EQ(3, ouma_wcrtomb(nullptr, L'x', &mbs));
+  ASSERT_NE(0, ouma_mbsinit(&mbs));
+  ASSERT_EQ(2, ouma_wcrtomb(buf, L'+', &mbs));
+  ASSERT_EQ(0, memcmp("+-", buf, 2));
+  ASSERT_EQ((size_t)-1, ouma_wcrtomb(buf, 0xd83d, &mbs));
+
+  const wchar_t *wsrc = L"A日+\U0001f600.";
+  char out[32];
+  mbs = {};
+  ASSERT_EQ(15, ouma_wcsrtombs(out, &wsrc, sizeof(out), &mbs));
+  ASSERT_STREQ("A+ZeUAK9g93gA-.", out);
+
+  ouma_uselocale(old);
+  ouma_freelocale(locale);
+}
+
+TEST(mbrtowc, utf7) {
+  ouma_locale_t locale = ouma_newlocale(0x3f, "C.UTF-7", nullptr);
+  ASSERT_NE(nullptr, locale);
+  ouma_locale_t old = ouma_uselocale(locale);
+
+  const char *src = "A+ZeUAKw-+2D3eAA-+-!";
+  wchar_t out[8];
+  ouma_mbstate_t mbs{};
+  ASSERT_EQ(6, ouma_mbsrtowcs(out, &src, 8, &mbs));
+  ASSERT_EQ(nullptr, src);
+  ASSERT_EQ(0, wmemcmp(L"A日+\U0001f600+!", out, 7));
+
+  wchar_t wc;
+  mbs = {};
+  ASSERT_EQ((size_t)-2, ouma_mbrtowc(&wc, "+", 1, &mbs));
+  ASSERT_EQ(0, ouma_mbsinit(&mbs));
+  ASSERT_EQ(1, ouma_mbrtowc(&wc, "-A", 2, &mbs));
+  ASSERT_EQ(L'+', wc);
+  ASSERT_NE(0, ouma_mbsinit(&mbs));
+
+  // A lone surrogate, and bits left over.
+  mbs = {};
+  ASSERT_EQ((size_t)-1, ouma_mbrtowc(&wc, "+2D0-", 5, &mbs));
+  mbs = {};
+  ASSERT_EQ(4, ouma_mbrtowc(&wc, "+AGEB-", 6, &mbs));
+  ASSERT_EQ(L'a', wc);
+  ASSERT_EQ((size_t)-1, ouma_mbrtowc(&wc, "B-", 2, &mbs));
+
+  ouma_uselocale(old);
+  ouma_freelocale(locale);
+}
+
 TEST(wcwidth, korean) {
   EXPECT_EQ(2, ouma_wcwidth(L'ㅜ'));
   EXPECT_EQ(2, ouma_wcwidth(L'ㅋ'));
@@ -486,3 +755,75 @@ TEST(wcswidth, zalgo) {
   const wchar_t str[] = L"T̫̺̳o̬̜ ì̬͎̲̟nv̖̗̻̣̹̕o͖̗̠̜̤k͍͚̹͖̼e̦̗̪͍̪͍ ̬ͅt̕h̠͙̮͕͓e̱̜̗͙̭ ̥͔̫͙̪͍̣͝ḥi̼̦͈̼v҉̩̟͚̞͎e͈̟̻͙̦̤-m̷̘̝̱í͚̞̦̳n̝̲̯̙̮͞d̴̺̦͕̫ ̗̭̘͎͖r̞͎̜̜͖͎̫͢ep͇r̝̯̝͖͉͎̺e̴s̥e̵̖̳͉͍̩̗n̢͓̪͕̜̰̠̦t̺̞̰i͟n҉̮̦̖̟g̮͍̱̻͍̜̳ ̳c̖̮̙̣̰̠̩h̷̗͍̖͙̭͇͈a̧͎̯̹̲̺̫ó̭̞̜̣̯͕s̶̤̮̩̘.̨̻̪̖͔";
   ASSERT_EQ(43, ouma_wcswidth(str, std::size(str)));
 }
+
+TEST(wcstol, endptr) {
+  wchar_t *end = nullptr;
+  const wchar_t *src = L"\u3000\u2003-0x1fz";
+  EXPECT_EQ(-31, ouma_wcstol(src, &end, 0));
+  EXPECT_EQ(src + 7, end);
+  src = L" +";
+  EXPECT_EQ(0, ouma_wcstol(src, &end, 10));
+  EXPECT_EQ(src, end);
+  src = L"12\uff13";
+  EXPECT_EQ(12, ouma_wcstol(src, &end, 10));
+  EXPECT_EQ(src + 2, end);
+  src = L"0b101";
+  EXPECT_EQ(5, ouma_wcstol(src, &end, 2));
+  EXPECT_EQ(src + 5, end);
+}
+
+TEST(wcstol, range) {
+  __oumalibc_errno = 0;
+  EXPECT_EQ(LONG_MAX, ouma_wcstol(L"9223372036854775808", nullptr, 10));
+  EXPECT_EQ(ERANGE, __oumalibc_errno);
+  __oumalibc_errno = 0;
+  EXPECT_EQ(LLONG_MIN, ouma_wcstoll(L"-9223372036854775808", nullptr, 10));
+  EXPECT_EQ(INTMAX_MAX, ouma_wcstoimax(L"7fffffffffffffff", nullptr, 16));
+  EXPECT_EQ(ULONG_MAX, ouma_wcstoul(L"-1", nullptr, 10));
+  EXPECT_EQ(ULLONG_MAX, ouma_wcstoull(L"3w5e11264sgsf", nullptr, 36));
+  EXPECT_EQ(0, __oumalibc_errno);
+  EXPECT_EQ(UINTMAX_MAX, ouma_wcstoumax(L"18446744073709551616", nullptr, 0));
+  EXPECT_EQ(ERANGE, __oumalibc_errno);
+}
+
+TEST(wcstod, endptr) {
+  wchar_t *end = nullptr;
+  const wchar_t *src = L"\u2028 -1.5e3 x";
+  EXPECT_EQ(-1500.0, ouma_wcstod(src, &end));
+  EXPECT_EQ(src + 8, end);
+  src = L"1e\u00b2";
+  EXPECT_EQ(1.0, ouma_wcstod(src, &end));
+  EXPECT_EQ(src + 1, end);
+  src = L" .";
+  EXPECT_EQ(0.0, ouma_wcstod(src, &end));
+  EXPECT_EQ(src, end);
+  src = L"-infinity";
+  EXPECT_EQ(-HUGE_VAL, ouma_wcstod(src, &end));
+  EXPECT_EQ(src + 9, end);
+  src = L"nan(x1)";
+  EXPECT_TRUE(std::isnan(ouma_wcstod(src, &end)));
+  EXPECT_EQ(src + 7, end);
+}
+
+TEST(wcstod, values) {
+  EXPECT_EQ(0.1, ouma_wcstod(L"0.1", nullptr));
+  EXPECT_EQ(0.1f, ouma_wcstof(L"0.1", nullptr));
+  EXPECT_EQ(0.1L, ouma_wcstold(L"0.1", nullptr));
+  EXPECT_EQ(0x1.8p-3, ouma_wcstod(L"0x1.8p-3", nullptr));
+  __oumalibc_errno = 0;
+  EXPECT_EQ(HUGE_VALF, ouma_wcstof(L"1e39", nullptr));
+  EXPECT_EQ(ERANGE, __oumalibc_errno);
+}
+
+TEST(wcsftime, example) {
+  struct tm tm = {};
+  tm.tm_year = 124;
+  tm.tm_mon = 2;
+  tm.tm_mday = 5;
+  tm.tm_wday = 2;
+  wchar_t buf[32];
+  EXPECT_EQ(18U, ouma_wcsftime(buf, 32, L"%A %d %B \u00e9", &tm));
+  EXPECT_STREQ(L"Tuesday 05 March \u00e9", buf);
+  EXPECT_EQ(0U, ouma_wcsftime(buf, 18, L"%A %d %B \u00e9", &tm));
+  EXPECT_EQ(0U, ouma_wcsftime(buf, 32, L"", &tm));
+}
@@ -25,6 +25,7 @@ pub type ptrdiff_t = isize;
 pub type intptr_t = isize;
 pub type uintptr_t = usize;
 pub type ssize_t = isize;
+pub type time_t = i64;
 
 // Platform dependent C language types
 pub use crate::arch::types::{c_char, c_long, c_ulong, max_align_t, wchar_t};
@@ -40,6 +41,8 @@ pub type char32_t = u32;
 #[repr(C)]
 pub struct MBStateStruct {
   pub surrogate: char16_t,
+  // Shift state of a stateful encoding, 0 being the initial one.
+  pub shift: c_ushort,
   pub bytesleft: c_uint,
   pub partial: char32_t,
   pub lowerbound: char32_t
@@ -47,22 +50,45 @@ pub struct MBStateStruct {
 
 impl MBStateStruct {
   pub const fn new() -> Self {
-    Self { bytesleft: 0, partial: 0, lowerbound: 0, surrogate: 0 }
+    Self { surrogate: 0, shift: 0, bytesleft: 0, partial: 0, lowerbound: 0 }
   }
 }
 
 pub type mbstate_t = MBStateStruct;
 
+pub type FILE = crate::support::stream::FileStruct;
+
 // POSIX types
-#[derive(Clone, Copy, Eq, PartialEq)]
+pub type off_t = i64;
+pub type iconv_t = *mut crate::std::iconv::IconvStruct;
+
+#[derive(Clone, Copy)]
 #[repr(C)]
 pub struct LocaleStruct {
-  pub ctype: crate::support::locale::ctype::LocaleCtype
+  pub ctype: crate::support::locale::ctype::LocaleCtype,
+  pub numeric: crate::support::locale::numeric::LocaleNumeric,
+  pub monetary: crate::support::locale::monetary::LocaleMonetary,
+  pub time: crate::support::locale::time::LocaleTime,
+  pub messages: crate::support::locale::messages::LocaleMessages,
+  pub collate: crate::support::locale::collate::LocaleCollate,
+  // The classes and case maps of a compiled locale, if it has them.
+  pub classes: crate::support::locale::ctype::classes::LocaleClasses,
+  // Name of each category, which setlocale returns.
+  pub names: crate::support::locale::Names
 }
 
 impl LocaleStruct {
   pub fn new() -> Self {
-    Self { ctype: crate::support::locale::ctype::LocaleCtype::new() }
+    Self {
+      ctype: crate::support::locale::ctype::LocaleCtype::new(),
+      numeric: crate::support::locale::numeric::LOCALE_NUMERIC_C,
+      monetary: crate::support::locale::monetary::LOCALE_MONETARY_C,
+      time: crate::support::locale::time::LOCALE_TIME_C,
+      messages: crate::support::locale::messages::LOCALE_MESSAGES_C,
+      collate: crate::support::locale::collate::LOCALE_COLLATE_C,
+      classes: crate::support::locale::ctype::classes::LOCALE_CLASSES_UNICODE,
+      names: crate::support::locale::names(b"C")
+    }
   }
 
   pub fn as_ptr(&mut self) -> locale_t {
@@ -77,5 +103,6 @@ impl Default for LocaleStruct {
 }
 
 pub type locale_t = *mut LocaleStruct;
+pub type nl_item = c_int;
 
 // Linux-specific types
@@ -8,6 +8,7 @@ fi
 BUILD_CFLAGS="-std=c++20"
 BUILD_LDFLAGS=""
 BUILD_RUSTFLAGS=""
+LOCALEDEF_MANIFEST="$PWD/tools/localedef/Cargo.toml"
 
 # TODO: add instrumented libc++ and gtest for MSan
 
@@ -34,9 +35,12 @@ for sanitizers in none address leak; do
       ;;
   esac
 
+  # localedef runs on the host, for the tests to compile locales with. It is
+  # built from outside the tree, clear of .cargo/config.toml and its build-std.
+  (cd / && cargo build --manifest-path "$LOCALEDEF_MANIFEST")
   RUSTFLAGS="$BUILD_RUSTFLAGS $SANITIZER_RUSTFLAGS" \
   cargo build --target $TARGET
-  for test in ctype string uchar wchar wctype; do
+  for test in ctype iconv langinfo locale localedef stdio stdlib string time uchar wchar wctype; do
     echo "Testing: $test"
     clang++ $BUILD_CFLAGS $SANITIZER_CFLAGS $BUILD_LDFLAGS \
       -lgtest -lgtest_main src/tests/${test}.cc \
@@ -45,5 +49,19 @@ for sanitizers in none address leak; do
   done
 done
 
+# The hardened allocator, with guard pages, ends the process on the errors it
+# detects, which its death tests check for. The malloc tests run on it too.
+echo "Building libc with the hardened allocator"
+rm -rf a.out target
+RUSTFLAGS="$BUILD_RUSTFLAGS" \
+cargo build --target $TARGET --features malloc-guard-pages
+for test in hardened stdlib; do
+  echo "Testing: $test"
+  clang++ $BUILD_CFLAGS $BUILD_LDFLAGS \
+    -lgtest -lgtest_main src/tests/${test}.cc \
+    "$PWD/target/$TARGET/debug/libc.a"
+  ./a.out
+done
+
 exit 0
 
@@ -0,0 +1,86 @@
+#!/usr/bin/env python3
+
+# Prints src/support/locale/ctype/charsets.rs, the tables of the single-byte
+# codesets, from the codecs Python ships with.
+#
+#   tools/generate-charsets.py > src/support/locale/ctype/charsets.rs
+
+import codecs
+
+# Name as nl_langinfo(CODESET) gives it, Python codec and further names,
+# which the codec registry matches ignoring case and punctuation.
+CHARSETS = [
+  ('ISO-8859-1', 'iso8859_1', ['latin1', 'l1']),
+  ('ISO-8859-2', 'iso8859_2', ['latin2', 'l2']),
+  ('ISO-8859-3', 'iso8859_3', ['latin3', 'l3']),
+  ('ISO-8859-4', 'iso8859_4', ['latin4', 'l4']),
+  ('ISO-8859-5', 'iso8859_5', ['cyrillic']),
+  ('ISO-8859-6', 'iso8859_6', ['arabic']),
+  ('ISO-8859-7', 'iso8859_7', ['greek']),
+  ('ISO-8859-8', 'iso8859_8', ['hebrew']),
+  ('ISO-8859-9', 'iso8859_9', ['latin5', 'l5']),
+  ('ISO-8859-10', 'iso8859_10', ['latin6', 'l6']),
+  ('ISO-8859-11', 'iso8859_11', []),
+  ('ISO-8859-13', 'iso8859_13', ['latin7', 'l7']),
+  ('ISO-8859-14', 'iso8859_14', ['latin8', 'l8']),
+  ('ISO-8859-15', 'iso8859_15', ['latin9', 'l9']),
+  ('ISO-8859-16', 'iso8859_16', ['latin10', 'l10']),
+  ('KOI8-R', 'koi8_r', []),
+  ('KOI8-U', 'koi8_u', []),
+  ('CP1250', 'cp1250', ['windows1250']),
+  ('CP1251', 'cp1251', ['windows1251']),
+  ('CP1252', 'cp1252', ['windows1252']),
+  ('CP1253', 'cp1253', ['windows1253']),
+  ('CP1254', 'cp1254', ['windows1254']),
+  ('CP1255', 'cp1255', ['windows1255']),
+  ('CP1256', 'cp1256', ['windows1256']),
+  ('CP1257', 'cp1257', ['windows1257']),
+  ('CP1258', 'cp1258', ['windows1258']),
+]
+
+
+def key(name):
+  return ''.join(c for c in name.lower() if c.isalnum())
+
+
+print('// Generated by tools/generate-charsets.py.')
+print()
+print('use core::ffi::CStr;')
+print()
+print('pub const COUNT: usize = %d;' % len(CHARSETS))
+print()
+print('pub const NAMES: [&CStr; COUNT] = [')
+print(',\n'.join('  c"%s"' % name for name, _, _ in CHARSETS))
+print('];')
+print()
+print('// The names of each codeset, lowercase and without punctuation.')
+print('pub const KEYS: [&[&[u8]]; COUNT] = [')
+lines = []
+for name, _, aliases in CHARSETS:
+  keys = [key(name)] + aliases
+  lines.append('  &[%s]' % ', '.join('b"%s"' % k for k in keys))
+print(',\n'.join(lines))
+print('];')
+print()
+print('// The characters of bytes 0x80 to 0xff, where 0 marks a byte that does')
+print('// not encode any.')
+print('pub static HIGH: [[u16; 128]; COUNT] = [')
+tables = []
+for name, codec, _ in CHARSETS:
+  decoder = codecs.getdecoder(codec)
+  for b in range(128):
+    assert decoder(bytes([b]))[0] == chr(b)
+  values = []
+  for b in range(128, 256):
+    try:
+      c = decoder(bytes([b]))[0]
+      assert len(c) == 1 and 0 < ord(c) <= 0xffff
+      values.append(ord(c))
+    except UnicodeDecodeError:
+      values.append(0)
+  rows = []
+  for i in range(0, 128, 8):
+    rows.append('    ' + ', '.join('%#06x' % v for v in values[i:i + 8]))
+  tables.append('  // %s\n  [\n%s\n  ]' % (name, ',\n'.join(rows)))
+print(',\n'.join(tables))
+print('];')
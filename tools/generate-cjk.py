@@ -0,0 +1,105 @@
+#!/usr/bin/env python3
+
+# Prints src/support/locale/ctype/cjk.rs, the tables of the East Asian
+# multibyte codesets, from the codecs Python ships with.
+#
+#   tools/generate-cjk.py > src/support/locale/ctype/cjk.rs
+
+
+import textwrap
+
+
+def decode(codec, b):
+  try:
+    s = b.decode(codec)
+  except UnicodeDecodeError:
+    return 0
+  assert len(s) == 1 and ord(s) <= 0xffff
+  return ord(s)
+
+
+def encode(codec, c):
+  try:
+    return chr(c).encode(codec)
+  except UnicodeEncodeError:
+    return None
+
+
+def codes(leads, trails):
+  return [bytes([l, t]) for l in leads for t in trails]
+
+
+def table(name, comment, codec, seqs, prefix=b'', exclude=()):
+  values = [decode(codec, prefix + s) for s in seqs]
+  # Where characters repeat, the index the encoder picks comes first.
+  by_char = [
+    i for i, c in enumerate(values)
+    if c and c not in exclude and encode(codec, c) == prefix + seqs[i]
+  ]
+  assert len({values[i] for i in by_char}) == len(by_char)
+  by_char.sort(key=lambda i: values[i])
+  for line in textwrap.wrap(comment, 77):
+    print('// ' + line)
+  print_array(name, 'u16', values)
+  print()
+  print_array(name + '_BY_CHAR', 'u16', by_char)
+  print()
+  return values
+
+
+def print_array(name, kind, values):
+  print('#[rustfmt::skip]')
+  print('pub static %s: [%s; %d] = [' % (name, kind, len(values)))
+  rows = []
+  for i in range(0, len(values), 8):
+    rows.append('  ' + ', '.join('%#06x' % v for v in values[i:i + 8]))
+  print(',\n'.join(rows))
+  print('];')
+
+
+def index_of(i):
+  # The four bytes of the GB18030 sequence with linear index i.
+  b4, i = i % 10, i // 10
+  b3, i = i % 126, i // 126
+  b2, b1 = i % 10, i // 10
+  return bytes([0x81 + b1, 0x30 + b2, 0x81 + b3, 0x30 + b4])
+
+
+EUC = range(0xa1, 0xff)
+
+print('// Generated by tools/generate-cjk.py.')
+print()
+print('// Each table maps the index of a two byte code to a character, 0 where')
+print('// the code is unassigned, and the table _BY_CHAR lists the indexes')
+print('// again, sorted by character, for encoding.')
+print()
+jis0208 = table('JIS0208', 'JIS X 0208, 94 rows of 94 cells.', 'euc_jp',
+                codes(EUC, EUC))
+table('JIS0212', 'JIS X 0212, 94 rows of 94 cells.', 'euc_jp', codes(EUC, EUC),
+      b'\x8f', set(jis0208))
+table('KSC5601', 'KS X 1001, 94 rows of 94 cells.', 'euc_kr', codes(EUC, EUC))
+table('BIG5', 'Big5, leads 0xa1 to 0xf9 and trails 0x40 to 0x7e and 0xa1 to '
+      '0xfe.', 'big5',
+      codes(range(0xa1, 0xfa), list(range(0x40, 0x7f)) + list(EUC)))
+table('GB18030', 'GB18030 two byte codes, leads 0x81 to 0xfe and trails 0x40 '
+      'to 0x7e and 0x80 to 0xfe.', 'gb18030',
+      codes(range(0x81, 0xff), list(range(0x40, 0x7f)) + list(range(0x80,
+                                                                     0xff))))
+
+# The four byte codes below 0x90308130 cover the rest of the Basic
+# Multilingual Plane in order, in runs of consecutive characters.
+ranges = []
+previous = None
+for i in range(39420):
+  c = decode('gb18030', index_of(i))
+  assert c
+  if previous is None or c != previous + 1:
+    ranges.append((i, c))
+  previous = c
+assert [c for _, c in ranges] == sorted(c for _, c in ranges)
+print('// GB18030 four byte codes below 0x90308130 as runs of consecutive')
+print('// characters: the linear index of the first code and its character.')
+print('#[rustfmt::skip]')
+print('pub static GB18030_RANGES: [(u16, u16); %d] = [' % len(ranges))
+print(',\n'.join('  (%#06x, %#06x)' % r for r in ranges))
+print('];')
@@ -0,0 +1,234 @@
+#!/usr/bin/env python3
+
+# Prints src/support/locale/ctype/translit.rs, the ASCII stand-ins iconv's
+# //TRANSLIT writes for characters the target codeset lacks, from the
+# compatibility decompositions Python knows and the list below.
+#
+#   tools/generate-translit.py > src/support/locale/ctype/translit.rs
+
+import unicodedata
+
+# Blocks whose characters get a stand-in when one is found.
+BLOCKS = [
+  (0x00a0, 0x0250),  # Latin-1 Supplement, Latin Extended-A and B
+  (0x02b0, 0x0300),  # Spacing Modifier Letters
+  (0x1e00, 0x1f00),  # Latin Extended Additional
+  (0x2000, 0x2070),  # General Punctuation
+  (0x2070, 0x20a0),  # Superscripts and Subscripts
+  (0x20a0, 0x20d0),  # Currency Symbols
+  (0x2100, 0x2190),  # Letterlike Symbols and Number Forms
+  (0x2190, 0x2200),  # Arrows
+  (0x2200, 0x2300),  # Mathematical Operators
+  (0x2460, 0x2500),  # Enclosed Alphanumerics
+  (0xfb00, 0xfb07),  # Latin ligatures
+  (0xff01, 0xff5f),  # Fullwidth ASCII
+]
+
+# Characters without a decomposition to ASCII, or a better stand-in than it.
+MANUAL = {
+  0x00a1: '!',
+  0x00a2: 'c',
+  0x00a3: 'GBP',
+  0x00a4: '?',
+  0x00a5: 'JPY',
+  0x00a6: '|',
+  0x00a7: 'SS',
+  0x00a9: '(C)',
+  0x00ab: '<<',
+  0x00ac: '!',
+  0x00ad: '-',
+  0x00ae: '(R)',
+  0x00b0: '^0',
+  0x00b1: '+/-',
+  0x00b5: 'u',
+  0x00b6: 'P',
+  0x00b7: '.',
+  0x00bb: '>>',
+  0x00bc: ' 1/4',
+  0x00bd: ' 1/2',
+  0x00be: ' 3/4',
+  0x00bf: '?',
+  0x00c6: 'AE',
+  0x00d0: 'D',
+  0x00d7: 'x',
+  0x00d8: 'O',
+  0x00de: 'TH',
+  0x00df: 'ss',
+  0x00e6: 'ae',
+  0x00f0: 'd',
+  0x00f7: ':',
+  0x00f8: 'o',
+  0x00fe: 'th',
+  0x0110: 'D',
+  0x0111: 'd',
+  0x0126: 'H',
+  0x0127: 'h',
+  0x0131: 'i',
+  0x0138: 'q',
+  0x0141: 'L',
+  0x0142: 'l',
+  0x014a: 'N',
+  0x014b: 'n',
+  0x0152: 'OE',
+  0x0153: 'oe',
+  0x0166: 'T',
+  0x0167: 't',
+  0x0180: 'b',
+  0x0189: 'D',
+  0x0191: 'F',
+  0x0192: 'f',
+  0x0197: 'I',
+  0x019a: 'l',
+  0x019f: 'O',
+  0x01b5: 'Z',
+  0x01b6: 'z',
+  0x01e4: 'G',
+  0x01e5: 'g',
+  0x0237: 'j',
+  0x023a: 'A',
+  0x023b: 'C',
+  0x023c: 'c',
+  0x023d: 'L',
+  0x023e: 'T',
+  0x0243: 'B',
+  0x0246: 'E',
+  0x0247: 'e',
+  0x0248: 'J',
+  0x0249: 'j',
+  0x024c: 'R',
+  0x024d: 'r',
+  0x024e: 'Y',
+  0x024f: 'y',
+  0x02b9: "'",
+  0x02ba: '"',
+  0x02bb: "'",
+  0x02bc: "'",
+  0x02bd: "'",
+  0x02c6: '^',
+  0x02c8: "'",
+  0x02cb: '`',
+  0x02cd: '_',
+  0x02d0: ':',
+  0x02dc: '~',
+  0x1e9e: 'SS',
+  0x2010: '-',
+  0x2011: '-',
+  0x2012: '-',
+  0x2013: '-',
+  0x2014: '-',
+  0x2015: '-',
+  0x2016: '||',
+  0x2018: "'",
+  0x2019: "'",
+  0x201a: ',',
+  0x201b: "'",
+  0x201c: '"',
+  0x201d: '"',
+  0x201e: ',,',
+  0x201f: '"',
+  0x2020: '+',
+  0x2022: 'o',
+  0x2030: ' 0/00',
+  0x2032: "'",
+  0x2033: '"',
+  0x2035: '`',
+  0x2039: '<',
+  0x203a: '>',
+  0x2044: '/',
+  0x204e: '*',
+  0x2052: '%',
+  0x2060: '',
+  0x20a0: 'CE',
+  0x20a1: 'C=',
+  0x20a2: 'Cr',
+  0x20a3: 'Fr.',
+  0x20a4: 'L.',
+  0x20a6: 'N=',
+  0x20a7: 'Pts',
+  0x20a9: 'W=',
+  0x20aa: 'NIS',
+  0x20ab: 'Dong',
+  0x20ac: 'EUR',
+  0x20b1: 'PHP',
+  0x20b9: 'INR',
+  0x20ba: 'TL',
+  0x20bd: 'RUB',
+  0x2103: '^0C',
+  0x2109: '^0F',
+  0x2116: 'No',
+  0x2117: '(P)',
+  0x2120: '(SM)',
+  0x2122: '(TM)',
+  0x2126: 'Ohm',
+  0x2190: '<-',
+  0x2192: '->',
+  0x2194: '<->',
+  0x21d0: '<=',
+  0x21d2: '=>',
+  0x21d4: '<=>',
+  0x2212: '-',
+  0x2215: '/',
+  0x2216: '\\',
+  0x2217: '*',
+  0x2223: '|',
+  0x2236: ':',
+  0x223c: '~',
+  0x2260: '!=',
+  0x2264: '<=',
+  0x2265: '>=',
+  0x226a: '<<',
+  0x226b: '>>',
+}
+
+# The stand-ins run no longer than this, so that iconv encodes them into a
+# buffer on the stack.
+LIMIT = 8
+
+
+def standin(cp):
+  if cp in MANUAL:
+    return MANUAL[cp]
+  decomposed = unicodedata.normalize('NFKD', chr(cp))
+  result = ''
+  for c in decomposed:
+    if unicodedata.combining(c):
+      continue
+    if ord(c) < 0x80:
+      result += c
+    elif ord(c) in MANUAL:
+      result += MANUAL[ord(c)]
+    else:
+      return None
+  if not result or result == chr(cp):
+    return None
+  return result
+
+
+entries = []
+for first, end in BLOCKS:
+  for cp in range(first, end):
+    s = standin(cp)
+    if s is not None:
+      assert len(s) <= LIMIT and all(0 < ord(c) < 0x80 for c in s), hex(cp)
+      entries.append((cp, s))
+for cp in MANUAL:
+  assert any(first <= cp < end for first, end in BLOCKS), hex(cp)
+
+
+def literal(s):
+  return '"%s"' % s.replace('\\', '\\\\').replace('"', '\\"')
+
+
+print('// Generated by tools/generate-translit.py.')
+print()
+print('pub const LIMIT: usize = %d;' % LIMIT)
+print()
+print('// Characters and the ASCII written for them, sorted by character.')
+print('pub static TRANSLIT: [(u32, &str); %d] = [' % len(entries))
+print(',\n'.join('  (%#06x, %s)' % (cp, literal(s)) for cp, s in entries))
+print('];')
+print()
+print('// What to write for `c32` instead, if anything.')
+print('pub fn find(c32: u32) -> Option<&\'static str> {')
+print('  TRANSLIT.binary_search_by_key(&c32, |&(c, _)| c).ok().map(|i| TRANSLIT[i].1)')
+print('}')
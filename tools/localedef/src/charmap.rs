@@ -0,0 +1,181 @@
+use {
+  crate::{
+    lexer::{Lexer, Token},
+    search
+  },
+  std::collections::HashMap
+};
+
+// The codeset a locale's strings are written in, mapping the characters
+// the sources name to their bytes. UTF-8 is known without reading a file.
+pub struct Charmap {
+  pub name: String,
+  utf8: bool,
+  encode: HashMap<u32, Vec<u8>>,
+  // The characters given a name other than that of their code point.
+  names: HashMap<String, u32>
+}
+
+// The letters and digits of a codeset name, in lower case, as the registry
+// of the library matches them.
+pub fn key(name: &str) -> String {
+  name
+    .chars()
+    .filter(char::is_ascii_alphanumeric)
+    .map(|c| c.to_ascii_lowercase())
+    .collect()
+}
+
+// The code point of a name such as <U00E9> or <U0001F600>.
+pub fn code_point(name: &str) -> Option<u32> {
+  let hex = name.strip_prefix('U')?;
+  if !matches!(hex.len(), 4 | 8) {
+    return None;
+  }
+  u32::from_str_radix(hex, 16).ok().filter(|&c| char::from_u32(c).is_some())
+}
+
+impl Charmap {
+  pub fn utf8() -> Self {
+    Charmap {
+      name: "UTF-8".into(),
+      utf8: true,
+      encode: HashMap::new(),
+      names: HashMap::new()
+    }
+  }
+
+  // The charmap called `name`: a path, or a file of the charmaps
+  // directories, compressed or not.
+  pub fn open(name: &str) -> Result<Self, String> {
+    if key(name) == "utf8" {
+      return Ok(Charmap::utf8());
+    }
+    let path =
+      search::charmap(name).ok_or_else(|| format!("no charmap {name}"))?;
+    let text = search::read(&path)?;
+    Charmap::parse(&text).map_err(|err| format!("{}: {err}", path.display()))
+  }
+
+  fn parse(text: &str) -> Result<Self, String> {
+    let mut charmap = Charmap {
+      name: String::new(),
+      utf8: false,
+      encode: HashMap::new(),
+      names: HashMap::new()
+    };
+    // Characters by their bytes, for those of other names.
+    let mut decode = HashMap::new();
+    let mut named = Vec::new();
+    let mut lexer = Lexer::new(text);
+    let mut within = false;
+    let at = |lexer: &Lexer, err: String| format!("{}: {err}", lexer.number());
+    while let Some(line) = lexer.next_line().map_err(|err| at(&lexer, err))? {
+      let tokens = &line.tokens[..];
+      let word = |i: usize| match tokens.get(i) {
+        | Some(Token::Word(word)) => Some(word.as_str()),
+        | _ => None
+      };
+      match (tokens, within) {
+        | ([Token::Name(keyword), Token::Word(value)], false)
+          if keyword == "code_set_name" =>
+        {
+          charmap.name = value.clone();
+        },
+        | (_, false) => within = word(0) == Some("CHARMAP"),
+        | (_, true) if word(0) == Some("END") => break,
+        | (
+          [
+            Token::Name(first),
+            Token::Word(dots),
+            Token::Name(last),
+            Token::Bytes(bytes),
+            ..
+          ],
+          true
+        ) if dots == ".." || dots == "..." => {
+          let (Some(first), Some(last)) = (code_point(first), code_point(last))
+          else {
+            return Err(at(
+              &lexer,
+              format!("range <{first}>..<{last}> is not of code points")
+            ));
+          };
+          let mut bytes = bytes.clone();
+          for c in first..=last {
+            charmap.encode.insert(c, bytes.clone());
+            let end = bytes.last_mut().unwrap();
+            if c != last {
+              *end = end.checked_add(1).ok_or_else(|| {
+                at(&lexer, "range overflows its last byte".into())
+              })?;
+            }
+          }
+        },
+        | ([Token::Name(name), Token::Bytes(bytes), ..], true) => {
+          match code_point(name) {
+            | Some(c) => {
+              decode.insert(bytes.clone(), c);
+              charmap.encode.insert(c, bytes.clone());
+            },
+            | None => named.push((name.clone(), bytes.clone()))
+          }
+        },
+        | _ => return Err(at(&lexer, "expected a name and bytes".into()))
+      }
+    }
+    if charmap.name.is_empty() {
+      return Err("no <code_set_name>".into());
+    }
+    for (name, bytes) in named {
+      if let Some(&c) = decode.get(&bytes) {
+        charmap.names.insert(name, c);
+      }
+    }
+    Ok(charmap)
+  }
+
+  pub fn is_utf8(&self) -> bool {
+    self.utf8
+  }
+
+  // The character called `name`.
+  pub fn char(
+    &self,
+    name: &str
+  ) -> Option<u32> {
+    code_point(name).or_else(|| self.names.get(name).copied())
+  }
+
+  // Whether the codeset has the character `c`.
+  pub fn has(
+    &self,
+    c: u32
+  ) -> bool {
+    if self.utf8 {
+      char::from_u32(c).is_some()
+    } else {
+      self.encode.contains_key(&c)
+    }
+  }
+
+  // Append the bytes of `c` to `out`, giving whether the codeset has it.
+  pub fn encode(
+    &self,
+    c: u32,
+    out: &mut Vec<u8>
+  ) -> bool {
+    if self.utf8 {
+      let Some(c) = char::from_u32(c) else {
+        return false;
+      };
+      out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+      true
+    } else if let Some(bytes) = self.encode.get(&c) {
+      out.extend_from_slice(bytes);
+      true
+    } else {
+      false
+    }
+  }
+}
@@ -0,0 +1,407 @@
+use {
+  crate::{
+    charmap::Charmap,
+    format::{self, LEVELS_MAX, SEQUENCE_MAX},
+    lexer::{Line, Part, Token},
+    locale::{self, Category, Context},
+    writer::{Word, Writer}
+  },
+  std::collections::{HashMap, HashSet}
+};
+
+// What a line of the order places: a character, a sequence declared by
+// collating-element or a collating-symbol, which only other lines use as a
+// weight.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum Key {
+  Char(u32),
+  Element(String),
+  Symbol(String)
+}
+
+// A weight at one level.
+#[derive(Clone)]
+enum Weight {
+  Ignore,
+  // The place of the entry itself, as when the line gives none or gives
+  // "..".
+  Own,
+  // The places of what a string names, one after the other.
+  Of(Vec<Key>)
+}
+
+struct Entry {
+  key: Key,
+  weights: Vec<Weight>
+}
+
+// LC_COLLATE: the order the lines give, with reorder-after moving them, and
+// every entry weighted by the places of what its weights name. Characters
+// the order leaves out follow it by code point, and directions, beyond the
+// third level and UNDEFINED are passed over.
+#[derive(Default)]
+pub struct Collate {
+  levels: usize,
+  symbols: HashSet<String>,
+  elements: HashMap<String, Vec<u32>>,
+  order: Vec<Entry>,
+  listed: HashSet<Key>,
+  // Where reorder-after puts the lines up to reorder-end.
+  insert_at: Option<usize>,
+  // The weights of a line of "..", which stands for every character
+  // between those of the lines around it.
+  ellipsis: Option<(u32, Vec<Weight>)>,
+  // The character of the last line, which a line of ".." starts after.
+  previous: Option<u32>,
+  codepoint: bool
+}
+
+// The names of a range such as <S0200>..<S1100>, which count in hex from
+// the first to the last, keeping the width of the first.
+fn name_range(
+  first: &str,
+  last: &str
+) -> Result<Vec<String>, String> {
+  let split = |name: &str| {
+    let hex = name.bytes().rev().take_while(u8::is_ascii_hexdigit).count();
+    // The first character is the prefix even if it is a hex digit.
+    let at = (name.len() - hex).max(1).min(name.len());
+    (name[..at].to_string(), name[at..].to_string())
+  };
+  let (prefix, from) = split(first);
+  let (other, to) = split(last);
+  let (Ok(from_n), Ok(to_n)) =
+    (u32::from_str_radix(&from, 16), u32::from_str_radix(&to, 16))
+  else {
+    return Err(format!("<{first}>..<{last}> is not a range of names"));
+  };
+  if prefix != other || from_n > to_n {
+    return Err(format!("<{first}>..<{last}> is not a range of names"));
+  }
+  Ok(
+    (from_n..=to_n)
+      .map(|n| format!("{prefix}{n:0width$X}", width = from.len()))
+      .collect()
+  )
+}
+
+impl Collate {
+  // What the name `name` stands for in this category.
+  fn key(
+    &self,
+    name: &str,
+    cx: &Context
+  ) -> Result<Key, String> {
+    if self.symbols.contains(name) {
+      Ok(Key::Symbol(name.into()))
+    } else if self.elements.contains_key(name) {
+      Ok(Key::Element(name.into()))
+    } else {
+      cx.charmap
+        .char(name)
+        .map(Key::Char)
+        .ok_or_else(|| format!("unknown symbol <{name}>"))
+    }
+  }
+
+  fn token_key(
+    &self,
+    token: &Token,
+    cx: &Context
+  ) -> Result<Key, String> {
+    match token {
+      | Token::Name(name) => self.key(name, cx),
+      | token => cx.char(token).map(Key::Char)
+    }
+  }
+
+  fn weight(
+    &self,
+    operand: &[Token],
+    cx: &Context
+  ) -> Result<Weight, String> {
+    Ok(match operand {
+      | [] => Weight::Own,
+      | [Token::Word(word)] if word == "IGNORE" => Weight::Ignore,
+      | [Token::Word(word)] if word == ".." || word == "..." => Weight::Own,
+      | [Token::Str(parts)] if parts.is_empty() => Weight::Ignore,
+      | [Token::Str(parts)] => Weight::Of(
+        parts
+          .iter()
+          .map(|part| match part {
+            | Part::Name(name) => self.key(name, cx),
+            | part => Ok(Key::Char(cx.chars(std::slice::from_ref(part))?[0]))
+          })
+          .collect::<Result<_, _>>()?
+      ),
+      | [token] => Weight::Of(vec![self.token_key(token, cx)?]),
+      | _ => return Err("expected a weight".into())
+    })
+  }
+
+  // Place `key` in the order, taking it from where it was if it was.
+  fn place(
+    &mut self,
+    key: Key,
+    weights: Vec<Weight>
+  ) {
+    if self.listed.contains(&key) {
+      let at = self.order.iter().position(|entry| entry.key == key).unwrap();
+      self.order.remove(at);
+      if let Some(insert_at) = &mut self.insert_at {
+        if at < *insert_at {
+          *insert_at -= 1;
+        }
+      }
+    }
+    self.listed.insert(key.clone());
+    let entry = Entry { key, weights };
+    match &mut self.insert_at {
+      | Some(insert_at) => {
+        self.order.insert(*insert_at, entry);
+        *insert_at += 1;
+      },
+      | None => self.order.push(entry)
+    }
+  }
+
+  fn entry(
+    &mut self,
+    line: &Line,
+    cx: &Context
+  ) -> Result<(), String> {
+    // As with other implementations, what a line places without it being
+    // declared is taken as a collating-symbol, and a line weighted by what
+    // is not declared is left out rather than failing the locale.
+    if let Token::Name(name) = &line.tokens[0] {
+      if self.key(name, cx).is_err() {
+        cx.warn(&format!(
+          "<{name}> is not declared, taken as a collating-symbol"
+        ));
+        self.symbols.insert(name.clone());
+      }
+    }
+    let key = self.token_key(&line.tokens[0], cx);
+    let weights = locale::operands(&line.tokens[1..])
+      .into_iter()
+      .take(LEVELS_MAX)
+      .map(|operand| self.weight(operand, cx))
+      .collect::<Result<Vec<_>, _>>();
+    let (key, weights) = match (key, weights) {
+      | (Ok(key), Ok(weights)) => (key, weights),
+      | (Err(err), _) | (_, Err(err)) => {
+        cx.warn(&format!("{err}, line ignored"));
+        return Ok(());
+      }
+    };
+    if let Some((from, ellipsis)) = self.ellipsis.take() {
+      let Key::Char(to) = key else {
+        return Err("expected a character after \"..\"".into());
+      };
+      for c in from + 1..to {
+        self.place(Key::Char(c), ellipsis.clone());
+      }
+    }
+    self.previous = match key {
+      | Key::Char(c) => Some(c),
+      | _ => None
+    };
+    self.place(key, weights);
+    Ok(())
+  }
+}
+
+impl Category for Collate {
+  fn line(
+    &mut self,
+    keyword: &str,
+    line: &Line,
+    cx: &Context
+  ) -> Result<(), String> {
+    let tokens = &line.tokens[1..];
+    match keyword {
+      | "collating-symbol" => match tokens {
+        | [Token::Name(name)] => {
+          self.symbols.insert(name.clone());
+        },
+        | [Token::Name(first), Token::Word(dots), Token::Name(last)]
+          if dots == ".." =>
+        {
+          self.symbols.extend(name_range(first, last)?);
+        },
+        | _ => return Err("expected a name after collating-symbol".into())
+      },
+      | "collating-element" => {
+        let [Token::Name(name), Token::Word(from), Token::Str(parts)] = tokens
+        else {
+          return Err(
+            "expected collating-element <name> from \"string\"".into()
+          );
+        };
+        if from != "from" {
+          return Err(
+            "expected collating-element <name> from \"string\"".into()
+          );
+        }
+        let chars = cx.chars(parts)?;
+        if chars.is_empty() || chars.len() > SEQUENCE_MAX {
+          // It still has a place to be weighted by.
+          cx.warn(&format!(
+            "<{name}> is not of 1 to {SEQUENCE_MAX} characters, taken as a \
+             collating-symbol"
+          ));
+          self.symbols.insert(name.clone());
+        } else {
+          self.elements.insert(name.clone(), chars);
+        }
+      },
+      | "order_start" => {
+        let directions = locale::operands(tokens)
+          .iter()
+          .filter(|operand| matches!(operand.first(), Some(Token::Word(_))))
+          .count();
+        self.levels = self.levels.max(directions.clamp(1, LEVELS_MAX));
+      },
+      | "order_end" | "reorder-end" => {
+        self.insert_at = None;
+        self.ellipsis = None;
+      },
+      | "reorder-after" => {
+        let [token] = tokens else {
+          return Err("expected what to reorder after".into());
+        };
+        let key = self.token_key(token, cx)?;
+        let at =
+          self.order.iter().position(|entry| entry.key == key).ok_or_else(
+            || format!("{} is not in the order", locale::describe(token))
+          )?;
+        self.insert_at = Some(at + 1);
+      },
+      | "codepoint_collation" => self.codepoint = true,
+      | ".." | "..." => {
+        let Some(c) = self.previous else {
+          return Err("expected a character before \"..\"".into());
+        };
+        let weights = locale::operands(tokens)
+          .into_iter()
+          .take(LEVELS_MAX)
+          .map(|operand| self.weight(operand, cx));
+        self.ellipsis = Some((c, weights.collect::<Result<_, _>>()?));
+      },
+      | "script" |
+      "symbol-equivalence" |
+      "reorder-sections-after" |
+      "reorder-sections-end" |
+      "UNDEFINED" => {},
+      | "" => self.entry(line, cx)?,
+      | keyword if keyword.chars().count() == 1 => self.entry(line, cx)?,
+      | keyword => {
+        return Err(format!("unknown keyword {keyword} in LC_COLLATE"));
+      }
+    }
+    Ok(())
+  }
+}
+
+impl Collate {
+  // The section, or None for the order of code points.
+  pub fn section(
+    &self,
+    writer: &mut Writer,
+    charmap: &Charmap
+  ) -> Result<Option<Vec<Word>>, String> {
+    if self.codepoint || self.order.is_empty() {
+      return Ok(None);
+    }
+    let levels = self.levels.max(1);
+    let places: HashMap<&Key, u32> = self
+      .order
+      .iter()
+      .enumerate()
+      .map(|(i, entry)| (&entry.key, i as u32 + 1))
+      .collect();
+    // Characters a weight names without placing them come after all else.
+    let beyond = self.order.len() as u32 + 1;
+    let list = |entry: &Entry, level: usize| -> Result<Vec<u32>, String> {
+      Ok(match entry.weights.get(level).unwrap_or(&Weight::Own) {
+        | Weight::Ignore => Vec::new(),
+        | Weight::Own => vec![places[&entry.key]],
+        | Weight::Of(keys) => keys
+          .iter()
+          .map(|key| match (places.get(key), key) {
+            | (Some(&place), _) => Ok(place),
+            | (None, Key::Char(c)) => Ok(beyond + c),
+            | (None, Key::Symbol(name) | Key::Element(name)) => Err(format!(
+              "<{name}> weights an entry but has no place in the order"
+            ))
+          })
+          .collect::<Result<_, _>>()?
+      })
+    };
+    // What each character and sequence the codeset has collates by, as
+    // lists of places at every level.
+    let mut elements = Vec::new();
+    for entry in &self.order {
+      let seq = match &entry.key {
+        | Key::Char(c) => vec![*c],
+        | Key::Element(name) => self.elements[name].clone(),
+        | Key::Symbol(_) => continue
+      };
+      if !seq.iter().all(|&c| charmap.has(c)) {
+        continue;
+      }
+      let mut lists: [Vec<u32>; LEVELS_MAX] = Default::default();
+      for (level, l) in lists.iter_mut().enumerate().take(levels) {
+        *l = list(entry, level)?;
+      }
+      elements.push((seq, lists));
+    }
+    // An element has one weight at each level, so that the lists are
+    // ranked: weights order as the lists do, though a list of several no
+    // longer expands to compare with the elements after it.
+    let mut ranked: [Vec<&[u32]>; LEVELS_MAX] = Default::default();
+    for (level, ranks) in ranked.iter_mut().enumerate() {
+      ranks.extend(
+        elements
+          .iter()
+          .map(|(_, lists)| &lists[level][..])
+          .filter(|l| !l.is_empty())
+      );
+      ranks.sort_unstable();
+      ranks.dedup();
+    }
+    let undefined = ranked[0].len() as u32 + 1;
+    if undefined + 0x110000 > format::WEIGHT_LIMIT {
+      return Err("LC_COLLATE orders too many entries".into());
+    }
+    let (mut chars, mut sequences) = (Vec::new(), Vec::new());
+    for (seq, lists) in &elements {
+      let mut weights = [0; LEVELS_MAX];
+      for (level, w) in weights.iter_mut().enumerate() {
+        if !lists[level].is_empty() {
+          *w =
+            ranked[level].binary_search(&&lists[level][..]).unwrap() as u32 + 1;
+        }
+      }
+      if let [c] = seq[..] {
+        chars.push([c, weights[0], weights[1], weights[2]]);
+      } else {
+        let mut record = [0; format::SEQUENCE_WORDS];
+        record[..seq.len()].copy_from_slice(seq);
+        record[SEQUENCE_MAX..].copy_from_slice(&weights);
+        sequences.push(record);
+      }
+    }
+    chars.sort_unstable();
+    chars.dedup_by_key(|record| record[0]);
+    sequences.sort_unstable();
+    sequences.dedup_by_key(|record| {
+      <[u32; SEQUENCE_MAX]>::try_from(&record[..SEQUENCE_MAX]).unwrap()
+    });
+    let mut words = vec![Word::Value(levels as u32), Word::Value(undefined)];
+    words.extend(writer.table(chars.as_flattened(), format::CHAR_WORDS));
+    words
+      .extend(writer.table(sequences.as_flattened(), format::SEQUENCE_WORDS));
+    Ok(Some(words))
+  }
+}
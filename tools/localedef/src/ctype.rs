@@ -0,0 +1,163 @@
+use {
+  crate::{
+    charmap::Charmap,
+    format::{self, CLASS_NAMES},
+    lexer::{Line, Token},
+    locale::{self, Category, Context},
+    writer::{Word, Writer}
+  },
+  std::collections::BTreeMap
+};
+
+// LC_CTYPE: the classes wctype knows and the case maps. Other classes and
+// maps, transliteration and the rest are left to the library's own tables.
+#[derive(Default)]
+pub struct Ctype {
+  // The ranges of each class, in the order of CLASS_NAMES, as the source
+  // gives them.
+  classes: [Vec<(u32, u32)>; CLASS_NAMES.len()],
+  toupper: BTreeMap<u32, u32>,
+  tolower: BTreeMap<u32, u32>,
+  translit: bool
+}
+
+fn class(name: &str) -> Option<usize> {
+  CLASS_NAMES.iter().position(|&class| class == name)
+}
+
+// Sort ranges and merge those that overlap or touch.
+fn merge(mut ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+  ranges.sort_unstable();
+  let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+  for (first, last) in ranges {
+    match merged.last_mut() {
+      | Some(prev) if first <= prev.1.saturating_add(1) => {
+        prev.1 = prev.1.max(last)
+      },
+      | _ => merged.push((first, last))
+    }
+  }
+  merged
+}
+
+// The characters of `ranges` the codeset has, as ranges.
+fn restrict(
+  ranges: &[(u32, u32)],
+  charmap: &Charmap
+) -> Vec<(u32, u32)> {
+  if charmap.is_utf8() {
+    return ranges.to_vec();
+  }
+  let chars = ranges
+    .iter()
+    .flat_map(|&(first, last)| first..=last)
+    .filter(|&c| charmap.has(c));
+  merge(chars.map(|c| (c, c)).collect())
+}
+
+impl Category for Ctype {
+  fn line(
+    &mut self,
+    keyword: &str,
+    line: &Line,
+    cx: &Context
+  ) -> Result<(), String> {
+    match keyword {
+      | "translit_start" => self.translit = true,
+      | "translit_end" => self.translit = false,
+      | _ if self.translit => {},
+      | "toupper" | "tolower" => {
+        let map = if keyword == "toupper" {
+          &mut self.toupper
+        } else {
+          &mut self.tolower
+        };
+        for operand in locale::operands(&line.tokens[1..]) {
+          let [Token::Open, from, Token::Comma, to, Token::Close] = operand
+          else {
+            return Err(format!(
+              "expected a pair such as (<U0061>,<U0041>) in {keyword}"
+            ));
+          };
+          map.insert(cx.char(from)?, cx.char(to)?);
+        }
+      },
+      | _ => {
+        // Classes and maps of other names are passed over.
+        let Some(class) = class(keyword) else {
+          return Ok(());
+        };
+        for operand in locale::operands(&line.tokens[1..]) {
+          let range = match operand {
+            | [c] => (cx.char(c)?, cx.char(c)?),
+            | [first, Token::Word(dots), last]
+              if dots == ".." || dots == "..." =>
+            {
+              (cx.char(first)?, cx.char(last)?)
+            },
+            | _ => {
+              return Err(format!(
+                "expected characters or ranges in {keyword}"
+              ));
+            }
+          };
+          if range.0 > range.1 {
+            return Err(format!(
+              "range U+{:04X} to U+{:04X} is backwards",
+              range.0, range.1
+            ));
+          }
+          self.classes[class].push(range);
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+impl Ctype {
+  pub fn section(
+    &self,
+    writer: &mut Writer,
+    charmap: &Charmap
+  ) -> Vec<Word> {
+    let index = |name| class(name).unwrap();
+    let mut classes = self.classes.clone();
+    // The classes POSIX has include others, and some characters.
+    let implied: [(&str, &[&str], &[u32]); 7] = [
+      ("alpha", &["upper", "lower"], &[]),
+      ("alnum", &["alpha", "digit"], &[]),
+      ("xdigit", &["digit"], &[]),
+      ("graph", &["alnum", "xdigit", "punct"], &[]),
+      ("print", &["graph"], &[0x20]),
+      ("space", &[], &[0x20, 0x09, 0x0a, 0x0b, 0x0c, 0x0d]),
+      ("blank", &[], &[0x20, 0x09])
+    ];
+    for (class, included, chars) in implied {
+      let mut ranges = classes[index(class)].clone();
+      for &other in included {
+        ranges.extend_from_slice(&classes[index(other)]);
+      }
+      ranges.extend(chars.iter().map(|&c| (c, c)));
+      classes[index(class)] = ranges;
+    }
+    let mut words = Vec::with_capacity(format::CTYPE_WORDS);
+    for ranges in classes {
+      let ranges = restrict(&merge(ranges), charmap);
+      let records: Vec<u32> =
+        ranges.iter().flat_map(|&(first, last)| [first, last]).collect();
+      words.extend(writer.table(&records, format::RANGE_WORDS));
+    }
+    for map in [&self.toupper, &self.tolower] {
+      let records: Vec<u32> = map
+        .iter()
+        .filter(|&(&c, &mapped)| {
+          c != mapped && charmap.has(c) && charmap.has(mapped)
+        })
+        .flat_map(|(&c, &mapped)| [c, mapped])
+        .collect();
+      words.extend(writer.table(&records, format::CASE_WORDS));
+    }
+    words
+  }
+}
@@ -0,0 +1,317 @@
+// The lexical conventions localedef sources and charmaps share, as POSIX
+// gives them: lines of tokens, where a comment character starts a comment
+// and an escape character ends a line continued on the next one, starts a
+// byte such as /x41, /d65 or /o101, or quotes the character after it.
+// Either is changed for the lines after a comment_char or escape_char line.
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Token {
+  // A symbolic name such as <U0041>, without the angle brackets.
+  Name(String),
+  // A string in double quotes.
+  Str(Vec<Part>),
+  // Bytes given one after the other outside a string.
+  Bytes(Vec<u8>),
+  // Anything else up to a space or punctuation: keywords, numbers, an
+  // ellipsis or a character written as itself.
+  Word(String),
+  Semicolon,
+  Comma,
+  Open,
+  Close
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Part {
+  Char(char),
+  Name(String),
+  Byte(u8)
+}
+
+pub struct Line {
+  pub number: usize,
+  pub tokens: Vec<Token>
+}
+
+pub struct Lexer {
+  text: Vec<char>,
+  at: usize,
+  number: usize,
+  comment: char,
+  escape: char
+}
+
+impl Lexer {
+  pub fn new(text: &str) -> Self {
+    Lexer {
+      text: text.chars().collect(),
+      at: 0,
+      number: 1,
+      comment: '#',
+      escape: '\\'
+    }
+  }
+
+  fn peek(&self) -> Option<char> {
+    self.text.get(self.at).copied()
+  }
+
+  fn peek_at(
+    &self,
+    n: usize
+  ) -> Option<char> {
+    self.text.get(self.at + n).copied()
+  }
+
+  // Skip the rest of the physical line, leaving the newline.
+  fn skip_line(&mut self) {
+    while self.peek().is_some_and(|c| c != '\n') {
+      self.at += 1;
+    }
+  }
+
+  // After an escaped newline, the physical lines that hold nothing but a
+  // comment do not end the logical one.
+  fn skip_comment_lines(&mut self) {
+    loop {
+      let mut n = 0;
+      while self.peek_at(n).is_some_and(|c| c == ' ' || c == '\t') {
+        n += 1;
+      }
+      if self.peek_at(n) != Some(self.comment) {
+        return;
+      }
+      self.skip_line();
+      if self.peek() == Some('\n') {
+        self.at += 1;
+        self.number += 1;
+      }
+    }
+  }
+
+  // A byte escape at the escape character, if there is one.
+  fn byte(&mut self) -> Result<Option<u8>, String> {
+    let (radix, max) = match self.peek_at(1) {
+      | Some('x' | 'X') => (16, 2),
+      | Some('d' | 'D') => (10, 3),
+      | Some('o' | 'O') => (8, 3),
+      | _ => return Ok(None)
+    };
+    let mut len = 0;
+    while len < max && self.peek_at(2 + len).is_some_and(|c| c.is_digit(radix))
+    {
+      len += 1;
+    }
+    if len == 0 {
+      return Ok(None);
+    }
+    let digits: String =
+      self.text[self.at + 2..self.at + 2 + len].iter().collect();
+    let byte = u32::from_str_radix(&digits, radix).unwrap();
+    self.at += 2 + len;
+    u8::try_from(byte)
+      .map(Some)
+      .map_err(|_| format!("byte {byte} is too large"))
+  }
+
+  // The name at '<', up to the '>' closing it.
+  fn name(&mut self) -> Result<String, String> {
+    self.at += 1;
+    let mut name = String::new();
+    loop {
+      match self.peek() {
+        | Some('>') => {
+          self.at += 1;
+          return Ok(name);
+        },
+        | Some(c)
+          if c == self.escape && self.peek_at(1).is_some_and(|c| c != '\n') =>
+        {
+          name.push(self.peek_at(1).unwrap());
+          self.at += 2;
+        },
+        | Some(c) if c != '\n' => {
+          name.push(c);
+          self.at += 1;
+        },
+        | _ => return Err(format!("unterminated name <{name}"))
+      }
+    }
+  }
+
+  // Whether a '<' within a string starts a name rather than standing for
+  // itself.
+  fn is_name(&self) -> bool {
+    self.text[self.at + 1..]
+      .iter()
+      .take_while(|&&c| c != '"' && c != '\n')
+      .any(|&c| c == '>')
+  }
+
+  fn string(&mut self) -> Result<Vec<Part>, String> {
+    self.at += 1;
+    let mut parts = Vec::new();
+    loop {
+      match self.peek() {
+        | Some('"') => {
+          self.at += 1;
+          return Ok(parts);
+        },
+        | Some('<') if self.is_name() => parts.push(Part::Name(self.name()?)),
+        | Some(c) if c == self.escape => {
+          if let Some(byte) = self.byte()? {
+            parts.push(Part::Byte(byte));
+          } else {
+            match self.peek_at(1) {
+              | Some('\n') => {
+                self.at += 2;
+                self.number += 1;
+              },
+              | Some(c) => {
+                parts.push(Part::Char(c));
+                self.at += 2;
+              },
+              | None => return Err("unterminated string".into())
+            }
+          }
+        },
+        | Some(c) if c != '\n' => {
+          parts.push(Part::Char(c));
+          self.at += 1;
+        },
+        | _ => return Err("unterminated string".into())
+      }
+    }
+  }
+
+  fn word(&mut self) -> String {
+    let mut word = String::new();
+    while let Some(c) = self.peek() {
+      if c.is_whitespace() || ";,()<\"".contains(c) || c == self.comment {
+        break;
+      }
+      if c == self.escape {
+        match self.peek_at(1) {
+          | Some(next) if next != '\n' => {
+            word.push(next);
+            self.at += 2;
+            continue;
+          },
+          | _ => break
+        }
+      }
+      word.push(c);
+      self.at += 1;
+    }
+    word
+  }
+
+  // Take a comment_char or escape_char line into account.
+  fn directive(
+    &mut self,
+    tokens: &[Token]
+  ) {
+    let (
+      Some(Token::Name(keyword) | Token::Word(keyword)),
+      Some(Token::Word(value))
+    ) = (tokens.first(), tokens.get(1))
+    else {
+      return;
+    };
+    let mut chars = value.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+      return;
+    };
+    match keyword.as_str() {
+      | "comment_char" => self.comment = c,
+      | "escape_char" => self.escape = c,
+      | _ => {}
+    }
+  }
+
+  // The number of the physical line the lexer is at.
+  pub fn number(&self) -> usize {
+    self.number
+  }
+
+  // The next logical line holding any token.
+  pub fn next_line(&mut self) -> Result<Option<Line>, String> {
+    let mut tokens = Vec::new();
+    let mut number = self.number;
+    while let Some(c) = self.peek() {
+      if c == '\n' {
+        self.at += 1;
+        self.number += 1;
+        if !tokens.is_empty() {
+          break;
+        }
+        number = self.number;
+        continue;
+      }
+      if c.is_whitespace() {
+        self.at += 1;
+      } else if c == self.comment {
+        // A comment ending in the escape character continues the line all
+        // the same.
+        let start = self.at;
+        self.skip_line();
+        let comment = &self.text[start..self.at];
+        if comment.iter().rev().find(|c| !c.is_whitespace()) ==
+          Some(&self.escape)
+        {
+          if self.peek() == Some('\n') {
+            self.at += 1;
+            self.number += 1;
+          }
+          self.skip_comment_lines();
+        }
+      } else if c == self.escape {
+        if let Some(byte) = self.byte()? {
+          let mut bytes = vec![byte];
+          while self.peek() == Some(self.escape) {
+            match self.byte()? {
+              | Some(byte) => bytes.push(byte),
+              | None => break
+            }
+          }
+          tokens.push(Token::Bytes(bytes));
+        } else if self.text[self.at + 1..]
+          .iter()
+          .take_while(|&&c| c != '\n')
+          .all(|c| c.is_whitespace())
+        {
+          // A continued line.
+          self.skip_line();
+          if self.peek() == Some('\n') {
+            self.at += 1;
+            self.number += 1;
+          }
+          self.skip_comment_lines();
+        } else {
+          tokens.push(Token::Word(self.word()));
+        }
+      } else {
+        let token = match c {
+          | '<' => Token::Name(self.name()?),
+          | '"' => Token::Str(self.string()?),
+          | ';' | ',' | '(' | ')' => {
+            self.at += 1;
+            match c {
+              | ';' => Token::Semicolon,
+              | ',' => Token::Comma,
+              | '(' => Token::Open,
+              | _ => Token::Close
+            }
+          },
+          | _ => Token::Word(self.word())
+        };
+        tokens.push(token);
+      }
+    }
+    if tokens.is_empty() {
+      return Ok(None);
+    }
+    self.directive(&tokens);
+    Ok(Some(Line { number, tokens }))
+  }
+}
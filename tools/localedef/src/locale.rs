@@ -0,0 +1,367 @@
+use {
+  crate::{
+    charmap::Charmap,
+    collate::Collate,
+    ctype::Ctype,
+    lexer::{Lexer, Line, Part, Token},
+    messages::Messages,
+    monetary::Monetary,
+    numeric::Numeric,
+    search,
+    time::Time
+  },
+  std::{collections::HashSet, path::Path}
+};
+
+// How deep copy may nest, which a file copying itself would exceed.
+const COPY_MAX: usize = 16;
+
+// The categories of a locale, with each that a source defines.
+#[derive(Default)]
+pub struct Locale {
+  pub ctype: Option<Ctype>,
+  pub numeric: Option<Numeric>,
+  pub time: Option<Time>,
+  pub collate: Option<Collate>,
+  pub monetary: Option<Monetary>,
+  pub messages: Option<Messages>
+}
+
+// What a category needs to take its lines.
+pub struct Context<'a> {
+  pub charmap: &'a Charmap,
+  path: &'a str,
+  line: usize
+}
+
+impl Context<'_> {
+  pub fn warn(
+    &self,
+    message: &str
+  ) {
+    eprintln!("localedef: {}:{}: warning: {message}", self.path, self.line);
+  }
+
+  // The character `token` names or is.
+  pub fn char(
+    &self,
+    token: &Token
+  ) -> Result<u32, String> {
+    match token {
+      | Token::Name(name) => self
+        .charmap
+        .char(name)
+        .ok_or_else(|| format!("unknown character <{name}>")),
+      | Token::Word(word) if word.chars().count() == 1 => {
+        Ok(word.chars().next().unwrap() as u32)
+      },
+      | Token::Bytes(bytes) if bytes.len() == 1 && bytes[0] < 0x80 => {
+        Ok(u32::from(bytes[0]))
+      },
+      | _ => Err(format!("expected a character, not {}", describe(token)))
+    }
+  }
+
+  // The characters of a string.
+  pub fn chars(
+    &self,
+    parts: &[Part]
+  ) -> Result<Vec<u32>, String> {
+    parts
+      .iter()
+      .map(|part| match part {
+        | Part::Char(c) => Ok(*c as u32),
+        | Part::Name(name) => self
+          .charmap
+          .char(name)
+          .ok_or_else(|| format!("unknown character <{name}>")),
+        | Part::Byte(byte) if *byte < 0x80 => Ok(u32::from(*byte)),
+        | Part::Byte(byte) => {
+          Err(format!("byte {byte:#x} in a string of characters"))
+        },
+      })
+      .collect()
+  }
+
+  // A string in the codeset of the charmap, leaving out with a warning the
+  // characters it lacks.
+  pub fn string(
+    &self,
+    operands: &[&[Token]]
+  ) -> Result<Vec<u8>, String> {
+    let [[Token::Str(parts)]] = operands else {
+      return Err("expected a string".into());
+    };
+    let mut bytes = Vec::new();
+    for part in parts {
+      match part {
+        | Part::Byte(byte) => bytes.push(*byte),
+        | part => {
+          let c = self.chars(std::slice::from_ref(part))?[0];
+          if !self.charmap.encode(c, &mut bytes) {
+            self.warn(&format!("{} lacks U+{c:04X}", self.charmap.name));
+          }
+        }
+      }
+    }
+    if bytes.contains(&0) {
+      return Err("null character in a string".into());
+    }
+    Ok(bytes)
+  }
+
+  // A list of strings, separated by semicolons.
+  pub fn strings(
+    &self,
+    operands: &[&[Token]]
+  ) -> Result<Vec<Vec<u8>>, String> {
+    operands.iter().map(|operand| self.string(&[operand])).collect()
+  }
+}
+
+pub fn describe(token: &Token) -> String {
+  match token {
+    | Token::Name(name) => format!("<{name}>"),
+    | Token::Str(_) => "a string".into(),
+    | Token::Bytes(_) => "bytes".into(),
+    | Token::Word(word) => format!("\"{word}\""),
+    | Token::Semicolon => "';'".into(),
+    | Token::Comma => "','".into(),
+    | Token::Open => "'('".into(),
+    | Token::Close => "')'".into()
+  }
+}
+
+// The operands of a line after its keyword, separated by semicolons.
+pub fn operands(tokens: &[Token]) -> Vec<&[Token]> {
+  if tokens.is_empty() {
+    return Vec::new();
+  }
+  tokens.split(|token| *token == Token::Semicolon).collect()
+}
+
+pub fn number(operand: &[Token]) -> Result<i64, String> {
+  match operand {
+    | [Token::Word(word)] => {
+      word.parse().map_err(|_| format!("expected a number, not \"{word}\""))
+    },
+    | _ => Err("expected a number".into())
+  }
+}
+
+// A list of numbers as the bytes of a grouping string, -1 becoming
+// CHAR_MAX. A semicolon may end the list.
+pub fn grouping(operands: &[&[Token]]) -> Result<Vec<u8>, String> {
+  let operands = match operands {
+    | [list @ .., []] => list,
+    | list => list
+  };
+  operands
+    .iter()
+    .map(|operand| match number(operand)? {
+      | -1 => Ok(127),
+      | n => u8::try_from(n)
+        .ok()
+        .filter(|&n| n < 127)
+        .ok_or_else(|| format!("group size {n}"))
+    })
+    .collect()
+}
+
+// A category of a locale, which takes the lines of its section in a source
+// that are not copy, END or conditionals.
+pub trait Category {
+  fn line(
+    &mut self,
+    keyword: &str,
+    line: &Line,
+    cx: &Context
+  ) -> Result<(), String>;
+}
+
+// The state of a section's ifdef, ifndef, elif and else lines, which only
+// LC_COLLATE uses in the sources around.
+struct Conditional {
+  // Whether the lines are taken.
+  active: bool,
+  // Whether a branch was, so that the others are not.
+  taken: bool,
+  // Whether the enclosing lines are.
+  outer: bool
+}
+
+pub struct Reader<'a> {
+  charmap: &'a Charmap,
+  defines: HashSet<String>,
+  depth: usize
+}
+
+fn category_of<'l>(
+  locale: &'l mut Locale,
+  name: &str
+) -> Option<&'l mut dyn Category> {
+  Some(match name {
+    | "LC_CTYPE" => locale.ctype.get_or_insert_with(Default::default),
+    | "LC_NUMERIC" => locale.numeric.get_or_insert_with(Default::default),
+    | "LC_TIME" => locale.time.get_or_insert_with(Default::default),
+    | "LC_COLLATE" => locale.collate.get_or_insert_with(Default::default),
+    | "LC_MONETARY" => locale.monetary.get_or_insert_with(Default::default),
+    | "LC_MESSAGES" => locale.messages.get_or_insert_with(Default::default),
+    | _ => return None
+  })
+}
+
+impl<'a> Reader<'a> {
+  pub fn new(charmap: &'a Charmap) -> Self {
+    Reader { charmap, defines: HashSet::new(), depth: 0 }
+  }
+
+  // Read the source at `path` into `locale`, only its section of `only` if
+  // it is copied from.
+  pub fn read(
+    &mut self,
+    path: &Path,
+    locale: &mut Locale,
+    only: Option<&str>
+  ) -> Result<(), String> {
+    let text = search::read(path)?;
+    self.read_text(&text, path, locale, only)
+  }
+
+  // The same with the source `text` of the file at `path`.
+  pub fn read_text(
+    &mut self,
+    text: &str,
+    path: &Path,
+    locale: &mut Locale,
+    only: Option<&str>
+  ) -> Result<(), String> {
+    let shown = path.display().to_string();
+    let mut lexer = Lexer::new(text);
+    let at = |line: usize, err: String| format!("{shown}:{line}: {err}");
+    let mut section: Option<String> = None;
+    let mut conditionals: Vec<Conditional> = Vec::new();
+    while let Some(line) =
+      lexer.next_line().map_err(|err| at(lexer.number(), err))?
+    {
+      let keyword = match line.tokens.first() {
+        | Some(Token::Word(word)) => word.as_str(),
+        | _ => ""
+      };
+      let Some(name) = &section else {
+        if keyword.starts_with("LC_") {
+          if only.is_none_or(|only| only == keyword) {
+            category_of(locale, keyword);
+          }
+          section = Some(keyword.into());
+        } else if !matches!(keyword, "comment_char" | "escape_char") {
+          return Err(at(
+            line.number,
+            format!("expected a category, not {}", describe(&line.tokens[0]))
+          ));
+        }
+        continue;
+      };
+      if keyword == "END" {
+        match line.tokens.get(1) {
+          | Some(Token::Word(end)) if end == name => {},
+          | _ => return Err(at(line.number, format!("expected END {name}")))
+        }
+        if !conditionals.is_empty() {
+          return Err(at(line.number, "ifdef without endif".into()));
+        }
+        section = None;
+        continue;
+      }
+      if only.is_some_and(|only| only != name) {
+        continue;
+      }
+      let active = conditionals.last().is_none_or(|c| c.active);
+      let symbol = || match line.tokens.get(1..) {
+        | Some([Token::Word(symbol)]) => Ok(symbol.clone()),
+        | _ => {
+          Err(at(line.number, format!("expected a symbol after {keyword}")))
+        },
+      };
+      match keyword {
+        | "ifdef" | "ifndef" => {
+          let defined = self.defines.contains(&symbol()?);
+          let taken = active && defined == (keyword == "ifdef");
+          conditionals.push(Conditional {
+            active: taken,
+            taken,
+            outer: active
+          });
+          continue;
+        },
+        | "elif" | "else" => {
+          let Some(c) = conditionals.last_mut() else {
+            return Err(at(line.number, format!("{keyword} without ifdef")));
+          };
+          let holds = keyword == "else" || self.defines.contains(&symbol()?);
+          c.active = c.outer && !c.taken && holds;
+          c.taken |= c.active;
+          continue;
+        },
+        | "endif" => {
+          conditionals
+            .pop()
+            .ok_or_else(|| at(line.number, "endif without ifdef".into()))?;
+          continue;
+        },
+        | _ if !active => continue,
+        | "define" => {
+          self.defines.insert(symbol()?);
+          continue;
+        },
+        | "undef" => {
+          self.defines.remove(&symbol()?);
+          continue;
+        },
+        | _ => {}
+      }
+      if keyword == "copy" {
+        let [_, Token::Str(parts)] = &line.tokens[..] else {
+          return Err(at(
+            line.number,
+            "expected the name of a source after copy".into()
+          ));
+        };
+        let source: String = parts
+          .iter()
+          .map(|part| match part {
+            | Part::Char(c) => Ok(*c),
+            | _ => Err(at(
+              line.number,
+              "expected the name of a source after copy".into()
+            ))
+          })
+          .collect::<Result<_, _>>()?;
+        if self.depth == COPY_MAX {
+          return Err(at(
+            line.number,
+            format!("copy of {source} nests too deep")
+          ));
+        }
+        let near = path.parent().unwrap_or(Path::new("."));
+        let found = search::source(&source, near)
+          .ok_or_else(|| at(line.number, format!("no source {source}")))?;
+        self.depth += 1;
+        let result = self.read(&found, locale, Some(name));
+        self.depth -= 1;
+        result?;
+        continue;
+      }
+      let Some(category) = category_of(locale, name) else {
+        continue;
+      };
+      let cx =
+        Context { charmap: self.charmap, path: &shown, line: line.number };
+      category.line(keyword, &line, &cx).map_err(|err| at(line.number, err))?;
+    }
+    if let Some(name) = section {
+      return Err(format!("{shown}: {name} without END"));
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,154 @@
+// localedef compiles a locale from its POSIX source and a charmap into the
+// file newlocale maps from LOCPATH:
+//
+//   localedef [-f charmap] [-i source] output
+//
+// The source is read from standard input when -i does not give it, and is
+// looked for, as the sources it copies are, in its own directory, then in
+// the locales directory of each directory of I18NPATH and of
+// /usr/share/i18n. Charmaps are looked for in the charmaps directories the
+// same way, compressed or not, UTF-8 being the default and built in.
+//
+// The categories the library has conventions for are compiled: LC_CTYPE,
+// LC_NUMERIC, LC_TIME, LC_COLLATE, LC_MONETARY and LC_MESSAGES. The others
+// are read and passed over.
+
+#[allow(dead_code)]
+#[path = "../../../src/support/locale/format.rs"]
+mod format;
+
+mod charmap;
+mod collate;
+mod ctype;
+mod lexer;
+mod locale;
+mod messages;
+mod monetary;
+mod numeric;
+mod search;
+mod time;
+mod writer;
+
+use {
+  charmap::Charmap,
+  locale::{Locale, Reader},
+  std::{
+    env,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::ExitCode
+  },
+  writer::Writer
+};
+
+const USAGE: &str = "usage: localedef [-f charmap] [-i source] output";
+
+struct Options {
+  charmap: Option<String>,
+  source: Option<String>,
+  output: PathBuf
+}
+
+fn options() -> Result<Options, String> {
+  let mut args = env::args().skip(1);
+  let (mut charmap, mut source, mut output) = (None, None, None);
+  while let Some(arg) = args.next() {
+    let mut value =
+      |name: &str| args.next().ok_or_else(|| format!("{name} needs a value"));
+    match arg.as_str() {
+      | "-f" | "--charmap" => charmap = Some(value(&arg)?),
+      | "-i" | "--inputfile" => source = Some(value(&arg)?),
+      | "-h" | "--help" => return Err(USAGE.into()),
+      | _ if arg.starts_with("--charmap=") => {
+        charmap = Some(arg["--charmap=".len()..].into())
+      },
+      | _ if arg.starts_with("--inputfile=") => {
+        source = Some(arg["--inputfile=".len()..].into())
+      },
+      | _ if arg.starts_with('-') => {
+        return Err(format!("unknown option {arg}\n{USAGE}"));
+      },
+      | _ if output.is_none() => output = Some(PathBuf::from(arg)),
+      | _ => return Err(USAGE.into())
+    }
+  }
+  let output = output.ok_or(USAGE)?;
+  Ok(Options { charmap, source, output })
+}
+
+// Write `data` to `path` through a file renamed over it, so that programs
+// that have the old one mapped keep it whole.
+fn write(
+  path: &Path,
+  data: &[u8]
+) -> Result<(), String> {
+  let mut temporary = path.as_os_str().to_owned();
+  temporary.push(".new");
+  let show = |err: io::Error| format!("{}: {err}", path.display());
+  fs::write(&temporary, data).map_err(show)?;
+  fs::rename(&temporary, path).map_err(show)
+}
+
+fn compile(options: &Options) -> Result<(), String> {
+  let charmap = match &options.charmap {
+    | Some(name) => Charmap::open(name)?,
+    | None => Charmap::utf8()
+  };
+  let mut locale = Locale::default();
+  let mut reader = Reader::new(&charmap);
+  match &options.source {
+    | Some(name) => {
+      let path = search::source(name, Path::new("."))
+        .ok_or_else(|| format!("no source {name}"))?;
+      reader.read(&path, &mut locale, None)?;
+    },
+    | None => {
+      let mut text = String::new();
+      io::stdin()
+        .read_to_string(&mut text)
+        .map_err(|err| format!("standard input: {err}"))?;
+      reader.read_text(&text, Path::new("<stdin>"), &mut locale, None)?;
+    }
+  }
+  let mut writer = Writer::default();
+  if let Some(ctype) = &locale.ctype {
+    let words = ctype.section(&mut writer, &charmap);
+    writer.section(format::LC_CTYPE, words);
+  }
+  if let Some(numeric) = &locale.numeric {
+    let words = numeric.section(&mut writer);
+    writer.section(format::LC_NUMERIC, words);
+  }
+  if let Some(time) = &locale.time {
+    let words = time.section(&mut writer);
+    writer.section(format::LC_TIME, words);
+  }
+  if let Some(collate) = &locale.collate {
+    if let Some(words) = collate.section(&mut writer, &charmap)? {
+      writer.section(format::LC_COLLATE, words);
+    }
+  }
+  if let Some(monetary) = &locale.monetary {
+    let words = monetary.section(&mut writer);
+    writer.section(format::LC_MONETARY, words);
+  }
+  if let Some(messages) = &locale.messages {
+    let words = messages.section(&mut writer);
+    writer.section(format::LC_MESSAGES, words);
+  }
+  let data = writer.finish(&charmap.name);
+  assert!(format::File::parse(&data).is_some());
+  write(&options.output, &data)
+}
+
+fn main() -> ExitCode {
+  let result = options().and_then(|options| compile(&options));
+  match result {
+    | Ok(()) => ExitCode::SUCCESS,
+    | Err(err) => {
+      eprintln!("localedef: {err}");
+      ExitCode::FAILURE
+    }
+  }
+}
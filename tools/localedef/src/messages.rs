@@ -0,0 +1,54 @@
+use crate::{
+  lexer::Line,
+  locale::{self, Category, Context},
+  writer::{Word, Writer}
+};
+
+// LC_MESSAGES, with C's answers for any keyword the source leaves out.
+pub struct Messages {
+  yesexpr: Vec<u8>,
+  noexpr: Vec<u8>,
+  yesstr: Vec<u8>,
+  nostr: Vec<u8>
+}
+
+impl Default for Messages {
+  fn default() -> Self {
+    Messages {
+      yesexpr: b"^[yY]".to_vec(),
+      noexpr: b"^[nN]".to_vec(),
+      yesstr: b"yes".to_vec(),
+      nostr: b"no".to_vec()
+    }
+  }
+}
+
+impl Category for Messages {
+  fn line(
+    &mut self,
+    keyword: &str,
+    line: &Line,
+    cx: &Context
+  ) -> Result<(), String> {
+    let field = match keyword {
+      | "yesexpr" => &mut self.yesexpr,
+      | "noexpr" => &mut self.noexpr,
+      | "yesstr" => &mut self.yesstr,
+      | "nostr" => &mut self.nostr,
+      | _ => return Err(format!("unknown keyword {keyword} in LC_MESSAGES"))
+    };
+    *field = cx.string(&locale::operands(&line.tokens[1..]))?;
+    Ok(())
+  }
+}
+
+impl Messages {
+  pub fn section(
+    &self,
+    writer: &mut Writer
+  ) -> Vec<Word> {
+    [&self.yesexpr, &self.noexpr, &self.yesstr, &self.nostr]
+      .map(|s| writer.string(s))
+      .to_vec()
+  }
+}
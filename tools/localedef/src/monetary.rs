@@ -0,0 +1,113 @@
+use crate::{
+  lexer::Line,
+  locale::{self, Category, Context},
+  writer::{Word, Writer}
+};
+
+const CHAR_MAX: u32 = 127;
+
+// The values of LC_MONETARY in the order of the file, the int_ ones, which
+// default to those without, last.
+const VALUES: [&str; 14] = [
+  "int_frac_digits",
+  "frac_digits",
+  "p_cs_precedes",
+  "p_sep_by_space",
+  "n_cs_precedes",
+  "n_sep_by_space",
+  "p_sign_posn",
+  "n_sign_posn",
+  "int_p_cs_precedes",
+  "int_p_sep_by_space",
+  "int_n_cs_precedes",
+  "int_n_sep_by_space",
+  "int_p_sign_posn",
+  "int_n_sign_posn"
+];
+
+// LC_MONETARY, with C's conventions for any keyword the source leaves out.
+#[derive(Default)]
+pub struct Monetary {
+  int_curr_symbol: Vec<u8>,
+  currency_symbol: Vec<u8>,
+  mon_decimal_point: Vec<u8>,
+  mon_thousands_sep: Vec<u8>,
+  mon_grouping: Vec<u8>,
+  positive_sign: Vec<u8>,
+  negative_sign: Vec<u8>,
+  values: [Option<u32>; VALUES.len()]
+}
+
+impl Category for Monetary {
+  fn line(
+    &mut self,
+    keyword: &str,
+    line: &Line,
+    cx: &Context
+  ) -> Result<(), String> {
+    let operands = locale::operands(&line.tokens[1..]);
+    let field = match keyword {
+      | "int_curr_symbol" => &mut self.int_curr_symbol,
+      | "currency_symbol" => &mut self.currency_symbol,
+      | "mon_decimal_point" => &mut self.mon_decimal_point,
+      | "mon_thousands_sep" => &mut self.mon_thousands_sep,
+      | "positive_sign" => &mut self.positive_sign,
+      | "negative_sign" => &mut self.negative_sign,
+      | "mon_grouping" => {
+        self.mon_grouping = locale::grouping(&operands)?;
+        return Ok(());
+      },
+      | _ => {
+        let i = VALUES
+          .iter()
+          .position(|&value| value == keyword)
+          .ok_or_else(|| format!("unknown keyword {keyword} in LC_MONETARY"))?;
+        let [operand] = operands[..] else {
+          return Err(format!("expected one number after {keyword}"));
+        };
+        self.values[i] = match locale::number(operand)? {
+          | -1 => None,
+          | n => Some(
+            u32::try_from(n)
+              .ok()
+              .filter(|&n| n < CHAR_MAX)
+              .ok_or_else(|| format!("{keyword} {n}"))?
+          )
+        };
+        return Ok(());
+      }
+    };
+    *field = cx.string(&operands)?;
+    Ok(())
+  }
+}
+
+impl Monetary {
+  pub fn section(
+    &self,
+    writer: &mut Writer
+  ) -> Vec<Word> {
+    // nl_langinfo(CRNCYSTR) marks the symbol with where it goes: '-' before
+    // the value and '+' after.
+    let sign = if self.values[2] == Some(0) { b'+' } else { b'-' };
+    let mut crncystr = vec![sign];
+    crncystr.extend_from_slice(&self.currency_symbol);
+    let strings = [
+      &self.int_curr_symbol,
+      &self.currency_symbol,
+      &self.mon_decimal_point,
+      &self.mon_thousands_sep,
+      &self.mon_grouping,
+      &self.positive_sign,
+      &self.negative_sign,
+      &crncystr
+    ];
+    let mut words: Vec<Word> =
+      strings.iter().map(|s| writer.string(s)).collect();
+    for (i, value) in self.values.iter().enumerate() {
+      let value = if i >= 8 { value.or(self.values[i - 6]) } else { *value };
+      words.push(Word::Value(value.unwrap_or(CHAR_MAX)));
+    }
+    words
+  }
+}
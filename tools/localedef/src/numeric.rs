@@ -0,0 +1,51 @@
+use crate::{
+  lexer::Line,
+  locale::{self, Category, Context},
+  writer::{Word, Writer}
+};
+
+// LC_NUMERIC, with C's conventions for any keyword the source leaves out.
+pub struct Numeric {
+  decimal_point: Vec<u8>,
+  thousands_sep: Vec<u8>,
+  grouping: Vec<u8>
+}
+
+impl Default for Numeric {
+  fn default() -> Self {
+    Numeric {
+      decimal_point: b".".to_vec(),
+      thousands_sep: Vec::new(),
+      grouping: Vec::new()
+    }
+  }
+}
+
+impl Category for Numeric {
+  fn line(
+    &mut self,
+    keyword: &str,
+    line: &Line,
+    cx: &Context
+  ) -> Result<(), String> {
+    let operands = locale::operands(&line.tokens[1..]);
+    match keyword {
+      | "decimal_point" => self.decimal_point = cx.string(&operands)?,
+      | "thousands_sep" => self.thousands_sep = cx.string(&operands)?,
+      | "grouping" => self.grouping = locale::grouping(&operands)?,
+      | _ => return Err(format!("unknown keyword {keyword} in LC_NUMERIC"))
+    }
+    Ok(())
+  }
+}
+
+impl Numeric {
+  pub fn section(
+    &self,
+    writer: &mut Writer
+  ) -> Vec<Word> {
+    [&self.decimal_point, &self.thousands_sep, &self.grouping]
+      .map(|s| writer.string(s))
+      .to_vec()
+  }
+}
@@ -0,0 +1,76 @@
+use std::{
+  env,
+  fs,
+  path::{Path, PathBuf},
+  process::Command
+};
+
+// Where the sources and charmaps are when I18NPATH, a list of directories
+// separated by colons, has neither: in its locales and charmaps
+// subdirectories.
+const I18NPATH_DEFAULT: &str = "/usr/share/i18n";
+
+fn dirs() -> Vec<PathBuf> {
+  let i18npath = env::var_os("I18NPATH").unwrap_or_default();
+  env::split_paths(&i18npath)
+    .filter(|dir| !dir.as_os_str().is_empty())
+    .chain([PathBuf::from(I18NPATH_DEFAULT)])
+    .collect()
+}
+
+fn is_path(name: &str) -> bool {
+  name.contains('/')
+}
+
+// The source called `name`, as -i, copy or include give it: a path, or a
+// file of the directory `near`, then of the locales directories.
+pub fn source(
+  name: &str,
+  near: &Path
+) -> Option<PathBuf> {
+  if is_path(name) {
+    return Some(PathBuf::from(name));
+  }
+  let mut candidates = vec![near.join(name)];
+  for dir in dirs() {
+    candidates.push(dir.join("locales").join(name));
+    candidates.push(dir.join(name));
+  }
+  candidates.into_iter().find(|path| path.is_file())
+}
+
+// The charmap called `name`: a path, or a file of the charmaps directories,
+// either of them perhaps compressed with gzip.
+pub fn charmap(name: &str) -> Option<PathBuf> {
+  let bases = if is_path(name) {
+    vec![PathBuf::from(name)]
+  } else {
+    dirs().into_iter().map(|dir| dir.join("charmaps").join(name)).collect()
+  };
+  bases
+    .into_iter()
+    .flat_map(|base| {
+      let mut gz = base.clone().into_os_string();
+      gz.push(".gz");
+      [base, PathBuf::from(gz)]
+    })
+    .find(|path| path.is_file())
+}
+
+// The text of the file at `path`, through gzip if it is compressed.
+pub fn read(path: &Path) -> Result<String, String> {
+  let bytes = if path.extension().is_some_and(|ext| ext == "gz") {
+    let output = Command::new("gzip")
+      .arg("-dc")
+      .arg(path)
+      .output()
+      .map_err(|err| format!("{}: gzip: {err}", path.display()))?;
+    if !output.status.success() {
+      return Err(format!("{}: gzip failed", path.display()));
+    }
+    output.stdout
+  } else {
+    fs::read(path).map_err(|err| format!("{}: {err}", path.display()))?
+  };
+  String::from_utf8(bytes).map_err(|_| format!("{}: not UTF-8", path.display()))
+}
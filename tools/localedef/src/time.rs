@@ -0,0 +1,150 @@
+use crate::{
+  lexer::Line,
+  locale::{self, Category, Context},
+  writer::{Word, Writer}
+};
+
+// The lists of LC_TIME in the order of the file, with how many strings
+// each has.
+const LISTS: [(&str, usize); 7] = [
+  ("abday", 7),
+  ("day", 7),
+  ("abmon", 12),
+  ("mon", 12),
+  ("ab_alt_mon", 12),
+  ("alt_mon", 12),
+  ("am_pm", 2)
+];
+
+// Then the strings, era and alt_digits being lists joined by semicolons.
+const STRINGS: [&str; 9] = [
+  "d_t_fmt",
+  "d_fmt",
+  "t_fmt",
+  "t_fmt_ampm",
+  "era",
+  "era_d_fmt",
+  "era_t_fmt",
+  "era_d_t_fmt",
+  "alt_digits"
+];
+
+// What the library takes from elsewhere, or not at all.
+const IGNORED: [&str; 6] = [
+  "date_fmt",
+  "week",
+  "first_weekday",
+  "first_workday",
+  "cal_direction",
+  "timezone"
+];
+
+const ABDAY: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const DAY: [&str; 7] = [
+  "Sunday",
+  "Monday",
+  "Tuesday",
+  "Wednesday",
+  "Thursday",
+  "Friday",
+  "Saturday"
+];
+const ABMON: [&str; 12] = [
+  "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov",
+  "Dec"
+];
+const MON: [&str; 12] = [
+  "January",
+  "February",
+  "March",
+  "April",
+  "May",
+  "June",
+  "July",
+  "August",
+  "September",
+  "October",
+  "November",
+  "December"
+];
+const AM_PM: [&str; 2] = ["AM", "PM"];
+const C_STRINGS: [&str; 9] = [
+  "%a %b %e %H:%M:%S %Y",
+  "%m/%d/%y",
+  "%H:%M:%S",
+  "%I:%M:%S %p",
+  "",
+  "",
+  "",
+  "",
+  ""
+];
+
+// LC_TIME, with C's names and formats for any keyword the source leaves
+// out, but for the alternative month names, which default to the others.
+#[derive(Default)]
+pub struct Time {
+  lists: [Option<Vec<Vec<u8>>>; LISTS.len()],
+  strings: [Option<Vec<u8>>; STRINGS.len()]
+}
+
+impl Category for Time {
+  fn line(
+    &mut self,
+    keyword: &str,
+    line: &Line,
+    cx: &Context
+  ) -> Result<(), String> {
+    let operands = locale::operands(&line.tokens[1..]);
+    if let Some(i) = LISTS.iter().position(|&(name, _)| name == keyword) {
+      let list = cx.strings(&operands)?;
+      if list.len() != LISTS[i].1 {
+        return Err(format!(
+          "{keyword} has {} strings, not {}",
+          list.len(),
+          LISTS[i].1
+        ));
+      }
+      self.lists[i] = Some(list);
+    } else if let Some(i) = STRINGS.iter().position(|&name| name == keyword) {
+      let list = cx.strings(&operands)?;
+      if !matches!(keyword, "era" | "alt_digits") && list.len() != 1 {
+        return Err(format!("expected one string after {keyword}"));
+      }
+      self.strings[i] = Some(list.join(&b';'));
+    } else if !IGNORED.contains(&keyword) {
+      return Err(format!("unknown keyword {keyword} in LC_TIME"));
+    }
+    Ok(())
+  }
+}
+
+impl Time {
+  pub fn section(
+    &self,
+    writer: &mut Writer
+  ) -> Vec<Word> {
+    let defaults: [&[&str]; LISTS.len()] =
+      [&ABDAY, &DAY, &ABMON, &MON, &ABMON, &MON, &AM_PM];
+    let mut words = Vec::new();
+    for (i, default) in defaults.iter().enumerate() {
+      // ab_alt_mon and alt_mon fall back on abmon and mon.
+      let list = self.lists[i].as_ref().or(if i == 4 || i == 5 {
+        self.lists[i - 2].as_ref()
+      } else {
+        None
+      });
+      match list {
+        | Some(list) => words.extend(list.iter().map(|s| writer.string(s))),
+        | None => {
+          words.extend(default.iter().map(|s| writer.string(s.as_bytes())))
+        },
+      }
+    }
+    for (string, default) in self.strings.iter().zip(C_STRINGS) {
+      words
+        .push(writer.string(string.as_deref().unwrap_or(default.as_bytes())));
+    }
+    words
+  }
+}
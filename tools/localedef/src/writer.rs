@@ -0,0 +1,97 @@
+use {
+  crate::format::{self, ENTRY_SIZE, HEADER_SIZE, MAGIC, VERSION},
+  std::collections::HashMap
+};
+
+// A word of a section: a value, or the offset of data the writer keeps.
+#[derive(Clone, Copy)]
+pub enum Word {
+  Value(u32),
+  Data(usize)
+}
+
+// A locale file being put together: its sections, then the strings and
+// tables they point to, each string once.
+#[derive(Default)]
+pub struct Writer {
+  sections: Vec<(u32, Vec<Word>)>,
+  data: Vec<u8>,
+  strings: HashMap<Vec<u8>, usize>
+}
+
+impl Writer {
+  // The string `s`, which holds no null character.
+  pub fn string(
+    &mut self,
+    s: &[u8]
+  ) -> Word {
+    if let Some(&at) = self.strings.get(s) {
+      return Word::Data(at);
+    }
+    let at = self.data.len();
+    self.data.extend_from_slice(s);
+    self.data.push(0);
+    self.strings.insert(s.to_vec(), at);
+    Word::Data(at)
+  }
+
+  // The table of `records`, `words` words each.
+  pub fn table(
+    &mut self,
+    records: &[u32],
+    words: usize
+  ) -> [Word; 2] {
+    let len = records.len() / words;
+    if len == 0 {
+      return [Word::Value(0), Word::Value(0)];
+    }
+    self.data.resize(self.data.len().next_multiple_of(4), 0);
+    let at = self.data.len();
+    for record in records {
+      self.data.extend_from_slice(&record.to_le_bytes());
+    }
+    [Word::Data(at), Word::Value(len as u32)]
+  }
+
+  pub fn section(
+    &mut self,
+    category: u32,
+    words: Vec<Word>
+  ) {
+    assert_eq!(words.len(), format::SECTION_WORDS[category as usize]);
+    self.sections.push((category, words));
+  }
+
+  // The file, with its strings in `codeset`.
+  pub fn finish(
+    mut self,
+    codeset: &str
+  ) -> Vec<u8> {
+    let codeset = self.string(codeset.as_bytes());
+    let words: usize = self.sections.iter().map(|(_, words)| words.len()).sum();
+    let base = HEADER_SIZE + self.sections.len() * ENTRY_SIZE + words * 4;
+    let value = |word: Word| match word {
+      | Word::Value(value) => value,
+      | Word::Data(at) => (base + at) as u32
+    };
+    let mut file = Vec::with_capacity(base + self.data.len());
+    file.extend_from_slice(&MAGIC);
+    for word in [VERSION, value(codeset), self.sections.len() as u32] {
+      file.extend_from_slice(&word.to_le_bytes());
+    }
+    let mut offset = HEADER_SIZE + self.sections.len() * ENTRY_SIZE;
+    for (category, words) in &self.sections {
+      for word in [*category, offset as u32, words.len() as u32] {
+        file.extend_from_slice(&word.to_le_bytes());
+      }
+      offset += words.len() * 4;
+    }
+    for (_, words) in &self.sections {
+      for &word in words {
+        file.extend_from_slice(&value(word).to_le_bytes());
+      }
+    }
+    file.extend_from_slice(&self.data);
+    file
+  }
+}